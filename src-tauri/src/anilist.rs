@@ -1,8 +1,34 @@
 use serde::{Deserialize, Serialize};
 use serde_json::json;
+use std::sync::RwLock;
+#[cfg(feature = "debug-auth")]
+use tracing::debug;
+use tracing::{info, warn};
 
-/// AniList API endpoint
-const ANILIST_API_URL: &str = "https://graphql.anilist.co";
+use crate::config::TitleLanguage;
+
+/// Public AniList API endpoint, used unless overridden
+pub const ANILIST_API_URL: &str = "https://graphql.anilist.co";
+
+lazy_static::lazy_static! {
+    /// Endpoint every request goes to, see `set_api_url`
+    static ref API_URL: RwLock<String> = RwLock::new(ANILIST_API_URL.to_string());
+}
+
+/// Point all AniList requests at another endpoint (a proxy or a local mock)
+pub fn set_api_url(url: &str) {
+    if let Ok(mut api_url) = API_URL.write() {
+        *api_url = url.trim_end_matches('/').to_string();
+    }
+}
+
+/// Endpoint in use
+pub fn api_url() -> String {
+    API_URL
+        .read()
+        .map(|url| url.clone())
+        .unwrap_or_else(|_| ANILIST_API_URL.to_string())
+}
 
 /// Result of a simple title search
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -61,8 +87,8 @@ pub async fn progressive_search_anime(
         let search_query: String = words[..word_count].join(" ");
         let search_query_lower = search_query.to_lowercase();
 
-        println!(
-            "[AniList] Searching with {} word(s): \"{}\"",
+        info!(
+            "Searching with {} word(s): \"{}\"",
             word_count, search_query
         );
 
@@ -89,7 +115,7 @@ pub async fn progressive_search_anime(
 
         let client = reqwest::Client::new();
         let response = client
-            .post(ANILIST_API_URL)
+            .post(api_url())
             .header("Content-Type", "application/json")
             .header("Accept", "application/json")
             .json(&request_body)
@@ -102,7 +128,7 @@ pub async fn progressive_search_anime(
             .await
             .map_err(|e| format!("Failed to parse response: {}", e))?;
 
-        if let Some(ref media) = anilist_response.data.media {
+        if let Some(ref media) = anilist_response.into_data()?.media {
             // Validate: Check if returned title contains our search query
             let english_lower = media
                 .title
@@ -123,7 +149,7 @@ pub async fn progressive_search_anime(
                 .all(|word| english_lower.contains(word) || romaji_lower.contains(word));
 
             if title_matches {
-                println!("[AniList] ✓ Valid match: {:?}", media.title);
+                info!("✓ Valid match: {:?}", media.title);
                 return Ok(Some(ProgressiveSearchResult {
                     title: media.title.clone(),
                     matched_query: search_query,
@@ -131,32 +157,48 @@ pub async fn progressive_search_anime(
                     total_words,
                 }));
             } else {
-                println!(
-                    "[AniList] ✗ Rejected (title doesn't match query): {:?}",
-                    media.title
-                );
+                warn!("✗ Rejected (title doesn't match query): {:?}", media.title);
                 // Continue with more words
             }
         }
     }
 
-    println!(
-        "[AniList] No valid match found after trying all {} words",
+    info!(
+        "No valid match found after trying all {} words",
         total_words
     );
     Ok(None)
 }
 
+/// AniList media format, used to narrow a search
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum MediaFormat {
+    Tv,
+    TvShort,
+    Movie,
+    Special,
+    Ova,
+    Ona,
+    Music,
+}
+
 /// Represents an anime from AniList
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Anime {
     pub id: i32,
+    /// MyAnimeList id (None when AniList has no MAL mapping)
+    #[serde(rename = "idMal", default)]
+    pub id_mal: Option<i64>,
     pub title: AnimeTitle,
     #[serde(rename = "coverImage")]
     pub cover_image: CoverImage,
     pub episodes: Option<i32>,
     pub status: Option<String>,
     pub description: Option<String>,
+    /// Adult (18+) entry, so the UI can warn before showing it
+    #[serde(rename = "isAdult", default)]
+    pub is_adult: Option<bool>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -166,22 +208,116 @@ pub struct AnimeTitle {
     pub native: Option<String>,
 }
 
+impl AnimeTitle {
+    /// Title in the preferred language, falling back to the other variants
+    pub fn preferred(&self, language: TitleLanguage) -> String {
+        let (first, second, third) = match language {
+            TitleLanguage::English => (&self.english, &self.romaji, &self.native),
+            TitleLanguage::Romaji => (&self.romaji, &self.english, &self.native),
+            TitleLanguage::Native => (&self.native, &self.romaji, &self.english),
+        };
+
+        first
+            .as_ref()
+            .or(second.as_ref())
+            .or(third.as_ref())
+            .cloned()
+            .unwrap_or_default()
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct CoverImage {
     pub large: Option<String>,
     pub medium: Option<String>,
 }
 
+/// An entry of a GraphQL `errors` array
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct GraphQlError {
+    pub message: String,
+    /// HTTP-like status AniList attaches to some errors
+    pub status: Option<u16>,
+}
+
+/// Why a successful (HTTP 200) AniList response couldn't be used
+#[derive(Debug, Clone, PartialEq)]
+pub enum AniListError {
+    /// The query failed, AniList explains why in `errors`
+    GraphQl(Vec<GraphQlError>),
+    /// `data` is missing or doesn't have the expected shape
+    InvalidData(String),
+    /// The requested entry doesn't exist (null `Media`, e.g. an invalid id)
+    NotFound,
+}
+
+impl std::fmt::Display for AniListError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::GraphQl(errors) => {
+                let messages: Vec<&str> = errors.iter().map(|e| e.message.as_str()).collect();
+                write!(f, "AniList error: {}", messages.join("; "))
+            }
+            Self::InvalidData(e) => write!(f, "Failed to parse response: {}", e),
+            Self::NotFound => write!(f, "Not found on AniList"),
+        }
+    }
+}
+
+impl From<AniListError> for String {
+    fn from(error: AniListError) -> Self {
+        error.to_string()
+    }
+}
+
 /// Response wrapper for AniList GraphQL queries
+///
+/// AniList answers some query errors with HTTP 200, `data: null` and an
+/// `errors` array. `data` is kept raw until the errors have been checked, so
+/// those come out as `AniListError::GraphQl` rather than a parse error.
 #[derive(Debug, Deserialize)]
 struct AniListResponse<T> {
-    data: T,
+    data: Option<serde_json::Value>,
+    #[serde(default)]
+    errors: Option<Vec<GraphQlError>>,
+    #[serde(skip)]
+    data_type: std::marker::PhantomData<T>,
+}
+
+impl<T: serde::de::DeserializeOwned> AniListResponse<T> {
+    /// The typed `data`, or the GraphQL errors if there are any
+    fn into_data(self) -> Result<T, AniListError> {
+        if let Some(errors) = self.errors.filter(|errors| !errors.is_empty()) {
+            return Err(AniListError::GraphQl(errors));
+        }
+        let data = self
+            .data
+            .ok_or_else(|| AniListError::InvalidData("missing field `data`".to_string()))?;
+        serde_json::from_value(data).map_err(|e| AniListError::InvalidData(e.to_string()))
+    }
 }
 
 #[derive(Debug, Deserialize)]
 struct MediaResponse {
+    /// null when there's no entry with the requested id
     #[serde(rename = "Media")]
-    media: Anime,
+    media: Option<Anime>,
+}
+
+impl AniListResponse<MediaResponse> {
+    /// The `Media` entry, or `NotFound` when AniList has none
+    ///
+    /// AniList reports a missing entry as null `Media`, usually along with a
+    /// "Not Found." error with status 404.
+    fn into_media(self) -> Result<Anime, AniListError> {
+        let not_found = self.errors.as_ref().is_some_and(|errors| {
+            !errors.is_empty() && errors.iter().all(|e| e.status == Some(404))
+        });
+        if not_found {
+            return Err(AniListError::NotFound);
+        }
+        self.into_data()?.media.ok_or(AniListError::NotFound)
+    }
 }
 
 #[derive(Debug, Deserialize)]
@@ -195,20 +331,51 @@ struct PageData {
     media: Vec<Anime>,
 }
 
+/// Variables for the search query
+///
+/// `isAdult: false` filters out adult entries; leaving it out returns both.
+/// `format` is only sent when set, so no format means every format.
+fn search_variables(
+    query: &str,
+    limit: i32,
+    include_adult: bool,
+    format: Option<MediaFormat>,
+) -> serde_json::Value {
+    let mut variables = json!({
+        "search": query,
+        "perPage": limit
+    });
+    if !include_adult {
+        variables["isAdult"] = json!(false);
+    }
+    if let Some(format) = format {
+        variables["format"] = json!(format);
+    }
+    variables
+}
+
 /// Search for anime by title
 ///
 /// # Arguments
 /// * `query` - The search query (anime title)
 /// * `limit` - Maximum number of results to return
+/// * `include_adult` - Also return adult (18+) entries
+/// * `format` - Only return this format (e.g. `Movie`), None for any
 ///
 /// # Returns
 /// * `Result<Vec<Anime>, String>` - List of matching anime or error message
-pub async fn search_anime(query: &str, limit: i32) -> Result<Vec<Anime>, String> {
+pub async fn search_anime(
+    query: &str,
+    limit: i32,
+    include_adult: bool,
+    format: Option<MediaFormat>,
+) -> Result<Vec<Anime>, String> {
     let graphql_query = r#"
-        query ($search: String, $perPage: Int) {
+        query ($search: String, $perPage: Int, $isAdult: Boolean, $format: MediaFormat) {
             Page(perPage: $perPage) {
-                media(search: $search, type: ANIME) {
+                media(search: $search, type: ANIME, isAdult: $isAdult, format: $format) {
                     id
+                    idMal
                     title {
                         romaji
                         english
@@ -221,25 +388,21 @@ pub async fn search_anime(query: &str, limit: i32) -> Result<Vec<Anime>, String>
                     episodes
                     status
                     description
+                    isAdult
                 }
             }
         }
     "#;
 
-    let variables = json!({
-        "search": query,
-        "perPage": limit
-    });
-
     let request_body = json!({
         "query": graphql_query,
-        "variables": variables
+        "variables": search_variables(query, limit, include_adult, format)
     });
 
     // Make HTTP request
     let client = reqwest::Client::new();
     let response = client
-        .post(ANILIST_API_URL)
+        .post(api_url())
         .header("Content-Type", "application/json")
         .header("Accept", "application/json")
         .json(&request_body)
@@ -253,7 +416,7 @@ pub async fn search_anime(query: &str, limit: i32) -> Result<Vec<Anime>, String>
         .await
         .map_err(|e| format!("Failed to parse response: {}", e))?;
 
-    Ok(anilist_response.data.page.media)
+    Ok(anilist_response.into_data()?.page.media)
 }
 
 /// Get anime details by ID
@@ -268,6 +431,7 @@ pub async fn get_anime_by_id(id: i32) -> Result<Anime, String> {
         query ($id: Int) {
             Media(id: $id, type: ANIME) {
                 id
+                idMal
                 title {
                     romaji
                     english
@@ -280,6 +444,7 @@ pub async fn get_anime_by_id(id: i32) -> Result<Anime, String> {
                 episodes
                 status
                 description
+                isAdult
             }
         }
     "#;
@@ -296,7 +461,7 @@ pub async fn get_anime_by_id(id: i32) -> Result<Anime, String> {
     // Make HTTP request
     let client = reqwest::Client::new();
     let response = client
-        .post(ANILIST_API_URL)
+        .post(api_url())
         .header("Content-Type", "application/json")
         .header("Accept", "application/json")
         .json(&request_body)
@@ -310,12 +475,44 @@ pub async fn get_anime_by_id(id: i32) -> Result<Anime, String> {
         .await
         .map_err(|e| format!("Failed to parse response: {}", e))?;
 
-    Ok(anilist_response.data.media)
+    Ok(anilist_response.into_media()?)
+}
+
+/// Queries to try for a title, in order: as-is, then transliterated to ASCII
+///
+/// Stylized titles ("Shōjo", "Pokémon") often fail AniList's search with the
+/// diacritics but match without them.
+fn title_search_queries(title: &str) -> Vec<String> {
+    let mut queries = vec![title.to_string()];
+
+    let transliterated = deunicode::deunicode(title);
+    if transliterated != title && !transliterated.trim().is_empty() {
+        queries.push(transliterated);
+    }
+
+    queries
+}
+
+/// Shorter queries for a title that found nothing, dropping trailing words
+///
+/// The reverse of `progressive_search_anime`, which builds up from the first
+/// word: trailing subtitles and season words ("Frieren 2nd Season") are what
+/// usually keep a title from matching, so they go first. The full title itself
+/// isn't included.
+fn trailing_trim_queries(title: &str) -> Vec<String> {
+    let words: Vec<&str> = title.split_whitespace().collect();
+    (1..words.len())
+        .rev()
+        .map(|word_count| words[..word_count].join(" "))
+        .collect()
 }
 
 /// Search for anime by window title (fuzzy matching)
 /// This is useful for matching detected media player titles to AniList entries
 ///
+/// Falls back to a transliterated search when the title as written finds
+/// nothing, then to the title with trailing words trimmed one at a time.
+///
 /// # Arguments
 /// * `window_title` - The window title from media player
 ///
@@ -329,137 +526,2121 @@ pub async fn match_anime_from_title(window_title: &str) -> Result<Option<Anime>,
         .unwrap_or(window_title)
         .trim();
 
-    // Search for the anime
-    let results = search_anime(cleaned_title, 5).await?;
+    for query in title_search_queries(cleaned_title) {
+        // Return the first result (best match)
+        if let Some(anime) = search_anime(&query, 5, false, None)
+            .await?
+            .into_iter()
+            .next()
+        {
+            return Ok(Some(anime));
+        }
+        info!("No AniList results for \"{}\"", query);
+    }
+
+    for query in trailing_trim_queries(cleaned_title) {
+        if let Some(anime) = search_anime(&query, 5, false, None)
+            .await?
+            .into_iter()
+            .next()
+        {
+            info!("Matched after trimming to \"{}\"", query);
+            return Ok(Some(anime));
+        }
+    }
 
-    // Return the first result (best match)
-    Ok(results.into_iter().next())
+    Ok(None)
 }
 
-#[derive(Debug, Serialize, Deserialize)]
-pub struct TokenResponse {
-    pub access_token: String,
-    pub token_type: String,
-    pub expires_in: i32,
-    pub refresh_token: Option<String>,
+/// AniList season, as used for seasonal charts
+///
+/// AniList's winter runs December to February, so December counts towards the
+/// following year's winter.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "UPPERCASE")]
+pub enum MediaSeason {
+    Winter,
+    Spring,
+    Summer,
+    Fall,
 }
 
-/// Exchange Authorization Code for Access Token
-pub async fn exchange_code_for_token(
-    code: String,
-    client_id: String,
-    client_secret: String,
-    redirect_uri: String,
-) -> Result<TokenResponse, String> {
+impl MediaSeason {
+    /// Parse a season name, ignoring case ("winter", "FALL")
+    pub fn parse(name: &str) -> Result<Self, String> {
+        match name.trim().to_uppercase().as_str() {
+            "WINTER" => Ok(Self::Winter),
+            "SPRING" => Ok(Self::Spring),
+            "SUMMER" => Ok(Self::Summer),
+            "FALL" => Ok(Self::Fall),
+            _ => Err(format!(
+                "Invalid season \"{}\": expected WINTER, SPRING, SUMMER or FALL",
+                name
+            )),
+        }
+    }
+
+    /// Season and season year of a calendar month (1 - 12)
+    pub fn of_month(year: i32, month: u32) -> (Self, i32) {
+        match month {
+            12 => (Self::Winter, year + 1),
+            1 | 2 => (Self::Winter, year),
+            3..=5 => (Self::Spring, year),
+            6..=8 => (Self::Summer, year),
+            _ => (Self::Fall, year),
+        }
+    }
+
+    /// The season airing now, with its season year
+    pub fn current() -> (Self, i32) {
+        let days = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map(|d| d.as_secs() / 86_400)
+            .unwrap_or(0);
+        let (year, month) = year_month_from_days(days as i64);
+        Self::of_month(year, month)
+    }
+}
+
+/// Calendar year and month (1 - 12) of a day count since 1970-01-01
+fn year_month_from_days(days: i64) -> (i32, u32) {
+    // Howard Hinnant's civil_from_days, with years starting in March
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let day_of_era = z.rem_euclid(146_097);
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let shifted_month = (5 * day_of_year + 2) / 153;
+    let month = if shifted_month < 10 {
+        shifted_month + 3
+    } else {
+        shifted_month - 9
+    };
+    let year = year_of_era + era * 400 + i64::from(month <= 2);
+    (year as i32, month as u32)
+}
+
+/// Number of entries returned by `get_popular_season`
+const POPULAR_SEASON_LIMIT: i32 = 20;
+
+/// Most pages `fetch_all_pages` requests, in case `hasNextPage` never ends
+const MAX_PAGES: i32 = 20;
+
+/// Entries per page when walking every page (AniList's maximum)
+const ALL_PAGES_PER_PAGE: i32 = 50;
+
+#[derive(Debug, Default, Deserialize)]
+struct PageInfo {
+    #[serde(rename = "hasNextPage", default)]
+    has_next_page: bool,
+}
+
+#[derive(Debug, Deserialize)]
+struct PagedResponse<P> {
+    #[serde(rename = "Page")]
+    page: PageWithInfo<P>,
+}
+
+/// A `Page` with its `pageInfo` next to the page's own fields
+#[derive(Debug, Deserialize)]
+struct PageWithInfo<P> {
+    #[serde(rename = "pageInfo", default)]
+    page_info: PageInfo,
+    #[serde(flatten)]
+    items: P,
+}
+
+/// Request numbered pages from 1 until one reports it is the last
+///
+/// `fetch` returns a page's entries and whether another page follows.
+/// Stops after `max_pages` pages at most, with a warning naming `what`.
+async fn walk_pages<T, F, Fut>(what: &str, max_pages: i32, mut fetch: F) -> Result<Vec<T>, String>
+where
+    F: FnMut(i32) -> Fut,
+    Fut: std::future::Future<Output = Result<(Vec<T>, bool), String>>,
+{
+    let mut items = Vec::new();
+
+    for page in 1..=max_pages {
+        let (entries, has_next) = fetch(page).await?;
+        items.extend(entries);
+
+        if !has_next {
+            return Ok(items);
+        }
+    }
+
+    warn!(
+        "Stopped fetching {} after {} pages, more are available",
+        what, max_pages
+    );
+    Ok(items)
+}
+
+/// Run a `Page` query for every page and collect the results
+///
+/// The query must take a `$page: Int` variable and select
+/// `pageInfo { hasNextPage }`. Pages are requested from 1 until
+/// `hasNextPage` is false, stopping after `MAX_PAGES` pages at most.
+///
+/// # Arguments
+/// * `graphql_query` - The `Page` query
+/// * `base_variables` - Variables sent with every page; `page` is set per request
+/// * `extract` - Takes the entries out of one page's fields
+async fn fetch_all_pages<P, T>(
+    graphql_query: &str,
+    base_variables: serde_json::Value,
+    extract: impl Fn(P) -> Vec<T>,
+) -> Result<Vec<T>, String>
+where
+    P: serde::de::DeserializeOwned,
+{
     let client = reqwest::Client::new();
-    let params = json!({
-        "grant_type": "authorization_code",
-        "client_id": client_id,
-        "client_secret": client_secret,
-        "redirect_uri": redirect_uri,
-        "code": code
+
+    walk_pages("pages", MAX_PAGES, |page| {
+        let mut variables = base_variables.clone();
+        variables["page"] = json!(page);
+        let request = client
+            .post(api_url())
+            .header("Content-Type", "application/json")
+            .header("Accept", "application/json")
+            .json(&json!({ "query": graphql_query, "variables": variables }));
+        let extract = &extract;
+
+        async move {
+            let response = request
+                .send()
+                .await
+                .map_err(|e| format!("Failed to send request: {}", e))?;
+
+            let status = response.status();
+            if !status.is_success() {
+                let error_text = response.text().await.unwrap_or_default();
+                return Err(format!(
+                    "Failed to get page {} (HTTP {}): {}",
+                    page,
+                    status.as_u16(),
+                    error_text
+                ));
+            }
+
+            let anilist_response: AniListResponse<PagedResponse<P>> = response
+                .json()
+                .await
+                .map_err(|e| format!("Failed to parse response: {}", e))?;
+
+            let data = anilist_response.into_data()?.page;
+            Ok((extract(data.items), data.page_info.has_next_page))
+        }
+    })
+    .await
+}
+
+/// Run a `Page { media }` query and return the media
+async fn fetch_media_page(
+    graphql_query: &str,
+    variables: serde_json::Value,
+) -> Result<Vec<Anime>, String> {
+    let request_body = json!({
+        "query": graphql_query,
+        "variables": variables
     });
 
+    let client = reqwest::Client::new();
     let response = client
-        .post("https://anilist.co/api/v2/oauth/token")
+        .post(api_url())
         .header("Content-Type", "application/json")
         .header("Accept", "application/json")
-        .json(&params)
+        .json(&request_body)
         .send()
         .await
-        .map_err(|e| format!("Request failed: {}", e))?;
-
-    if !response.status().is_success() {
-        let error_text = response.text().await.unwrap_or_default();
-        return Err(format!("Token exchange failed: {}", error_text));
-    }
+        .map_err(|e| format!("Failed to send request: {}", e))?;
 
-    let token_data: TokenResponse = response
+    let anilist_response: AniListResponse<SearchResponse> = response
         .json()
         .await
-        .map_err(|e| format!("Parse error: {}", e))?;
+        .map_err(|e| format!("Failed to parse response: {}", e))?;
 
-    Ok(token_data)
+    Ok(anilist_response.into_data()?.page.media)
 }
 
-/// Response from SaveMediaListEntry mutation
-#[derive(Debug, Serialize, Deserialize)]
-pub struct MediaListEntry {
-    pub id: i32,
-    pub progress: i32,
-    pub status: String,
-}
+/// Get the anime trending on AniList right now
+///
+/// # Arguments
+/// * `page` - Page number, starting at 1
+/// * `per_page` - Entries per page (AniList allows up to 50)
+///
+/// # Returns
+/// * `Result<Vec<Anime>, String>` - Trending anime, most trending first
+pub async fn get_trending(page: i32, per_page: i32) -> Result<Vec<Anime>, String> {
+    let graphql_query = r#"
+        query ($page: Int, $perPage: Int) {
+            Page(page: $page, perPage: $perPage) {
+                media(type: ANIME, sort: TRENDING_DESC, isAdult: false) {
+                    id
+                    idMal
+                    title {
+                        romaji
+                        english
+                        native
+                    }
+                    coverImage {
+                        large
+                        medium
+                    }
+                    episodes
+                    status
+                    description
+                    isAdult
+                }
+            }
+        }
+    "#;
 
-#[derive(Debug, Deserialize)]
-struct SaveMediaListResponse {
-    #[serde(rename = "SaveMediaListEntry")]
-    save_media_list_entry: MediaListEntry,
+    fetch_media_page(
+        graphql_query,
+        json!({ "page": page.max(1), "perPage": per_page.clamp(1, 50) }),
+    )
+    .await
 }
 
-/// Update anime progress on AniList (requires authentication)
+/// Get the most popular anime of a season
 ///
 /// # Arguments
-/// * `access_token` - OAuth access token for authentication
-/// * `media_id` - AniList media ID
-/// * `progress` - Episode number to set as progress
-/// * `status` - Optional status (CURRENT, COMPLETED, PAUSED, DROPPED, PLANNING, REPEATING)
+/// * `season` - Season to list, None for the current one
+/// * `year` - Season year, None for the current season's year
+/// * `all` - Return the whole season instead of the top `POPULAR_SEASON_LIMIT`
 ///
 /// # Returns
-/// * `Result<MediaListEntry, String>` - Updated entry or error message
-pub async fn update_media_progress(
-    access_token: &str,
-    media_id: i32,
-    progress: i32,
-    status: Option<&str>,
-) -> Result<MediaListEntry, String> {
-    let graphql_mutation = r#"
-        mutation UpdateMediaProgress($mediaId: Int, $progress: Int, $status: MediaListStatus) {
-            SaveMediaListEntry(mediaId: $mediaId, progress: $progress, status: $status) {
-                id
-                progress
-                status
+/// * `Result<Vec<Anime>, String>` - The season's anime, most popular first
+pub async fn get_popular_season(
+    season: Option<MediaSeason>,
+    year: Option<i32>,
+    all: bool,
+) -> Result<Vec<Anime>, String> {
+    let (current_season, current_year) = MediaSeason::current();
+    let season = season.unwrap_or(current_season);
+    let year = year.unwrap_or(current_year);
+
+    let graphql_query = r#"
+        query ($season: MediaSeason, $seasonYear: Int, $page: Int, $perPage: Int) {
+            Page(page: $page, perPage: $perPage) {
+                pageInfo {
+                    hasNextPage
+                }
+                media(type: ANIME, season: $season, seasonYear: $seasonYear, sort: POPULARITY_DESC, isAdult: false) {
+                    id
+                    idMal
+                    title {
+                        romaji
+                        english
+                        native
+                    }
+                    coverImage {
+                        large
+                        medium
+                    }
+                    episodes
+                    status
+                    description
+                    isAdult
+                }
             }
         }
     "#;
 
-    let variables = if let Some(s) = status {
-        json!({
-            "mediaId": media_id,
-            "progress": progress,
-            "status": s
-        })
-    } else {
-        json!({
-            "mediaId": media_id,
-            "progress": progress
-        })
+    if all {
+        return fetch_all_pages(
+            graphql_query,
+            json!({ "season": season, "seasonYear": year, "perPage": ALL_PAGES_PER_PAGE }),
+            |page: PageData| page.media,
+        )
+        .await;
+    }
+
+    fetch_media_page(
+        graphql_query,
+        json!({ "season": season, "seasonYear": year, "perPage": POPULAR_SEASON_LIMIT }),
+    )
+    .await
+}
+
+/// Get the MAL id of an AniList anime
+///
+/// # Returns
+/// * `Option<i64>` - MAL id, or None if AniList has no MAL mapping for the entry
+pub fn map_anilist_to_mal(anime: &Anime) -> Option<i64> {
+    anime.id_mal.filter(|id| *id > 0)
+}
+
+/// AniList and MAL ids of the same anime
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct CrossIds {
+    pub anilist_id: Option<i32>,
+    pub mal_id: Option<i64>,
+}
+
+#[derive(Debug, Deserialize)]
+struct CrossIdMedia {
+    id: i32,
+    #[serde(rename = "idMal")]
+    id_mal: Option<i64>,
+}
+
+#[derive(Debug, Deserialize)]
+struct CrossIdResponse {
+    #[serde(rename = "Media")]
+    media: Option<CrossIdMedia>,
+}
+
+/// Resolve the AniList/MAL id pair from either id
+///
+/// # Arguments
+/// * `anilist_id` - AniList id, if known
+/// * `mal_id` - MAL id, if known (used when `anilist_id` is None)
+///
+/// # Returns
+/// * `Result<CrossIds, String>` - Both ids; a side is None when no mapping exists
+pub async fn resolve_cross_ids(
+    anilist_id: Option<i32>,
+    mal_id: Option<i64>,
+) -> Result<CrossIds, String> {
+    let (graphql_query, variables) = match (anilist_id, mal_id) {
+        (Some(id), _) => (
+            "query ($id: Int) { Media(id: $id, type: ANIME) { id idMal } }",
+            json!({ "id": id }),
+        ),
+        (None, Some(id)) => (
+            "query ($idMal: Int) { Media(idMal: $idMal, type: ANIME) { id idMal } }",
+            json!({ "idMal": id }),
+        ),
+        (None, None) => return Err("Either an AniList or a MAL id is required".to_string()),
     };
 
     let request_body = json!({
-        "query": graphql_mutation,
+        "query": graphql_query,
         "variables": variables
     });
 
     let client = reqwest::Client::new();
     let response = client
-        .post(ANILIST_API_URL)
+        .post(api_url())
         .header("Content-Type", "application/json")
         .header("Accept", "application/json")
-        .header("Authorization", format!("Bearer {}", access_token))
         .json(&request_body)
         .send()
         .await
         .map_err(|e| format!("Failed to send request: {}", e))?;
 
-    if !response.status().is_success() {
-        let error_text = response.text().await.unwrap_or_default();
-        return Err(format!("Update failed: {}", error_text));
-    }
-
-    let anilist_response: AniListResponse<SaveMediaListResponse> = response
+    // An unknown id comes back as 404 with `"Media": null`, which is handled below
+    let anilist_response: AniListResponse<CrossIdResponse> = response
         .json()
         .await
         .map_err(|e| format!("Failed to parse response: {}", e))?;
 
-    Ok(anilist_response.data.save_media_list_entry)
+    Ok(match anilist_response.into_data()?.media {
+        Some(media) => CrossIds {
+            anilist_id: Some(media.id),
+            mal_id: media.id_mal.filter(|id| *id > 0),
+        },
+        None => CrossIds { anilist_id, mal_id },
+    })
+}
+
+/// Relation types worth suggesting after finishing a show
+const SUGGESTED_RELATIONS: [&str; 3] = ["SEQUEL", "PREQUEL", "SIDE_STORY"];
+
+/// A related entry (sequel, prequel or side story)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MediaRelation {
+    pub relation_type: String,
+    pub id: i32,
+    pub title: AnimeTitle,
+    /// Episode count, None while airing or unknown
+    pub episodes: Option<i32>,
+}
+
+#[derive(Debug, Deserialize)]
+struct RelationsResponse {
+    #[serde(rename = "Media")]
+    media: Option<RelationsMedia>,
+}
+
+#[derive(Debug, Deserialize)]
+struct RelationsMedia {
+    relations: Option<RelationConnection>,
+}
+
+#[derive(Debug, Deserialize)]
+struct RelationConnection {
+    #[serde(default)]
+    edges: Vec<RelationEdge>,
+}
+
+#[derive(Debug, Deserialize)]
+struct RelationEdge {
+    #[serde(rename = "relationType")]
+    relation_type: Option<String>,
+    node: Option<RelationNode>,
+}
+
+#[derive(Debug, Deserialize)]
+struct RelationNode {
+    id: i32,
+    title: AnimeTitle,
+    episodes: Option<i32>,
+}
+
+/// Keep the sequel/prequel/side story edges, in AniList's order
+fn suggested_relations(edges: Vec<RelationEdge>) -> Vec<MediaRelation> {
+    edges
+        .into_iter()
+        .filter_map(|edge| {
+            let relation_type = edge.relation_type?;
+            let node = edge.node?;
+            SUGGESTED_RELATIONS
+                .contains(&relation_type.as_str())
+                .then_some(MediaRelation {
+                    relation_type,
+                    id: node.id,
+                    title: node.title,
+                    episodes: node.episodes,
+                })
+        })
+        .collect()
+}
+
+/// Get the sequels, prequels and side stories of an anime
+///
+/// # Arguments
+/// * `media_id` - AniList media ID
+///
+/// # Returns
+/// * `Result<Vec<MediaRelation>, String>` - Related entries, empty when there are none
+pub async fn get_relations(media_id: i32) -> Result<Vec<MediaRelation>, String> {
+    let graphql_query = r#"
+        query ($id: Int) {
+            Media(id: $id) {
+                relations {
+                    edges {
+                        relationType
+                        node {
+                            id
+                            title {
+                                romaji
+                                english
+                                native
+                            }
+                            episodes
+                        }
+                    }
+                }
+            }
+        }
+    "#;
+
+    let request_body = json!({
+        "query": graphql_query,
+        "variables": { "id": media_id }
+    });
+
+    let client = reqwest::Client::new();
+    let response = client
+        .post(api_url())
+        .header("Content-Type", "application/json")
+        .header("Accept", "application/json")
+        .json(&request_body)
+        .send()
+        .await
+        .map_err(|e| format!("Failed to send request: {}", e))?;
+
+    let status = response.status();
+    if !status.is_success() {
+        let error_text = response.text().await.unwrap_or_default();
+        return Err(format!(
+            "Failed to get relations (HTTP {}): {}",
+            status.as_u16(),
+            error_text
+        ));
+    }
+
+    let anilist_response: AniListResponse<RelationsResponse> = response
+        .json()
+        .await
+        .map_err(|e| format!("Failed to parse response: {}", e))?;
+
+    let edges = anilist_response
+        .into_data()?
+        .media
+        .and_then(|media| media.relations)
+        .map(|relations| relations.edges)
+        .unwrap_or_default();
+
+    Ok(suggested_relations(edges))
+}
+
+/// The authenticated AniList user
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Viewer {
+    pub id: i32,
+    pub name: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct ViewerResponse {
+    #[serde(rename = "Viewer")]
+    viewer: Viewer,
+}
+
+/// Get the user that owns the access token
+///
+/// # Arguments
+/// * `access_token` - OAuth access token for authentication
+///
+/// # Returns
+/// * `Result<Viewer, String>` - The authenticated user or error message
+pub async fn get_viewer(access_token: &str) -> Result<Viewer, String> {
+    let graphql_query = r#"
+        query {
+            Viewer {
+                id
+                name
+            }
+        }
+    "#;
+
+    let request_body = json!({
+        "query": graphql_query
+    });
+
+    let client = reqwest::Client::new();
+    let response = client
+        .post(api_url())
+        .header("Content-Type", "application/json")
+        .header("Accept", "application/json")
+        .header("Authorization", format!("Bearer {}", access_token))
+        .json(&request_body)
+        .send()
+        .await
+        .map_err(|e| format!("Failed to send request: {}", e))?;
+
+    let status = response.status();
+    if !status.is_success() {
+        let error_text = response.text().await.unwrap_or_default();
+        return Err(format!(
+            "Failed to get viewer (HTTP {}): {}",
+            status.as_u16(),
+            error_text
+        ));
+    }
+
+    let anilist_response: AniListResponse<ViewerResponse> = response
+        .json()
+        .await
+        .map_err(|e| format!("Failed to parse response: {}", e))?;
+
+    Ok(anilist_response.into_data()?.viewer)
+}
+
+/// Profile of the logged-in AniList user, for showing who is signed in
+#[derive(Debug, Clone, Serialize, PartialEq)]
+pub struct AniListUser {
+    pub id: i32,
+    pub name: String,
+    /// Avatar image URL (None when the user has no avatar)
+    pub avatar: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct UserAvatar {
+    large: Option<String>,
+    medium: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct UserProfile {
+    id: i32,
+    name: String,
+    avatar: Option<UserAvatar>,
+}
+
+#[derive(Debug, Deserialize)]
+struct UserProfileResponse {
+    #[serde(rename = "Viewer")]
+    viewer: UserProfile,
+}
+
+/// Get the profile of the user that owns the access token
+///
+/// Like `get_viewer`, plus the avatar. The large avatar is preferred.
+///
+/// # Arguments
+/// * `access_token` - OAuth access token for authentication
+///
+/// # Returns
+/// * `Result<AniListUser, String>` - The user's profile or error message
+pub async fn get_anilist_user(access_token: &str) -> Result<AniListUser, String> {
+    let graphql_query = r#"
+        query {
+            Viewer {
+                id
+                name
+                avatar {
+                    large
+                    medium
+                }
+            }
+        }
+    "#;
+
+    let request_body = json!({
+        "query": graphql_query
+    });
+
+    let client = reqwest::Client::new();
+    let response = client
+        .post(api_url())
+        .header("Content-Type", "application/json")
+        .header("Accept", "application/json")
+        .header("Authorization", format!("Bearer {}", access_token))
+        .json(&request_body)
+        .send()
+        .await
+        .map_err(|e| format!("Failed to send request: {}", e))?;
+
+    let status = response.status();
+    if !status.is_success() {
+        let error_text = response.text().await.unwrap_or_default();
+        return Err(format!(
+            "Failed to get user (HTTP {}): {}",
+            status.as_u16(),
+            error_text
+        ));
+    }
+
+    let anilist_response: AniListResponse<UserProfileResponse> = response
+        .json()
+        .await
+        .map_err(|e| format!("Failed to parse response: {}", e))?;
+
+    let profile = anilist_response.into_data()?.viewer;
+    Ok(AniListUser {
+        id: profile.id,
+        name: profile.name,
+        avatar: profile
+            .avatar
+            .and_then(|avatar| avatar.large.or(avatar.medium)),
+    })
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct TokenResponse {
+    pub access_token: String,
+    pub token_type: String,
+    pub expires_in: i32,
+    pub refresh_token: Option<String>,
+}
+
+/// Exchange Authorization Code for Access Token
+pub async fn exchange_code_for_token(
+    code: String,
+    client_id: String,
+    client_secret: String,
+    redirect_uri: String,
+) -> Result<TokenResponse, String> {
+    let client = reqwest::Client::new();
+
+    // Only built with `--features debug-auth`; secrets are redacted even then
+    #[cfg(feature = "debug-auth")]
+    debug!(
+        client_id = %client_id,
+        client_secret = %crate::logging::redact(&client_secret),
+        code = %crate::logging::redact(&code),
+        redirect_uri = %redirect_uri,
+        "Exchanging authorization code for token"
+    );
+
+    let params = json!({
+        "grant_type": "authorization_code",
+        "client_id": client_id,
+        "client_secret": client_secret,
+        "redirect_uri": redirect_uri,
+        "code": code
+    });
+
+    let response = client
+        .post("https://anilist.co/api/v2/oauth/token")
+        .header("Content-Type", "application/json")
+        .header("Accept", "application/json")
+        .json(&params)
+        .send()
+        .await
+        .map_err(|e| format!("Request failed: {}", e))?;
+
+    if !response.status().is_success() {
+        let error_text = response.text().await.unwrap_or_default();
+        return Err(format!("Token exchange failed: {}", error_text));
+    }
+
+    let token_data: TokenResponse = response
+        .json()
+        .await
+        .map_err(|e| format!("Parse error: {}", e))?;
+
+    Ok(token_data)
+}
+
+/// Response from SaveMediaListEntry mutation
+#[derive(Debug, Serialize, Deserialize)]
+pub struct MediaListEntry {
+    pub id: i32,
+    pub progress: i32,
+    pub status: String,
+    /// Times the user has rewatched the anime
+    #[serde(default)]
+    pub repeat: Option<i32>,
+}
+
+#[derive(Debug, Deserialize)]
+struct SaveMediaListResponse {
+    #[serde(rename = "SaveMediaListEntry")]
+    save_media_list_entry: MediaListEntry,
+}
+
+/// Update anime progress on AniList (requires authentication)
+///
+/// # Arguments
+/// * `access_token` - OAuth access token for authentication
+/// * `media_id` - AniList media ID
+/// * `progress` - Episode number to set as progress
+/// * `status` - Optional status (CURRENT, COMPLETED, PAUSED, DROPPED, PLANNING, REPEATING)
+/// * `repeat` - Optional rewatch count, usually sent with REPEATING
+///
+/// # Returns
+/// * `Result<MediaListEntry, String>` - Updated entry or error message
+pub async fn update_media_progress(
+    access_token: &str,
+    media_id: i32,
+    progress: i32,
+    status: Option<&str>,
+    repeat: Option<i32>,
+) -> Result<MediaListEntry, String> {
+    let graphql_mutation = r#"
+        mutation UpdateMediaProgress($mediaId: Int, $progress: Int, $status: MediaListStatus, $repeat: Int) {
+            SaveMediaListEntry(mediaId: $mediaId, progress: $progress, status: $status, repeat: $repeat) {
+                id
+                progress
+                status
+                repeat
+            }
+        }
+    "#;
+
+    let mut variables = json!({
+        "mediaId": media_id,
+        "progress": progress
+    });
+    if let Some(s) = status {
+        variables["status"] = json!(s);
+    }
+    if let Some(repeat) = repeat {
+        if repeat < 0 {
+            return Err(format!("Invalid rewatch count: {}", repeat));
+        }
+        variables["repeat"] = json!(repeat);
+    }
+
+    let request_body = json!({
+        "query": graphql_mutation,
+        "variables": variables
+    });
+
+    let client = reqwest::Client::new();
+    let response = client
+        .post(api_url())
+        .header("Content-Type", "application/json")
+        .header("Accept", "application/json")
+        .header("Authorization", format!("Bearer {}", access_token))
+        .json(&request_body)
+        .send()
+        .await
+        .map_err(|e| format!("Failed to send request: {}", e))?;
+
+    if !response.status().is_success() {
+        let error_text = response.text().await.unwrap_or_default();
+        return Err(format!("Update failed: {}", error_text));
+    }
+
+    let anilist_response: AniListResponse<SaveMediaListResponse> = response
+        .json()
+        .await
+        .map_err(|e| format!("Failed to parse response: {}", e))?;
+
+    Ok(anilist_response.into_data()?.save_media_list_entry)
+}
+
+#[derive(Debug, Deserialize)]
+struct ListStatusResponse {
+    #[serde(rename = "Media")]
+    media: Option<ListStatusMedia>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ListStatusMedia {
+    #[serde(rename = "mediaListEntry")]
+    media_list_entry: Option<ListStatusEntry>,
+}
+
+/// Status and progress of a media on the user's list
+#[derive(Debug, Clone, Deserialize)]
+pub struct ListStatusEntry {
+    pub status: Option<String>,
+    pub progress: Option<i32>,
+}
+
+/// Get the status and progress of a media on the token owner's list
+///
+/// # Arguments
+/// * `access_token` - OAuth access token for authentication
+/// * `media_id` - AniList media ID
+///
+/// # Returns
+/// * `Ok(None)` - The media isn't on the user's list
+pub async fn get_list_status(
+    access_token: &str,
+    media_id: i32,
+) -> Result<Option<ListStatusEntry>, String> {
+    let graphql_query = r#"
+        query ($id: Int) {
+            Media(id: $id) {
+                mediaListEntry {
+                    status
+                    progress
+                }
+            }
+        }
+    "#;
+
+    let request_body = json!({
+        "query": graphql_query,
+        "variables": { "id": media_id }
+    });
+
+    let client = reqwest::Client::new();
+    let response = client
+        .post(api_url())
+        .header("Content-Type", "application/json")
+        .header("Accept", "application/json")
+        .header("Authorization", format!("Bearer {}", access_token))
+        .json(&request_body)
+        .send()
+        .await
+        .map_err(|e| format!("Failed to send request: {}", e))?;
+
+    let status = response.status();
+    if !status.is_success() {
+        let error_text = response.text().await.unwrap_or_default();
+        return Err(format!(
+            "Failed to get list status (HTTP {}): {}",
+            status.as_u16(),
+            error_text
+        ));
+    }
+
+    let anilist_response: AniListResponse<ListStatusResponse> = response
+        .json()
+        .await
+        .map_err(|e| format!("Failed to parse response: {}", e))?;
+
+    Ok(anilist_response
+        .into_data()?
+        .media
+        .and_then(|media| media.media_list_entry))
+}
+
+/// An entry of the user's AniList anime list
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MediaListItem {
+    #[serde(rename = "mediaId")]
+    pub media_id: i32,
+    pub status: String,
+    pub progress: i32,
+    pub score: f64,
+    pub media: MediaListMedia,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MediaListMedia {
+    pub title: AnimeTitle,
+    pub episodes: Option<i32>,
+}
+
+#[derive(Debug, Deserialize)]
+struct MediaListCollectionResponse {
+    #[serde(rename = "MediaListCollection")]
+    collection: MediaListCollection,
+}
+
+#[derive(Debug, Deserialize)]
+struct MediaListCollection {
+    lists: Vec<MediaListGroup>,
+    #[serde(rename = "hasNextChunk", default)]
+    has_next_chunk: bool,
+}
+
+#[derive(Debug, Deserialize)]
+struct MediaListGroup {
+    entries: Vec<MediaListItem>,
+}
+
+/// Number of entries AniList returns per collection chunk (its maximum)
+const MEDIA_LIST_CHUNK_SIZE: i32 = 500;
+
+/// Most chunks `get_media_list` requests, in case `hasNextChunk` never ends
+const MAX_MEDIA_LIST_CHUNKS: i32 = 20;
+
+/// Get the user's entire anime list (requires authentication)
+///
+/// Large lists are returned by AniList in chunks; all chunks are fetched,
+/// up to `MAX_MEDIA_LIST_CHUNKS`.
+///
+/// # Arguments
+/// * `access_token` - OAuth access token for authentication
+///
+/// # Returns
+/// * `Result<Vec<MediaListItem>, String>` - All list entries or error message
+pub async fn get_media_list(access_token: &str) -> Result<Vec<MediaListItem>, String> {
+    let viewer = get_viewer(access_token).await?;
+
+    let graphql_query = r#"
+        query ($userId: Int, $chunk: Int, $perChunk: Int) {
+            MediaListCollection(userId: $userId, type: ANIME, chunk: $chunk, perChunk: $perChunk) {
+                hasNextChunk
+                lists {
+                    entries {
+                        mediaId
+                        status
+                        progress
+                        score
+                        media {
+                            title {
+                                romaji
+                                english
+                                native
+                            }
+                            episodes
+                        }
+                    }
+                }
+            }
+        }
+    "#;
+
+    let client = reqwest::Client::new();
+
+    let entries = walk_pages("the media list", MAX_MEDIA_LIST_CHUNKS, |chunk| {
+        let request = client
+            .post(api_url())
+            .header("Content-Type", "application/json")
+            .header("Accept", "application/json")
+            .header("Authorization", format!("Bearer {}", access_token))
+            .json(&json!({
+                "query": graphql_query,
+                "variables": {
+                    "userId": viewer.id,
+                    "chunk": chunk,
+                    "perChunk": MEDIA_LIST_CHUNK_SIZE
+                }
+            }));
+
+        async move {
+            let response = request
+                .send()
+                .await
+                .map_err(|e| format!("Failed to send request: {}", e))?;
+
+            let status = response.status();
+            if !status.is_success() {
+                let error_text = response.text().await.unwrap_or_default();
+                return Err(format!(
+                    "Failed to get media list (HTTP {}): {}",
+                    status.as_u16(),
+                    error_text
+                ));
+            }
+
+            let anilist_response: AniListResponse<MediaListCollectionResponse> = response
+                .json()
+                .await
+                .map_err(|e| format!("Failed to parse response: {}", e))?;
+
+            let collection = anilist_response.into_data()?.collection;
+            let entries = collection
+                .lists
+                .into_iter()
+                .flat_map(|list| list.entries)
+                .collect();
+            Ok((entries, collection.has_next_chunk))
+        }
+    })
+    .await?;
+
+    // Entries in custom lists also appear in their status list, keep the first copy
+    let mut seen = std::collections::HashSet::new();
+    Ok(entries
+        .into_iter()
+        .filter(|entry| seen.insert(entry.media_id))
+        .collect())
+}
+
+/// A tag from AniList's MediaTagCollection
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MediaTag {
+    pub name: String,
+    pub description: Option<String>,
+    pub category: Option<String>,
+    #[serde(rename = "isAdult", default)]
+    pub is_adult: bool,
+}
+
+#[derive(Debug, Deserialize)]
+struct GenreCollectionResponse {
+    #[serde(rename = "GenreCollection")]
+    genres: Vec<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct MediaTagCollectionResponse {
+    #[serde(rename = "MediaTagCollection")]
+    tags: Vec<MediaTag>,
+}
+
+/// Post a query without variables or auth and parse its `data`
+async fn fetch_collection<T: serde::de::DeserializeOwned>(
+    graphql_query: &str,
+    what: &str,
+) -> Result<T, String> {
+    let client = reqwest::Client::new();
+    let response = client
+        .post(api_url())
+        .header("Content-Type", "application/json")
+        .header("Accept", "application/json")
+        .json(&json!({ "query": graphql_query }))
+        .send()
+        .await
+        .map_err(|e| format!("Failed to send request: {}", e))?;
+
+    let status = response.status();
+    if !status.is_success() {
+        let error_text = response.text().await.unwrap_or_default();
+        return Err(format!(
+            "Failed to get {} (HTTP {}): {}",
+            what,
+            status.as_u16(),
+            error_text
+        ));
+    }
+
+    let anilist_response: AniListResponse<T> = response
+        .json()
+        .await
+        .map_err(|e| format!("Failed to parse response: {}", e))?;
+
+    Ok(anilist_response.into_data()?)
+}
+
+/// Fetch the list of valid genres
+pub async fn fetch_genre_collection() -> Result<Vec<String>, String> {
+    let response: GenreCollectionResponse =
+        fetch_collection("query { GenreCollection }", "genres").await?;
+    Ok(response.genres)
+}
+
+/// Fetch the list of valid media tags
+pub async fn fetch_media_tags() -> Result<Vec<MediaTag>, String> {
+    let response: MediaTagCollectionResponse = fetch_collection(
+        "query { MediaTagCollection { name description category isAdult } }",
+        "media tags",
+    )
+    .await?;
+    Ok(response.tags)
+}
+
+/// Number of notifications fetched per request
+const NOTIFICATION_PAGE_SIZE: i32 = 50;
+
+/// A notification in simplified form
+#[derive(Debug, Clone, Serialize, PartialEq)]
+pub struct Notification {
+    pub id: i32,
+    /// AniList notification type, e.g. "AIRING" or "RELATED_MEDIA_ADDITION"
+    pub kind: String,
+    pub media_id: Option<i32>,
+    pub text: String,
+    /// Unix timestamp
+    pub created_at: i64,
+}
+
+#[derive(Debug, Deserialize)]
+struct NotificationsResponse {
+    #[serde(rename = "Viewer")]
+    viewer: NotificationViewer,
+    #[serde(rename = "Page")]
+    page: NotificationPage,
+}
+
+#[derive(Debug, Deserialize)]
+struct NotificationViewer {
+    #[serde(rename = "unreadNotificationCount", default)]
+    unread_notification_count: usize,
+}
+
+#[derive(Debug, Deserialize)]
+struct NotificationPage {
+    #[serde(default)]
+    notifications: Vec<RawNotification>,
+}
+
+/// Common fields of the `NotificationUnion` members
+///
+/// Members not covered by the query's fragments come back as `{}`, so every
+/// field is optional and entries without an id or type are dropped.
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+struct RawNotification {
+    id: Option<i32>,
+    #[serde(rename = "type")]
+    kind: Option<String>,
+    #[serde(rename = "animeId")]
+    anime_id: Option<i32>,
+    #[serde(rename = "mediaId")]
+    media_id: Option<i32>,
+    episode: Option<i32>,
+    contexts: Option<Vec<String>>,
+    context: Option<String>,
+    media: Option<NotificationMedia>,
+    #[serde(rename = "createdAt")]
+    created_at: Option<i64>,
+}
+
+#[derive(Debug, Deserialize)]
+struct NotificationMedia {
+    title: AnimeTitle,
+}
+
+/// Build the display text AniList shows for a notification
+///
+/// Airing notifications split their text around the episode and title
+/// ("Episode ", " of ", " aired."); the others have a single context that
+/// follows the title.
+fn notification_text(raw: &RawNotification, title: &str) -> String {
+    match (&raw.contexts, raw.episode) {
+        (Some(contexts), Some(episode)) if contexts.len() >= 3 => format!(
+            "{}{}{}{}{}",
+            contexts[0], episode, contexts[1], title, contexts[2]
+        ),
+        _ => format!("{}{}", title, raw.context.as_deref().unwrap_or_default())
+            .trim()
+            .to_string(),
+    }
+}
+
+fn simplify_notification(raw: RawNotification, language: TitleLanguage) -> Option<Notification> {
+    let title = raw
+        .media
+        .as_ref()
+        .map(|media| media.title.preferred(language))
+        .unwrap_or_default();
+    let text = notification_text(&raw, &title);
+
+    Some(Notification {
+        id: raw.id?,
+        kind: raw.kind?,
+        media_id: raw.anime_id.or(raw.media_id),
+        text,
+        created_at: raw.created_at.unwrap_or_default(),
+    })
+}
+
+/// Get the user's recent notifications (newest first)
+///
+/// # Arguments
+/// * `access_token` - OAuth access token for authentication
+/// * `unread_only` - Only return notifications the user hasn't seen yet
+///
+/// # Returns
+/// * `Result<Vec<Notification>, String>` - Notifications; unknown types are skipped
+pub async fn get_notifications(
+    access_token: &str,
+    unread_only: bool,
+) -> Result<Vec<Notification>, String> {
+    // Reading notifications normally resets the unread count, which would
+    // hide them from the AniList site; keep the count as it is
+    let graphql_query = r#"
+        query ($perPage: Int) {
+            Viewer {
+                unreadNotificationCount
+            }
+            Page(perPage: $perPage) {
+                notifications(resetNotificationCount: false) {
+                    ... on AiringNotification {
+                        id
+                        type
+                        animeId
+                        episode
+                        contexts
+                        createdAt
+                        media { title { romaji english native } }
+                    }
+                    ... on RelatedMediaAdditionNotification {
+                        id
+                        type
+                        mediaId
+                        context
+                        createdAt
+                        media { title { romaji english native } }
+                    }
+                    ... on MediaDataChangeNotification {
+                        id
+                        type
+                        mediaId
+                        context
+                        createdAt
+                        media { title { romaji english native } }
+                    }
+                }
+            }
+        }
+    "#;
+
+    let request_body = json!({
+        "query": graphql_query,
+        "variables": { "perPage": NOTIFICATION_PAGE_SIZE }
+    });
+
+    let client = reqwest::Client::new();
+    let response = client
+        .post(api_url())
+        .header("Content-Type", "application/json")
+        .header("Accept", "application/json")
+        .header("Authorization", format!("Bearer {}", access_token))
+        .json(&request_body)
+        .send()
+        .await
+        .map_err(|e| format!("Failed to send request: {}", e))?;
+
+    let status = response.status();
+    if !status.is_success() {
+        let error_text = response.text().await.unwrap_or_default();
+        return Err(format!(
+            "Failed to get notifications (HTTP {}): {}",
+            status.as_u16(),
+            error_text
+        ));
+    }
+
+    let anilist_response: AniListResponse<NotificationsResponse> = response
+        .json()
+        .await
+        .map_err(|e| format!("Failed to parse response: {}", e))?;
+
+    Ok(collect_notifications(
+        anilist_response.into_data()?,
+        unread_only,
+        crate::config::current().title_language,
+    ))
+}
+
+/// Simplify the notifications, keeping only the unread ones when asked
+///
+/// AniList has no unread filter, but notifications are newest first, so the
+/// unread ones are the first `unreadNotificationCount` entries.
+fn collect_notifications(
+    response: NotificationsResponse,
+    unread_only: bool,
+    language: TitleLanguage,
+) -> Vec<Notification> {
+    let limit = if unread_only {
+        response.viewer.unread_notification_count
+    } else {
+        usize::MAX
+    };
+
+    response
+        .page
+        .notifications
+        .into_iter()
+        .take(limit)
+        .filter_map(|raw| simplify_notification(raw, language))
+        .collect()
+}
+
+/// Parse user-supplied GraphQL variables, treating empty input as no variables
+fn parse_variables(variables_json: Option<&str>) -> Result<serde_json::Value, String> {
+    let variables_json = variables_json.map(str::trim).unwrap_or_default();
+    if variables_json.is_empty() {
+        return Ok(json!({}));
+    }
+
+    let variables: serde_json::Value = serde_json::from_str(variables_json)
+        .map_err(|e| format!("Invalid variables JSON: {}", e))?;
+    if !variables.is_object() {
+        return Err("Invalid variables JSON: expected an object".to_string());
+    }
+    Ok(variables)
+}
+
+/// Run an arbitrary GraphQL document against AniList
+///
+/// Always posts to the AniList endpoint (`api_url`), so callers can't use it to
+/// reach other hosts.
+///
+/// # Arguments
+/// * `query` - GraphQL query or mutation
+/// * `variables_json` - JSON object with the query variables, if any
+/// * `access_token` - OAuth access token, for queries that need a user
+///
+/// # Returns
+/// * `Result<serde_json::Value, String>` - The raw response body, including any GraphQL `errors`
+pub async fn raw_query(
+    query: &str,
+    variables_json: Option<&str>,
+    access_token: Option<&str>,
+) -> Result<serde_json::Value, String> {
+    if query.trim().is_empty() {
+        return Err("Query is empty".to_string());
+    }
+
+    let request_body = json!({
+        "query": query,
+        "variables": parse_variables(variables_json)?
+    });
+
+    let client = reqwest::Client::new();
+    let mut request = client
+        .post(api_url())
+        .header("Content-Type", "application/json")
+        .header("Accept", "application/json")
+        .json(&request_body);
+    if let Some(token) = access_token {
+        request = request.header("Authorization", format!("Bearer {}", token));
+    }
+
+    let response = request
+        .send()
+        .await
+        .map_err(|e| format!("Failed to send request: {}", e))?;
+
+    let status = response.status();
+    let body = response.text().await.unwrap_or_default();
+
+    // GraphQL errors come back as 4xx with an `errors` body, pass those through
+    let json = serde_json::from_str::<serde_json::Value>(&body);
+    let graphql_error = matches!(&json, Ok(json) if json.get("errors").is_some());
+    if !status.is_success() && !graphql_error {
+        return Err(format!("Query failed (HTTP {}): {}", status.as_u16(), body));
+    }
+
+    json.map_err(|e| format!("Failed to parse response: {}", e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use wiremock::matchers::{
+        bearer_token, body_partial_json, body_string_contains, header, method, path,
+    };
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    /// Tests that point the API URL elsewhere take this so they don't interleave
+    static API_URL_LOCK: tokio::sync::Mutex<()> = tokio::sync::Mutex::const_new(());
+
+    /// A mock AniList that requests go to until it's dropped
+    struct MockApi {
+        server: MockServer,
+        _lock: tokio::sync::MutexGuard<'static, ()>,
+    }
+
+    impl MockApi {
+        async fn start() -> Self {
+            let lock = API_URL_LOCK.lock().await;
+            let server = MockServer::start().await;
+            set_api_url(&server.uri());
+            Self {
+                server,
+                _lock: lock,
+            }
+        }
+
+        /// Answer one POST whose GraphQL variables include `variables`
+        async fn respond(
+            &self,
+            variables: serde_json::Value,
+            status: u16,
+            response: serde_json::Value,
+        ) {
+            Mock::given(method("POST"))
+                .and(path("/"))
+                .and(header("Content-Type", "application/json"))
+                .and(body_partial_json(json!({ "variables": variables })))
+                .respond_with(ResponseTemplate::new(status).set_body_json(response))
+                .expect(1)
+                .mount(&self.server)
+                .await;
+        }
+    }
+
+    impl Drop for MockApi {
+        fn drop(&mut self) {
+            set_api_url(ANILIST_API_URL);
+        }
+    }
+
+    fn anime_json(id_mal: &str) -> String {
+        format!(
+            r#"{{"id": 154587, "idMal": {}, "title": {{"romaji": "Sousou no Frieren"}},
+                "coverImage": {{}}, "episodes": 28}}"#,
+            id_mal
+        )
+    }
+
+    #[tokio::test]
+    async fn test_overridden_api_url_receives_requests() {
+        let api = MockApi::start().await;
+        api.respond(
+            json!({ "id": 154587 }),
+            200,
+            json!({ "data": { "Media": {
+                "id": 154587,
+                "title": { "romaji": "Sousou no Frieren" },
+                "coverImage": {}
+            } } }),
+        )
+        .await;
+
+        // A trailing slash is trimmed
+        let url = api.server.uri();
+        set_api_url(&format!("{}/", url));
+        assert_eq!(api_url(), url);
+        let anime = get_anime_by_id(154587).await;
+
+        assert_eq!(
+            anime.unwrap().title.romaji.as_deref(),
+            Some("Sousou no Frieren")
+        );
+        let requests = api.server.received_requests().await.unwrap();
+        assert_eq!(requests[0].method.as_str(), "POST");
+        assert_eq!(requests[0].url.path(), "/");
+
+        drop(api);
+        assert_eq!(api_url(), ANILIST_API_URL);
+    }
+
+    #[test]
+    fn test_graphql_error_with_200() {
+        let body = r#"{
+            "errors": [{
+                "message": "Variable \"$id\" got invalid value \"abc\".",
+                "status": 400,
+                "locations": [{ "line": 1, "column": 8 }]
+            }],
+            "data": null
+        }"#;
+
+        let response: AniListResponse<MediaResponse> = serde_json::from_str(body).unwrap();
+        let error = response.into_data().unwrap_err();
+        assert_eq!(
+            error,
+            AniListError::GraphQl(vec![GraphQlError {
+                message: "Variable \"$id\" got invalid value \"abc\".".to_string(),
+                status: Some(400),
+            }])
+        );
+        assert_eq!(
+            String::from(error),
+            "AniList error: Variable \"$id\" got invalid value \"abc\"."
+        );
+    }
+
+    #[test]
+    fn test_response_data() {
+        let body = format!(r#"{{"data": {{"Media": {}}}}}"#, anime_json("null"));
+        let response: AniListResponse<MediaResponse> = serde_json::from_str(&body).unwrap();
+        assert_eq!(response.into_media().unwrap().id, 154587);
+
+        let response: AniListResponse<MediaResponse> = serde_json::from_str("{}").unwrap();
+        assert!(matches!(
+            response.into_data(),
+            Err(AniListError::InvalidData(_))
+        ));
+    }
+
+    #[test]
+    fn test_null_media_is_not_found() {
+        let response: AniListResponse<MediaResponse> =
+            serde_json::from_str(r#"{"data": {"Media": null}}"#).unwrap();
+        assert_eq!(response.into_media().unwrap_err(), AniListError::NotFound);
+
+        let response: AniListResponse<MediaResponse> =
+            serde_json::from_value(not_found_json()).unwrap();
+        assert_eq!(response.into_media().unwrap_err(), AniListError::NotFound);
+    }
+
+    #[test]
+    fn test_map_anilist_to_mal() {
+        let anime: Anime = serde_json::from_str(&anime_json("52991")).unwrap();
+        assert_eq!(map_anilist_to_mal(&anime), Some(52991));
+    }
+
+    #[test]
+    fn test_map_anilist_without_mal_entry() {
+        let anime: Anime = serde_json::from_str(&anime_json("null")).unwrap();
+        assert_eq!(map_anilist_to_mal(&anime), None);
+
+        let anime: Anime =
+            serde_json::from_str(r#"{"id": 1, "title": {}, "coverImage": {}}"#).unwrap();
+        assert_eq!(map_anilist_to_mal(&anime), None);
+    }
+
+    #[test]
+    fn test_parse_variables() {
+        assert_eq!(parse_variables(None).unwrap(), json!({}));
+        assert_eq!(parse_variables(Some("  ")).unwrap(), json!({}));
+        assert_eq!(
+            parse_variables(Some(r#"{"id": 154587}"#)).unwrap(),
+            json!({ "id": 154587 })
+        );
+    }
+
+    #[test]
+    fn test_parse_variables_rejects_invalid_json() {
+        assert!(parse_variables(Some("{ id: 1 }"))
+            .unwrap_err()
+            .starts_with("Invalid variables JSON"));
+        assert!(parse_variables(Some("[1, 2]")).is_err());
+    }
+
+    #[test]
+    fn test_relations_keep_sequels_and_prequels() {
+        let connection: RelationConnection = serde_json::from_str(
+            r#"{"edges": [
+                {"relationType": "ADAPTATION", "node": {"id": 1, "title": {"romaji": "Manga"}}},
+                {"relationType": "SEQUEL", "node": {"id": 2, "title": {"romaji": "Season 2"}}},
+                {"relationType": "CHARACTER", "node": {"id": 3, "title": {}}},
+                {"relationType": "PREQUEL", "node": {"id": 4, "title": {"romaji": "Zero"}}}
+            ]}"#,
+        )
+        .unwrap();
+
+        let relations = suggested_relations(connection.edges);
+        let ids: Vec<(i32, &str)> = relations
+            .iter()
+            .map(|r| (r.id, r.relation_type.as_str()))
+            .collect();
+        assert_eq!(ids, vec![(2, "SEQUEL"), (4, "PREQUEL")]);
+    }
+
+    #[test]
+    fn test_no_relations_is_empty() {
+        let connection: RelationConnection = serde_json::from_str(r#"{"edges": []}"#).unwrap();
+        assert!(suggested_relations(connection.edges).is_empty());
+    }
+
+    fn notifications_response(unread: usize) -> NotificationsResponse {
+        serde_json::from_str(&format!(
+            r#"{{
+                "Viewer": {{ "unreadNotificationCount": {} }},
+                "Page": {{ "notifications": [
+                    {{
+                        "id": 3, "type": "AIRING", "animeId": 154587, "episode": 5,
+                        "contexts": ["Episode ", " of ", " aired."], "createdAt": 1700000300,
+                        "media": {{ "title": {{ "romaji": "Sousou no Frieren", "english": "Frieren" }} }}
+                    }},
+                    {{}},
+                    {{
+                        "id": 1, "type": "RELATED_MEDIA_ADDITION", "mediaId": 2,
+                        "context": " was recently added to the site.", "createdAt": 1700000000,
+                        "media": {{ "title": {{ "romaji": "Dungeon Meshi" }} }}
+                    }}
+                ]}}
+            }}"#,
+            unread
+        ))
+        .unwrap()
+    }
+
+    #[test]
+    fn test_notifications_are_simplified() {
+        let notifications =
+            collect_notifications(notifications_response(0), false, TitleLanguage::English);
+
+        // The unknown `{}` member is skipped
+        assert_eq!(notifications.len(), 2);
+        assert_eq!(
+            notifications[0],
+            Notification {
+                id: 3,
+                kind: "AIRING".to_string(),
+                media_id: Some(154587),
+                text: "Episode 5 of Frieren aired.".to_string(),
+                created_at: 1700000300,
+            }
+        );
+        assert_eq!(
+            notifications[1].text,
+            "Dungeon Meshi was recently added to the site."
+        );
+    }
+
+    #[test]
+    fn test_unread_only_uses_unread_count() {
+        let unread = collect_notifications(notifications_response(1), true, TitleLanguage::Romaji);
+        assert_eq!(unread.len(), 1);
+        assert_eq!(unread[0].text, "Episode 5 of Sousou no Frieren aired.");
+
+        assert!(
+            collect_notifications(notifications_response(0), true, TitleLanguage::Romaji)
+                .is_empty()
+        );
+    }
+
+    #[test]
+    fn test_search_excludes_adult_by_default() {
+        assert_eq!(
+            search_variables("Frieren", 5, false, None),
+            json!({ "search": "Frieren", "perPage": 5, "isAdult": false })
+        );
+        assert_eq!(
+            search_variables("Frieren", 5, true, None),
+            json!({ "search": "Frieren", "perPage": 5 })
+        );
+    }
+
+    #[test]
+    fn test_search_format_variable() {
+        assert_eq!(
+            search_variables("Kimi no Na wa", 5, false, Some(MediaFormat::Movie))["format"],
+            "MOVIE"
+        );
+        assert_eq!(json!(MediaFormat::TvShort), "TV_SHORT");
+    }
+
+    #[test]
+    fn test_search_queries_transliterate_diacritics() {
+        assert_eq!(
+            title_search_queries("Shōjo Kageki Revue Starlight"),
+            vec![
+                "Shōjo Kageki Revue Starlight",
+                "Shojo Kageki Revue Starlight"
+            ]
+        );
+        assert_eq!(
+            title_search_queries("Pokémon Horizons"),
+            vec!["Pokémon Horizons", "Pokemon Horizons"]
+        );
+    }
+
+    #[test]
+    fn test_search_queries_plain_title_searched_once() {
+        assert_eq!(
+            title_search_queries("Sousou no Frieren"),
+            vec!["Sousou no Frieren"]
+        );
+    }
+
+    #[test]
+    fn test_trailing_trim_queries() {
+        assert_eq!(
+            trailing_trim_queries("Sousou no Frieren 2nd Season"),
+            vec![
+                "Sousou no Frieren 2nd",
+                "Sousou no Frieren",
+                "Sousou no",
+                "Sousou"
+            ]
+        );
+        assert!(trailing_trim_queries("Frieren").is_empty());
+        assert!(trailing_trim_queries("").is_empty());
+    }
+
+    fn frieren_json() -> serde_json::Value {
+        json!({
+            "id": 154587,
+            "idMal": 52991,
+            "title": { "romaji": "Sousou no Frieren", "english": "Frieren: Beyond Journey's End" },
+            "coverImage": { "large": "https://img.example/frieren.jpg" },
+            "episodes": 28,
+            "status": "FINISHED",
+            "isAdult": false
+        })
+    }
+
+    fn not_found_json() -> serde_json::Value {
+        json!({ "data": { "Media": null }, "errors": [{ "message": "Not Found.", "status": 404 }] })
+    }
+
+    #[tokio::test]
+    async fn test_mock_search_anime() {
+        let api = MockApi::start().await;
+        api.respond(
+            json!({ "search": "frieren", "perPage": 5, "isAdult": false }),
+            200,
+            json!({ "data": { "Page": { "media": [frieren_json()] } } }),
+        )
+        .await;
+
+        let results = search_anime("frieren", 5, false, None).await.unwrap();
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].id, 154587);
+        assert_eq!(results[0].id_mal, Some(52991));
+        assert_eq!(results[0].episodes, Some(28));
+    }
+
+    #[tokio::test]
+    async fn test_mock_search_format_narrows_results() {
+        let api = MockApi::start().await;
+        let movie = json!({
+            "id": 21519,
+            "title": { "romaji": "Kimi no Na wa." },
+            "coverImage": {}
+        });
+        let series = json!({
+            "id": 99999,
+            "title": { "romaji": "Kimi no Na wa. (TV)" },
+            "coverImage": {}
+        });
+        api.respond(
+            json!({ "search": "Kimi no Na wa", "format": "MOVIE" }),
+            200,
+            json!({ "data": { "Page": { "media": [movie] } } }),
+        )
+        .await;
+        api.respond(
+            json!({ "search": "Kimi no Na wa" }),
+            200,
+            json!({ "data": { "Page": { "media": [series, movie] } } }),
+        )
+        .await;
+
+        let movies = search_anime("Kimi no Na wa", 5, false, Some(MediaFormat::Movie))
+            .await
+            .unwrap();
+        assert_eq!(movies.len(), 1);
+        assert_eq!(movies[0].id, 21519);
+
+        let all = search_anime("Kimi no Na wa", 5, false, None).await.unwrap();
+        assert_eq!(all.len(), 2);
+    }
+
+    #[test]
+    fn test_parse_season() {
+        assert_eq!(MediaSeason::parse("winter"), Ok(MediaSeason::Winter));
+        assert_eq!(MediaSeason::parse(" FALL "), Ok(MediaSeason::Fall));
+        assert!(MediaSeason::parse("AUTUMN").unwrap_err().contains("AUTUMN"));
+        assert_eq!(json!(MediaSeason::Summer), "SUMMER");
+    }
+
+    #[test]
+    fn test_season_of_month() {
+        assert_eq!(MediaSeason::of_month(2024, 1), (MediaSeason::Winter, 2024));
+        assert_eq!(MediaSeason::of_month(2024, 4), (MediaSeason::Spring, 2024));
+        assert_eq!(MediaSeason::of_month(2024, 8), (MediaSeason::Summer, 2024));
+        assert_eq!(MediaSeason::of_month(2024, 11), (MediaSeason::Fall, 2024));
+        // December already belongs to next year's winter
+        assert_eq!(MediaSeason::of_month(2024, 12), (MediaSeason::Winter, 2025));
+    }
+
+    #[test]
+    fn test_year_month_from_days() {
+        assert_eq!(year_month_from_days(0), (1970, 1));
+        // 2024-02-29
+        assert_eq!(year_month_from_days(19_782), (2024, 2));
+        // 2024-12-31
+        assert_eq!(year_month_from_days(20_088), (2024, 12));
+    }
+
+    #[tokio::test]
+    async fn test_mock_get_trending() {
+        let api = MockApi::start().await;
+        api.respond(
+            json!({ "page": 2, "perPage": 50 }),
+            200,
+            json!({ "data": { "Page": { "media": [frieren_json()] } } }),
+        )
+        .await;
+
+        let trending = get_trending(2, 500).await.unwrap();
+        assert_eq!(trending.len(), 1);
+        assert_eq!(trending[0].id, 154587);
+    }
+
+    #[tokio::test]
+    async fn test_mock_get_popular_season() {
+        let api = MockApi::start().await;
+        api.respond(
+            json!({ "season": "FALL", "seasonYear": 2023 }),
+            200,
+            json!({ "data": { "Page": { "media": [frieren_json()] } } }),
+        )
+        .await;
+
+        let popular = get_popular_season(Some(MediaSeason::Fall), Some(2023), false)
+            .await
+            .unwrap();
+        assert_eq!(popular[0].id, 154587);
+    }
+
+    fn media_page_json(id: i32, has_next_page: bool) -> serde_json::Value {
+        json!({ "data": { "Page": {
+            "pageInfo": { "hasNextPage": has_next_page },
+            "media": [{ "id": id, "title": { "romaji": "Anime" }, "coverImage": {} }]
+        } } })
+    }
+
+    #[tokio::test]
+    async fn test_mock_fetch_all_pages_stops_on_last_page() {
+        let api = MockApi::start().await;
+        api.respond(
+            json!({ "page": 1, "perPage": 50 }),
+            200,
+            media_page_json(1, true),
+        )
+        .await;
+        api.respond(
+            json!({ "page": 2, "perPage": 50 }),
+            200,
+            media_page_json(2, false),
+        )
+        .await;
+
+        let season = get_popular_season(Some(MediaSeason::Fall), Some(2023), true)
+            .await
+            .unwrap();
+        let ids: Vec<i32> = season.iter().map(|anime| anime.id).collect();
+        assert_eq!(ids, vec![1, 2]);
+    }
+
+    #[tokio::test]
+    async fn test_mock_fetch_all_pages_has_page_limit() {
+        let api = MockApi::start().await;
+        Mock::given(method("POST"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(media_page_json(1, true)))
+            .expect(MAX_PAGES as u64)
+            .mount(&api.server)
+            .await;
+
+        let anime = fetch_all_pages("query", json!({}), |page: PageData| page.media)
+            .await
+            .unwrap();
+        assert_eq!(anime.len(), MAX_PAGES as usize);
+    }
+
+    #[tokio::test]
+    async fn test_mock_fetch_all_pages_reports_http_status() {
+        let api = MockApi::start().await;
+        api.respond(json!({ "page": 1 }), 500, json!({})).await;
+
+        let error = fetch_all_pages("query", json!({}), |page: PageData| page.media)
+            .await
+            .unwrap_err();
+        assert!(error.contains("(HTTP 500)"), "{}", error);
+    }
+
+    #[tokio::test]
+    async fn test_mock_get_media_list_has_chunk_limit() {
+        let api = MockApi::start().await;
+        Mock::given(method("POST"))
+            .and(body_string_contains("Viewer"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                "data": { "Viewer": { "id": 1, "name": "user" } }
+            })))
+            .mount(&api.server)
+            .await;
+        Mock::given(method("POST"))
+            .and(body_string_contains("MediaListCollection"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                "data": { "MediaListCollection": {
+                    "hasNextChunk": true,
+                    "lists": [{ "entries": [] }]
+                } }
+            })))
+            .expect(MAX_MEDIA_LIST_CHUNKS as u64)
+            .mount(&api.server)
+            .await;
+
+        assert!(get_media_list("token").await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_mock_get_anilist_user() {
+        let api = MockApi::start().await;
+        Mock::given(method("POST"))
+            .and(bearer_token("token"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                "data": { "Viewer": {
+                    "id": 42,
+                    "name": "frieren_fan",
+                    "avatar": { "large": null, "medium": "https://img.example/medium.png" }
+                } }
+            })))
+            .expect(1)
+            .mount(&api.server)
+            .await;
+
+        let user = get_anilist_user("token").await.unwrap();
+        assert_eq!(
+            user,
+            AniListUser {
+                id: 42,
+                name: "frieren_fan".to_string(),
+                avatar: Some("https://img.example/medium.png".to_string()),
+            }
+        );
+    }
+
+    #[tokio::test]
+    async fn test_mock_get_anilist_user_without_avatar() {
+        let api = MockApi::start().await;
+        Mock::given(method("POST"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                "data": { "Viewer": { "id": 42, "name": "frieren_fan", "avatar": null } }
+            })))
+            .mount(&api.server)
+            .await;
+
+        let user = get_anilist_user("token").await.unwrap();
+        assert_eq!(user.avatar, None);
+    }
+
+    #[tokio::test]
+    async fn test_mock_search_anime_error() {
+        let api = MockApi::start().await;
+        api.respond(
+            json!({ "search": "frieren" }),
+            400,
+            json!({ "data": null, "errors": [{ "message": "Invalid search", "status": 400 }] }),
+        )
+        .await;
+
+        let err = search_anime("frieren", 5, false, None).await.unwrap_err();
+        assert!(err.contains("Invalid search"));
+    }
+
+    #[tokio::test]
+    async fn test_mock_get_anime_by_id() {
+        let api = MockApi::start().await;
+        api.respond(
+            json!({ "id": 154587 }),
+            200,
+            json!({ "data": { "Media": frieren_json() } }),
+        )
+        .await;
+
+        let anime = get_anime_by_id(154587).await.unwrap();
+        assert_eq!(anime.title.romaji.as_deref(), Some("Sousou no Frieren"));
+        assert_eq!(
+            anime.cover_image.large.as_deref(),
+            Some("https://img.example/frieren.jpg")
+        );
+    }
+
+    #[tokio::test]
+    async fn test_mock_get_anime_by_id_not_found() {
+        let api = MockApi::start().await;
+        api.respond(json!({ "id": 1 }), 404, not_found_json()).await;
+
+        let err = get_anime_by_id(1).await.unwrap_err();
+        assert_eq!(err, AniListError::NotFound.to_string());
+    }
+
+    #[tokio::test]
+    async fn test_mock_update_media_progress() {
+        let api = MockApi::start().await;
+        Mock::given(method("POST"))
+            .and(header("Authorization", "Bearer token"))
+            .and(body_partial_json(json!({
+                "variables": { "mediaId": 154587, "progress": 5, "status": "CURRENT" }
+            })))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                "data": { "SaveMediaListEntry": { "id": 9, "progress": 5, "status": "CURRENT" } }
+            })))
+            .expect(1)
+            .mount(&api.server)
+            .await;
+
+        let entry = update_media_progress("token", 154587, 5, Some("CURRENT"), None)
+            .await
+            .unwrap();
+        assert_eq!(entry.progress, 5);
+        assert_eq!(entry.status, "CURRENT");
+    }
+
+    #[tokio::test]
+    async fn test_mock_update_media_progress_repeating() {
+        let api = MockApi::start().await;
+        api.respond(
+            json!({ "mediaId": 154587, "progress": 3, "status": "REPEATING", "repeat": 2 }),
+            200,
+            json!({
+                "data": { "SaveMediaListEntry": {
+                    "id": 9, "progress": 3, "status": "REPEATING", "repeat": 2
+                } }
+            }),
+        )
+        .await;
+
+        let entry = update_media_progress("token", 154587, 3, Some("REPEATING"), Some(2))
+            .await
+            .unwrap();
+        assert_eq!(entry.status, "REPEATING");
+        assert_eq!(entry.repeat, Some(2));
+
+        let err = update_media_progress("token", 154587, 3, Some("REPEATING"), Some(-1))
+            .await
+            .unwrap_err();
+        assert!(err.contains("rewatch count"));
+    }
+
+    #[tokio::test]
+    async fn test_mock_update_media_progress_unauthorized() {
+        let api = MockApi::start().await;
+        api.respond(
+            json!({ "mediaId": 154587, "progress": 5 }),
+            401,
+            json!({ "errors": [{ "message": "Invalid token", "status": 401 }] }),
+        )
+        .await;
+
+        let err = update_media_progress("expired", 154587, 5, None, None)
+            .await
+            .unwrap_err();
+        assert!(err.starts_with("Update failed:"));
+        assert!(err.contains("Invalid token"));
+    }
+
+    #[tokio::test]
+    async fn test_match_from_title_trims_trailing_words() {
+        let api = MockApi::start().await;
+        api.respond(
+            json!({ "search": "Sousou no Frieren" }),
+            200,
+            json!({ "data": { "Page": { "media": [frieren_json()] } } }),
+        )
+        .await;
+        // Anything else finds nothing
+        Mock::given(method("POST"))
+            .respond_with(
+                ResponseTemplate::new(200)
+                    .set_body_json(json!({ "data": { "Page": { "media": [] } } })),
+            )
+            .expect(2)
+            .mount(&api.server)
+            .await;
+
+        let anime = match_anime_from_title("Sousou no Frieren Season 2 - VLC media player")
+            .await
+            .unwrap();
+        assert_eq!(anime.map(|a| a.id), Some(154587));
+    }
 }
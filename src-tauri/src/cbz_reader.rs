@@ -7,6 +7,7 @@ use base64::{engine::general_purpose::STANDARD as BASE64, Engine as _};
  */
 use std::fs::File;
 use std::io::Read;
+use std::path::{Component, Path, PathBuf};
 use zip::ZipArchive;
 
 /// Information about pages in a CBZ file
@@ -126,3 +127,102 @@ pub fn read_cbz_page_bytes(path: &str, page_name: &str) -> Result<(Vec<u8>, Stri
 
     Ok((buffer, mime.to_string()))
 }
+
+/// Check a zip entry name and return it as a relative path
+///
+/// Guards against "zip-slip": entries like `../../etc/passwd`, absolute paths or
+/// drive-prefixed Windows paths are rejected. Backslashes are treated as
+/// separators since some Windows tools write them. Only the name is looked at,
+/// nothing on disk.
+///
+/// # Returns
+/// * `Ok(path)` - The entry name as a relative path of normal components
+/// * `Err` - If the entry name is empty or would escape the folder it's extracted to
+pub fn safe_entry_name(entry_name: &str) -> Result<PathBuf, String> {
+    let normalized = entry_name.replace('\\', "/");
+    let mut relative = PathBuf::new();
+
+    for component in Path::new(&normalized).components() {
+        match component {
+            Component::Normal(part) => {
+                // A drive prefix ("C:") only parses as a component on Windows
+                if part.to_string_lossy().contains(':') {
+                    return Err(format!("Unsafe entry name in archive: {}", entry_name));
+                }
+                relative.push(part);
+            }
+            Component::CurDir => {}
+            Component::ParentDir | Component::RootDir | Component::Prefix(_) => {
+                return Err(format!("Unsafe entry name in archive: {}", entry_name));
+            }
+        }
+    }
+
+    if relative.as_os_str().is_empty() {
+        return Err(format!("Empty entry name in archive: {:?}", entry_name));
+    }
+
+    Ok(relative)
+}
+
+/// Resolve a zip entry name to a path inside `base`, rejecting names that escape it
+///
+/// The name is checked with `safe_entry_name`.
+///
+/// # Returns
+/// * `Ok(path)` - `base` (canonicalized when it exists) joined with the entry name
+/// * `Err` - If the entry name is empty or would escape `base`
+pub fn safe_zip_path(base: &Path, entry_name: &str) -> Result<PathBuf, String> {
+    let relative = safe_entry_name(entry_name)?;
+    let base = base.canonicalize().unwrap_or_else(|_| base.to_path_buf());
+    let path = base.join(relative);
+
+    // Only normal components were joined, so this holds unless `base` itself is odd
+    if !path.starts_with(&base) {
+        return Err(format!("Unsafe entry name in archive: {}", entry_name));
+    }
+
+    Ok(path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_safe_entry_name() {
+        assert_eq!(
+            safe_entry_name("./chapter/002.png"),
+            Ok(Path::new("chapter").join("002.png"))
+        );
+        assert!(safe_entry_name("../../evil.jpg").is_err());
+        assert!(safe_entry_name("/etc/passwd").is_err());
+        assert!(safe_entry_name("").is_err());
+    }
+
+    #[test]
+    fn test_safe_zip_path_accepts_pages() {
+        let base = Path::new("/nonexistent/playon/pages");
+        assert_eq!(safe_zip_path(base, "001.jpg"), Ok(base.join("001.jpg")));
+        assert_eq!(
+            safe_zip_path(base, "./chapter/002.png"),
+            Ok(base.join("chapter").join("002.png"))
+        );
+    }
+
+    #[test]
+    fn test_safe_zip_path_rejects_traversal() {
+        let base = Path::new("/nonexistent/playon/pages");
+        for name in [
+            "../../etc/passwd",
+            "pages/../../etc/passwd",
+            "..\\..\\Windows\\win.ini",
+            "/etc/passwd",
+            "C:\\Windows\\win.ini",
+            "",
+            ".",
+        ] {
+            assert!(safe_zip_path(base, name).is_err(), "accepted {:?}", name);
+        }
+    }
+}
@@ -0,0 +1,273 @@
+//! App Configuration
+//!
+//! PURPOSE: One place for user preferences instead of each feature storing its own
+//! Settings live in `config.json` in the app config dir and are kept in memory
+//! after loading; modules read them through `current()`.
+//!
+//! Missing fields fall back to their defaults so older config files keep working.
+//! A file that can't be parsed is backed up to `config.json.bak` and replaced
+//! with defaults rather than blocking startup.
+
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::RwLock;
+use tracing::{info, warn};
+
+use crate::anilist;
+use crate::detector::IgnorePattern;
+use crate::jellyfin::JellyfinSettings;
+use crate::matcher;
+use crate::mpv_ipc;
+use crate::myanimelist;
+use crate::now_playing::NowPlayingSources;
+use crate::title_parser::ParserOptions;
+use crate::vlc_http::VlcHttpSettings;
+
+const CONFIG_FILE: &str = "config.json";
+const BACKUP_FILE: &str = "config.json.bak";
+
+/// Which AniList title variant to show
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum TitleLanguage {
+    #[default]
+    English,
+    Romaji,
+    Native,
+}
+
+/// User preferences
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(default)]
+pub struct AppConfig {
+    /// Seconds an episode must keep playing before it counts as watched
+    pub dwell_time_secs: u64,
+    pub title_language: TitleLanguage,
+    /// Chapters downloaded at the same time by the download queue
+    pub download_concurrency: usize,
+    /// Trackers to sync with ("anilist", "mal")
+    pub enabled_providers: Vec<String>,
+    /// Free space (MB) downloads must leave on the target volume
+    pub min_free_space_mb: u64,
+    /// Window title parsing options
+    pub parser: ParserOptions,
+    /// Mark entries completed when the final episode is scrobbled
+    pub auto_complete: bool,
+    /// Add shows that aren't on the list yet as watching
+    pub auto_start: bool,
+    /// Executables whose windows are scanned for playing media (Windows)
+    pub player_processes: Vec<String>,
+    /// Polls in a row without media before detection reports idle
+    pub idle_debounce_polls: usize,
+    /// Milliseconds between detection polls while media is playing
+    pub poll_interval_ms: u64,
+    /// Longest the poll interval backs off to while nothing is playing
+    pub max_poll_interval_ms: u64,
+    /// Auto-scrobbles with a lower match confidence (0.0 - 1.0) are skipped
+    pub min_confidence: f64,
+    /// mpv `--input-ipc-server` socket (named pipe on Windows) queried before window titles
+    pub mpv_ipc_path: String,
+    /// Window titles that are never detected
+    pub ignored_patterns: Vec<IgnorePattern>,
+    /// When signed in, match detections against the user's list before searching
+    pub prefer_list_entries: bool,
+    /// AniList GraphQL endpoint override (a proxy or local mock), None for the public API
+    pub anilist_api_url: Option<String>,
+    /// MAL REST API base override, None for api.myanimelist.net
+    pub mal_api_url: Option<String>,
+    /// MAL OAuth base override (token endpoint), None for myanimelist.net
+    pub mal_auth_url: Option<String>,
+    /// Sources the now-playing command consults
+    pub now_playing_sources: NowPlayingSources,
+    /// VLC HTTP interface, None when it isn't set up
+    pub vlc_http: Option<VlcHttpSettings>,
+    /// Jellyfin server, None when it isn't set up
+    pub jellyfin: Option<JellyfinSettings>,
+}
+
+impl Default for AppConfig {
+    fn default() -> Self {
+        Self {
+            dwell_time_secs: 120,
+            title_language: TitleLanguage::default(),
+            download_concurrency: 2,
+            enabled_providers: vec!["anilist".to_string(), "mal".to_string()],
+            min_free_space_mb: 200,
+            parser: ParserOptions::default(),
+            auto_complete: true,
+            auto_start: true,
+            player_processes: [
+                "vlc.exe",
+                "mpv.exe",
+                "mpc-hc.exe",
+                "mpc-hc64.exe",
+                "chrome.exe",
+                "firefox.exe",
+                "msedge.exe",
+            ]
+            .iter()
+            .map(|p| p.to_string())
+            .collect(),
+            idle_debounce_polls: 3,
+            poll_interval_ms: 3000,
+            max_poll_interval_ms: 30000,
+            min_confidence: matcher::MIN_CONFIDENCE,
+            mpv_ipc_path: mpv_ipc::DEFAULT_IPC_PATH.to_string(),
+            ignored_patterns: Vec::new(),
+            prefer_list_entries: true,
+            anilist_api_url: None,
+            mal_api_url: None,
+            mal_auth_url: None,
+            now_playing_sources: NowPlayingSources::default(),
+            vlc_http: None,
+            jellyfin: None,
+        }
+    }
+}
+
+impl AppConfig {
+    /// Whether syncing with a provider is enabled
+    pub fn provider_enabled(&self, provider: &str) -> bool {
+        self.enabled_providers
+            .iter()
+            .any(|p| p.eq_ignore_ascii_case(provider))
+    }
+}
+
+lazy_static::lazy_static! {
+    /// Config in use, defaults until `load` is called
+    static ref CONFIG: RwLock<AppConfig> = RwLock::new(AppConfig::default());
+}
+
+/// Get a copy of the config in use
+pub fn current() -> AppConfig {
+    CONFIG.read().map(|c| c.clone()).unwrap_or_default()
+}
+
+fn set_current(config: AppConfig) {
+    anilist::set_api_url(
+        config
+            .anilist_api_url
+            .as_deref()
+            .unwrap_or(anilist::ANILIST_API_URL),
+    );
+    myanimelist::set_base_urls(
+        config
+            .mal_api_url
+            .as_deref()
+            .unwrap_or(myanimelist::MAL_API_URL),
+        config
+            .mal_auth_url
+            .as_deref()
+            .unwrap_or(myanimelist::MAL_AUTH_URL),
+    );
+    if let Ok(mut current) = CONFIG.write() {
+        *current = config;
+    }
+}
+
+fn config_path(config_dir: &Path) -> PathBuf {
+    config_dir.join(CONFIG_FILE)
+}
+
+/// Read the config file, creating it with defaults on first run
+///
+/// A corrupt file is backed up and replaced with defaults.
+pub fn read_config(config_dir: &Path) -> Result<AppConfig, String> {
+    let path = config_path(config_dir);
+
+    let json = match fs::read_to_string(&path) {
+        Ok(json) => json,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+            info!("No config found, creating defaults");
+            let config = AppConfig::default();
+            write_config(config_dir, &config)?;
+            return Ok(config);
+        }
+        Err(e) => return Err(format!("Failed to read config: {}", e)),
+    };
+
+    match serde_json::from_str(&json) {
+        Ok(config) => Ok(config),
+        Err(e) => {
+            warn!("Corrupt config ({}), resetting to defaults", e);
+            fs::rename(&path, config_dir.join(BACKUP_FILE))
+                .map_err(|e| format!("Failed to back up corrupt config: {}", e))?;
+
+            let config = AppConfig::default();
+            write_config(config_dir, &config)?;
+            Ok(config)
+        }
+    }
+}
+
+/// Write the config file
+pub fn write_config(config_dir: &Path, config: &AppConfig) -> Result<(), String> {
+    fs::create_dir_all(config_dir).map_err(|e| format!("Failed to create config dir: {}", e))?;
+
+    let json =
+        serde_json::to_string_pretty(config).map_err(|e| format!("Serialization error: {}", e))?;
+    fs::write(config_path(config_dir), json).map_err(|e| format!("Failed to write config: {}", e))
+}
+
+/// Load the config from disk and make it the config in use
+pub fn load(config_dir: &Path) -> Result<AppConfig, String> {
+    let config = read_config(config_dir)?;
+    set_current(config.clone());
+    Ok(config)
+}
+
+/// Save a config to disk and make it the config in use
+pub fn save(config_dir: &Path, config: AppConfig) -> Result<(), String> {
+    write_config(config_dir, &config)?;
+    set_current(config);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::TempDir;
+
+    #[test]
+    fn test_creates_defaults_on_first_run() {
+        let temp = TempDir::new("config");
+        let dir = temp.join("missing");
+
+        assert_eq!(read_config(&dir).unwrap(), AppConfig::default());
+        assert!(config_path(&dir).exists());
+    }
+
+    #[test]
+    fn test_round_trip_and_partial_file() {
+        let dir = TempDir::new("config_round_trip");
+        let config = AppConfig {
+            dwell_time_secs: 30,
+            title_language: TitleLanguage::Romaji,
+            enabled_providers: vec!["mal".to_string()],
+            ..AppConfig::default()
+        };
+        write_config(&dir, &config).unwrap();
+        assert_eq!(read_config(&dir).unwrap(), config);
+
+        // Fields missing from older files use defaults
+        fs::write(config_path(&dir), r#"{ "dwell_time_secs": 45 }"#).unwrap();
+        let config = read_config(&dir).unwrap();
+        assert_eq!(config.dwell_time_secs, 45);
+        assert_eq!(config.download_concurrency, 2);
+        assert!(config.provider_enabled("AniList"));
+    }
+
+    #[test]
+    fn test_corrupt_config_is_backed_up() {
+        let dir = TempDir::new("config_corrupt");
+        fs::write(config_path(&dir), "{ not json").unwrap();
+
+        assert_eq!(read_config(&dir).unwrap(), AppConfig::default());
+        assert_eq!(
+            fs::read_to_string(dir.join(BACKUP_FILE)).unwrap(),
+            "{ not json"
+        );
+    }
+}
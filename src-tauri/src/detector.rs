@@ -0,0 +1,931 @@
+//! Anime Detection
+//!
+//! PURPOSE: Turn a window title into a parsed title and an AniList match
+//! Finding the window is platform specific and stays with the window code; this
+//! module only needs the title string, so it also serves titles that come from
+//! elsewhere (e.g. a browser extension).
+//!
+//! AniList lookups are cached for a few minutes so polling the same window
+//! doesn't hammer the API. When signed in, a show already on the user's list is
+//! preferred over a fresh search that might pick a remake.
+//!
+//! DEBOUNCE: Alt-tabbing away from a player for a moment shouldn't end the
+//! session, so the last detection is kept until several polls in a row find
+//! nothing (see `Debouncer`).
+//!
+//! IGNORE LIST: Windows that keep getting mis-detected (screen recorders, test
+//! files) can be blocked by substring or regex in the config.
+
+use regex::RegexBuilder;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::future::Future;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use tracing::{info, warn};
+
+use crate::anilist::{self, Anime, AnimeTitle, MediaListItem};
+use crate::config;
+use crate::matcher;
+use crate::title_parser::{self, ParsedTitle};
+
+/// How long an AniList lookup stays cached
+const CACHE_DURATION: Duration = Duration::from_secs(300); // 5 minutes
+
+/// Candidates returned for the UI to choose from
+pub const MAX_CANDIDATES: usize = 5;
+
+/// AniList results scored when collecting candidates
+const CANDIDATE_SEARCH_LIMIT: i32 = 15;
+
+/// Idle polls in a row before the poll interval starts backing off
+const IDLE_POLLS_BEFORE_BACKOFF: usize = 5;
+
+/// Outcome of a detection, serialized with a `status` tag
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "status", rename_all = "snake_case")]
+pub enum DetectionResult {
+    /// Something is playing
+    Detected {
+        /// None when the title didn't come from a detected player
+        player: Option<String>,
+        window_title: String,
+        parsed: ParsedTitle,
+        anilist_match: Option<Box<Anime>>,
+        /// How well `anilist_match` fits the parsed title (0.0 - 1.0)
+        confidence: Option<f64>,
+    },
+    /// A player is open with nothing loaded
+    PlayerIdle { player: String, window: String },
+    /// The active window isn't a media player
+    NotMediaPlayer { window: String },
+    /// No window could be read
+    NoWindow { window: String },
+    /// The window matched the ignore list
+    Ignored { window: String, pattern: String },
+}
+
+/// A window title pattern on the ignore list
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(tag = "kind", content = "pattern", rename_all = "snake_case")]
+pub enum IgnorePattern {
+    /// Case-insensitive substring
+    Substring(String),
+    /// Case-insensitive regular expression
+    Regex(String),
+}
+
+impl IgnorePattern {
+    pub fn new(pattern: String, is_regex: bool) -> Self {
+        if is_regex {
+            Self::Regex(pattern)
+        } else {
+            Self::Substring(pattern)
+        }
+    }
+
+    /// Reject empty patterns and regexes that don't compile
+    pub fn validate(&self) -> Result<(), String> {
+        match self {
+            Self::Substring(text) if text.trim().is_empty() => {
+                Err("Ignore pattern must not be empty".to_string())
+            }
+            Self::Substring(_) => Ok(()),
+            Self::Regex(pattern) => RegexBuilder::new(pattern)
+                .case_insensitive(true)
+                .build()
+                .map(|_| ())
+                .map_err(|e| format!("Invalid regex \"{}\": {}", pattern, e)),
+        }
+    }
+
+    pub fn matches(&self, title: &str) -> bool {
+        match self {
+            Self::Substring(text) => {
+                !text.trim().is_empty() && title.to_lowercase().contains(&text.to_lowercase())
+            }
+            Self::Regex(pattern) => RegexBuilder::new(pattern)
+                .case_insensitive(true)
+                .build()
+                .is_ok_and(|re| re.is_match(title)),
+        }
+    }
+
+    pub fn as_str(&self) -> &str {
+        match self {
+            Self::Substring(text) | Self::Regex(text) => text,
+        }
+    }
+}
+
+/// First ignore pattern matching the title, if any
+pub fn ignored_by<'a>(title: &str, patterns: &'a [IgnorePattern]) -> Option<&'a IgnorePattern> {
+    patterns.iter().find(|pattern| pattern.matches(title))
+}
+
+/// A possible AniList match for the UI to confirm
+#[derive(Debug, Clone, Serialize)]
+pub struct Candidate {
+    pub id: i32,
+    pub id_mal: Option<i64>,
+    pub title: AnimeTitle,
+    pub cover: Option<String>,
+    pub episodes: Option<i32>,
+    /// Similarity of the best title to the query (0.0 - 1.0)
+    pub confidence: f64,
+}
+
+impl Candidate {
+    fn scored(query: &str, anime: Anime) -> Self {
+        Self {
+            confidence: matcher::score_anime(query, &anime),
+            id: anime.id,
+            id_mal: anime.id_mal,
+            cover: anime.cover_image.large.or(anime.cover_image.medium),
+            episodes: anime.episodes,
+            title: anime.title,
+        }
+    }
+}
+
+/// Smooths detection results across polls
+///
+/// A miss (anything but `Detected`) only replaces the last detection after
+/// `threshold` consecutive misses; until then the last detection is repeated.
+#[derive(Debug, Default)]
+pub struct Debouncer {
+    misses: usize,
+    last_detected: Option<DetectionResult>,
+}
+
+impl Debouncer {
+    /// Feed a poll result and get the result to report
+    pub fn update(&mut self, result: DetectionResult, threshold: usize) -> DetectionResult {
+        if matches!(result, DetectionResult::Detected { .. }) {
+            self.misses = 0;
+            self.last_detected = Some(result.clone());
+            return result;
+        }
+
+        self.misses += 1;
+        match &self.last_detected {
+            Some(last) if self.misses < threshold => last.clone(),
+            _ => {
+                self.last_detected = None;
+                result
+            }
+        }
+    }
+}
+
+/// Slows polling down while nothing is playing
+///
+/// After `IDLE_POLLS_BEFORE_BACKOFF` idle polls in a row, every further idle
+/// poll doubles the interval, up to the maximum. A detection goes straight
+/// back to the base interval.
+#[derive(Debug, Default)]
+pub struct PollBackoff {
+    idle_polls: usize,
+}
+
+impl PollBackoff {
+    /// Feed a poll result (whether media was detected)
+    pub fn update(&mut self, detected: bool) {
+        if detected {
+            self.idle_polls = 0;
+        } else {
+            self.idle_polls = self.idle_polls.saturating_add(1);
+        }
+    }
+
+    /// Interval to wait before the next poll
+    pub fn interval(&self, base: Duration, max: Duration) -> Duration {
+        if self.idle_polls < IDLE_POLLS_BEFORE_BACKOFF {
+            return base;
+        }
+
+        // Capped so the shift can't overflow; max is reached long before
+        let doublings = (self.idle_polls - IDLE_POLLS_BEFORE_BACKOFF + 1).min(16) as u32;
+        base.saturating_mul(1 << doublings).min(max.max(base))
+    }
+}
+
+/// What the user said they're watching, used instead of detection until cleared
+///
+/// For players detection can't read (DRM browsers hide the title).
+#[derive(Debug, Clone)]
+pub struct ManualTracking {
+    pub anime: Anime,
+    pub episode: i32,
+}
+
+impl ManualTracking {
+    /// Reject episodes outside the entry
+    pub fn new(anime: Anime, episode: i32) -> Result<Self, String> {
+        if episode < 1 {
+            return Err(format!("Invalid episode number: {}", episode));
+        }
+        if let Some(total) = anime
+            .episodes
+            .filter(|total| *total > 0 && episode > *total)
+        {
+            return Err(format!(
+                "Episode {} is past the last episode ({})",
+                episode, total
+            ));
+        }
+
+        Ok(Self { anime, episode })
+    }
+
+    /// Report the manual entry as a detection
+    ///
+    /// The confidence is left out since the user picked the entry, so
+    /// scrobbling never skips it as ambiguous.
+    pub fn detection(&self) -> DetectionResult {
+        let title = self.anime.title.preferred(config::current().title_language);
+
+        DetectionResult::Detected {
+            player: None,
+            window_title: title.clone(),
+            parsed: ParsedTitle {
+                title: Some(title),
+                episode: Some(self.episode),
+                episode_end: None,
+                season: None,
+                language: None,
+                confidence: 1.0,
+            },
+            anilist_match: Some(Box::new(self.anime.clone())),
+            confidence: None,
+        }
+    }
+}
+
+/// Detect what's playing unless manual tracking is set
+///
+/// `detect` isn't run at all while `manual` is set.
+pub async fn detect_or_manual<F, Fut>(manual: Option<ManualTracking>, detect: F) -> DetectionResult
+where
+    F: FnOnce() -> Fut,
+    Fut: Future<Output = DetectionResult>,
+{
+    match manual {
+        Some(manual) => manual.detection(),
+        None => detect().await,
+    }
+}
+
+struct CacheEntry {
+    anime: Option<Anime>,
+    timestamp: Instant,
+}
+
+/// The user's list, cached per token
+struct ListCacheEntry {
+    access_token: String,
+    list: Vec<MediaListItem>,
+    timestamp: Instant,
+}
+
+lazy_static::lazy_static! {
+    static ref ANILIST_CACHE: Mutex<HashMap<String, CacheEntry>> = Mutex::new(HashMap::new());
+    static ref LIST_CACHE: Mutex<Option<ListCacheEntry>> = Mutex::new(None);
+    /// Debounce state for window polling, per poller
+    static ref DEBOUNCERS: Mutex<HashMap<String, Debouncer>> = Mutex::new(HashMap::new());
+    /// Idle backoff state for window polling, per poller
+    static ref POLL_BACKOFFS: Mutex<HashMap<String, PollBackoff>> = Mutex::new(HashMap::new());
+    /// Manual override, None when detection is used
+    static ref MANUAL_TRACKING: Mutex<Option<ManualTracking>> = Mutex::new(None);
+}
+
+/// Track `manual` instead of detecting until cleared
+pub fn set_manual_tracking(manual: ManualTracking) {
+    if let Ok(mut current) = MANUAL_TRACKING.lock() {
+        info!(
+            "Manual tracking: {} episode {}",
+            manual.anime.id, manual.episode
+        );
+        *current = Some(manual);
+    }
+}
+
+/// Go back to detecting what's playing
+pub fn clear_manual_tracking() {
+    if let Ok(mut current) = MANUAL_TRACKING.lock() {
+        *current = None;
+    }
+}
+
+/// The manual override, if set
+pub fn manual_tracking() -> Option<ManualTracking> {
+    MANUAL_TRACKING
+        .lock()
+        .ok()
+        .and_then(|current| current.clone())
+}
+
+/// Poller name used when a caller doesn't give one
+pub const DEFAULT_POLLER: &str = "default";
+
+/// Run a window poll result through the poller's debouncer
+///
+/// Each poller (a UI polling on its own timer) has separate state, so two
+/// of them polling at once don't count each other's misses.
+pub fn debounce(poller: &str, result: DetectionResult, threshold: usize) -> DetectionResult {
+    match DEBOUNCERS.lock() {
+        Ok(mut debouncers) => debouncers
+            .entry(poller.to_string())
+            .or_default()
+            .update(result, threshold),
+        Err(_) => result,
+    }
+}
+
+/// Record whether the poller's window poll found media, for its idle backoff
+pub fn record_poll(poller: &str, result: &DetectionResult) {
+    if let Ok(mut backoffs) = POLL_BACKOFFS.lock() {
+        backoffs
+            .entry(poller.to_string())
+            .or_default()
+            .update(matches!(result, DetectionResult::Detected { .. }));
+    }
+}
+
+/// Interval the poller should wait before its next window poll
+pub fn poll_interval(poller: &str, base: Duration, max: Duration) -> Duration {
+    match POLL_BACKOFFS.lock() {
+        Ok(backoffs) => backoffs
+            .get(poller)
+            .map_or(base, |backoff| backoff.interval(base, max)),
+        Err(_) => base,
+    }
+}
+
+fn get_cached_anime(title: &str) -> Option<Option<Anime>> {
+    let cache = ANILIST_CACHE.lock().ok()?;
+    if let Some(entry) = cache.get(title) {
+        if entry.timestamp.elapsed() < CACHE_DURATION {
+            return Some(entry.anime.clone());
+        }
+    }
+    None
+}
+
+fn set_cached_anime(title: String, anime: Option<Anime>) {
+    if let Ok(mut cache) = ANILIST_CACHE.lock() {
+        cache.insert(
+            title,
+            CacheEntry {
+                anime,
+                timestamp: Instant::now(),
+            },
+        );
+    }
+}
+
+/// Search AniList for the top result, using the cache when possible
+pub async fn search_with_cache(query: String) -> Option<Anime> {
+    if let Some(cached) = get_cached_anime(&query) {
+        info!("Cache hit for: {}", query);
+        return cached;
+    }
+
+    info!("Cache miss, searching AniList for: {}", query);
+    let result = match anilist::search_anime(&query, 1, false, None).await {
+        Ok(results) => results.into_iter().next(),
+        Err(e) => {
+            warn!("AniList search error: {}", e);
+            None
+        }
+    };
+
+    // Cache the result (even if None)
+    set_cached_anime(query, result.clone());
+    result
+}
+
+/// Get the user's list, refetching once `CACHE_DURATION` has passed
+///
+/// Errors are logged and give an empty list, so detection falls back to searching.
+async fn cached_media_list(access_token: &str) -> Vec<MediaListItem> {
+    if let Ok(cache) = LIST_CACHE.lock() {
+        if let Some(entry) = cache.as_ref() {
+            if entry.access_token == access_token && entry.timestamp.elapsed() < CACHE_DURATION {
+                return entry.list.clone();
+            }
+        }
+    }
+
+    let list = match anilist::get_media_list(access_token).await {
+        Ok(list) => list,
+        Err(e) => {
+            warn!("Failed to get list for matching: {}", e);
+            return Vec::new();
+        }
+    };
+    if let Ok(mut cache) = LIST_CACHE.lock() {
+        *cache = Some(ListCacheEntry {
+            access_token: access_token.to_string(),
+            list: list.clone(),
+            timestamp: Instant::now(),
+        });
+    }
+    list
+}
+
+/// Look up the list entry matching `query`, if there is one
+///
+/// # Arguments
+/// * `get_anime` - Fetches the full entry by id, `anilist::get_anime_by_id` outside tests
+pub async fn match_list_entry<F, Fut>(
+    query: &str,
+    list: &[MediaListItem],
+    get_anime: F,
+) -> Option<Anime>
+where
+    F: FnOnce(i32) -> Fut,
+    Fut: Future<Output = Result<Anime, String>>,
+{
+    let item = matcher::best_list_entry(query, list)?;
+    info!("\"{}\" is on the user's list as {}", query, item.media_id);
+    match get_anime(item.media_id).await {
+        Ok(anime) => Some(anime),
+        Err(e) => {
+            warn!("Failed to get list entry {}: {}", item.media_id, e);
+            None
+        }
+    }
+}
+
+/// Search for a detected title, preferring the user's list when signed in
+///
+/// The list is only consulted with a token and `prefer_list_entries` enabled.
+pub async fn search_for_user(query: String, access_token: Option<&str>) -> Option<Anime> {
+    if let Some(token) = access_token.filter(|_| config::current().prefer_list_entries) {
+        let list = cached_media_list(token).await;
+        if let Some(anime) = match_list_entry(&query, &list, anilist::get_anime_by_id).await {
+            return Some(anime);
+        }
+    }
+    search_with_cache(query).await
+}
+
+/// Parse a title and look it up with `search`
+///
+/// # Arguments
+/// * `player` - Player the title came from, if any
+/// * `window_title` - Raw window (or page) title
+/// * `search` - Lookup for the built search query
+pub async fn match_title<F, Fut>(
+    player: Option<String>,
+    window_title: &str,
+    search: F,
+) -> DetectionResult
+where
+    F: FnOnce(String) -> Fut,
+    Fut: Future<Output = Option<Anime>>,
+{
+    let parsed = title_parser::parse_window_title(window_title);
+    info!(
+        "Parsed result: title={:?}, episode={:?}",
+        parsed.title, parsed.episode
+    );
+
+    // Titles that parse to nothing aren't worth an API call
+    let query = title_parser::build_search_query(&parsed).filter(|q| !q.trim().is_empty());
+    let (anilist_match, confidence) = match query {
+        Some(query) => {
+            let anime = search(query.clone()).await;
+            let confidence = anime.as_ref().map(|a| matcher::score_anime(&query, a));
+            (anime.map(Box::new), confidence)
+        }
+        None => (None, None),
+    };
+    info!(
+        "AniList match found: {} (confidence {:?})",
+        anilist_match.is_some(),
+        confidence
+    );
+
+    DetectionResult::Detected {
+        player,
+        window_title: window_title.to_string(),
+        parsed,
+        anilist_match,
+        confidence,
+    }
+}
+
+/// Detect anime from a title string without looking at any window
+pub async fn detect_from_title(title: &str) -> DetectionResult {
+    match_title(None, title, search_with_cache).await
+}
+
+/// Score search results against the query, best first, at most `MAX_CANDIDATES`
+///
+/// Ties keep AniList's order.
+pub fn rank_candidates(query: &str, results: Vec<Anime>) -> Vec<Candidate> {
+    let mut candidates: Vec<Candidate> = results
+        .into_iter()
+        .map(|anime| Candidate::scored(query, anime))
+        .collect();
+    candidates.sort_by(|a, b| b.confidence.total_cmp(&a.confidence));
+    candidates.truncate(MAX_CANDIDATES);
+    candidates
+}
+
+/// Search AniList for a parsed title and return ranked candidates
+///
+/// Unlike `match_title` nothing is picked; the UI shows the candidates and
+/// updates the one the user chooses.
+pub async fn find_candidates(parsed: &ParsedTitle) -> Result<Vec<Candidate>, String> {
+    let Some(query) = title_parser::build_search_query(parsed).filter(|q| !q.trim().is_empty())
+    else {
+        return Ok(Vec::new());
+    };
+
+    let results = anilist::search_anime(&query, CANDIDATE_SEARCH_LIMIT, false, None).await?;
+    Ok(rank_candidates(&query, results))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn anime(id: i32, romaji: &str) -> Anime {
+        serde_json::from_value(serde_json::json!({
+            "id": id,
+            "title": { "romaji": romaji },
+            "coverImage": {}
+        }))
+        .unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_match_title_searches_parsed_title() {
+        let result = match_title(
+            None,
+            "[SubsPlease] Sousou no Frieren - 05 (1080p).mkv",
+            |query| async move {
+                assert_eq!(query, "Sousou no Frieren");
+                Some(anime(154587, "Sousou no Frieren"))
+            },
+        )
+        .await;
+
+        let json = serde_json::to_value(&result).unwrap();
+        assert_eq!(json["status"], "detected");
+        assert_eq!(json["player"], serde_json::Value::Null);
+        assert_eq!(json["parsed"]["title"], "Sousou no Frieren");
+        assert_eq!(json["parsed"]["episode"], 5);
+        assert_eq!(json["anilist_match"]["id"], 154587);
+        assert_eq!(json["confidence"], 1.0);
+    }
+
+    #[tokio::test]
+    async fn test_match_title_without_title_skips_search() {
+        let result = match_title(Some("Vlc".to_string()), "", |_| async {
+            panic!("nothing to search for")
+        })
+        .await;
+
+        match result {
+            DetectionResult::Detected {
+                parsed,
+                anilist_match,
+                ..
+            } => {
+                assert!(parsed.title.unwrap_or_default().is_empty());
+                assert!(anilist_match.is_none());
+            }
+            other => panic!("unexpected result: {:?}", other),
+        }
+    }
+
+    fn list_item(media_id: i32, romaji: &str) -> MediaListItem {
+        serde_json::from_value(serde_json::json!({
+            "mediaId": media_id,
+            "status": "CURRENT",
+            "progress": 3,
+            "score": 0.0,
+            "media": { "title": { "romaji": romaji } }
+        }))
+        .unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_list_entry_preferred() {
+        // The 2011 remake is on the list, a search would rank the 1999 one first
+        let list = vec![
+            list_item(11061, "Hunter x Hunter (2011)"),
+            list_item(21, "One Piece"),
+        ];
+
+        let anime = match_list_entry("Hunter x Hunter (2011)", &list, |id| async move {
+            assert_eq!(id, 11061);
+            Ok(anime(id, "Hunter x Hunter (2011)"))
+        })
+        .await;
+        assert_eq!(anime.map(|a| a.id), Some(11061));
+    }
+
+    #[tokio::test]
+    async fn test_not_on_list_falls_through() {
+        let list = vec![list_item(21, "One Piece")];
+        let anime = match_list_entry("Sousou no Frieren", &list, |_| async {
+            panic!("nothing on the list should match")
+        })
+        .await;
+        assert!(anime.is_none());
+    }
+
+    #[test]
+    fn test_rank_candidates_best_first() {
+        let results = vec![
+            anime(1, "Sousou no Frieren: Marumaru no Mahou"),
+            anime(2, "One Piece"),
+            anime(3, "Sousou no Frieren"),
+            anime(4, "Frieren 2"),
+            anime(5, "Naruto"),
+            anime(6, "Bleach"),
+        ];
+
+        let candidates = rank_candidates("Sousou no Frieren", results);
+        assert_eq!(candidates.len(), MAX_CANDIDATES);
+        assert_eq!(candidates[0].id, 3);
+        assert_eq!(candidates[0].confidence, 1.0);
+        assert_eq!(candidates[1].id, 1);
+        assert!(candidates
+            .windows(2)
+            .all(|pair| pair[0].confidence >= pair[1].confidence));
+    }
+
+    #[tokio::test]
+    async fn test_find_candidates_without_title() {
+        let parsed = ParsedTitle {
+            title: None,
+            episode: Some(3),
+            episode_end: None,
+            season: None,
+            language: None,
+            confidence: title_parser::CONFIDENCE_EPISODE_KEYWORD,
+        };
+        assert!(find_candidates(&parsed).await.unwrap().is_empty());
+    }
+
+    fn detected(episode: i32) -> DetectionResult {
+        DetectionResult::Detected {
+            player: Some("Vlc".to_string()),
+            window_title: format!("Frieren - {:02}.mkv", episode),
+            parsed: ParsedTitle {
+                title: Some("Frieren".to_string()),
+                episode: Some(episode),
+                episode_end: None,
+                season: None,
+                language: None,
+                confidence: title_parser::CONFIDENCE_DASH_NUMBER,
+            },
+            anilist_match: None,
+            confidence: None,
+        }
+    }
+
+    fn not_media_player() -> DetectionResult {
+        DetectionResult::NotMediaPlayer {
+            window: "Discord".to_string(),
+        }
+    }
+
+    fn result_episode(result: &DetectionResult) -> Option<i32> {
+        match result {
+            DetectionResult::Detected { parsed, .. } => parsed.episode,
+            _ => None,
+        }
+    }
+
+    fn status(result: &DetectionResult) -> String {
+        serde_json::to_value(result).unwrap()["status"]
+            .as_str()
+            .unwrap()
+            .to_string()
+    }
+
+    #[test]
+    fn test_debounce_holds_detection_through_short_misses() {
+        let mut debouncer = Debouncer::default();
+        let polls = [
+            detected(5),
+            not_media_player(),
+            not_media_player(),
+            detected(5),
+            not_media_player(),
+            not_media_player(),
+            not_media_player(),
+            not_media_player(),
+        ];
+
+        let reported: Vec<String> = polls
+            .into_iter()
+            .map(|poll| status(&debouncer.update(poll, 3)))
+            .collect();
+
+        assert_eq!(
+            reported,
+            [
+                "detected",
+                "detected",
+                "detected",
+                "detected",
+                "detected",
+                "detected",
+                "not_media_player",
+                "not_media_player",
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_manual_tracking_takes_precedence() {
+        let manual = ManualTracking::new(anime(154587, "Sousou no Frieren"), 7).unwrap();
+
+        let result = detect_or_manual(Some(manual), || async {
+            panic!("detection must not run while tracking manually")
+        })
+        .await;
+
+        match result {
+            DetectionResult::Detected {
+                parsed,
+                anilist_match,
+                confidence,
+                ..
+            } => {
+                assert_eq!(parsed.episode, Some(7));
+                assert_eq!(anilist_match.map(|anime| anime.id), Some(154587));
+                assert_eq!(confidence, None);
+            }
+            other => panic!("unexpected result: {:?}", other),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_detection_used_without_manual_tracking() {
+        let result = detect_or_manual(None, || async { detected(3) }).await;
+        assert_eq!(status(&result), "detected");
+        assert_eq!(result_episode(&result), Some(3));
+    }
+
+    #[test]
+    fn test_manual_tracking_rejects_invalid_episode() {
+        let mut frieren = anime(154587, "Sousou no Frieren");
+        frieren.episodes = Some(28);
+
+        assert!(ManualTracking::new(frieren.clone(), 0).is_err());
+        assert!(ManualTracking::new(frieren.clone(), 29).is_err());
+        assert!(ManualTracking::new(frieren, 28).is_ok());
+    }
+
+    #[test]
+    fn test_backoff_doubles_after_idle_polls() {
+        let base = Duration::from_secs(3);
+        let max = Duration::from_secs(30);
+        let mut backoff = PollBackoff::default();
+
+        let intervals: Vec<u64> = (0..10)
+            .map(|_| {
+                backoff.update(false);
+                backoff.interval(base, max).as_secs()
+            })
+            .collect();
+
+        assert_eq!(intervals, [3, 3, 3, 3, 6, 12, 24, 30, 30, 30]);
+    }
+
+    #[test]
+    fn test_backoff_resets_on_detection() {
+        let base = Duration::from_secs(3);
+        let max = Duration::from_secs(30);
+        let mut backoff = PollBackoff::default();
+        for _ in 0..20 {
+            backoff.update(false);
+        }
+        assert_eq!(backoff.interval(base, max), max);
+
+        backoff.update(true);
+        assert_eq!(backoff.interval(base, max), base);
+    }
+
+    #[test]
+    fn test_backoff_max_below_base_keeps_base() {
+        let mut backoff = PollBackoff::default();
+        for _ in 0..20 {
+            backoff.update(false);
+        }
+        assert_eq!(
+            backoff.interval(Duration::from_secs(5), Duration::from_secs(1)),
+            Duration::from_secs(5)
+        );
+    }
+
+    #[test]
+    fn test_debounce_repeats_last_detection() {
+        let mut debouncer = Debouncer::default();
+        debouncer.update(detected(5), 3);
+
+        match debouncer.update(not_media_player(), 3) {
+            DetectionResult::Detected { parsed, .. } => assert_eq!(parsed.episode, Some(5)),
+            other => panic!("unexpected result: {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_debounce_threshold_of_one_disables_smoothing() {
+        let mut debouncer = Debouncer::default();
+        debouncer.update(detected(5), 1);
+        assert_eq!(
+            status(&debouncer.update(not_media_player(), 1)),
+            "not_media_player"
+        );
+
+        // Nothing detected yet, misses pass straight through
+        let mut debouncer = Debouncer::default();
+        assert_eq!(
+            status(&debouncer.update(not_media_player(), 3)),
+            "not_media_player"
+        );
+    }
+
+    #[test]
+    fn test_pollers_keep_separate_state() {
+        let base = Duration::from_secs(3);
+        let max = Duration::from_secs(30);
+        for _ in 0..10 {
+            record_poll("test_idle_poller", &not_media_player());
+        }
+        record_poll("test_busy_poller", &detected(5));
+
+        assert_eq!(poll_interval("test_idle_poller", base, max), max);
+        assert_eq!(poll_interval("test_busy_poller", base, max), base);
+
+        debounce("test_busy_poller", detected(5), 2);
+        assert_eq!(
+            status(&debounce("test_idle_poller", not_media_player(), 2)),
+            "not_media_player"
+        );
+        assert_eq!(
+            status(&debounce("test_busy_poller", not_media_player(), 2)),
+            "detected"
+        );
+    }
+
+    #[test]
+    fn test_ignore_substring() {
+        let patterns = vec![IgnorePattern::Substring("OBS".to_string())];
+        assert!(ignored_by("obs 30.1 - Profile: Untitled", &patterns).is_some());
+        assert!(ignored_by("Frieren - 05.mkv - VLC media player", &patterns).is_none());
+    }
+
+    #[test]
+    fn test_ignore_regex() {
+        let patterns = vec![IgnorePattern::Regex(r"^test_\w+\.mkv".to_string())];
+        assert_eq!(
+            ignored_by("TEST_clip.mkv - mpv", &patterns),
+            Some(&patterns[0])
+        );
+        assert!(ignored_by("my test_clip.mkv - mpv", &patterns).is_none());
+    }
+
+    #[test]
+    fn test_invalid_ignore_patterns() {
+        assert!(IgnorePattern::Regex("(unclosed".to_string())
+            .validate()
+            .unwrap_err()
+            .starts_with("Invalid regex"));
+        assert!(IgnorePattern::Substring("  ".to_string())
+            .validate()
+            .is_err());
+        assert!(IgnorePattern::Regex("Recording".to_string())
+            .validate()
+            .is_ok());
+
+        // A broken regex that slipped into the config never matches
+        assert!(!IgnorePattern::Regex("(unclosed".to_string()).matches("(unclosed"));
+    }
+
+    #[test]
+    fn test_idle_result_shape() {
+        let result = DetectionResult::PlayerIdle {
+            player: "Vlc".to_string(),
+            window: "VLC media player".to_string(),
+        };
+        assert_eq!(
+            serde_json::to_value(&result).unwrap(),
+            serde_json::json!({
+                "status": "player_idle",
+                "player": "Vlc",
+                "window": "VLC media player"
+            })
+        );
+    }
+}
@@ -0,0 +1,139 @@
+//! Diagnostics
+//!
+//! PURPOSE: Environment details to attach to bug reports
+//! Detection works differently on each platform (Win32 APIs on Windows, Core
+//! Graphics on macOS), so most detection issues can't be triaged without
+//! knowing the platform, the backend in use and, on macOS, whether the app can
+//! read other apps' window titles at all.
+
+use serde::Serialize;
+
+/// Window title backend compiled into this build
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DetectionBackend {
+    Windows,
+    Macos,
+    /// No window title backend for this platform (Linux included, for now)
+    Unsupported,
+}
+
+/// State of a permission detection depends on
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PermissionStatus {
+    Granted,
+    /// Detection can't see other apps' window titles until the user allows it
+    Denied,
+    /// This platform doesn't gate window titles behind a permission
+    NotRequired,
+}
+
+/// Environment info for bug reports
+#[derive(Debug, Clone, Serialize)]
+pub struct Diagnostics {
+    pub app_version: String,
+    /// `std::env::consts::OS`, e.g. "windows", "macos", "linux"
+    pub os: String,
+    /// `std::env::consts::ARCH`, e.g. "x86_64", "aarch64"
+    pub arch: String,
+    pub detection_backend: DetectionBackend,
+    /// Screen Recording permission (macOS), needed to read window titles
+    pub screen_recording_permission: PermissionStatus,
+}
+
+pub fn detection_backend() -> DetectionBackend {
+    if cfg!(windows) {
+        DetectionBackend::Windows
+    } else if cfg!(target_os = "macos") {
+        DetectionBackend::Macos
+    } else {
+        DetectionBackend::Unsupported
+    }
+}
+
+#[cfg(target_os = "macos")]
+fn granted_or_denied(granted: bool) -> PermissionStatus {
+    if granted {
+        PermissionStatus::Granted
+    } else {
+        PermissionStatus::Denied
+    }
+}
+
+/// Check the Screen Recording permission
+#[cfg(target_os = "macos")]
+pub fn screen_recording_permission() -> PermissionStatus {
+    granted_or_denied(crate::mac_name::has_screen_recording_permission())
+}
+
+#[cfg(not(target_os = "macos"))]
+pub fn screen_recording_permission() -> PermissionStatus {
+    PermissionStatus::NotRequired
+}
+
+/// Show the Screen Recording prompt if the permission isn't granted yet
+///
+/// On macOS a grant only applies after the app restarts, so `Denied` right
+/// after prompting is expected; the UI should ask the user to relaunch.
+#[cfg(target_os = "macos")]
+pub fn request_screen_recording_permission() -> PermissionStatus {
+    granted_or_denied(crate::mac_name::request_screen_recording_permission())
+}
+
+#[cfg(not(target_os = "macos"))]
+pub fn request_screen_recording_permission() -> PermissionStatus {
+    PermissionStatus::NotRequired
+}
+
+/// Collect diagnostics for the running app
+///
+/// # Arguments
+/// * `app_version` - Version from the Tauri package info
+pub fn collect(app_version: String) -> Diagnostics {
+    Diagnostics {
+        app_version,
+        os: std::env::consts::OS.to_string(),
+        arch: std::env::consts::ARCH.to_string(),
+        detection_backend: detection_backend(),
+        screen_recording_permission: screen_recording_permission(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_collect_reports_platform() {
+        let diagnostics = collect("1.0.0".to_string());
+        assert_eq!(diagnostics.app_version, "1.0.0");
+        assert_eq!(diagnostics.os, std::env::consts::OS);
+        assert_eq!(diagnostics.arch, std::env::consts::ARCH);
+
+        let expected = match std::env::consts::OS {
+            "windows" => DetectionBackend::Windows,
+            "macos" => DetectionBackend::Macos,
+            _ => DetectionBackend::Unsupported,
+        };
+        assert_eq!(diagnostics.detection_backend, expected);
+        assert_eq!(
+            diagnostics.screen_recording_permission == PermissionStatus::NotRequired,
+            !cfg!(target_os = "macos")
+        );
+    }
+
+    #[test]
+    fn test_permission_status_serializes_for_ui() {
+        assert_eq!(
+            serde_json::to_string(&PermissionStatus::NotRequired).unwrap(),
+            "\"not_required\""
+        );
+        if !cfg!(target_os = "macos") {
+            assert_eq!(
+                request_screen_recording_permission(),
+                PermissionStatus::NotRequired
+            );
+        }
+    }
+}
@@ -0,0 +1,593 @@
+//! Download Queue
+//!
+//! PURPOSE: Download many chapters without one blocking invoke per chapter
+//! Jobs are processed in order with at most `download_concurrency` (from the app
+//! config) chapters at once, each through `downloader::download_chapter_to_cbz`.
+//!
+//! The queue is persisted to `download_queue.json` in the app data dir after
+//! every state change. Progress isn't persisted, since restored jobs start over
+//! anyway. Jobs that were queued or downloading when the app closed are
+//! restarted on the next launch; finished jobs are dropped on load.
+
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::SystemTime;
+use tracing::{info, warn};
+
+use crate::config;
+use crate::downloader::{self, DownloadOptions};
+
+const QUEUE_FILE: &str = "download_queue.json";
+
+/// A chapter to download (same arguments as `download_chapter_command`)
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct DownloadJob {
+    pub chapter_title: String,
+    pub manga_title: String,
+    pub urls: Vec<String>,
+    pub download_dir: String,
+    /// Re-encode WebP pages as JPEG
+    #[serde(default)]
+    pub convert_webp: bool,
+    /// Request headers for the page host (Referer, User-Agent, ...)
+    #[serde(default)]
+    pub headers: HashMap<String, String>,
+    /// Leave out pages that keep failing instead of failing the chapter
+    #[serde(default)]
+    pub skip_failed: bool,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum JobState {
+    Queued,
+    Downloading,
+    Completed,
+    Failed,
+    Cancelled,
+}
+
+impl JobState {
+    fn is_finished(self) -> bool {
+        matches!(self, Self::Completed | Self::Failed | Self::Cancelled)
+    }
+}
+
+/// A job with its current state and progress
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct QueuedJob {
+    pub id: String,
+    pub job: DownloadJob,
+    pub state: JobState,
+    pub downloaded_pages: usize,
+    pub total_pages: usize,
+    /// Page bytes received so far
+    #[serde(default)]
+    pub bytes_done: u64,
+    /// Estimated chapter size, None when the server doesn't send Content-Length
+    #[serde(default)]
+    pub bytes_total: Option<u64>,
+    /// Path of the CBZ once completed
+    pub path: Option<String>,
+    pub error: Option<String>,
+}
+
+/// Called with a job every time its state or progress changes
+///
+/// Runs without the queue locked, so it may call back into the queue.
+pub type JobListener = Arc<dyn Fn(&QueuedJob) + Send + Sync>;
+
+struct QueueInner {
+    jobs: Vec<QueuedJob>,
+    /// Options of running jobs, used to cancel them
+    running: HashMap<String, DownloadOptions>,
+    data_dir: Option<PathBuf>,
+}
+
+/// Shared handle to a download queue
+#[derive(Clone)]
+pub struct DownloadQueue {
+    inner: Arc<Mutex<QueueInner>>,
+    listener: Option<JobListener>,
+}
+
+static NEXT_JOB_ID: AtomicU64 = AtomicU64::new(1);
+
+fn new_job_id() -> String {
+    let millis = SystemTime::now()
+        .duration_since(SystemTime::UNIX_EPOCH)
+        .map(|d| d.as_millis())
+        .unwrap_or_default();
+    format!(
+        "job-{}-{}",
+        millis,
+        NEXT_JOB_ID.fetch_add(1, Ordering::SeqCst)
+    )
+}
+
+impl DownloadQueue {
+    /// Create a queue, restoring unfinished jobs from `data_dir` if given
+    ///
+    /// Call `resume` from within the async runtime to start restored jobs.
+    pub fn new(data_dir: Option<PathBuf>, listener: Option<JobListener>) -> Self {
+        let jobs = data_dir
+            .as_deref()
+            .map(load_jobs)
+            .unwrap_or_default()
+            .into_iter()
+            .filter(|j| !j.state.is_finished())
+            .map(|mut j| {
+                // Partial downloads are discarded, so the chapter starts over
+                j.state = JobState::Queued;
+                j.downloaded_pages = 0;
+                j.bytes_done = 0;
+                j
+            })
+            .collect();
+
+        Self {
+            inner: Arc::new(Mutex::new(QueueInner {
+                jobs,
+                running: HashMap::new(),
+                data_dir,
+            })),
+            listener,
+        }
+    }
+
+    /// Add a job to the end of the queue and return its id
+    ///
+    /// Must be called from within the async runtime, which runs the download.
+    pub fn enqueue(&self, job: DownloadJob) -> Result<String, String> {
+        let id = new_job_id();
+        let queued = {
+            let mut inner = self.lock()?;
+            let queued = QueuedJob {
+                id: id.clone(),
+                total_pages: job.urls.len(),
+                job,
+                state: JobState::Queued,
+                downloaded_pages: 0,
+                bytes_done: 0,
+                bytes_total: None,
+                path: None,
+                error: None,
+            };
+            inner.jobs.push(queued.clone());
+            persist(&inner);
+            queued
+        };
+        self.notify(&queued);
+
+        self.pump();
+        Ok(id)
+    }
+
+    /// All jobs in queue order
+    pub fn status(&self) -> Result<Vec<QueuedJob>, String> {
+        Ok(self.lock()?.jobs.clone())
+    }
+
+    /// Cancel a queued or running job
+    ///
+    /// Returns false if the job doesn't exist or has already finished.
+    pub fn cancel(&self, id: &str) -> Result<bool, String> {
+        let mut inner = self.lock()?;
+
+        if let Some(options) = inner.running.get(id) {
+            // The download task marks the job cancelled once it stops
+            options.cancel.store(true, Ordering::SeqCst);
+            return Ok(true);
+        }
+
+        let Some(job) = inner
+            .jobs
+            .iter_mut()
+            .find(|j| j.id == id && j.state == JobState::Queued)
+        else {
+            return Ok(false);
+        };
+
+        job.state = JobState::Cancelled;
+        let job = job.clone();
+        persist(&inner);
+        drop(inner);
+
+        self.notify(&job);
+        Ok(true)
+    }
+
+    /// Start restored jobs (must be called from within the async runtime)
+    pub fn resume(&self) {
+        self.pump();
+    }
+
+    fn lock(&self) -> Result<std::sync::MutexGuard<'_, QueueInner>, String> {
+        self.inner
+            .lock()
+            .map_err(|_| "Download queue lock error".to_string())
+    }
+
+    /// Start queued jobs while fewer than the configured concurrency are running
+    fn pump(&self) {
+        let max_parallel = config::current().download_concurrency.max(1);
+        let Ok(mut inner) = self.inner.lock() else {
+            return;
+        };
+        let mut started = Vec::new();
+
+        while inner.running.len() < max_parallel {
+            let Some(job) = inner.jobs.iter_mut().find(|j| j.state == JobState::Queued) else {
+                break;
+            };
+
+            job.state = JobState::Downloading;
+            let job = job.clone();
+
+            let queue = self.clone();
+            let job_id = job.id.clone();
+            let options = DownloadOptions {
+                convert_webp: job.job.convert_webp,
+                headers: job.job.headers.clone(),
+                skip_failed: job.job.skip_failed,
+                progress: Some(Arc::new(move |progress| {
+                    queue.update(&job_id, |j| {
+                        j.downloaded_pages = progress.pages_done;
+                        j.total_pages = progress.pages_total;
+                        j.bytes_done = progress.bytes_done;
+                        j.bytes_total = progress.bytes_total;
+                    });
+                })),
+                ..DownloadOptions::default()
+            };
+
+            inner.running.insert(job.id.clone(), options.clone());
+            persist(&inner);
+            started.push((job, options));
+        }
+        drop(inner);
+
+        for (job, options) in started {
+            self.notify(&job);
+            let queue = self.clone();
+            tokio::spawn(async move { queue.run(job, options).await });
+        }
+    }
+
+    /// Download a job and record the outcome
+    async fn run(self, job: QueuedJob, options: DownloadOptions) {
+        info!(
+            "Starting {}: {} - {}",
+            job.id, job.job.manga_title, job.job.chapter_title
+        );
+
+        let result = downloader::download_chapter_to_cbz(
+            job.job.chapter_title,
+            job.job.manga_title,
+            job.job.urls,
+            job.job.download_dir,
+            &options,
+        )
+        .await;
+
+        if let Ok(mut inner) = self.inner.lock() {
+            inner.running.remove(&job.id);
+        }
+
+        self.update(&job.id, |j| match result {
+            Ok(report) => {
+                j.state = JobState::Completed;
+                j.downloaded_pages = report.page_count;
+                j.path = Some(report.path);
+            }
+            Err(e) if e == downloader::CANCELLED_ERROR => j.state = JobState::Cancelled,
+            Err(e) => {
+                j.state = JobState::Failed;
+                j.error = Some(e);
+            }
+        });
+
+        self.pump();
+    }
+
+    /// Apply `f` to a job, then notify, and persist if its state changed
+    ///
+    /// Page progress comes through here once per page, so progress-only
+    /// updates don't rewrite the queue file.
+    fn update(&self, id: &str, f: impl FnOnce(&mut QueuedJob)) {
+        let Ok(mut inner) = self.inner.lock() else {
+            return;
+        };
+        let Some(job) = inner.jobs.iter_mut().find(|j| j.id == id) else {
+            return;
+        };
+
+        let state = job.state;
+        f(job);
+        let job = job.clone();
+        if job.state != state {
+            persist(&inner);
+        }
+        drop(inner);
+
+        self.notify(&job);
+    }
+
+    /// Tell the listener about a job; never called with the queue locked
+    fn notify(&self, job: &QueuedJob) {
+        if let Some(listener) = &self.listener {
+            listener(job);
+        }
+    }
+}
+
+fn persist(inner: &QueueInner) {
+    if let Some(dir) = &inner.data_dir {
+        if let Err(e) = save_jobs(dir, &inner.jobs) {
+            warn!("Failed to save queue: {}", e);
+        }
+    }
+}
+
+fn queue_path(data_dir: &Path) -> PathBuf {
+    data_dir.join(QUEUE_FILE)
+}
+
+fn load_jobs(data_dir: &Path) -> Vec<QueuedJob> {
+    fs::read_to_string(queue_path(data_dir))
+        .ok()
+        .and_then(|json| serde_json::from_str(&json).ok())
+        .unwrap_or_default()
+}
+
+fn save_jobs(data_dir: &Path, jobs: &[QueuedJob]) -> Result<(), String> {
+    fs::create_dir_all(data_dir).map_err(|e| format!("Failed to create data dir: {}", e))?;
+
+    let json =
+        serde_json::to_string_pretty(jobs).map_err(|e| format!("Serialization error: {}", e))?;
+    fs::write(queue_path(data_dir), json).map_err(|e| format!("Failed to write queue: {}", e))
+}
+
+lazy_static::lazy_static! {
+    /// The app's download queue, created by `init`
+    static ref QUEUE: Mutex<Option<DownloadQueue>> = Mutex::new(None);
+}
+
+/// Create the shared queue and restart unfinished jobs
+///
+/// Must be called from within the async runtime.
+pub fn init(data_dir: PathBuf, listener: JobListener) {
+    let queue = DownloadQueue::new(Some(data_dir), Some(listener));
+    queue.resume();
+
+    if let Ok(mut shared) = QUEUE.lock() {
+        *shared = Some(queue);
+    }
+}
+
+fn shared_queue() -> Result<DownloadQueue, String> {
+    QUEUE
+        .lock()
+        .map_err(|_| "Download queue lock error".to_string())?
+        .clone()
+        .ok_or_else(|| "Download queue not initialized".to_string())
+}
+
+/// Add a job to the shared queue (from within the async runtime)
+pub fn enqueue_download(job: DownloadJob) -> Result<String, String> {
+    shared_queue()?.enqueue(job)
+}
+
+/// Status of all jobs in the shared queue
+pub fn get_queue_status() -> Result<Vec<QueuedJob>, String> {
+    shared_queue()?.status()
+}
+
+/// Cancel a job in the shared queue
+pub fn cancel_job(id: &str) -> Result<bool, String> {
+    shared_queue()?.cancel(id)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::TempDir;
+    use std::sync::atomic::AtomicUsize;
+    use wiremock::matchers::method;
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    /// Serve a small fake image for every request, slowly enough for jobs to overlap
+    async fn image_server() -> MockServer {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .respond_with(
+                ResponseTemplate::new(200)
+                    .set_body_bytes(b"fake-image-bytes".to_vec())
+                    .set_delay(std::time::Duration::from_millis(20)),
+            )
+            .mount(&server)
+            .await;
+        server
+    }
+
+    fn job(base: &str, dir: &Path, chapter: usize) -> DownloadJob {
+        DownloadJob {
+            chapter_title: format!("Chapter {}", chapter),
+            manga_title: "Manga".to_string(),
+            urls: (1..=3).map(|i| format!("{}/{}.jpg", base, i)).collect(),
+            download_dir: dir.to_string_lossy().to_string(),
+            convert_webp: false,
+            headers: HashMap::new(),
+            skip_failed: false,
+        }
+    }
+
+    async fn wait_until_finished(queue: &DownloadQueue) -> Vec<QueuedJob> {
+        for _ in 0..200 {
+            let jobs = queue.status().unwrap();
+            if jobs.iter().all(|j| j.state.is_finished()) {
+                return jobs;
+            }
+            tokio::time::sleep(std::time::Duration::from_millis(10)).await;
+        }
+        panic!("Queue did not finish");
+    }
+
+    #[tokio::test]
+    async fn test_queue_limits_parallel_jobs() {
+        let dir = TempDir::new("queue");
+        let server = image_server().await;
+
+        // Track how many jobs are downloading at once
+        let downloading = Arc::new(Mutex::new(HashMap::<String, bool>::new()));
+        let max_parallel = Arc::new(AtomicUsize::new(0));
+        let listener: JobListener = {
+            let downloading = downloading.clone();
+            let max_parallel = max_parallel.clone();
+            Arc::new(move |job: &QueuedJob| {
+                let mut downloading = downloading.lock().unwrap();
+                downloading.insert(job.id.clone(), job.state == JobState::Downloading);
+                let count = downloading.values().filter(|d| **d).count();
+                max_parallel.fetch_max(count, Ordering::SeqCst);
+            })
+        };
+
+        let queue = DownloadQueue::new(Some(dir.to_path_buf()), Some(listener));
+        for chapter in 1..=4 {
+            queue.enqueue(job(&server.uri(), &dir, chapter)).unwrap();
+        }
+
+        let jobs = wait_until_finished(&queue).await;
+        assert!(jobs.iter().all(|j| j.state == JobState::Completed));
+        assert!(jobs.iter().all(|j| j.downloaded_pages == 3));
+        assert_eq!(
+            max_parallel.load(Ordering::SeqCst),
+            config::current().download_concurrency
+        );
+        assert!(dir.join("Manga").join("Chapter 4.cbz").exists());
+    }
+
+    #[tokio::test]
+    async fn test_cancel_queued_job() {
+        let dir = TempDir::new("queue_cancel");
+        let server = image_server().await;
+
+        let queue = DownloadQueue::new(None, None);
+        let ids: Vec<String> = (1..=3)
+            .map(|chapter| queue.enqueue(job(&server.uri(), &dir, chapter)).unwrap())
+            .collect();
+
+        // The third job waits for a free slot
+        assert!(queue.cancel(&ids[2]).unwrap());
+
+        let jobs = wait_until_finished(&queue).await;
+        assert_eq!(jobs[2].state, JobState::Cancelled);
+        assert!(!queue.cancel(&ids[2]).unwrap());
+        assert!(!dir.join("Manga").join("Chapter 3.cbz").exists());
+    }
+
+    #[test]
+    fn test_restores_unfinished_jobs() {
+        let dir = TempDir::new("queue_restore");
+        let job = |id: &str, state| QueuedJob {
+            id: id.to_string(),
+            job: DownloadJob {
+                chapter_title: "Chapter 1".to_string(),
+                manga_title: "Manga".to_string(),
+                urls: vec!["http://localhost/1.jpg".to_string()],
+                download_dir: dir.to_string_lossy().to_string(),
+                convert_webp: false,
+                headers: HashMap::new(),
+                skip_failed: false,
+            },
+            state,
+            downloaded_pages: 1,
+            total_pages: 1,
+            bytes_done: 0,
+            bytes_total: None,
+            path: None,
+            error: None,
+        };
+        save_jobs(
+            &dir,
+            &[
+                job("a", JobState::Completed),
+                job("b", JobState::Downloading),
+                job("c", JobState::Queued),
+            ],
+        )
+        .unwrap();
+
+        let queue = DownloadQueue::new(Some(dir.to_path_buf()), None);
+        let jobs = queue.status().unwrap();
+        let ids: Vec<&str> = jobs.iter().map(|j| j.id.as_str()).collect();
+        assert_eq!(ids, vec!["b", "c"]);
+        assert!(jobs.iter().all(|j| j.state == JobState::Queued));
+        assert_eq!(jobs[0].downloaded_pages, 0);
+    }
+
+    #[test]
+    fn test_only_state_changes_are_persisted() {
+        let dir = TempDir::new("queue_persist");
+        let queue = DownloadQueue::new(Some(dir.to_path_buf()), None);
+        {
+            let mut inner = queue.lock().unwrap();
+            inner.jobs.push(QueuedJob {
+                id: "a".to_string(),
+                job: job("http://localhost", &dir, 1),
+                state: JobState::Downloading,
+                downloaded_pages: 0,
+                total_pages: 3,
+                bytes_done: 0,
+                bytes_total: None,
+                path: None,
+                error: None,
+            });
+        }
+
+        queue.update("a", |j| j.downloaded_pages = 1);
+        assert!(!queue_path(&dir).exists());
+        assert_eq!(queue.status().unwrap()[0].downloaded_pages, 1);
+
+        queue.update("a", |j| j.state = JobState::Failed);
+        assert_eq!(load_jobs(&dir)[0].state, JobState::Failed);
+    }
+
+    #[test]
+    fn test_listener_can_call_back_into_queue() {
+        // The listener reads the queue it's attached to, like an event handler would
+        let handle: Arc<Mutex<Option<DownloadQueue>>> = Arc::new(Mutex::new(None));
+        let seen = Arc::new(Mutex::new(Vec::new()));
+        let listener: JobListener = {
+            let handle = handle.clone();
+            let seen = seen.clone();
+            Arc::new(move |_: &QueuedJob| {
+                let queue = handle.lock().unwrap().clone().unwrap();
+                seen.lock().unwrap().push(queue.status().unwrap().len());
+            })
+        };
+
+        let queue = DownloadQueue::new(None, Some(listener));
+        *handle.lock().unwrap() = Some(queue.clone());
+        queue.lock().unwrap().jobs.push(QueuedJob {
+            id: "a".to_string(),
+            job: job("http://localhost", Path::new("/tmp"), 1),
+            state: JobState::Queued,
+            downloaded_pages: 0,
+            total_pages: 3,
+            bytes_done: 0,
+            bytes_total: None,
+            path: None,
+            error: None,
+        });
+
+        assert!(queue.cancel("a").unwrap());
+        queue.update("a", |j| j.downloaded_pages = 1);
+        assert_eq!(*seen.lock().unwrap(), vec![1, 1]);
+    }
+}
@@ -1,13 +1,188 @@
 use futures::stream::{self, StreamExt};
+use futures::FutureExt;
+use reqwest::header::{HeaderMap, HeaderName, HeaderValue, REFERER, USER_AGENT};
 use reqwest::Client;
+use std::collections::{BTreeSet, HashMap};
 use std::fs::File;
 use std::io::Write;
 use std::path::Path;
+use std::sync::atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Instant;
+use tracing::{info, warn};
 use zip::write::FileOptions;
 
+use crate::{cbz_reader, config};
+
 /// Maximum concurrent downloads
 const MAX_CONCURRENT_DOWNLOADS: usize = 6;
 
+/// Error returned when a download is cancelled
+pub const CANCELLED_ERROR: &str = "Download cancelled";
+
+/// Referer sent when the caller doesn't give one
+const DEFAULT_REFERER: &str = "https://weebcentral.com";
+
+/// User-Agent sent when the caller doesn't give one
+const DEFAULT_USER_AGENT: &str = "Mozilla/5.0 (Windows NT 10.0; Win64; x64) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/120.0.0.0 Safari/537.36";
+
+/// Tries per page before a dropped connection fails the download
+const PAGE_FETCH_ATTEMPTS: usize = 3;
+
+/// Rough size of a manga page, used to estimate a chapter's size before downloading
+const ESTIMATED_PAGE_BYTES: u64 = 1024 * 1024;
+
+/// Progress of a chapter download, reported after each page
+#[derive(Debug, Clone, Copy, serde::Serialize, PartialEq, Eq)]
+pub struct DownloadProgress {
+    pub pages_done: usize,
+    pub pages_total: usize,
+    /// Page bytes received so far
+    pub bytes_done: u64,
+    /// From the pages' Content-Length, extrapolated until every page has
+    /// answered; None when a server leaves it out (use the page counts then)
+    pub bytes_total: Option<u64>,
+}
+
+/// Called with the chapter's progress after each page
+pub type ProgressCallback = Arc<dyn Fn(&DownloadProgress) + Send + Sync>;
+
+/// Byte counts shared by the page fetches of a chapter
+#[derive(Default)]
+struct ByteProgress {
+    done: AtomicU64,
+    /// Sum of the Content-Length of the pages that sent one
+    known_total: AtomicU64,
+    known_pages: AtomicUsize,
+    /// A page came without Content-Length, so no total can be given
+    missing_length: AtomicBool,
+}
+
+impl ByteProgress {
+    /// Record a page's full size, once per page
+    fn page_length(&self, length: Option<u64>) {
+        match length {
+            Some(length) => {
+                self.known_total.fetch_add(length, Ordering::SeqCst);
+                self.known_pages.fetch_add(1, Ordering::SeqCst);
+            }
+            None => self.missing_length.store(true, Ordering::SeqCst),
+        }
+    }
+
+    fn total(&self, total_pages: usize) -> Option<u64> {
+        let known_pages = self.known_pages.load(Ordering::SeqCst);
+        if self.missing_length.load(Ordering::SeqCst) || known_pages == 0 {
+            return None;
+        }
+
+        let known_total = self.known_total.load(Ordering::SeqCst);
+        if known_pages >= total_pages {
+            Some(known_total)
+        } else {
+            Some(known_total * total_pages as u64 / known_pages as u64)
+        }
+    }
+}
+
+/// Per-download settings
+#[derive(Clone)]
+pub struct DownloadOptions {
+    /// Set to true to stop the download; checked between page fetches
+    pub cancel: Arc<AtomicBool>,
+    pub progress: Option<ProgressCallback>,
+    /// Re-encode WebP pages as JPEG for readers without WebP support
+    pub convert_webp: bool,
+    /// Free space that must remain on the target volume after the download
+    pub min_free_bytes: u64,
+    /// Request headers for the page host, replacing the default Referer/User-Agent by name
+    pub headers: HashMap<String, String>,
+    /// Leave out pages that still fail after retries instead of failing the chapter
+    pub skip_failed: bool,
+}
+
+impl Default for DownloadOptions {
+    fn default() -> Self {
+        Self {
+            cancel: Arc::default(),
+            progress: None,
+            convert_webp: false,
+            min_free_bytes: config::current()
+                .min_free_space_mb
+                .saturating_mul(1024 * 1024),
+            headers: HashMap::new(),
+            skip_failed: false,
+        }
+    }
+}
+
+impl DownloadOptions {
+    fn is_cancelled(&self) -> bool {
+        self.cancel.load(Ordering::SeqCst)
+    }
+}
+
+lazy_static::lazy_static! {
+    /// Cancel flags of running downloads, keyed by download id
+    static ref ACTIVE_DOWNLOADS: Mutex<HashMap<String, Arc<AtomicBool>>> = Mutex::new(HashMap::new());
+}
+
+static NEXT_DOWNLOAD_ID: AtomicU64 = AtomicU64::new(1);
+
+/// Register a new download and return its id with options carrying its cancel flag
+pub fn register_download() -> (String, DownloadOptions) {
+    let id = format!(
+        "download-{}",
+        NEXT_DOWNLOAD_ID.fetch_add(1, Ordering::SeqCst)
+    );
+    let options = DownloadOptions::default();
+
+    if let Ok(mut active) = ACTIVE_DOWNLOADS.lock() {
+        active.insert(id.clone(), options.cancel.clone());
+    }
+
+    (id, options)
+}
+
+/// Request cancellation of a running download
+///
+/// Returns false if no download with this id is running.
+pub fn cancel_download(id: &str) -> bool {
+    match ACTIVE_DOWNLOADS.lock() {
+        Ok(active) => match active.get(id) {
+            Some(cancel) => {
+                cancel.store(true, Ordering::SeqCst);
+                true
+            }
+            None => false,
+        },
+        Err(_) => false,
+    }
+}
+
+/// Forget a download once it has finished
+pub fn finish_download(id: &str) {
+    if let Ok(mut active) = ACTIVE_DOWNLOADS.lock() {
+        active.remove(id);
+    }
+}
+
+/// Stats of a finished chapter download
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize, PartialEq)]
+pub struct DownloadReport {
+    /// Path of the created CBZ
+    pub path: String,
+    pub page_count: usize,
+    /// Sum of all page sizes (after any conversion)
+    pub total_bytes: u64,
+    /// Size of each page in page order
+    pub page_sizes: Vec<u64>,
+    pub duration_ms: u64,
+    /// Indices (into the page URLs) of pages left out with `skip_failed`
+    #[serde(default)]
+    pub skipped_pages: Vec<usize>,
+}
+
 /// Result of downloading a single page
 struct PageDownload {
     index: usize,
@@ -15,18 +190,53 @@ struct PageDownload {
     bytes: Vec<u8>,
 }
 
+/// Make a manga or chapter title safe to use as a file name
+pub fn sanitize(name: &str) -> String {
+    name.replace(['/', '\\', '?', '*', ':', '"', '<', '>', '|'], "_")
+        .trim()
+        .to_string()
+}
+
+/// Names of the chapters already downloaded into a manga folder
+///
+/// These are the CBZ file names without the extension, i.e. the sanitized
+/// chapter titles. A missing folder means nothing was downloaded yet.
+pub fn list_downloaded_chapters(manga_dir: &Path) -> Result<BTreeSet<String>, String> {
+    if !manga_dir.exists() {
+        return Ok(BTreeSet::new());
+    }
+
+    let entries = std::fs::read_dir(manga_dir)
+        .map_err(|e| format!("Failed to read manga directory: {}", e))?;
+
+    Ok(entries
+        .flatten()
+        .map(|entry| entry.path())
+        .filter(|path| path.is_file())
+        .filter(|path| {
+            path.extension()
+                .is_some_and(|ext| ext.eq_ignore_ascii_case("cbz"))
+        })
+        .filter_map(|path| Some(path.file_stem()?.to_string_lossy().to_string()))
+        .collect())
+}
+
+/// Whether a chapter is among the downloaded ones, comparing sanitized titles
+pub fn is_downloaded(downloaded: &BTreeSet<String>, chapter_title: &str) -> bool {
+    downloaded.contains(&sanitize(chapter_title))
+}
+
+/// Download a chapter's pages and pack them into a CBZ
+///
+/// When cancelled, returns `CANCELLED_ERROR` and leaves no partial CBZ behind.
 pub async fn download_chapter_to_cbz(
     chapter_title: String,
     manga_title: String,
     urls: Vec<String>,
     download_dir: String,
-) -> Result<String, String> {
-    // Basic sanitization
-    let sanitize = |s: &str| -> String {
-        s.replace(['/', '\\', '?', '*', ':', '"', '<', '>', '|'], "_")
-            .trim()
-            .to_string()
-    };
+    options: &DownloadOptions,
+) -> Result<DownloadReport, String> {
+    let started = Instant::now();
 
     let sanitized_manga = sanitize(&manga_title);
     let sanitized_chapter = sanitize(&chapter_title);
@@ -48,97 +258,313 @@ pub async fn download_chapter_to_cbz(
 
     let cbz_path = manga_dir.join(format!("{}.cbz", sanitized_chapter));
 
+    // Fail early instead of leaving a partial CBZ on a full disk
+    check_disk_space(&manga_dir, urls.len(), options.min_free_bytes)?;
+
+    let headers = page_headers(&options.headers)?;
+
     // Build client with connection pool for better performance
     let client = Client::builder()
         .pool_max_idle_per_host(MAX_CONCURRENT_DOWNLOADS)
         .build()
         .map_err(|e| format!("Failed to build HTTP client: {}", e))?;
 
-    println!(
-        "[Downloader] Starting parallel download of {} pages (max {} concurrent)",
+    info!(
+        "Starting parallel download of {} pages (max {} concurrent)",
         urls.len(),
         MAX_CONCURRENT_DOWNLOADS
     );
 
     // Download all pages in parallel with limited concurrency
+    let total_pages = urls.len();
+    let completed_pages = AtomicUsize::new(0);
+    let byte_progress = ByteProgress::default();
     let urls_with_index: Vec<(usize, String)> = urls.into_iter().enumerate().collect();
 
-    let download_results: Vec<Result<PageDownload, String>> = stream::iter(urls_with_index)
-        .map(|(i, url)| {
-            let client = client.clone();
-            async move {
-                // Fetch image with proper headers
-                let response = client
-                    .get(&url)
-                    .header("Referer", "https://weebcentral.com")
-                    .header(
-                        "User-Agent",
-                        "Mozilla/5.0 (Windows NT 10.0; Win64; x64) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/120.0.0.0 Safari/537.36",
-                    )
-                    .send()
-                    .await
-                    .map_err(|e| format!("Failed to fetch page {}: {}", i + 1, e))?;
-
-                if !response.status().is_success() {
-                    return Err(format!(
-                        "Failed to fetch page {}: HTTP {}",
-                        i + 1,
-                        response.status()
-                    ));
-                }
+    let download_results: Vec<(usize, Result<PageDownload, String>)> =
+        stream::iter(urls_with_index)
+            .map(|(i, url)| {
+                let client = client.clone();
+                let headers = &headers;
+                let completed_pages = &completed_pages;
+                let byte_progress = &byte_progress;
+                async move {
+                    if options.is_cancelled() {
+                        return Err(CANCELLED_ERROR.to_string());
+                    }
 
-                let bytes = response
-                    .bytes()
-                    .await
-                    .map_err(|e| format!("Failed to read bytes for page {}: {}", i + 1, e))?;
+                    let bytes = fetch_page(&client, &url, i, headers, byte_progress).await?;
 
-                // Determine extension (default to jpg if unknown)
-                let ext = if url.to_lowercase().contains(".png") {
-                    "png"
-                } else if url.to_lowercase().contains(".webp") {
-                    "webp"
-                } else {
-                    "jpg"
-                };
+                    // Determine extension (default to jpg if unknown)
+                    let mut ext = if url.to_lowercase().contains(".png") {
+                        "png"
+                    } else if url.to_lowercase().contains(".webp") {
+                        "webp"
+                    } else {
+                        "jpg"
+                    };
 
-                Ok(PageDownload {
-                    index: i,
-                    extension: ext.to_string(),
-                    bytes: bytes.to_vec(),
-                })
-            }
-        })
-        .buffer_unordered(MAX_CONCURRENT_DOWNLOADS)
-        .collect()
-        .await;
+                    let mut bytes = bytes;
+                    if options.convert_webp && is_webp(&bytes) {
+                        bytes = webp_to_jpeg(&bytes)
+                            .map_err(|e| format!("Failed to convert page {}: {}", i + 1, e))?;
+                        ext = "jpg";
+                    }
+
+                    let done = completed_pages.fetch_add(1, Ordering::SeqCst) + 1;
+                    if let Some(progress) = &options.progress {
+                        progress(&DownloadProgress {
+                            pages_done: done,
+                            pages_total: total_pages,
+                            bytes_done: byte_progress.done.load(Ordering::SeqCst),
+                            bytes_total: byte_progress.total(total_pages),
+                        });
+                    }
+
+                    Ok(PageDownload {
+                        index: i,
+                        extension: ext.to_string(),
+                        bytes,
+                    })
+                }
+                .map(move |result| (i, result))
+            })
+            .buffer_unordered(MAX_CONCURRENT_DOWNLOADS)
+            .collect()
+            .await;
+
+    if options.is_cancelled() {
+        info!("Download cancelled before writing CBZ");
+        return Err(CANCELLED_ERROR.to_string());
+    }
 
     // Check for errors and collect successful downloads
     let mut pages: Vec<PageDownload> = Vec::with_capacity(download_results.len());
-    for result in download_results {
+    let mut skipped_pages = Vec::new();
+    for (index, result) in download_results {
         match result {
             Ok(page) => pages.push(page),
+            Err(e) if options.skip_failed && e != CANCELLED_ERROR => {
+                warn!("Skipping page {}: {}", index + 1, e);
+                skipped_pages.push(index);
+            }
             Err(e) => return Err(e),
         }
     }
+    if pages.is_empty() && !skipped_pages.is_empty() {
+        return Err("No pages could be downloaded".to_string());
+    }
+    skipped_pages.sort_unstable();
 
     // Sort pages by index to maintain correct order in CBZ
     pages.sort_by_key(|p| p.index);
 
-    println!("[Downloader] All pages downloaded, creating CBZ...");
+    info!("All pages downloaded, creating CBZ...");
+
+    let page_sizes: Vec<u64> = pages.iter().map(|p| p.bytes.len() as u64).collect();
+
+    // Remove the partial file if writing fails or the download is cancelled
+    if let Err(e) = write_cbz(&cbz_path, pages, total_pages, options) {
+        let _ = std::fs::remove_file(&cbz_path);
+        return Err(e);
+    }
+
+    let report = DownloadReport {
+        path: cbz_path.to_string_lossy().to_string(),
+        page_count: page_sizes.len(),
+        total_bytes: page_sizes.iter().sum(),
+        page_sizes,
+        duration_ms: started.elapsed().as_millis() as u64,
+        skipped_pages,
+    };
+
+    info!(
+        "CBZ created successfully: {} ({} pages, {} bytes in {} ms)",
+        report.path, report.page_count, report.total_bytes, report.duration_ms
+    );
+
+    Ok(report)
+}
+
+/// Headers sent with every page request
+///
+/// Starts from the default Referer and User-Agent; `custom` entries replace
+/// them (names are case-insensitive) or are added.
+fn page_headers(custom: &HashMap<String, String>) -> Result<HeaderMap, String> {
+    let mut headers = HeaderMap::new();
+    headers.insert(REFERER, HeaderValue::from_static(DEFAULT_REFERER));
+    headers.insert(USER_AGENT, HeaderValue::from_static(DEFAULT_USER_AGENT));
+
+    for (name, value) in custom {
+        let name = HeaderName::from_bytes(name.as_bytes())
+            .map_err(|e| format!("Invalid header name \"{}\": {}", name, e))?;
+        let value = HeaderValue::from_str(value)
+            .map_err(|e| format!("Invalid value for header {}: {}", name, e))?;
+        headers.insert(name, value);
+    }
+
+    Ok(headers)
+}
+
+/// Fetch a page, resuming where a dropped connection left off
+///
+/// When the server sends `Accept-Ranges: bytes`, a retry asks only for the
+/// missing bytes with a `Range` header. Otherwise (or if the server answers the
+/// range with a full 200 response) the page is fetched again from the start.
+async fn fetch_page(
+    client: &Client,
+    url: &str,
+    index: usize,
+    headers: &HeaderMap,
+    progress: &ByteProgress,
+) -> Result<Vec<u8>, String> {
+    let mut bytes: Vec<u8> = Vec::new();
+    let mut supports_ranges = false;
+    let mut length_recorded = false;
+    let mut last_error = String::new();
+
+    for attempt in 1..=PAGE_FETCH_ATTEMPTS {
+        let resume_from = if supports_ranges { bytes.len() } else { 0 };
+        let mut request = client.get(url).headers(headers.clone());
+        if resume_from > 0 {
+            request = request.header("Range", format!("bytes={}-", resume_from));
+        }
+
+        let mut response = match request.send().await {
+            Ok(response) => response,
+            Err(e) => {
+                last_error = format!("Failed to fetch page {}: {}", index + 1, e);
+                continue;
+            }
+        };
+
+        let status = response.status();
+        if !status.is_success() {
+            return Err(format!(
+                "Failed to fetch page {}: HTTP {}",
+                index + 1,
+                status
+            ));
+        }
 
+        if status == reqwest::StatusCode::PARTIAL_CONTENT && resume_from > 0 {
+            info!(
+                "Resuming page {} from byte {} (attempt {})",
+                index + 1,
+                resume_from,
+                attempt
+            );
+        } else {
+            // A full response, including a server that ignored the range
+            progress
+                .done
+                .fetch_sub(bytes.len() as u64, Ordering::SeqCst);
+            bytes.clear();
+
+            let length = response.content_length();
+            if !length_recorded {
+                progress.page_length(length);
+                length_recorded = true;
+            }
+            if let Some(length) = length {
+                bytes.reserve(length as usize);
+            }
+
+            supports_ranges = response
+                .headers()
+                .get(reqwest::header::ACCEPT_RANGES)
+                .and_then(|value| value.to_str().ok())
+                .is_some_and(|value| value.eq_ignore_ascii_case("bytes"));
+        }
+
+        loop {
+            match response.chunk().await {
+                Ok(Some(chunk)) => {
+                    progress
+                        .done
+                        .fetch_add(chunk.len() as u64, Ordering::SeqCst);
+                    bytes.extend_from_slice(&chunk);
+                }
+                Ok(None) => return Ok(bytes),
+                Err(e) => {
+                    last_error = format!("Failed to read bytes for page {}: {}", index + 1, e);
+                    break;
+                }
+            }
+        }
+    }
+
+    Err(last_error)
+}
+
+/// Get the free space available on the volume containing `path`
+pub fn available_space(path: &Path) -> Result<u64, String> {
+    fs2::available_space(path).map_err(|e| format!("Failed to read free disk space: {}", e))
+}
+
+/// Check that a chapter of `page_count` pages fits while keeping `min_free_bytes` free
+fn check_disk_space(dir: &Path, page_count: usize, min_free_bytes: u64) -> Result<(), String> {
+    let available = available_space(dir)?;
+    let needed = (page_count as u64)
+        .saturating_mul(ESTIMATED_PAGE_BYTES)
+        .saturating_add(min_free_bytes);
+
+    if available < needed {
+        return Err(format!(
+            "Insufficient disk space: {} MB available, about {} MB needed",
+            available / (1024 * 1024),
+            needed / (1024 * 1024)
+        ));
+    }
+
+    Ok(())
+}
+
+/// Minimum digits in page file names, so existing chapters keep their names
+const MIN_PAGE_DIGITS: usize = 3;
+
+/// Zero-pad width that keeps every page name of a chapter in sort order
+fn page_digits(total_pages: usize) -> usize {
+    total_pages.to_string().len().max(MIN_PAGE_DIGITS)
+}
+
+/// File name of a page inside the CBZ, numbered from 1
+fn page_file_name(index: usize, total_pages: usize, extension: &str) -> String {
+    format!(
+        "{:0width$}.{}",
+        index + 1,
+        extension,
+        width = page_digits(total_pages)
+    )
+}
+
+/// Write downloaded pages to a CBZ file in page order
+///
+/// `total_pages` is the chapter's page count (including skipped pages), used
+/// to size the zero padding of page names.
+fn write_cbz(
+    cbz_path: &Path,
+    pages: Vec<PageDownload>,
+    total_pages: usize,
+    options: &DownloadOptions,
+) -> Result<(), String> {
     // Create the CBZ file
-    let file = File::create(&cbz_path).map_err(|e| format!("Failed to create CBZ file: {}", e))?;
+    let file = File::create(cbz_path).map_err(|e| format!("Failed to create CBZ file: {}", e))?;
     let mut zip = zip::ZipWriter::new(file);
 
     // ZIP options: Stored (no compression) is faster for already compressed images
-    let options = FileOptions::default()
+    let file_options = FileOptions::default()
         .compression_method(zip::CompressionMethod::Stored)
         .unix_permissions(0o755);
 
     // Write all pages to zip
     for page in pages {
-        let file_name = format!("{:03}.{}", page.index + 1, page.extension);
-        zip.start_file(file_name, options)
+        if options.is_cancelled() {
+            return Err(CANCELLED_ERROR.to_string());
+        }
+
+        let file_name = page_file_name(page.index, total_pages, &page.extension);
+        zip.start_file(file_name, file_options)
             .map_err(|e| format!("Zip error: {}", e))?;
         zip.write_all(&page.bytes)
             .map_err(|e| format!("Zip write error: {}", e))?;
@@ -147,10 +573,808 @@ pub async fn download_chapter_to_cbz(
     zip.finish()
         .map_err(|e| format!("Failed to finalize zip: {}", e))?;
 
-    println!(
-        "[Downloader] CBZ created successfully: {}",
-        cbz_path.display()
+    Ok(())
+}
+
+/// Whether the bytes are a WebP image (RIFF container with a WEBP form type)
+fn is_webp(bytes: &[u8]) -> bool {
+    bytes.len() >= 12 && &bytes[0..4] == b"RIFF" && &bytes[8..12] == b"WEBP"
+}
+
+/// Decode a WebP image and re-encode it as JPEG
+///
+/// JPEG has no alpha channel, so transparency is dropped.
+fn webp_to_jpeg(bytes: &[u8]) -> Result<Vec<u8>, String> {
+    let image = image::load_from_memory_with_format(bytes, image::ImageFormat::WebP)
+        .map_err(|e| format!("Invalid WebP image: {}", e))?;
+
+    let mut jpeg = std::io::Cursor::new(Vec::new());
+    image::DynamicImage::ImageRgb8(image.to_rgb8())
+        .write_to(&mut jpeg, image::ImageFormat::Jpeg)
+        .map_err(|e| format!("JPEG encoding failed: {}", e))?;
+
+    Ok(jpeg.into_inner())
+}
+
+/// Extract the pages of a CBZ into a folder for external viewers
+///
+/// Page file names are kept as they are in the archive. The archive is validated
+/// before anything is written: entries whose names would escape `dest_dir`
+/// (e.g. "../") cause the whole extraction to be rejected.
+///
+/// # Returns
+/// * Paths of the extracted images in page order
+pub fn extract_cbz(path: &str, dest_dir: &str) -> Result<Vec<String>, String> {
+    let file = File::open(path).map_err(|e| format!("Failed to open file: {}", e))?;
+    let mut archive =
+        zip::ZipArchive::new(file).map_err(|e| format!("Invalid CBZ archive: {}", e))?;
+
+    // Validate every entry and collect the image pages
+    let mut pages: Vec<(usize, String, std::path::PathBuf)> = Vec::new();
+    for i in 0..archive.len() {
+        let entry = archive
+            .by_index(i)
+            .map_err(|e| format!("Failed to read entry: {}", e))?;
+        let name = entry.name().to_string();
+
+        let out_path = cbz_reader::safe_zip_path(Path::new(dest_dir), &name)?;
+
+        if entry.is_file() && is_page_name(&name) {
+            pages.push((i, name, out_path));
+        }
+    }
+
+    pages.sort_by(|(_, a, _), (_, b, _)| natord::compare(&a.to_lowercase(), &b.to_lowercase()));
+
+    let mut extracted = Vec::with_capacity(pages.len());
+    for (index, name, out_path) in pages {
+        if let Some(parent) = out_path.parent() {
+            std::fs::create_dir_all(parent)
+                .map_err(|e| format!("Failed to create directory: {}", e))?;
+        }
+
+        let mut entry = archive
+            .by_index(index)
+            .map_err(|e| format!("Failed to read entry: {}", e))?;
+        let mut out =
+            File::create(&out_path).map_err(|e| format!("Failed to create file: {}", e))?;
+        std::io::copy(&mut entry, &mut out)
+            .map_err(|e| format!("Failed to extract {}: {}", name, e))?;
+
+        extracted.push(out_path.to_string_lossy().to_string());
+    }
+
+    info!("Extracted {} pages to {}", extracted.len(), dest_dir);
+
+    Ok(extracted)
+}
+
+/// Merge several CBZ files into one, e.g. chapters into a volume
+///
+/// Pages are taken from each input in the given order and renumbered across
+/// all of them, so the merged archive reads front to back. Every input is
+/// validated before the output is created: unreadable archives and entries
+/// with unsafe names (e.g. "../") reject the whole merge.
+///
+/// The archive is written next to `output` under a temporary name and only
+/// moved into place once complete, so a failed merge never touches an
+/// existing file.
+///
+/// # Arguments
+/// * `inputs` - CBZ files in reading order
+/// * `output` - Path of the merged CBZ
+/// * `overwrite` - Replace `output` if it already exists
+///
+/// # Returns
+/// * Number of pages in the merged CBZ
+pub fn merge_cbz(inputs: &[String], output: &str, overwrite: bool) -> Result<usize, String> {
+    if inputs.is_empty() {
+        return Err("No CBZ files to merge".to_string());
+    }
+    let output_path = Path::new(output);
+    if output_path.exists() && !overwrite {
+        return Err(format!("Output already exists: {}", output));
+    }
+
+    // Compare resolved paths so "./a.cbz" and "a.cbz" count as the same file
+    let resolved_output = resolve_path(output_path);
+    for input in inputs {
+        let resolved_input = Path::new(input)
+            .canonicalize()
+            .map_err(|e| format!("Failed to open {}: {}", input, e))?;
+        if Some(&resolved_input) == resolved_output.as_ref() {
+            return Err(format!("Output is also an input: {}", output));
+        }
+    }
+
+    // Open and validate every input, collecting its pages in reading order
+    let mut archives = Vec::with_capacity(inputs.len());
+    for input in inputs {
+        let file = File::open(input).map_err(|e| format!("Failed to open {}: {}", input, e))?;
+        let mut archive = zip::ZipArchive::new(file)
+            .map_err(|e| format!("Invalid CBZ archive {}: {}", input, e))?;
+
+        let mut pages: Vec<(usize, String)> = Vec::new();
+        for i in 0..archive.len() {
+            let entry = archive
+                .by_index(i)
+                .map_err(|e| format!("Failed to read entry in {}: {}", input, e))?;
+            let name = entry.name().to_string();
+            cbz_reader::safe_entry_name(&name)?;
+
+            if entry.is_file() && is_page_name(&name) {
+                pages.push((i, name));
+            }
+        }
+        pages.sort_by(|(_, a), (_, b)| natord::compare(&a.to_lowercase(), &b.to_lowercase()));
+        archives.push((archive, pages));
+    }
+
+    let total_pages: usize = archives.iter().map(|(_, pages)| pages.len()).sum();
+    if total_pages == 0 {
+        return Err("The CBZ files contain no pages".to_string());
+    }
+
+    let partial = output_path.with_extension("cbz.part");
+    let written = write_merged_cbz(&partial, &mut archives, total_pages).and_then(|_| {
+        std::fs::rename(&partial, output_path)
+            .map_err(|e| format!("Failed to save merged CBZ: {}", e))
+    });
+    if let Err(e) = written {
+        let _ = std::fs::remove_file(&partial);
+        return Err(e);
+    }
+
+    info!(
+        "Merged {} CBZ files ({} pages) into {}",
+        inputs.len(),
+        total_pages,
+        output
     );
 
-    Ok(cbz_path.to_string_lossy().to_string())
+    Ok(total_pages)
+}
+
+/// Canonical form of a path that may not exist yet (its parent must)
+fn resolve_path(path: &Path) -> Option<std::path::PathBuf> {
+    if let Ok(resolved) = path.canonicalize() {
+        return Some(resolved);
+    }
+    let parent = match path.parent() {
+        Some(parent) if !parent.as_os_str().is_empty() => parent,
+        _ => Path::new("."),
+    };
+    Some(parent.canonicalize().ok()?.join(path.file_name()?))
+}
+
+/// An opened CBZ with its page entries (index, name) in reading order
+type MergeInput = (zip::ZipArchive<File>, Vec<(usize, String)>);
+
+/// Copy the collected pages of each archive into a new CBZ, renumbered from 1
+fn write_merged_cbz(
+    output: &Path,
+    archives: &mut [MergeInput],
+    total_pages: usize,
+) -> Result<(), String> {
+    let file = File::create(output).map_err(|e| format!("Failed to create CBZ file: {}", e))?;
+    let mut zip = zip::ZipWriter::new(file);
+    let file_options = FileOptions::default()
+        .compression_method(zip::CompressionMethod::Stored)
+        .unix_permissions(0o755);
+
+    let mut index = 0;
+    for (archive, pages) in archives.iter_mut() {
+        for (entry_index, name) in pages.iter() {
+            let extension = name
+                .rsplit('.')
+                .next()
+                .unwrap_or("jpg")
+                .to_ascii_lowercase();
+            zip.start_file(page_file_name(index, total_pages, &extension), file_options)
+                .map_err(|e| format!("Zip error: {}", e))?;
+
+            let mut entry = archive
+                .by_index(*entry_index)
+                .map_err(|e| format!("Failed to read entry: {}", e))?;
+            std::io::copy(&mut entry, &mut zip)
+                .map_err(|e| format!("Failed to copy {}: {}", name, e))?;
+            index += 1;
+        }
+    }
+
+    zip.finish()
+        .map_err(|e| format!("Failed to finalize zip: {}", e))?;
+
+    Ok(())
+}
+
+/// Whether an archive entry is a page image (skipping macOS metadata)
+fn is_page_name(name: &str) -> bool {
+    let lower = name.to_lowercase();
+    let is_image = [".jpg", ".jpeg", ".png", ".gif", ".webp"]
+        .iter()
+        .any(|ext| lower.ends_with(ext));
+    let file_name = name.rsplit('/').next().unwrap_or(name);
+
+    is_image && !name.contains("__MACOSX") && !file_name.starts_with("._")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::TempDir;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::net::TcpListener;
+    use wiremock::matchers::{header, method, path};
+    use wiremock::{Mock, MockServer, Request, ResponseTemplate};
+
+    fn fake_image() -> ResponseTemplate {
+        ResponseTemplate::new(200)
+            .insert_header("Content-Type", "image/jpeg")
+            .set_body_bytes(b"fake-image-bytes".to_vec())
+    }
+
+    /// Serve a small fake image for every request, calling `on_request` with the request count
+    async fn image_server(on_request: impl Fn(usize) + Send + Sync + 'static) -> MockServer {
+        let server = MockServer::start().await;
+        let count = AtomicUsize::new(0);
+        Mock::given(method("GET"))
+            .respond_with(move |_: &Request| {
+                on_request(count.fetch_add(1, Ordering::SeqCst) + 1);
+                fake_image()
+            })
+            .mount(&server)
+            .await;
+        server
+    }
+
+    /// Serve a 64-byte page, dropping the first connection after 20 bytes
+    ///
+    /// Returns the base URL and the Range header of each request. A raw
+    /// listener, since `MockServer` always sends the whole body it promises.
+    async fn flaky_server(accept_ranges: bool) -> (String, Arc<Mutex<Vec<Option<String>>>>) {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let base = format!("http://{}", listener.local_addr().unwrap());
+        let ranges = Arc::new(Mutex::new(Vec::new()));
+        let seen = ranges.clone();
+
+        tokio::spawn(async move {
+            let body: Vec<u8> = (0..64).collect();
+            while let Ok((mut socket, _)) = listener.accept().await {
+                let mut buf = vec![0u8; 4096];
+                let read = socket.read(&mut buf).await.unwrap_or(0);
+                let request = String::from_utf8_lossy(&buf[..read]).to_lowercase();
+                let range = request
+                    .lines()
+                    .find_map(|line| line.strip_prefix("range: bytes="))
+                    .and_then(|range| range.trim().trim_end_matches('-').parse::<usize>().ok());
+
+                let first = {
+                    let mut seen = seen.lock().unwrap();
+                    seen.push(range.map(|start| format!("bytes={}-", start)));
+                    seen.len() == 1
+                };
+                let accept = if accept_ranges {
+                    "Accept-Ranges: bytes\r\n"
+                } else {
+                    ""
+                };
+
+                let (status, extra, part) = match range.filter(|_| accept_ranges) {
+                    Some(start) => (
+                        "206 Partial Content",
+                        format!("Content-Range: bytes {}-63/64\r\n", start),
+                        &body[start..],
+                    ),
+                    None => ("200 OK", String::new(), &body[..]),
+                };
+                let header = format!(
+                    "HTTP/1.1 {}\r\nContent-Length: {}\r\n{}{}Connection: close\r\n\r\n",
+                    status,
+                    part.len(),
+                    accept,
+                    extra
+                );
+                let _ = socket.write_all(header.as_bytes()).await;
+                let sent = if first { &part[..20] } else { part };
+                let _ = socket.write_all(sent).await;
+                let _ = socket.shutdown().await;
+            }
+        });
+
+        (base, ranges)
+    }
+
+    #[tokio::test]
+    async fn test_dropped_page_resumes_with_range() {
+        let (base, ranges) = flaky_server(true).await;
+
+        let page = fetch_page(
+            &Client::new(),
+            &format!("{}/1.jpg", base),
+            0,
+            &HeaderMap::new(),
+            &ByteProgress::default(),
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(page, (0..64).collect::<Vec<u8>>());
+        assert_eq!(
+            *ranges.lock().unwrap(),
+            [None, Some("bytes=20-".to_string())]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_dropped_page_refetched_without_range_support() {
+        let (base, ranges) = flaky_server(false).await;
+
+        let page = fetch_page(
+            &Client::new(),
+            &format!("{}/1.jpg", base),
+            0,
+            &HeaderMap::new(),
+            &ByteProgress::default(),
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(page, (0..64).collect::<Vec<u8>>());
+        assert_eq!(*ranges.lock().unwrap(), [None, None]);
+    }
+
+    #[tokio::test]
+    async fn test_custom_headers_are_sent() {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(header("referer", "https://mangadex.org/"))
+            .and(header("x-api-key", "secret"))
+            .respond_with(ResponseTemplate::new(200).set_body_bytes(b"page".to_vec()))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let dir = TempDir::new("download_headers");
+        let options = DownloadOptions {
+            min_free_bytes: 0,
+            headers: HashMap::from([
+                ("referer".to_string(), "https://mangadex.org/".to_string()),
+                ("X-Api-Key".to_string(), "secret".to_string()),
+            ]),
+            ..DownloadOptions::default()
+        };
+        download_chapter_to_cbz(
+            "Chapter 1".to_string(),
+            "Manga".to_string(),
+            page_urls(&server.uri(), 1),
+            dir.to_string_lossy().to_string(),
+            &options,
+        )
+        .await
+        .unwrap();
+
+        // Headers that weren't given keep their defaults
+        let requests = server.received_requests().await.unwrap();
+        assert_eq!(
+            requests[0].headers.get("user-agent").unwrap(),
+            DEFAULT_USER_AGENT
+        );
+    }
+
+    #[test]
+    fn test_invalid_custom_header_is_rejected() {
+        let headers = HashMap::from([("bad header".to_string(), "x".to_string())]);
+        assert!(page_headers(&headers).is_err());
+    }
+
+    fn page_urls(base: &str, count: usize) -> Vec<String> {
+        (1..=count).map(|i| format!("{}/{}.jpg", base, i)).collect()
+    }
+
+    #[tokio::test]
+    async fn test_download_creates_cbz() {
+        let dir = TempDir::new("download");
+        let server = image_server(|_| {}).await;
+
+        let report = download_chapter_to_cbz(
+            "Chapter 1".to_string(),
+            "Manga".to_string(),
+            page_urls(&server.uri(), 3),
+            dir.to_string_lossy().to_string(),
+            &DownloadOptions::default(),
+        )
+        .await
+        .unwrap();
+
+        let archive = zip::ZipArchive::new(File::open(&report.path).unwrap()).unwrap();
+        assert_eq!(archive.len(), 3);
+
+        // Every page is the 16-byte fake image
+        assert_eq!(report.page_count, 3);
+        assert_eq!(report.page_sizes, vec![16, 16, 16]);
+        assert_eq!(report.total_bytes, 48);
+    }
+
+    /// Serve the fake image, except a 404 for page 2
+    async fn missing_page_server() -> MockServer {
+        let server = MockServer::start().await;
+        Mock::given(path("/2.jpg"))
+            .respond_with(ResponseTemplate::new(404))
+            .mount(&server)
+            .await;
+        Mock::given(method("GET"))
+            .respond_with(fake_image())
+            .mount(&server)
+            .await;
+        server
+    }
+
+    #[tokio::test]
+    async fn test_skip_failed_builds_cbz_without_missing_page() {
+        let dir = TempDir::new("download_skip");
+        let server = missing_page_server().await;
+        let options = DownloadOptions {
+            skip_failed: true,
+            ..DownloadOptions::default()
+        };
+
+        let report = download_chapter_to_cbz(
+            "Chapter 1".to_string(),
+            "Manga".to_string(),
+            page_urls(&server.uri(), 3),
+            dir.to_string_lossy().to_string(),
+            &options,
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(report.skipped_pages, vec![1]);
+        assert_eq!(report.page_count, 2);
+        let archive = zip::ZipArchive::new(File::open(&report.path).unwrap()).unwrap();
+        assert_eq!(archive.len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_failed_page_aborts_by_default() {
+        let dir = TempDir::new("download_abort");
+        let server = missing_page_server().await;
+
+        let result = download_chapter_to_cbz(
+            "Chapter 1".to_string(),
+            "Manga".to_string(),
+            page_urls(&server.uri(), 3),
+            dir.to_string_lossy().to_string(),
+            &DownloadOptions::default(),
+        )
+        .await;
+
+        assert!(result.unwrap_err().contains("HTTP 404"));
+        assert!(!dir.join("Manga").join("Chapter 1.cbz").exists());
+    }
+
+    /// Download 3 pages and collect every progress report, sorted by pages done
+    async fn collect_progress(base: &str, name: &str) -> Vec<DownloadProgress> {
+        let dir = TempDir::new(name);
+        let reports = Arc::new(Mutex::new(Vec::new()));
+        let seen = reports.clone();
+        let options = DownloadOptions {
+            progress: Some(Arc::new(move |progress: &DownloadProgress| {
+                seen.lock().unwrap().push(*progress)
+            })),
+            ..DownloadOptions::default()
+        };
+
+        download_chapter_to_cbz(
+            "Chapter 1".to_string(),
+            "Manga".to_string(),
+            page_urls(base, 3),
+            dir.to_string_lossy().to_string(),
+            &options,
+        )
+        .await
+        .unwrap();
+
+        let mut reports = reports.lock().unwrap().clone();
+        reports.sort_by_key(|progress| progress.pages_done);
+        reports
+    }
+
+    #[tokio::test]
+    async fn test_byte_progress_accumulates() {
+        let server = image_server(|_| {}).await;
+        let reports = collect_progress(&server.uri(), "download_bytes").await;
+
+        assert_eq!(reports.len(), 3);
+        let last = reports[2];
+        assert_eq!((last.pages_done, last.pages_total), (3, 3));
+        assert_eq!(last.bytes_done, 48);
+        assert_eq!(last.bytes_total, Some(48));
+        assert!(reports.iter().all(|p| p.bytes_total == Some(48)));
+    }
+
+    #[tokio::test]
+    async fn test_missing_content_length_falls_back_to_pages() {
+        // A raw listener, since `MockServer` always sends a Content-Length
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let base = format!("http://{}", listener.local_addr().unwrap());
+        tokio::spawn(async move {
+            while let Ok((mut socket, _)) = listener.accept().await {
+                let mut buf = vec![0u8; 4096];
+                let _ = socket.read(&mut buf).await;
+                // Body ends when the connection closes
+                let _ = socket
+                    .write_all(b"HTTP/1.1 200 OK\r\nConnection: close\r\n\r\npage")
+                    .await;
+                let _ = socket.shutdown().await;
+            }
+        });
+
+        let reports = collect_progress(&base, "download_no_length").await;
+
+        assert_eq!(reports[2].pages_done, 3);
+        assert_eq!(reports[2].bytes_done, 12);
+        assert!(reports.iter().all(|p| p.bytes_total.is_none()));
+    }
+
+    #[test]
+    fn test_byte_total_is_extrapolated() {
+        let progress = ByteProgress::default();
+        progress.page_length(Some(100));
+        assert_eq!(progress.total(4), Some(400));
+
+        progress.page_length(Some(300));
+        assert_eq!(progress.total(2), Some(400));
+    }
+
+    #[tokio::test]
+    async fn test_cancel_mid_download_leaves_no_file() {
+        let dir = TempDir::new("download_cancel");
+        let (id, options) = register_download();
+
+        // Cancel as soon as the first page is requested
+        let cancel_id = id.clone();
+        let server = image_server(move |count| {
+            if count == 1 {
+                assert!(cancel_download(&cancel_id));
+            }
+        })
+        .await;
+
+        let result = download_chapter_to_cbz(
+            "Chapter 1".to_string(),
+            "Manga".to_string(),
+            page_urls(&server.uri(), 20),
+            dir.to_string_lossy().to_string(),
+            &options,
+        )
+        .await;
+        finish_download(&id);
+
+        assert_eq!(result.unwrap_err(), CANCELLED_ERROR);
+        assert!(!dir.join("Manga").join("Chapter 1.cbz").exists());
+        assert!(!cancel_download(&id));
+    }
+
+    #[test]
+    fn test_page_padding_follows_page_count() {
+        assert_eq!(page_file_name(0, 20, "jpg"), "001.jpg");
+        assert_eq!(page_file_name(999, 1000, "png"), "1000.png");
+
+        let names: Vec<String> = (0..1500).map(|i| page_file_name(i, 1500, "jpg")).collect();
+        assert_eq!(names[0], "0001.jpg");
+        assert_eq!(names[1499], "1500.jpg");
+        assert!(names.windows(2).all(|pair| pair[0] < pair[1]));
+    }
+
+    /// Write a zip with the given (name, contents) entries
+    fn write_zip(path: &Path, entries: &[(&str, &[u8])]) {
+        let mut zip = zip::ZipWriter::new(File::create(path).unwrap());
+        for (name, contents) in entries {
+            zip.start_file(*name, FileOptions::default()).unwrap();
+            zip.write_all(contents).unwrap();
+        }
+        zip.finish().unwrap();
+    }
+
+    #[test]
+    fn test_extract_cbz_in_page_order() {
+        let dir = TempDir::new("extract");
+        let cbz = dir.join("chapter.cbz");
+        write_zip(
+            &cbz,
+            &[
+                ("10.jpg", b"page 10"),
+                ("2.jpg", b"page 2"),
+                ("1.png", b"page 1"),
+                ("ComicInfo.xml", b"<ComicInfo/>"),
+                ("__MACOSX/._1.png", b"metadata"),
+            ],
+        );
+
+        let out = dir.join("pages");
+        let paths = extract_cbz(&cbz.to_string_lossy(), &out.to_string_lossy()).unwrap();
+
+        let names: Vec<String> = paths
+            .iter()
+            .map(|p| {
+                Path::new(p)
+                    .file_name()
+                    .unwrap()
+                    .to_string_lossy()
+                    .to_string()
+            })
+            .collect();
+        assert_eq!(names, vec!["1.png", "2.jpg", "10.jpg"]);
+        assert_eq!(std::fs::read(&paths[1]).unwrap(), b"page 2");
+        assert!(!out.join("ComicInfo.xml").exists());
+    }
+
+    #[test]
+    fn test_extract_cbz_rejects_traversal() {
+        let dir = TempDir::new("extract_traversal");
+        let cbz = dir.join("evil.cbz");
+        write_zip(&cbz, &[("001.jpg", b"page"), ("../../evil.jpg", b"evil")]);
+
+        let out = dir.join("pages");
+        let result = extract_cbz(&cbz.to_string_lossy(), &out.to_string_lossy());
+
+        assert!(result.unwrap_err().contains("Unsafe entry name"));
+        assert!(!out.join("001.jpg").exists());
+        assert!(!dir.parent().unwrap().join("evil.jpg").exists());
+    }
+
+    #[test]
+    fn test_extract_invalid_archive() {
+        let dir = TempDir::new("extract_invalid");
+        let path = dir.join("broken.cbz");
+        std::fs::write(&path, b"not a zip").unwrap();
+
+        assert!(extract_cbz(&path.to_string_lossy(), &dir.to_string_lossy()).is_err());
+    }
+
+    #[test]
+    fn test_merge_cbz_renumbers_pages() {
+        let dir = TempDir::new("merge");
+        let first = dir.join("Chapter 1.cbz");
+        let second = dir.join("Chapter 2.cbz");
+        write_zip(
+            &first,
+            &[
+                ("10.jpg", b"ch1 p10"),
+                ("2.jpg", b"ch1 p2"),
+                ("ComicInfo.xml", b"<ComicInfo/>"),
+            ],
+        );
+        write_zip(&second, &[("001.png", b"ch2 p1")]);
+
+        let output = dir.join("Volume 1.cbz");
+        let inputs = vec![
+            first.to_string_lossy().to_string(),
+            second.to_string_lossy().to_string(),
+        ];
+        let count = merge_cbz(&inputs, &output.to_string_lossy(), false).unwrap();
+        assert_eq!(count, 3);
+
+        let mut archive = zip::ZipArchive::new(File::open(&output).unwrap()).unwrap();
+        let mut names: Vec<String> = archive.file_names().map(String::from).collect();
+        names.sort();
+        assert_eq!(names, vec!["001.jpg", "002.jpg", "003.png"]);
+
+        let page = std::io::read_to_string(archive.by_name("003.png").unwrap()).unwrap();
+        assert_eq!(page, "ch2 p1");
+    }
+
+    #[test]
+    fn test_merge_cbz_rejects_bad_inputs() {
+        let dir = TempDir::new("merge_invalid");
+        let good = dir.join("good.cbz");
+        let evil = dir.join("evil.cbz");
+        let broken = dir.join("broken.cbz");
+        write_zip(&good, &[("001.jpg", b"page")]);
+        write_zip(&evil, &[("../../evil.jpg", b"evil")]);
+        std::fs::write(&broken, b"not a zip").unwrap();
+
+        let output = dir.join("merged.cbz");
+        let merge = |other: &Path| {
+            let inputs = vec![
+                good.to_string_lossy().to_string(),
+                other.to_string_lossy().to_string(),
+            ];
+            merge_cbz(&inputs, &output.to_string_lossy(), true)
+        };
+
+        assert!(merge(&evil).unwrap_err().contains("Unsafe entry name"));
+        assert!(merge(&broken).unwrap_err().contains("Invalid CBZ archive"));
+        assert!(!output.exists());
+
+        // A failed merge leaves an existing output alone, even when overwriting
+        std::fs::write(&output, b"volume").unwrap();
+        assert!(merge(&broken).is_err());
+        assert_eq!(std::fs::read(&output).unwrap(), b"volume");
+    }
+
+    #[test]
+    fn test_merge_cbz_protects_existing_files() {
+        let dir = TempDir::new("merge_existing");
+        let chapter = dir.join("Chapter 1.cbz");
+        write_zip(&chapter, &[("001.jpg", b"page")]);
+        let output = dir.join("Volume 1.cbz");
+        std::fs::write(&output, b"volume").unwrap();
+
+        let inputs = vec![chapter.to_string_lossy().to_string()];
+        let error = merge_cbz(&inputs, &output.to_string_lossy(), false).unwrap_err();
+        assert!(error.contains("already exists"));
+        assert_eq!(std::fs::read(&output).unwrap(), b"volume");
+
+        assert_eq!(merge_cbz(&inputs, &output.to_string_lossy(), true), Ok(1));
+
+        // The same file reached through a different spelling of its path
+        let same = dir.join(".").join("Chapter 1.cbz");
+        let error = merge_cbz(&inputs, &same.to_string_lossy(), true).unwrap_err();
+        assert!(error.contains("also an input"));
+        assert!(chapter.exists());
+    }
+
+    #[test]
+    fn test_convert_webp_to_jpeg() {
+        let mut webp = std::io::Cursor::new(Vec::new());
+        image::DynamicImage::ImageRgba8(image::RgbaImage::new(4, 3))
+            .write_to(&mut webp, image::ImageFormat::WebP)
+            .unwrap();
+        let webp = webp.into_inner();
+        assert!(is_webp(&webp));
+
+        let jpeg = webp_to_jpeg(&webp).unwrap();
+        assert_eq!(&jpeg[0..2], &[0xFF, 0xD8]);
+
+        let decoded = image::load_from_memory_with_format(&jpeg, image::ImageFormat::Jpeg).unwrap();
+        assert_eq!((decoded.width(), decoded.height()), (4, 3));
+    }
+
+    #[test]
+    fn test_invalid_webp_is_rejected() {
+        assert!(!is_webp(b"\xFF\xD8\xFF\xE0 jpeg data"));
+        assert!(webp_to_jpeg(b"RIFF\x00\x00\x00\x00WEBPbroken").is_err());
+    }
+
+    #[test]
+    fn test_list_downloaded_chapters() {
+        let dir = TempDir::new("downloaded_chapters");
+        std::fs::write(dir.join("Chapter 1.cbz"), b"").unwrap();
+        std::fs::write(dir.join("Chapter 2_ The Return.CBZ"), b"").unwrap();
+        std::fs::write(dir.join("cover.jpg"), b"").unwrap();
+        std::fs::create_dir(dir.join("Chapter 3.cbz")).unwrap();
+
+        let downloaded = list_downloaded_chapters(&dir).unwrap();
+        assert_eq!(
+            downloaded.iter().map(String::as_str).collect::<Vec<_>>(),
+            ["Chapter 1", "Chapter 2_ The Return"]
+        );
+        assert!(is_downloaded(&downloaded, "Chapter 2: The Return"));
+        assert!(!is_downloaded(&downloaded, "Chapter 3"));
+
+        assert!(list_downloaded_chapters(&dir.join("missing"))
+            .unwrap()
+            .is_empty());
+    }
+
+    #[test]
+    fn test_available_space_for_temp_dir() {
+        let dir = TempDir::new("disk_space");
+        assert!(available_space(&dir).unwrap() > 0);
+        assert!(available_space(&dir.join("missing")).is_err());
+    }
+
+    #[test]
+    fn test_insufficient_disk_space() {
+        let dir = TempDir::new("disk_space_low");
+        let err = check_disk_space(&dir, 10, u64::MAX / 2).unwrap_err();
+        assert!(err.starts_with("Insufficient disk space"));
+        assert!(check_disk_space(&dir, 0, 0).is_ok());
+
+        // Huge margins saturate instead of wrapping around to a passing value
+        assert!(check_disk_space(&dir, 10, u64::MAX).is_err());
+    }
 }
@@ -0,0 +1,205 @@
+//! List Export
+//!
+//! PURPOSE: Dump the user's full anime list from AniList or MAL to a JSON or CSV
+//! file, for backups or moving to another tracker.
+//!
+//! Both providers are mapped to the same flat entry so the output has the same
+//! columns regardless of source: title, id, status, progress, score.
+
+use serde::Serialize;
+use std::fs;
+use std::path::Path;
+use tracing::info;
+
+use crate::{anilist, config, myanimelist};
+
+/// Output file format
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExportFormat {
+    Json,
+    Csv,
+}
+
+impl ExportFormat {
+    pub fn parse(format: &str) -> Result<Self, String> {
+        match format.to_lowercase().as_str() {
+            "json" => Ok(Self::Json),
+            "csv" => Ok(Self::Csv),
+            other => Err(format!("Unsupported export format: {}", other)),
+        }
+    }
+}
+
+/// A list entry in provider-independent form
+#[derive(Debug, Clone, Serialize, PartialEq)]
+pub struct ExportEntry {
+    pub title: String,
+    /// AniList or MAL id, depending on the provider
+    pub id: i64,
+    /// Status in the provider's own vocabulary
+    pub status: String,
+    pub progress: i32,
+    pub score: f64,
+}
+
+impl From<anilist::MediaListItem> for ExportEntry {
+    fn from(item: anilist::MediaListItem) -> Self {
+        Self {
+            title: item.media.title.preferred(config::current().title_language),
+            id: item.media_id as i64,
+            status: item.status,
+            progress: item.progress,
+            score: item.score,
+        }
+    }
+}
+
+impl From<myanimelist::MalAnimeListEntry> for ExportEntry {
+    fn from(entry: myanimelist::MalAnimeListEntry) -> Self {
+        Self {
+            title: entry.anime.title,
+            id: entry.anime.id,
+            status: entry.status,
+            progress: entry.num_episodes_watched,
+            score: entry.score as f64,
+        }
+    }
+}
+
+/// Fetch the full list from a provider and write it to `path`
+///
+/// # Arguments
+/// * `provider` - "anilist" or "mal"
+/// * `access_token` - OAuth access token for the provider
+/// * `format` - "json" or "csv"
+/// * `path` - Destination file
+///
+/// # Returns
+/// * `Ok(path)` - Path of the written file
+pub async fn export_list(
+    provider: &str,
+    access_token: &str,
+    format: &str,
+    path: &str,
+) -> Result<String, String> {
+    let format = ExportFormat::parse(format)?;
+
+    let entries: Vec<ExportEntry> = match provider.to_lowercase().as_str() {
+        "anilist" => anilist::get_media_list(access_token)
+            .await?
+            .into_iter()
+            .map(ExportEntry::from)
+            .collect(),
+        "mal" | "myanimelist" => myanimelist::get_full_anime_list(access_token, None)
+            .await?
+            .into_iter()
+            .map(ExportEntry::from)
+            .collect(),
+        other => return Err(format!("Unknown provider: {}", other)),
+    };
+
+    write_export(&entries, format, Path::new(path))?;
+    info!("Wrote {} entries to {}", entries.len(), path);
+
+    Ok(path.to_string())
+}
+
+/// Write entries to disk in the given format
+pub fn write_export(
+    entries: &[ExportEntry],
+    format: ExportFormat,
+    path: &Path,
+) -> Result<(), String> {
+    let contents = match format {
+        ExportFormat::Json => serde_json::to_string_pretty(entries)
+            .map_err(|e| format!("Serialization error: {}", e))?,
+        ExportFormat::Csv => to_csv(entries),
+    };
+
+    if let Some(parent) = path.parent().filter(|p| !p.as_os_str().is_empty()) {
+        fs::create_dir_all(parent).map_err(|e| format!("Failed to create directory: {}", e))?;
+    }
+    fs::write(path, contents).map_err(|e| format!("Failed to write export: {}", e))
+}
+
+/// Render entries as CSV with a header row
+pub fn to_csv(entries: &[ExportEntry]) -> String {
+    let mut csv = String::from("title,id,status,progress,score\n");
+    for entry in entries {
+        csv.push_str(&format!(
+            "{},{},{},{},{}\n",
+            csv_field(&entry.title),
+            entry.id,
+            csv_field(&entry.status),
+            entry.progress,
+            entry.score
+        ));
+    }
+    csv
+}
+
+/// Quote a field if it contains a separator, quote or line break
+fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::TempDir;
+
+    fn mal_entry(id: i64, title: &str, status: &str, watched: i32, score: i32) -> ExportEntry {
+        ExportEntry::from(myanimelist::MalAnimeListEntry {
+            anime: myanimelist::MalMediaNode {
+                id,
+                title: title.to_string(),
+                main_picture: None,
+                num_episodes: None,
+                num_chapters: None,
+                status: None,
+            },
+            status: status.to_string(),
+            score,
+            num_episodes_watched: watched,
+        })
+    }
+
+    #[test]
+    fn test_list_to_csv() {
+        let entries = vec![
+            mal_entry(52991, "Sousou no Frieren", "completed", 28, 10),
+            mal_entry(1, "Love, Chunibyo & Other \"Delusions\"", "watching", 3, 0),
+        ];
+
+        assert_eq!(
+            to_csv(&entries),
+            "title,id,status,progress,score\n\
+             Sousou no Frieren,52991,completed,28,10\n\
+             \"Love, Chunibyo & Other \"\"Delusions\"\"\",1,watching,3,0\n"
+        );
+    }
+
+    #[test]
+    fn test_write_json_round_trip() {
+        let dir = TempDir::new("export");
+        let path = dir.join("list.json");
+
+        let entries = vec![mal_entry(52991, "Sousou no Frieren", "completed", 28, 10)];
+        write_export(&entries, ExportFormat::Json, &path).unwrap();
+
+        let json: serde_json::Value =
+            serde_json::from_str(&fs::read_to_string(&path).unwrap()).unwrap();
+        assert_eq!(json[0]["id"], 52991);
+        assert_eq!(json[0]["progress"], 28);
+    }
+
+    #[test]
+    fn test_parse_format() {
+        assert_eq!(ExportFormat::parse("CSV"), Ok(ExportFormat::Csv));
+        assert!(ExportFormat::parse("xml").is_err());
+    }
+}
@@ -1,8 +1,13 @@
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 use std::fs;
+use std::io::Read;
 use std::path::Path;
 use std::time::SystemTime;
 
+/// Read buffer size used when hashing (videos can be several GB)
+const HASH_CHUNK_SIZE: usize = 1024 * 1024;
+
 #[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct FileItem {
     pub name: String,
@@ -93,3 +98,112 @@ pub fn get_folder_contents(path: String) -> Result<Vec<FileItem>, String> {
 
     Ok(files)
 }
+
+/// Compute the BLAKE3 hash of a file as a hex string
+/// The file is read in chunks so large videos are never loaded into memory
+#[tauri::command]
+pub fn hash_file(path: String) -> Result<String, String> {
+    let mut file = fs::File::open(&path).map_err(|e| format!("Failed to open file: {}", e))?;
+    let mut hasher = blake3::Hasher::new();
+    let mut buffer = vec![0u8; HASH_CHUNK_SIZE];
+
+    loop {
+        let read = file
+            .read(&mut buffer)
+            .map_err(|e| format!("Failed to read file: {}", e))?;
+        if read == 0 {
+            break;
+        }
+        hasher.update(&buffer[..read]);
+    }
+
+    Ok(hasher.finalize().to_hex().to_string())
+}
+
+/// Group files with identical contents
+/// Files are grouped by size first, so only same-size files are hashed.
+/// Returns groups of two or more paths; files that can't be read are skipped.
+#[tauri::command]
+pub fn find_duplicates(paths: Vec<String>) -> Result<Vec<Vec<String>>, String> {
+    let mut by_size: HashMap<u64, Vec<String>> = HashMap::new();
+    for path in paths {
+        if let Ok(metadata) = fs::metadata(&path) {
+            if metadata.is_file() {
+                by_size.entry(metadata.len()).or_default().push(path);
+            }
+        }
+    }
+
+    let mut groups = Vec::new();
+    for candidates in by_size.into_values().filter(|c| c.len() > 1) {
+        let mut by_hash: HashMap<String, Vec<String>> = HashMap::new();
+        for path in candidates {
+            if let Ok(hash) = hash_file(path.clone()) {
+                by_hash.entry(hash).or_default().push(path);
+            }
+        }
+        groups.extend(by_hash.into_values().filter(|g| g.len() > 1));
+    }
+
+    // Stable output order for the UI
+    for group in &mut groups {
+        group.sort();
+    }
+    groups.sort();
+
+    Ok(groups)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::TempDir;
+
+    fn path_string(path: &Path) -> String {
+        path.to_string_lossy().to_string()
+    }
+
+    #[test]
+    fn test_hash_file_blake3_digest() {
+        let dir = TempDir::new("hash");
+        let path = dir.join("empty.mkv");
+        fs::write(&path, b"").unwrap();
+
+        assert_eq!(
+            hash_file(path_string(&path)).unwrap(),
+            "af1349b9f5f9a1a6a0404dea36dcc9499bcb25c9adc112b7cc9a93cae41f3262"
+        );
+    }
+
+    #[test]
+    fn test_find_duplicate_copy() {
+        let dir = TempDir::new("duplicates");
+        let original = dir.join("Frieren - 01.mkv");
+        let copy = dir.join("copy of Frieren - 01.mkv");
+        let same_size = dir.join("Frieren - 02.mkv");
+        let episode: Vec<u8> = (0..3 * HASH_CHUNK_SIZE / 2).map(|i| i as u8).collect();
+        let mut other = episode.clone();
+        other[0] ^= 0xFF;
+
+        fs::write(&original, &episode).unwrap();
+        fs::write(&copy, &episode).unwrap();
+        fs::write(&same_size, &other).unwrap();
+
+        assert_eq!(
+            hash_file(path_string(&original)),
+            hash_file(path_string(&copy))
+        );
+
+        let groups = find_duplicates(vec![
+            path_string(&original),
+            path_string(&copy),
+            path_string(&same_size),
+            path_string(&dir.join("missing.mkv")),
+        ])
+        .unwrap();
+
+        let mut expected = vec![path_string(&original), path_string(&copy)];
+        expected.sort();
+        assert_eq!(groups, vec![expected]);
+    }
+}
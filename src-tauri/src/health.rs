@@ -0,0 +1,135 @@
+//! Integration Health Checks
+//!
+//! PURPOSE: Let users "test the connection" to each tracker before relying on auto-scrobble
+//! Each check makes a lightweight authenticated read (AniList Viewer / MAL @me)
+//! and never changes anything on the user's account.
+
+use serde::Serialize;
+
+use crate::{anilist, myanimelist};
+
+/// Why a health check failed
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum HealthErrorKind {
+    /// Token (or client ID) was rejected - the user needs to log in again
+    Unauthorized,
+    /// Too many requests, try again later
+    RateLimited,
+    /// The service couldn't be reached (offline, DNS, TLS, timeout)
+    Network,
+    /// Any other API or response error
+    Api,
+}
+
+/// Result of a health check
+#[derive(Debug, Clone, Serialize)]
+pub struct HealthCheck {
+    pub ok: bool,
+    pub user_name: Option<String>,
+    pub error_kind: Option<HealthErrorKind>,
+    pub message: Option<String>,
+}
+
+impl HealthCheck {
+    fn success(user_name: Option<String>) -> Self {
+        Self {
+            ok: true,
+            user_name,
+            error_kind: None,
+            message: None,
+        }
+    }
+
+    fn failure(message: String) -> Self {
+        Self {
+            ok: false,
+            user_name: None,
+            error_kind: Some(classify_error(&message)),
+            message: Some(message),
+        }
+    }
+}
+
+/// Check that an AniList access token works
+pub async fn check_anilist(access_token: &str) -> HealthCheck {
+    match anilist::get_viewer(access_token).await {
+        Ok(viewer) => HealthCheck::success(Some(viewer.name)),
+        Err(e) => HealthCheck::failure(e),
+    }
+}
+
+/// Check that a MAL access token and client ID work
+///
+/// The client ID is validated too when a token is given, since refreshing
+/// the token needs it. Without an access token, only the client ID is validated.
+pub async fn check_mal(access_token: &str, client_id: &str) -> HealthCheck {
+    if access_token.is_empty() {
+        return match myanimelist::check_client_id(client_id).await {
+            Ok(()) => HealthCheck::success(None),
+            Err(e) => HealthCheck::failure(e),
+        };
+    }
+
+    let user = match myanimelist::get_user_info(access_token).await {
+        Ok(user) => user,
+        Err(e) => return HealthCheck::failure(e),
+    };
+    if !client_id.is_empty() {
+        if let Err(e) = myanimelist::check_client_id(client_id).await {
+            return HealthCheck::failure(e);
+        }
+    }
+
+    HealthCheck::success(Some(user.name))
+}
+
+/// Map an API error message to an error kind
+///
+/// The provider modules report HTTP failures as "... (HTTP 401): ..." and
+/// transport failures as "Request failed: ..." / "Failed to send request: ...".
+fn classify_error(message: &str) -> HealthErrorKind {
+    if message.contains("(HTTP 401)") || message.contains("(HTTP 403)") {
+        HealthErrorKind::Unauthorized
+    } else if message.contains("(HTTP 429)") {
+        HealthErrorKind::RateLimited
+    } else if message.starts_with("Request failed") || message.starts_with("Failed to send request")
+    {
+        HealthErrorKind::Network
+    } else {
+        HealthErrorKind::Api
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_classify_unauthorized() {
+        assert_eq!(
+            classify_error("Failed to get viewer (HTTP 401): Invalid token"),
+            HealthErrorKind::Unauthorized
+        );
+        assert_eq!(
+            classify_error("Failed to get user info (HTTP 403): forbidden"),
+            HealthErrorKind::Unauthorized
+        );
+    }
+
+    #[test]
+    fn test_classify_other_errors() {
+        assert_eq!(
+            classify_error("Failed to get viewer (HTTP 429): Too Many Requests"),
+            HealthErrorKind::RateLimited
+        );
+        assert_eq!(
+            classify_error("Request failed: error sending request"),
+            HealthErrorKind::Network
+        );
+        assert_eq!(
+            classify_error("Failed to parse response: missing field `data`"),
+            HealthErrorKind::Api
+        );
+    }
+}
@@ -0,0 +1,308 @@
+//! Detection History
+//!
+//! PURPOSE: Keep the last N detection events so users can see what was
+//! recently detected and whether it was scrobbled.
+//!
+//! Records live in an in-memory ring buffer that is persisted to
+//! `history.json` in the app data dir after every change.
+
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::SystemTime;
+
+/// Default number of records kept
+pub const DEFAULT_HISTORY_LIMIT: usize = 50;
+
+const HISTORY_FILE: &str = "history.json";
+
+/// A single detection event
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct HistoryRecord {
+    /// Unix timestamp (seconds) of the detection
+    pub timestamp: u64,
+    pub window_title: String,
+    /// AniList id of the matched anime
+    pub anime_id: Option<i32>,
+    /// MAL id of the matched anime, when AniList links one
+    #[serde(default)]
+    pub mal_id: Option<i64>,
+    pub anime_title: Option<String>,
+    pub episode: Option<i32>,
+    /// Whether progress was sent to a tracker for this detection
+    pub scrobbled: bool,
+}
+
+impl HistoryRecord {
+    /// Create a record for a detection happening now
+    pub fn now(
+        window_title: String,
+        anime_id: Option<i32>,
+        mal_id: Option<i64>,
+        anime_title: Option<String>,
+        episode: Option<i32>,
+    ) -> Self {
+        let timestamp = SystemTime::now()
+            .duration_since(SystemTime::UNIX_EPOCH)
+            .map(|d| d.as_secs())
+            .unwrap_or_default();
+
+        Self {
+            timestamp,
+            window_title,
+            anime_id,
+            mal_id,
+            anime_title,
+            episode,
+            scrobbled: false,
+        }
+    }
+
+    /// Same detection as another record, ignoring time and scrobble state
+    fn same_detection(&self, other: &HistoryRecord) -> bool {
+        self.window_title == other.window_title
+            && self.anime_id == other.anime_id
+            && self.episode == other.episode
+    }
+}
+
+/// Fixed-size ring buffer of detection records (oldest first)
+#[derive(Debug)]
+pub struct History {
+    records: VecDeque<HistoryRecord>,
+    limit: usize,
+}
+
+impl History {
+    pub fn new(limit: usize) -> Self {
+        Self {
+            records: VecDeque::with_capacity(limit),
+            limit,
+        }
+    }
+
+    /// Add a record, dropping the oldest when full
+    ///
+    /// Detection is polled, so a record identical to the latest one is skipped.
+    /// Returns true if the record was added.
+    pub fn push(&mut self, record: HistoryRecord) -> bool {
+        if self
+            .records
+            .back()
+            .is_some_and(|last| last.same_detection(&record))
+        {
+            return false;
+        }
+
+        while self.records.len() >= self.limit.max(1) {
+            self.records.pop_front();
+        }
+        self.records.push_back(record);
+        true
+    }
+
+    /// Mark the latest record for this AniList anime/episode as scrobbled
+    ///
+    /// Returns true if a record was updated.
+    pub fn mark_scrobbled(&mut self, anime_id: i32, episode: i32) -> bool {
+        self.mark_latest(|r| r.anime_id == Some(anime_id) && r.episode == Some(episode))
+    }
+
+    /// Mark the latest record for this MAL anime/episode as scrobbled
+    ///
+    /// Returns true if a record was updated.
+    pub fn mark_mal_scrobbled(&mut self, mal_id: i64, episode: i32) -> bool {
+        self.mark_latest(|r| r.mal_id == Some(mal_id) && r.episode == Some(episode))
+    }
+
+    fn mark_latest(&mut self, matches: impl Fn(&HistoryRecord) -> bool) -> bool {
+        match self.records.iter_mut().rev().find(|r| matches(r)) {
+            Some(record) => {
+                record.scrobbled = true;
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// All records, newest first
+    pub fn records(&self) -> Vec<HistoryRecord> {
+        self.records.iter().rev().cloned().collect()
+    }
+
+    pub fn clear(&mut self) {
+        self.records.clear();
+    }
+
+    /// Load history from the data dir, starting empty if missing or unreadable
+    pub fn load(data_dir: &Path, limit: usize) -> Self {
+        let mut history = Self::new(limit);
+
+        let records: Vec<HistoryRecord> = fs::read_to_string(history_path(data_dir))
+            .ok()
+            .and_then(|json| serde_json::from_str(&json).ok())
+            .unwrap_or_default();
+
+        // Keep only the newest `limit` records
+        let skip = records.len().saturating_sub(limit);
+        history.records.extend(records.into_iter().skip(skip));
+        history
+    }
+
+    /// Persist history to the data dir
+    pub fn save(&self, data_dir: &Path) -> Result<(), String> {
+        fs::create_dir_all(data_dir).map_err(|e| format!("Failed to create data dir: {}", e))?;
+
+        let json = serde_json::to_string_pretty(&self.records)
+            .map_err(|e| format!("Serialization error: {}", e))?;
+        fs::write(history_path(data_dir), json)
+            .map_err(|e| format!("Failed to write history: {}", e))
+    }
+}
+
+fn history_path(data_dir: &Path) -> PathBuf {
+    data_dir.join(HISTORY_FILE)
+}
+
+lazy_static::lazy_static! {
+    /// Shared history, loaded from disk on first use
+    static ref HISTORY: Mutex<Option<History>> = Mutex::new(None);
+}
+
+/// Run `f` against the shared history, loading it from disk first if needed
+fn with_history<R>(data_dir: &Path, f: impl FnOnce(&mut History) -> R) -> Result<R, String> {
+    let mut guard = HISTORY.lock().map_err(|_| "History lock error")?;
+    let history = guard.get_or_insert_with(|| History::load(data_dir, DEFAULT_HISTORY_LIMIT));
+    Ok(f(history))
+}
+
+/// Append a detection and persist it (duplicates of the latest record are skipped)
+pub fn record_detection(data_dir: &Path, record: HistoryRecord) -> Result<(), String> {
+    with_history(data_dir, |history| {
+        if history.push(record) {
+            history.save(data_dir)
+        } else {
+            Ok(())
+        }
+    })?
+}
+
+/// Mark the latest detection of this AniList anime/episode as scrobbled and persist it
+pub fn mark_scrobbled(data_dir: &Path, anime_id: i32, episode: i32) -> Result<(), String> {
+    with_history(data_dir, |history| {
+        if history.mark_scrobbled(anime_id, episode) {
+            history.save(data_dir)
+        } else {
+            Ok(())
+        }
+    })?
+}
+
+/// Mark the latest detection of this MAL anime/episode as scrobbled and persist it
+pub fn mark_mal_scrobbled(data_dir: &Path, mal_id: i64, episode: i32) -> Result<(), String> {
+    with_history(data_dir, |history| {
+        if history.mark_mal_scrobbled(mal_id, episode) {
+            history.save(data_dir)
+        } else {
+            Ok(())
+        }
+    })?
+}
+
+/// Get all history records, newest first
+pub fn get_history(data_dir: &Path) -> Result<Vec<HistoryRecord>, String> {
+    with_history(data_dir, |history| history.records())
+}
+
+/// Remove all history records
+pub fn clear_history(data_dir: &Path) -> Result<(), String> {
+    with_history(data_dir, |history| {
+        history.clear();
+        history.save(data_dir)
+    })?
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::TempDir;
+
+    fn record(title: &str, episode: i32) -> HistoryRecord {
+        HistoryRecord::now(
+            title.to_string(),
+            Some(1),
+            Some(52991),
+            Some("Frieren".to_string()),
+            Some(episode),
+        )
+    }
+
+    #[test]
+    fn test_ring_buffer_drops_oldest() {
+        let mut history = History::new(3);
+        for ep in 1..=5 {
+            history.push(record(&format!("Frieren - {:02}", ep), ep));
+        }
+
+        let episodes: Vec<Option<i32>> = history.records().iter().map(|r| r.episode).collect();
+        assert_eq!(episodes, vec![Some(5), Some(4), Some(3)]);
+    }
+
+    #[test]
+    fn test_skips_repeated_detection() {
+        let mut history = History::new(10);
+        assert!(history.push(record("Frieren - 05", 5)));
+        assert!(!history.push(record("Frieren - 05", 5)));
+        assert!(history.push(record("Frieren - 06", 6)));
+        assert_eq!(history.records().len(), 2);
+    }
+
+    #[test]
+    fn test_mark_scrobbled() {
+        let mut history = History::new(10);
+        history.push(record("Frieren - 05", 5));
+        history.push(record("Frieren - 06", 6));
+
+        assert!(history.mark_scrobbled(1, 5));
+        assert!(!history.mark_scrobbled(1, 7));
+
+        let records = history.records();
+        assert!(!records[0].scrobbled);
+        assert!(records[1].scrobbled);
+    }
+
+    #[test]
+    fn test_mark_mal_scrobbled() {
+        let mut history = History::new(10);
+        history.push(record("Frieren - 05", 5));
+        history.push(record("Frieren - 06", 6));
+
+        // MAL updates are matched by the MAL id, not the AniList one
+        assert!(!history.mark_mal_scrobbled(1, 6));
+        assert!(history.mark_mal_scrobbled(52991, 6));
+        assert!(history.records()[0].scrobbled);
+    }
+
+    #[test]
+    fn test_records_without_mal_id_load() {
+        let json = r#"{"timestamp":1,"window_title":"Frieren - 05","anime_id":1,
+            "anime_title":null,"episode":5,"scrobbled":false}"#;
+        let record: HistoryRecord = serde_json::from_str(json).unwrap();
+        assert_eq!(record.mal_id, None);
+    }
+
+    #[test]
+    fn test_save_and_load() {
+        let dir = TempDir::new("history");
+        let mut history = History::new(10);
+        history.push(record("Frieren - 05", 5));
+        history.push(record("Frieren - 06", 6));
+        history.save(&dir).unwrap();
+
+        let loaded = History::load(&dir, 1);
+        assert_eq!(loaded.records().len(), 1);
+        assert_eq!(loaded.records()[0].episode, Some(6));
+    }
+}
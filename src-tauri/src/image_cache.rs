@@ -0,0 +1,279 @@
+//! Image Cache
+//!
+//! PURPOSE: Keep local copies of remote images such as AniList cover art
+//! Images are saved in a cache directory under a name derived from their URL.
+//! The ETag and Last-Modified headers of each download are stored next to the
+//! image, so a refresh can send a conditional request and keep the cached file
+//! when the server answers 304 Not Modified.
+
+use reqwest::header::{ETAG, IF_MODIFIED_SINCE, IF_NONE_MATCH, LAST_MODIFIED};
+use reqwest::{Client, StatusCode, Url};
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use tracing::info;
+
+/// Extensions kept for cached images; anything else is saved as .jpg
+const IMAGE_EXTENSIONS: [&str; 5] = ["jpg", "jpeg", "png", "gif", "webp"];
+
+/// Validators from the response an image was cached from
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct CacheValidators {
+    /// ETag header, sent back as If-None-Match
+    pub etag: Option<String>,
+    /// Last-Modified header, sent back as If-Modified-Since
+    pub last_modified: Option<String>,
+}
+
+impl CacheValidators {
+    fn is_empty(&self) -> bool {
+        self.etag.is_none() && self.last_modified.is_none()
+    }
+}
+
+/// Cached file location for an image URL
+///
+/// The name is a blake3 hash of the URL, so it stays the same across builds
+/// and cached files survive toolchain upgrades.
+pub fn cache_path(cache_dir: &Path, url: &str) -> PathBuf {
+    let mut hasher = blake3::Hasher::new();
+    hasher.update(url.as_bytes());
+    cache_dir.join(format!(
+        "{}.{}",
+        &hasher.finalize().to_hex()[..32],
+        image_extension(url)
+    ))
+}
+
+/// Image extension from the URL's path (query and host ignored), default "jpg"
+fn image_extension(url: &str) -> &'static str {
+    let extension = Url::parse(url).ok().and_then(|url| {
+        let file_name = url.path_segments()?.next_back()?.to_string();
+        let (_, extension) = file_name.rsplit_once('.')?;
+        Some(extension.to_ascii_lowercase())
+    });
+    IMAGE_EXTENSIONS
+        .iter()
+        .find(|known| Some(**known) == extension.as_deref())
+        .copied()
+        .unwrap_or("jpg")
+}
+
+/// Sidecar file holding the validators of a cached image
+fn validators_path(image: &Path) -> PathBuf {
+    image.with_extension("validators.json")
+}
+
+fn load_validators(image: &Path) -> CacheValidators {
+    std::fs::read_to_string(validators_path(image))
+        .ok()
+        .and_then(|json| serde_json::from_str(&json).ok())
+        .unwrap_or_default()
+}
+
+/// Return a cached image, downloading it when missing
+///
+/// # Arguments
+/// * `client` - HTTP client used for the download
+/// * `cache_dir` - Directory images are stored in (created if missing)
+/// * `url` - Image URL
+/// * `refresh` - Revalidate an already cached image with a conditional request
+///
+/// # Returns
+/// * `Ok(path)` - Local image, freshly downloaded or still valid
+/// * `Err` - The download failed and nothing is cached
+pub async fn fetch_image(
+    client: &Client,
+    cache_dir: &Path,
+    url: &str,
+    refresh: bool,
+) -> Result<PathBuf, String> {
+    let path = cache_path(cache_dir, url);
+    let cached = path.is_file();
+    if cached && !refresh {
+        info!("Cache hit: {}", url);
+        return Ok(path);
+    }
+
+    std::fs::create_dir_all(cache_dir).map_err(|e| format!("Failed to create cache dir: {}", e))?;
+
+    let mut request = client.get(url);
+    if cached {
+        let validators = load_validators(&path);
+        if let Some(etag) = &validators.etag {
+            request = request.header(IF_NONE_MATCH, etag);
+        }
+        if let Some(last_modified) = &validators.last_modified {
+            request = request.header(IF_MODIFIED_SINCE, last_modified);
+        }
+    }
+
+    info!("Downloading: {}", url);
+    let response = request
+        .send()
+        .await
+        .map_err(|e| format!("Download failed: {}", e))?;
+
+    if cached && response.status() == StatusCode::NOT_MODIFIED {
+        info!("Not modified: {}", url);
+        return Ok(path);
+    }
+    if !response.status().is_success() {
+        return Err(format!("Download failed: HTTP {}", response.status()));
+    }
+
+    let header = |name| {
+        response
+            .headers()
+            .get(name)
+            .and_then(|value| value.to_str().ok())
+            .map(String::from)
+    };
+    let validators = CacheValidators {
+        etag: header(ETAG),
+        last_modified: header(LAST_MODIFIED),
+    };
+
+    let bytes = response
+        .bytes()
+        .await
+        .map_err(|e| format!("Failed to read response: {}", e))?;
+
+    // Written under a temporary name so a failed write never replaces a good image
+    let partial = path.with_extension("part");
+    std::fs::write(&partial, &bytes).map_err(|e| format!("Failed to write file: {}", e))?;
+    std::fs::rename(&partial, &path).map_err(|e| format!("Failed to save image: {}", e))?;
+
+    let sidecar = validators_path(&path);
+    if validators.is_empty() {
+        let _ = std::fs::remove_file(&sidecar);
+    } else if let Ok(json) = serde_json::to_string(&validators) {
+        let _ = std::fs::write(&sidecar, json);
+    }
+
+    info!("Saved to: {}", path.display());
+    Ok(path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::TempDir;
+    use wiremock::matchers::{header, method, path};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    const ETAG_VALUE: &str = "\"cover-v1\"";
+    const LAST_MODIFIED_VALUE: &str = "Wed, 14 Oct 2026 08:00:00 GMT";
+
+    /// Serve a cover with validators, answering 304 when the ETag matches
+    ///
+    /// Returns the image URL and the server, which records the requests.
+    async fn cover_server() -> (String, MockServer) {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/cover.jpg"))
+            .and(header("if-none-match", ETAG_VALUE))
+            .respond_with(ResponseTemplate::new(304))
+            .mount(&server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/cover.jpg"))
+            .respond_with(
+                ResponseTemplate::new(200)
+                    .insert_header("ETag", ETAG_VALUE)
+                    .insert_header("Last-Modified", LAST_MODIFIED_VALUE)
+                    .set_body_bytes(b"cover".to_vec()),
+            )
+            .mount(&server)
+            .await;
+
+        (format!("{}/cover.jpg", server.uri()), server)
+    }
+
+    #[test]
+    fn test_cache_path_is_stable_per_url() {
+        let dir = Path::new("/cache");
+        let url = "https://s4.anilist.co/file/anilistcdn/media/anime/cover/large/bx1.png";
+
+        assert_eq!(cache_path(dir, url), cache_path(dir, url));
+        assert_eq!(
+            cache_path(dir, url).extension().unwrap(),
+            std::ffi::OsStr::new("png")
+        );
+        assert_ne!(
+            cache_path(dir, url),
+            cache_path(dir, "https://example.com/bx2.png")
+        );
+    }
+
+    #[test]
+    fn test_extension_comes_from_url_path() {
+        assert_eq!(image_extension("https://img.example/cover.PNG"), "png");
+        assert_eq!(image_extension("https://img.example/cover.jpg?v=2"), "jpg");
+        assert_eq!(image_extension("https://img.example/c.webp#top"), "webp");
+        // No extension in the last segment; the host's dots don't count
+        assert_eq!(image_extension("https://img.example.com/image"), "jpg");
+        assert_eq!(image_extension("https://img.example/cover.exe"), "jpg");
+        assert_eq!(image_extension("not a url"), "jpg");
+
+        let path = cache_path(Path::new("/cache"), "https://img.example.com/a/image");
+        assert_eq!(path.parent(), Some(Path::new("/cache")));
+    }
+
+    #[tokio::test]
+    async fn test_refresh_sends_validators_and_keeps_file_on_304() {
+        let dir = TempDir::new("image_cache");
+        let (url, server) = cover_server().await;
+        let client = Client::new();
+
+        let path = fetch_image(&client, &dir, &url, false).await.unwrap();
+        assert_eq!(std::fs::read(&path).unwrap(), b"cover");
+        assert_eq!(
+            load_validators(&path),
+            CacheValidators {
+                etag: Some(ETAG_VALUE.to_string()),
+                last_modified: Some(LAST_MODIFIED_VALUE.to_string()),
+            }
+        );
+
+        // Cached and not refreshing: no request at all
+        assert_eq!(
+            fetch_image(&client, &dir, &url, false).await,
+            Ok(path.clone())
+        );
+        assert_eq!(server.received_requests().await.unwrap().len(), 1);
+
+        let refreshed = fetch_image(&client, &dir, &url, true).await.unwrap();
+        assert_eq!(refreshed, path);
+        assert_eq!(std::fs::read(&path).unwrap(), b"cover");
+
+        let requests = server.received_requests().await.unwrap();
+        assert_eq!(requests.len(), 2);
+        assert_eq!(
+            requests[1].headers.get("if-none-match").unwrap(),
+            ETAG_VALUE
+        );
+        assert_eq!(
+            requests[1].headers.get("if-modified-since").unwrap(),
+            LAST_MODIFIED_VALUE
+        );
+    }
+
+    #[tokio::test]
+    async fn test_first_download_is_unconditional() {
+        let dir = TempDir::new("image_cache_first");
+        let (url, server) = cover_server().await;
+
+        // A stale sidecar without its image must not make the request conditional
+        let path = cache_path(&dir, &url);
+        std::fs::write(
+            validators_path(&path),
+            format!("{{\"etag\":{:?},\"last_modified\":null}}", ETAG_VALUE),
+        )
+        .unwrap();
+
+        fetch_image(&Client::new(), &dir, &url, true).await.unwrap();
+        assert_eq!(std::fs::read(&path).unwrap(), b"cover");
+        let requests = server.received_requests().await.unwrap();
+        assert!(!requests[0].headers.contains_key("if-none-match"));
+    }
+}
@@ -0,0 +1,204 @@
+//! MyAnimeList XML Import
+//!
+//! PURPOSE: Read a MAL list export (`animelist_*.xml`) so users migrating to PLAY-ON
+//! can push their existing watch list to AniList/MAL from the frontend.
+//!
+//! APPROACH: Stream the file with quick-xml instead of loading it into memory,
+//! since exports of long-time users can be large. Entries with missing or invalid
+//! fields are skipped and counted rather than failing the whole import.
+
+use quick_xml::events::Event;
+use quick_xml::Reader;
+use serde::Serialize;
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{BufRead, BufReader};
+use tracing::info;
+
+/// A single anime entry from the export
+#[derive(Debug, Clone, Serialize, PartialEq)]
+pub struct MalImportEntry {
+    pub mal_id: i64,
+    pub title: String,
+    pub watched_episodes: i32,
+    /// Status in MAL API vocabulary (watching, completed, on_hold, dropped, plan_to_watch)
+    pub status: String,
+    pub score: i32,
+}
+
+/// Result of importing an export file
+#[derive(Debug, Clone, Serialize)]
+pub struct MalImportResult {
+    pub entries: Vec<MalImportEntry>,
+    /// Number of malformed entries that were skipped
+    pub skipped: usize,
+}
+
+/// Import a MAL XML export from disk
+///
+/// # Arguments
+/// * `path` - Path to the exported XML file
+pub fn import_mal_xml(path: &str) -> Result<MalImportResult, String> {
+    let file = File::open(path).map_err(|e| format!("Failed to open file: {}", e))?;
+    parse_mal_xml(BufReader::new(file))
+}
+
+/// Parse a MAL XML export from any buffered reader
+pub fn parse_mal_xml<R: BufRead>(source: R) -> Result<MalImportResult, String> {
+    let mut reader = Reader::from_reader(source);
+    reader.config_mut().trim_text(true);
+
+    let mut buf = Vec::new();
+    let mut entries = Vec::new();
+    let mut skipped = 0;
+
+    // Fields of the <anime> element currently being read
+    let mut fields: Option<HashMap<String, String>> = None;
+    let mut current_tag: Option<String> = None;
+
+    loop {
+        let event = reader
+            .read_event_into(&mut buf)
+            .map_err(|e| format!("Invalid XML at position {}: {}", reader.error_position(), e))?;
+
+        match event {
+            Event::Start(e) => {
+                let name = String::from_utf8_lossy(e.name().as_ref()).to_string();
+                if name == "anime" {
+                    fields = Some(HashMap::new());
+                } else if fields.is_some() {
+                    current_tag = Some(name);
+                }
+            }
+            Event::Text(e) => {
+                if let (Some(fields), Some(tag)) = (fields.as_mut(), current_tag.as_ref()) {
+                    if let Ok(text) = e.unescape() {
+                        fields.insert(tag.clone(), text.trim().to_string());
+                    }
+                }
+            }
+            Event::CData(e) => {
+                if let (Some(fields), Some(tag)) = (fields.as_mut(), current_tag.as_ref()) {
+                    let text = String::from_utf8_lossy(&e.into_inner()).trim().to_string();
+                    fields.insert(tag.clone(), text);
+                }
+            }
+            Event::End(e) => {
+                if e.name().as_ref() == b"anime" {
+                    match fields.take().and_then(|f| entry_from_fields(&f)) {
+                        Some(entry) => entries.push(entry),
+                        None => skipped += 1,
+                    }
+                }
+                current_tag = None;
+            }
+            Event::Eof => break,
+            _ => {}
+        }
+
+        buf.clear();
+    }
+
+    if skipped > 0 {
+        info!("Skipped {} malformed entries", skipped);
+    }
+    info!("Imported {} entries", entries.len());
+
+    Ok(MalImportResult { entries, skipped })
+}
+
+/// Build an entry from the collected fields of an <anime> element
+fn entry_from_fields(fields: &HashMap<String, String>) -> Option<MalImportEntry> {
+    let mal_id: i64 = fields.get("series_animedb_id")?.parse().ok()?;
+    let title = fields.get("series_title")?.clone();
+    let status = map_export_status(fields.get("my_status")?)?;
+
+    // Episode count and score are optional in hand-edited exports
+    let watched_episodes = match fields.get("my_watched_episodes") {
+        Some(value) => value.parse().ok()?,
+        None => 0,
+    };
+    let score = match fields.get("my_score") {
+        Some(value) => value.parse().ok()?,
+        None => 0,
+    };
+
+    Some(MalImportEntry {
+        mal_id,
+        title,
+        watched_episodes,
+        status: status.to_string(),
+        score,
+    })
+}
+
+/// Map the export's display status to MAL API vocabulary
+fn map_export_status(status: &str) -> Option<&'static str> {
+    match status.to_lowercase().as_str() {
+        "watching" => Some("watching"),
+        "completed" => Some("completed"),
+        "on-hold" | "on hold" => Some("on_hold"),
+        "dropped" => Some("dropped"),
+        "plan to watch" => Some("plan_to_watch"),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SAMPLE_EXPORT: &str = r#"<?xml version="1.0" encoding="UTF-8" ?>
+<myanimelist>
+    <myinfo>
+        <user_name>tester</user_name>
+        <user_export_type>1</user_export_type>
+    </myinfo>
+    <anime>
+        <series_animedb_id>52991</series_animedb_id>
+        <series_title><![CDATA[Sousou no Frieren]]></series_title>
+        <series_episodes>28</series_episodes>
+        <my_watched_episodes>28</my_watched_episodes>
+        <my_score>10</my_score>
+        <my_status>Completed</my_status>
+    </anime>
+    <anime>
+        <series_animedb_id>40748</series_animedb_id>
+        <series_title><![CDATA[Jujutsu Kaisen]]></series_title>
+        <my_watched_episodes>5</my_watched_episodes>
+        <my_score>0</my_score>
+        <my_status>On-Hold</my_status>
+    </anime>
+    <anime>
+        <series_animedb_id>not-a-number</series_animedb_id>
+        <series_title><![CDATA[Broken Entry]]></series_title>
+        <my_status>Watching</my_status>
+    </anime>
+</myanimelist>"#;
+
+    #[test]
+    fn test_parse_sample_export() {
+        let result = parse_mal_xml(SAMPLE_EXPORT.as_bytes()).unwrap();
+
+        assert_eq!(result.entries.len(), 2);
+        assert_eq!(result.skipped, 1);
+
+        assert_eq!(
+            result.entries[0],
+            MalImportEntry {
+                mal_id: 52991,
+                title: "Sousou no Frieren".to_string(),
+                watched_episodes: 28,
+                status: "completed".to_string(),
+                score: 10,
+            }
+        );
+        assert_eq!(result.entries[1].status, "on_hold");
+        assert_eq!(result.entries[1].watched_episodes, 5);
+    }
+
+    #[test]
+    fn test_invalid_xml() {
+        assert!(parse_mal_xml("<myanimelist><anime></myanimelist>".as_bytes()).is_err());
+    }
+}
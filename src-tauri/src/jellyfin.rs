@@ -0,0 +1,237 @@
+//! Jellyfin Now Playing
+//!
+//! PURPOSE: Read what's playing on a Jellyfin server
+//! Jellyfin clients (web, TV apps, mobile) play inside their own windows or on
+//! other devices, so window titles can't see them. The server's `/Sessions`
+//! endpoint lists every active session with its now-playing item, including
+//! the series, season and episode numbers.
+//!
+//! AUTH: An API key (Dashboard > API Keys) sent as `X-Emby-Token`.
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::path::Path;
+use std::time::Duration;
+
+/// Jellyfin is usually on the local network
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(3);
+
+/// Sessions idle for longer than this aren't listed
+const ACTIVE_WITHIN_SECS: u32 = 60;
+
+/// Where to find the server
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+#[serde(default)]
+pub struct JellyfinSettings {
+    /// e.g. "http://192.168.1.10:8096"
+    pub server_url: String,
+    pub api_key: String,
+    /// Only report this user's sessions (None for any user)
+    pub user_name: Option<String>,
+}
+
+/// An item playing in a Jellyfin session
+#[derive(Debug, Clone, Serialize, PartialEq)]
+pub struct JellyfinNowPlaying {
+    /// Episode (or movie) name
+    pub name: String,
+    pub series_name: Option<String>,
+    pub season: Option<i32>,
+    pub episode: Option<i32>,
+    pub path: Option<String>,
+    /// Playback position in percent (0 - 100)
+    pub position_percent: Option<f64>,
+    pub is_paused: bool,
+    pub user_name: Option<String>,
+}
+
+impl JellyfinNowPlaying {
+    /// Title to parse for detection
+    ///
+    /// Built as "Series S01E05" when Jellyfin knows the episode, else the file
+    /// name, else the item name.
+    pub fn detection_title(&self) -> String {
+        if let (Some(series), Some(episode)) = (&self.series_name, self.episode) {
+            return format!("{} S{:02}E{:02}", series, self.season.unwrap_or(1), episode);
+        }
+        self.path
+            .as_deref()
+            .and_then(|p| Path::new(p).file_name())
+            .map(|name| name.to_string_lossy().to_string())
+            .unwrap_or_else(|| self.name.clone())
+    }
+}
+
+fn as_i32(value: &Value) -> Option<i32> {
+    value.as_i64().and_then(|n| i32::try_from(n).ok())
+}
+
+/// Pick the now-playing item from a `/Sessions` response
+///
+/// Sessions of other users are skipped when `user_name` is set. A playing
+/// session wins over a paused one.
+pub fn parse_sessions(json: &Value, user_name: Option<&str>) -> Option<JellyfinNowPlaying> {
+    let items: Vec<JellyfinNowPlaying> = json
+        .as_array()?
+        .iter()
+        .filter(|session| {
+            user_name.is_none_or(|user| {
+                session["UserName"]
+                    .as_str()
+                    .is_some_and(|name| name.eq_ignore_ascii_case(user))
+            })
+        })
+        .filter_map(|session| {
+            let item = session.get("NowPlayingItem")?;
+            let play_state = &session["PlayState"];
+
+            // Both in 100ns ticks
+            let runtime = item["RunTimeTicks"].as_f64().filter(|t| *t > 0.0);
+            let position = play_state["PositionTicks"].as_f64();
+            let position_percent = match (position, runtime) {
+                (Some(position), Some(runtime)) => Some(position / runtime * 100.0),
+                _ => None,
+            };
+
+            Some(JellyfinNowPlaying {
+                name: item["Name"].as_str().unwrap_or_default().to_string(),
+                series_name: item["SeriesName"].as_str().map(str::to_string),
+                season: as_i32(&item["ParentIndexNumber"]),
+                episode: as_i32(&item["IndexNumber"]),
+                path: item["Path"].as_str().map(str::to_string),
+                position_percent,
+                is_paused: play_state["IsPaused"].as_bool().unwrap_or(false),
+                user_name: session["UserName"].as_str().map(str::to_string),
+            })
+        })
+        .collect();
+
+    let playing = items.iter().position(|item| !item.is_paused).unwrap_or(0);
+    items.into_iter().nth(playing)
+}
+
+/// Get what's playing on the Jellyfin server
+///
+/// # Returns
+/// * `Ok(None)` - Nothing is playing (for the configured user)
+/// * `Err` - The server couldn't be reached or rejected the API key
+pub async fn now_playing(
+    settings: &JellyfinSettings,
+) -> Result<Option<JellyfinNowPlaying>, String> {
+    let url = format!("{}/Sessions", settings.server_url.trim_end_matches('/'));
+
+    let client = reqwest::Client::builder()
+        .timeout(REQUEST_TIMEOUT)
+        .build()
+        .map_err(|e| format!("Failed to build HTTP client: {}", e))?;
+
+    let response = client
+        .get(&url)
+        .header("X-Emby-Token", &settings.api_key)
+        .query(&[("activeWithinSeconds", ACTIVE_WITHIN_SECS)])
+        .send()
+        .await
+        .map_err(|e| format!("Request failed: {}", e))?;
+
+    let status = response.status();
+    if !status.is_success() {
+        let error_text = response.text().await.unwrap_or_default();
+        return Err(format!(
+            "Failed to get Jellyfin sessions (HTTP {}): {}",
+            status.as_u16(),
+            error_text
+        ));
+    }
+
+    let json: Value = response
+        .json()
+        .await
+        .map_err(|e| format!("Failed to parse response: {}", e))?;
+
+    Ok(parse_sessions(&json, settings.user_name.as_deref()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+    use wiremock::matchers::{header, method, path, query_param};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    fn sessions() -> Value {
+        json!([
+            { "UserName": "guest", "Client": "Jellyfin Web" },
+            {
+                "UserName": "alice",
+                "Client": "Jellyfin Android TV",
+                "NowPlayingItem": {
+                    "Name": "The Hero's Statue",
+                    "SeriesName": "Frieren: Beyond Journey's End",
+                    "ParentIndexNumber": 1,
+                    "IndexNumber": 5,
+                    "Type": "Episode",
+                    "RunTimeTicks": 14_400_000_000i64,
+                    "Path": "/media/anime/Frieren/Season 01/Frieren - S01E05.mkv"
+                },
+                "PlayState": { "PositionTicks": 3_600_000_000i64, "IsPaused": false }
+            }
+        ])
+    }
+
+    #[test]
+    fn test_parse_playing_session() {
+        let playing = parse_sessions(&sessions(), None).unwrap();
+        assert_eq!(playing.episode, Some(5));
+        assert_eq!(playing.season, Some(1));
+        assert_eq!(playing.position_percent, Some(25.0));
+        assert_eq!(
+            playing.detection_title(),
+            "Frieren: Beyond Journey's End S01E05"
+        );
+    }
+
+    #[test]
+    fn test_parse_sessions_for_user() {
+        assert!(parse_sessions(&sessions(), Some("ALICE")).is_some());
+        assert_eq!(parse_sessions(&sessions(), Some("guest")), None);
+        assert_eq!(parse_sessions(&json!([]), None), None);
+    }
+
+    #[tokio::test]
+    async fn test_now_playing_sends_api_key() {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/Sessions"))
+            .and(header("X-Emby-Token", "key"))
+            .and(query_param("activeWithinSeconds", "60"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(sessions()))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let settings = JellyfinSettings {
+            server_url: format!("{}/", server.uri()),
+            api_key: "key".to_string(),
+            user_name: None,
+        };
+        let playing = now_playing(&settings).await.unwrap().unwrap();
+        assert_eq!(playing.user_name.as_deref(), Some("alice"));
+    }
+
+    #[tokio::test]
+    async fn test_now_playing_rejected_key() {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .respond_with(ResponseTemplate::new(401))
+            .mount(&server)
+            .await;
+
+        let settings = JellyfinSettings {
+            server_url: server.uri(),
+            api_key: "wrong".to_string(),
+            user_name: None,
+        };
+        let err = now_playing(&settings).await.unwrap_err();
+        assert!(err.contains("(HTTP 401)"));
+    }
+}
@@ -21,6 +21,57 @@ mod cbz_reader;
 mod downloader;
 // Import MyAnimeList module
 mod myanimelist;
+// Import integration health checks
+mod health;
+// Import detection history module
+mod history;
+// Import scrobble rules module
+mod scrobble;
+// Import MAL list import module
+mod import;
+// Import list export module
+mod export;
+// Import title matcher module
+mod matcher;
+// Import download queue module
+mod download_queue;
+// Import app config module
+mod config;
+// Import logging module
+mod logging;
+// Import OAuth helpers (PKCE, callbacks)
+mod oauth;
+// Import anime detection module
+mod detector;
+// Import AniList genre/tag cache
+mod taxonomy;
+// Import unified list status module
+mod status;
+// Import mpv IPC client
+mod mpv_ipc;
+// Import VLC HTTP interface client
+mod vlc_http;
+// Import library grouping module
+mod library;
+// Import diagnostics module
+mod diagnostics;
+// Import Jellyfin sessions client
+mod jellyfin;
+// Import now-playing source aggregation
+mod now_playing;
+// Import common tracker interface
+mod tracker;
+// Import video file tag reader
+mod media_meta;
+// Import video thumbnail extraction
+mod thumbnail;
+// Import remote image cache
+mod image_cache;
+// Import cancellable search sessions
+mod search_session;
+// Import fixtures shared by the unit tests
+#[cfg(test)]
+mod test_util;
 
 // Platform-conditional imports for unified interface
 #[cfg(windows)]
@@ -29,25 +80,76 @@ use win_name as platform_window;
 #[cfg(target_os = "macos")]
 use mac_name as platform_window;
 
+use std::collections::HashMap;
 use std::net::{IpAddr, SocketAddr};
 use std::str::FromStr;
+use std::sync::Mutex;
 use tauri::{
     menu::{Menu, MenuItem},
     tray::{MouseButton, MouseButtonState, TrayIconBuilder, TrayIconEvent},
 };
 use tauri::{Emitter, Manager};
+use tracing::{debug, error, info, warn};
+
+use status::UnifiedStatus;
 
 /// Tauri command to search for anime on AniList
 ///
 /// # Arguments
 /// * `query` - Search query (anime title)
 /// * `limit` - Maximum number of results (default: 10)
+/// * `include_adult` - Include adult (18+) entries (default: false)
+/// * `format` - Only return this format, e.g. "MOVIE" (default: any)
+/// * `session` - Search session name; a new search in the same session makes
+///   the previous one fail with "Search cancelled" (default: never cancelled)
 ///
 /// # Returns
 /// * JSON string with array of anime results
 #[tauri::command]
-async fn search_anime_command(query: String, limit: Option<i32>) -> Result<String, String> {
-    let results = anilist::search_anime(&query, limit.unwrap_or(10)).await?;
+async fn search_anime_command(
+    query: String,
+    limit: Option<i32>,
+    include_adult: Option<bool>,
+    format: Option<anilist::MediaFormat>,
+    session: Option<String>,
+) -> Result<String, String> {
+    let search = anilist::search_anime(
+        &query,
+        limit.unwrap_or(10),
+        include_adult.unwrap_or(false),
+        format,
+    );
+    let results = match session {
+        Some(session) => search_session::run(&session, search).await??,
+        None => search.await?,
+    };
+    serde_json::to_string(&results).map_err(|e| format!("Serialization error: {}", e))
+}
+
+/// Tauri command to search AniList once typing has paused
+/// Each call restarts the wait; only the last call of a burst reaches AniList,
+/// the earlier ones fail with "Search cancelled"
+///
+/// # Arguments
+/// * `query` - Search query (anime title)
+/// * `debounce_ms` - Quiet period before searching (default: 300, at most 2000)
+/// * `session` - Search session name; calls only coalesce within a session
+///   (default: one shared session)
+/// * `limit` - Maximum number of results (default: 10)
+///
+/// # Returns
+/// * JSON string with array of anime results
+#[tauri::command]
+async fn search_anime_debounced(
+    query: String,
+    debounce_ms: Option<u64>,
+    session: Option<String>,
+    limit: Option<i32>,
+) -> Result<String, String> {
+    let session = session.unwrap_or_else(|| "search_anime_debounced".to_string());
+    let delay = std::time::Duration::from_millis(debounce_ms.unwrap_or(300));
+    let search = anilist::search_anime(&query, limit.unwrap_or(10), false, None);
+    let results = search_session::run_debounced(&session, delay, search).await??;
     serde_json::to_string(&results).map_err(|e| format!("Serialization error: {}", e))
 }
 
@@ -64,6 +166,157 @@ async fn get_anime_by_id_command(id: i32) -> Result<String, String> {
     serde_json::to_string(&anime).map_err(|e| format!("Serialization error: {}", e))
 }
 
+/// Tauri command to resolve the AniList/MAL id pair of an anime
+///
+/// # Arguments
+/// * `anilist_id` - AniList id, if known
+/// * `mal_id` - MAL id, if known
+///
+/// # Returns
+/// * JSON string `{ anilist_id, mal_id }` (either may be null when unmapped)
+#[tauri::command]
+async fn resolve_cross_ids_command(
+    anilist_id: Option<i32>,
+    mal_id: Option<i64>,
+) -> Result<String, String> {
+    let ids = anilist::resolve_cross_ids(anilist_id, mal_id).await?;
+    serde_json::to_string(&ids).map_err(|e| format!("Serialization error: {}", e))
+}
+
+/// Tauri command to get the sequels, prequels and side stories of an anime
+///
+/// # Arguments
+/// * `media_id` - AniList anime ID
+///
+/// # Returns
+/// * JSON string with array of `{ relation_type, id, title }`
+#[tauri::command]
+async fn get_relations(media_id: i32) -> Result<String, String> {
+    let relations = anilist::get_relations(media_id).await?;
+    serde_json::to_string(&relations).map_err(|e| format!("Serialization error: {}", e))
+}
+
+/// Tauri command to get the anime trending on AniList
+///
+/// # Arguments
+/// * `page` - Page number (default: 1)
+/// * `per_page` - Entries per page (default: 20, at most 50)
+///
+/// # Returns
+/// * JSON string with array of anime results
+#[tauri::command]
+async fn get_trending(page: Option<i32>, per_page: Option<i32>) -> Result<String, String> {
+    let trending = anilist::get_trending(page.unwrap_or(1), per_page.unwrap_or(20)).await?;
+    serde_json::to_string(&trending).map_err(|e| format!("Serialization error: {}", e))
+}
+
+/// Tauri command to get the most popular anime of a season
+///
+/// # Arguments
+/// * `season` - "WINTER", "SPRING", "SUMMER" or "FALL" (default: current season)
+/// * `year` - Season year (default: current season's year)
+/// * `all` - Return every anime of the season instead of the top 20 (default false)
+///
+/// # Returns
+/// * JSON string with array of anime results
+#[tauri::command]
+async fn get_popular_season(
+    season: Option<String>,
+    year: Option<i32>,
+    all: Option<bool>,
+) -> Result<String, String> {
+    let season = season
+        .as_deref()
+        .map(anilist::MediaSeason::parse)
+        .transpose()?;
+    let popular = anilist::get_popular_season(season, year, all.unwrap_or(false)).await?;
+    serde_json::to_string(&popular).map_err(|e| format!("Serialization error: {}", e))
+}
+
+/// Tauri command to get the logged-in AniList user's profile
+///
+/// # Arguments
+/// * `access_token` - AniList access token
+///
+/// # Returns
+/// * JSON string `{ id, name, avatar }`, avatar null when the user has none
+#[tauri::command]
+async fn get_anilist_user(access_token: String) -> Result<String, String> {
+    let user = anilist::get_anilist_user(&access_token).await?;
+    serde_json::to_string(&user).map_err(|e| format!("Serialization error: {}", e))
+}
+
+/// Tauri command to get the user's AniList notifications
+///
+/// # Arguments
+/// * `access_token` - AniList access token
+/// * `unread_only` - Only return unread notifications (default: false)
+///
+/// # Returns
+/// * JSON string with array of `{ id, kind, media_id, text, created_at }`
+#[tauri::command]
+async fn get_notifications(
+    access_token: String,
+    unread_only: Option<bool>,
+) -> Result<String, String> {
+    let notifications =
+        anilist::get_notifications(&access_token, unread_only.unwrap_or(false)).await?;
+    serde_json::to_string(&notifications).map_err(|e| format!("Serialization error: {}", e))
+}
+
+/// Tauri command to get AniList's genre list (cached on disk for a week)
+///
+/// # Returns
+/// * JSON string with array of genre names
+#[tauri::command]
+async fn get_genre_collection(app: tauri::AppHandle) -> Result<String, String> {
+    let genres = taxonomy::get_genre_collection(&app_data_dir(&app)?).await?;
+    serde_json::to_string(&genres).map_err(|e| format!("Serialization error: {}", e))
+}
+
+/// Tauri command to get AniList's media tag list (cached on disk for a week)
+///
+/// # Returns
+/// * JSON string with array of `{ name, description, category, isAdult }`
+#[tauri::command]
+async fn get_media_tags(app: tauri::AppHandle) -> Result<String, String> {
+    let tags = taxonomy::get_media_tags(&app_data_dir(&app)?).await?;
+    serde_json::to_string(&tags).map_err(|e| format!("Serialization error: {}", e))
+}
+
+/// Tauri command to run a custom GraphQL query against AniList
+///
+/// # Arguments
+/// * `query` - GraphQL document
+/// * `variables_json` - JSON object with the query variables (optional)
+/// * `token` - AniList access token (optional)
+///
+/// # Returns
+/// * The raw AniList JSON response
+#[tauri::command]
+async fn anilist_raw_query(
+    query: String,
+    variables_json: Option<String>,
+    token: Option<String>,
+) -> Result<String, String> {
+    let response = anilist::raw_query(&query, variables_json.as_deref(), token.as_deref()).await?;
+    serde_json::to_string(&response).map_err(|e| format!("Serialization error: {}", e))
+}
+
+/// Tauri command to search AniList and return the best match for a title
+///
+/// # Arguments
+/// * `title` - Anime title to resolve
+///
+/// # Returns
+/// * JSON string `{ anilist_id, mal_id, title, episodes, cover, confidence }`
+///   or null if nothing matched confidently
+#[tauri::command]
+async fn resolve_anime(title: String) -> Result<String, String> {
+    let resolved = matcher::resolve_anime(&title).await?;
+    serde_json::to_string(&resolved).map_err(|e| format!("Serialization error: {}", e))
+}
+
 /// Tauri command to match anime from window title
 /// This combines media detection with AniList search
 ///
@@ -87,6 +340,35 @@ async fn match_anime_from_window_command() -> Result<String, String> {
     serde_json::to_string(&anime).map_err(|e| format!("Serialization error: {}", e))
 }
 
+/// Tauri command to match anime from a local file path
+/// Uses the parent folders for context (e.g. "Season 2") when searching AniList
+///
+/// # Arguments
+/// * `path` - Full path to the video file
+///
+/// # Returns
+/// * JSON with parsed info and matched anime (or null if no match)
+#[tauri::command]
+async fn match_anime_from_path_command(path: String) -> Result<String, String> {
+    use serde_json::json;
+
+    let parsed = media_meta::parse_local_file(&path);
+
+    let anime = match title_parser::build_search_query(&parsed) {
+        Some(query) => anilist::search_anime(&query, 1, false, matcher::format_hint(&query))
+            .await?
+            .into_iter()
+            .next(),
+        None => None,
+    };
+
+    Ok(json!({
+        "parsed": parsed,
+        "anilist_match": anime
+    })
+    .to_string())
+}
+
 /// Tauri command to get the currently active window title
 /// Returns the window title as a String, or "No active window" if none found
 ///
@@ -109,11 +391,11 @@ fn get_active_media_window() -> String {
     // Get active window title
     let title = match platform_window::get_active_window_title() {
         Some(t) => {
-            println!("[DEBUG] Active window title: {:?}", t);
+            debug!("Active window title: {:?}", t);
             t
         }
         None => {
-            println!("[DEBUG] No active window found");
+            debug!("No active window found");
             return "No active window".to_string();
         }
     };
@@ -121,12 +403,12 @@ fn get_active_media_window() -> String {
     // Check if it's a media player
     match detect_media_player(&title) {
         Some(player) => {
-            println!("[DEBUG] Detected media player: {:?}", player);
+            debug!("Detected media player: {:?}", player);
             // Return structured info
             format!("{:?}: {}", player, title)
         }
         None => {
-            println!("[DEBUG] Not a media player: {}", title);
+            debug!("Not a media player: {}", title);
             // Not a media player - ignore
             "No media playing".to_string()
         }
@@ -145,6 +427,63 @@ async fn exchange_login_code(
     serde_json::to_string(&token_data).map_err(|e| format!("Serialization error: {}", e))
 }
 
+/// Generate an OAuth `state` to add to an authorize URL
+/// The callback is only accepted if it returns this state
+#[tauri::command]
+fn generate_oauth_state() -> String {
+    oauth::generate_oauth_state()
+}
+
+/// Parse an OAuth callback deep link (`playon://auth?...` or `playon://auth#...`)
+/// The `state` must match one from `generate_oauth_state`
+///
+/// # Returns
+/// * JSON `{ code, state, access_token, token_type, expires_in, error, error_description }`
+#[tauri::command]
+fn parse_auth_callback(url: String) -> Result<String, String> {
+    let callback = oauth::parse_auth_callback(&url)?;
+    if callback.error.is_none() {
+        oauth::verify_state(callback.state.as_deref())?;
+    }
+    serde_json::to_string(&callback).map_err(|e| format!("Serialization error: {}", e))
+}
+
+/// Build the authorize URL for a provider and open it in the default browser
+///
+/// # Arguments
+/// * `provider` - "anilist" or "mal"
+/// * `client_id` - OAuth client ID
+/// * `redirect_uri` - Callback URI registered with the provider
+/// * `scopes` - Optional scopes to request
+///
+/// # Returns
+/// * JSON `{ url, state, code_verifier }`; keep `code_verifier` for the MAL token exchange
+#[tauri::command]
+fn begin_oauth(
+    app: tauri::AppHandle,
+    provider: String,
+    client_id: String,
+    redirect_uri: String,
+    scopes: Option<Vec<String>>,
+) -> Result<String, String> {
+    use tauri_plugin_opener::OpenerExt;
+
+    let provider = oauth::OAuthProvider::parse(&provider)?;
+    let request = oauth::build_authorize_url(
+        provider,
+        &client_id,
+        &redirect_uri,
+        &scopes.unwrap_or_default(),
+    );
+
+    info!("Opening {:?} authorization page", provider);
+    app.opener()
+        .open_url(&request.url, None::<&str>)
+        .map_err(|e| format!("Failed to open browser: {}", e))?;
+
+    serde_json::to_string(&request).map_err(|e| format!("Serialization error: {}", e))
+}
+
 /// Tauri command to parse a window title and extract anime info
 ///
 /// # Arguments
@@ -158,246 +497,886 @@ fn parse_window_title_command(window_title: String) -> String {
     serde_json::to_string(&parsed).unwrap_or_else(|_| "null".to_string())
 }
 
-/// Simple in-memory cache for AniList lookups
-/// This prevents hammering the API with repeated lookups for the same title
-use std::collections::HashMap;
-use std::sync::Mutex;
-use std::time::{Duration, Instant};
+/// Tauri command to show how a title is parsed, step by step
+/// Meant for bug reports about misparsed titles
+///
+/// # Returns
+/// * JSON string `{ input, canonical, cleaned, normalized, site, strategy, result }`
+#[tauri::command]
+fn explain_parse(title: String) -> Result<String, String> {
+    let explanation = title_parser::explain_parse(&title);
+    serde_json::to_string(&explanation).map_err(|e| format!("Serialization error: {}", e))
+}
 
-struct CacheEntry {
-    anime: Option<anilist::Anime>,
-    timestamp: Instant,
+/// Tauri command to parse many titles in one invoke (e.g. a folder of episodes)
+///
+/// # Returns
+/// * JSON array of parsed titles, in the same order as `titles`
+#[tauri::command]
+fn parse_titles_batch(titles: Vec<String>) -> Result<String, String> {
+    let parsed = title_parser::parse_titles_batch(&titles);
+    serde_json::to_string(&parsed).map_err(|e| format!("Serialization error: {}", e))
 }
 
-lazy_static::lazy_static! {
-    static ref ANILIST_CACHE: Mutex<HashMap<String, CacheEntry>> = Mutex::new(HashMap::new());
+/// Tauri command to group file names by the series they belong to
+///
+/// # Returns
+/// * JSON object of series title → files `{ file, episode, season }` sorted by episode
+#[tauri::command]
+fn group_by_series(titles: Vec<String>) -> Result<String, String> {
+    let groups = library::group_by_series(&titles);
+    serde_json::to_string(&groups).map_err(|e| format!("Serialization error: {}", e))
 }
 
-const CACHE_DURATION: Duration = Duration::from_secs(300); // 5 minutes
+/// Tauri command to detect anime from the current media player window
+/// Combines: media detection → title parsing → AniList search (with caching)
+///
+/// # Arguments
+/// * `access_token` - Optional AniList token; shows on the user's list are matched first
+/// * `poller` - Name of the polling UI; each keeps its own debounce and backoff state
+///
+/// # Returns
+/// * JSON with detected anime info including parsed title, episode, and matched AniList entry
+#[tauri::command]
+async fn detect_anime_command(
+    app: tauri::AppHandle,
+    access_token: Option<String>,
+    poller: Option<String>,
+) -> Result<String, String> {
+    let poller = poller.as_deref().unwrap_or(detector::DEFAULT_POLLER);
+    let result = detector::detect_or_manual(detector::manual_tracking(), || {
+        detect_anime(&app, access_token.as_deref())
+    })
+    .await;
+    detector::record_poll(poller, &result);
 
-fn get_cached_anime(title: &str) -> Option<Option<anilist::Anime>> {
-    let cache = ANILIST_CACHE.lock().ok()?;
-    if let Some(entry) = cache.get(title) {
-        if entry.timestamp.elapsed() < CACHE_DURATION {
-            return Some(entry.anime.clone());
-        }
-    }
-    None
+    // Brief focus changes (alt-tab) shouldn't flip the state to idle
+    let result = detector::debounce(poller, result, config::current().idle_debounce_polls);
+    serde_json::to_string(&result).map_err(|e| format!("Serialization error: {}", e))
 }
 
-fn set_cached_anime(title: String, anime: Option<anilist::Anime>) {
-    if let Ok(mut cache) = ANILIST_CACHE.lock() {
-        cache.insert(
-            title,
-            CacheEntry {
-                anime,
-                timestamp: Instant::now(),
-            },
-        );
-    }
+/// Tauri command to set what's being watched by hand
+/// Detection is bypassed and reports this entry until `clear_manual_tracking`,
+/// so scrobbling targets it (for players detection can't read)
+///
+/// # Arguments
+/// * `media_id` - AniList media ID
+/// * `episode` - Episode being watched
+///
+/// # Returns
+/// * JSON detection result now reported for the entry
+#[tauri::command]
+async fn set_manual_tracking(media_id: i32, episode: i32) -> Result<String, String> {
+    let anime = anilist::get_anime_by_id(media_id).await?;
+    let manual = detector::ManualTracking::new(anime, episode)?;
+    let detection = manual.detection();
+    detector::set_manual_tracking(manual);
+    serde_json::to_string(&detection).map_err(|e| format!("Serialization error: {}", e))
 }
 
-/// Tauri command to detect anime from the current media player window
-/// Combines: media detection → title parsing → AniList search (with caching)
+/// Tauri command to go back to detecting what's playing
+#[tauri::command]
+fn clear_manual_tracking() {
+    detector::clear_manual_tracking();
+}
+
+/// Tauri command to get how long to wait before the next detection poll
+/// Backs off from `poll_interval_ms` up to `max_poll_interval_ms` while nothing is playing
+///
+/// # Arguments
+/// * `poller` - Name of the polling UI, as passed to `detect_anime_command`
 ///
 /// # Returns
-/// * JSON with detected anime info including parsed title, episode, and matched AniList entry
+/// * Interval in milliseconds
 #[tauri::command]
-async fn detect_anime_command() -> Result<String, String> {
-    use serde_json::json;
+fn get_poll_interval(poller: Option<String>) -> u64 {
+    let config = config::current();
+    detector::poll_interval(
+        poller.as_deref().unwrap_or(detector::DEFAULT_POLLER),
+        std::time::Duration::from_millis(config.poll_interval_ms),
+        std::time::Duration::from_millis(config.max_poll_interval_ms),
+    )
+    .as_millis() as u64
+}
 
-    // Helper function to search with caching
-    async fn search_with_cache(title: &str) -> Option<anilist::Anime> {
-        // Check cache first
-        if let Some(cached) = get_cached_anime(title) {
-            println!("[Detection] Cache hit for: {}", title);
-            return cached;
+/// Tauri command to detect the playing anime and list possible matches
+///
+/// For ambiguous titles: instead of auto-picking, the UI shows the candidates
+/// and calls an update command with the chosen id.
+///
+/// # Returns
+/// * JSON `{ detection, candidates }`; candidates are empty unless something
+///   was detected, otherwise up to 5 `{ id, id_mal, title, cover, episodes, confidence }`
+#[tauri::command]
+async fn detect_candidates(
+    app: tauri::AppHandle,
+    access_token: Option<String>,
+) -> Result<String, String> {
+    let detection = detect_anime(&app, access_token.as_deref()).await;
+    let candidates = match &detection {
+        detector::DetectionResult::Detected { parsed, .. } => {
+            detector::find_candidates(parsed).await?
         }
+        _ => Vec::new(),
+    };
+
+    serde_json::to_string(&serde_json::json!({
+        "detection": detection,
+        "candidates": candidates,
+    }))
+    .map_err(|e| format!("Serialization error: {}", e))
+}
+
+/// Tauri command to get mpv's now-playing info over its IPC socket
+///
+/// # Returns
+/// * JSON `{ media_title, path, percent_pos, time_pos }`, or `null` when mpv
+///   isn't running with `--input-ipc-server`
+#[tauri::command]
+async fn get_mpv_status() -> Result<String, String> {
+    let status = mpv_ipc::query_status(&config::current().mpv_ipc_path).await;
+    serde_json::to_string(&status).map_err(|e| format!("Serialization error: {}", e))
+}
+
+/// Tauri command to get VLC's playback state from its HTTP interface
+///
+/// # Arguments
+/// * `host` - Host VLC listens on (default "127.0.0.1")
+/// * `port` - HTTP interface port (default 8080)
+/// * `password` - Lua HTTP password
+///
+/// # Returns
+/// * JSON `{ title, filename, length, position, time, state }`, or `null` when
+///   VLC isn't reachable
+#[tauri::command]
+async fn get_vlc_status(
+    host: Option<String>,
+    port: Option<u16>,
+    password: String,
+) -> Result<String, String> {
+    let host = host.unwrap_or_else(|| "127.0.0.1".to_string());
+    let status =
+        vlc_http::vlc_status(&host, port.unwrap_or(vlc_http::DEFAULT_PORT), &password).await?;
+    serde_json::to_string(&status).map_err(|e| format!("Serialization error: {}", e))
+}
 
-        // Not in cache, make API call
-        println!("[Detection] Cache miss, searching AniList for: {}", title);
-        let result = match anilist::search_anime(title, 1).await {
-            Ok(results) => results.into_iter().next(),
-            Err(e) => {
-                println!("[Detection] AniList search error: {}", e);
-                None
+/// First media player window that has something loaded: the active window,
+/// then any visible one
+fn playing_window(
+    ignored_patterns: &[detector::IgnorePattern],
+) -> Option<(media_player::MediaPlayer, String)> {
+    std::iter::once(platform_window::get_active_window_title())
+        .flatten()
+        .chain(std::iter::once_with(platform_window::get_all_visible_window_titles).flatten())
+        .filter(|title| detector::ignored_by(title, ignored_patterns).is_none())
+        .find_map(|title| match media_player::detect_player_state(&title) {
+            Some((player, state)) if state != media_player::PlayerState::Idle => {
+                Some((player, title))
             }
-        };
+            _ => None,
+        })
+}
 
-        // Cache the result (even if None)
-        set_cached_anime(title.to_string(), result.clone());
-        result
+/// Ask one now-playing source
+/// Unreachable players count as nothing playing; other errors are logged
+async fn query_now_playing(
+    source: now_playing::Source,
+    config: &config::AppConfig,
+) -> Option<now_playing::NowPlaying> {
+    use now_playing::{NowPlaying, Source};
+
+    let now_playing = match source {
+        Source::Mpv => {
+            let status = mpv_ipc::query_status(&config.mpv_ipc_path).await?;
+            NowPlaying {
+                source,
+                title: status.detection_title()?,
+                player: Some("Mpv".to_string()),
+                position_percent: status.percent_pos,
+            }
+        }
+        Source::Vlc => {
+            let settings = config.vlc_http.as_ref()?;
+            let status =
+                match vlc_http::vlc_status(&settings.host, settings.port, &settings.password).await
+                {
+                    Ok(status) => status?,
+                    Err(e) => {
+                        warn!("VLC: {}", e);
+                        return None;
+                    }
+                };
+            if status.state == vlc_http::VlcState::Stopped {
+                return None;
+            }
+            NowPlaying {
+                source,
+                title: status.filename.or(status.title)?,
+                player: Some("Vlc".to_string()),
+                position_percent: Some(status.position * 100.0),
+            }
+        }
+        Source::Jellyfin => {
+            let settings = config.jellyfin.as_ref()?;
+            let item = match jellyfin::now_playing(settings).await {
+                Ok(item) => item?,
+                Err(e) => {
+                    warn!("Jellyfin: {}", e);
+                    return None;
+                }
+            };
+            NowPlaying {
+                source,
+                title: item.detection_title(),
+                player: Some("Jellyfin".to_string()),
+                position_percent: item.position_percent,
+            }
+        }
+        Source::WindowTitle => {
+            let (player, title) = playing_window(&config.ignored_patterns)?;
+            NowPlaying {
+                source,
+                title,
+                player: Some(format!("{:?}", player)),
+                position_percent: None,
+            }
+        }
+    };
+
+    if detector::ignored_by(&now_playing.title, &config.ignored_patterns).is_some() {
+        return None;
+    }
+    Some(now_playing)
+}
+
+/// Tauri command for what's playing, from the most accurate source available
+/// Tries mpv IPC, VLC HTTP, Jellyfin, then window titles; each can be turned
+/// off with the `now_playing_sources` config
+///
+/// # Returns
+/// * JSON `{ source, title, player, position_percent }`, or `null` when
+///   nothing is playing
+#[tauri::command]
+async fn now_playing() -> Result<String, String> {
+    let config = config::current();
+    let result = now_playing::first_available(&config.now_playing_sources, |source| {
+        query_now_playing(source, &config)
+    })
+    .await;
+    serde_json::to_string(&result).map_err(|e| format!("Serialization error: {}", e))
+}
+
+/// Tauri command to detect anime from a title the frontend already has
+/// (e.g. from a browser extension), skipping window detection
+///
+/// # Arguments
+/// * `title` - Window or page title to parse
+///
+/// # Returns
+/// * JSON in the same shape as `detect_anime_command`
+#[tauri::command]
+async fn detect_from_title(title: String) -> Result<String, String> {
+    let result = detector::detect_from_title(&title).await;
+    serde_json::to_string(&result).map_err(|e| format!("Serialization error: {}", e))
+}
+
+async fn detect_anime(
+    app: &tauri::AppHandle,
+    access_token: Option<&str>,
+) -> detector::DetectionResult {
+    use detector::DetectionResult;
+
+    let ignored_patterns = config::current().ignored_patterns;
+    let search = |query| detector::search_for_user(query, access_token);
+
+    // 0. mpv's IPC socket reports the exact file, no window title guessing needed
+    if let Some(title) = mpv_ipc::query_status(&config::current().mpv_ipc_path)
+        .await
+        .and_then(|status| status.detection_title())
+        .filter(|title| detector::ignored_by(title, &ignored_patterns).is_none())
+    {
+        info!("mpv IPC reports: {:?}", title);
+        let result = detector::match_title(Some("Mpv".to_string()), &title, search).await;
+        record_detection(app, &result);
+        return result;
     }
 
     // 1. Try active window first
     let active_title = platform_window::get_active_window_title();
-    println!("[Detection] Active window title: {:?}", active_title);
+    info!("Active window title: {:?}", active_title);
+
+    // A player that is open with nothing loaded, reported if nothing else is playing
+    let mut idle_player: Option<media_player::MediaPlayer> = None;
 
     if let Some(ref window_title) = active_title {
-        let player_result = media_player::detect_media_player(window_title);
-        println!("[Detection] Media player detected: {:?}", player_result);
-
-        if let Some(player) = player_result {
-            let parsed = title_parser::parse_window_title(window_title);
-            println!(
-                "[Detection] Parsed result: title={:?}, episode={:?}",
-                parsed.title, parsed.episode
-            );
-
-            let anime_match = if let Some(ref title) = parsed.title {
-                search_with_cache(title).await
-            } else {
-                None
+        if let Some(pattern) = detector::ignored_by(window_title, &ignored_patterns) {
+            info!("Ignoring window (matches {:?})", pattern);
+            return DetectionResult::Ignored {
+                window: window_title.clone(),
+                pattern: pattern.as_str().to_string(),
             };
-            println!("[Detection] AniList match found: {}", anime_match.is_some());
-
-            return Ok(json!({
-                "status": "detected",
-                "player": format!("{:?}", player),
-                "window_title": window_title,
-                "parsed": {
-                    "title": parsed.title,
-                    "episode": parsed.episode,
-                    "season": parsed.season
-                },
-                "anilist_match": anime_match
-            })
-            .to_string());
+        }
+
+        let player_result = media_player::detect_player_state(window_title);
+        info!("Media player detected: {:?}", player_result);
+
+        if let Some((player, media_player::PlayerState::Idle)) = player_result {
+            idle_player = Some(player);
+        } else if let Some((player, _)) = player_result {
+            let result =
+                detector::match_title(Some(format!("{:?}", player)), window_title, search).await;
+            record_detection(app, &result);
+            return result;
         }
     }
 
     // 2. If active window isn't a media player, search ALL visible windows
     let all_titles = platform_window::get_all_visible_window_titles();
-    println!(
-        "[Detection] Fallback: searching {} visible windows",
-        all_titles.len()
-    );
+    info!("Fallback: searching {} visible windows", all_titles.len());
     for (i, title) in all_titles.iter().enumerate() {
-        println!("[Detection] Window {}: {:?}", i, title);
+        info!("Window {}: {:?}", i, title);
     }
 
     for window_title in all_titles {
-        if let Some(player) = media_player::detect_media_player(&window_title) {
-            let parsed = title_parser::parse_window_title(&window_title);
-            println!(
-                "[Detection] Fallback found browser: {:?}, parsed title={:?}, ep={:?}",
-                player, parsed.title, parsed.episode
-            );
+        if detector::ignored_by(&window_title, &ignored_patterns).is_some() {
+            continue;
+        }
+        if let Some((player, state)) = media_player::detect_player_state(&window_title) {
+            if state == media_player::PlayerState::Idle {
+                idle_player.get_or_insert(player);
+                continue;
+            }
+
+            info!("Fallback found browser: {:?}", player);
+            let result =
+                detector::match_title(Some(format!("{:?}", player)), &window_title, search).await;
 
             // Only count as "detected" if we actually parsed a title or episode
             // This avoids catching empty media player windows
-            if parsed.title.is_some() || parsed.episode.is_some() {
-                let anime_match = if let Some(ref title) = parsed.title {
-                    search_with_cache(title).await
-                } else {
-                    None
-                };
+            if let DetectionResult::Detected { parsed, .. } = &result {
+                if parsed.title.is_some() || parsed.episode.is_some() {
+                    record_detection(app, &result);
+                    return result;
+                }
+            }
+        }
+    }
+
+    // 3. Fallback
+    let window = active_title.clone().unwrap_or_default();
+    if let Some(player) = idle_player {
+        return DetectionResult::PlayerIdle {
+            player: format!("{:?}", player),
+            window,
+        };
+    }
+
+    if active_title.is_some() {
+        DetectionResult::NotMediaPlayer { window }
+    } else {
+        DetectionResult::NoWindow { window }
+    }
+}
+
+/// Resolve the app data directory (history, caches)
+fn app_data_dir(app: &tauri::AppHandle) -> Result<std::path::PathBuf, String> {
+    app.path()
+        .app_data_dir()
+        .map_err(|e| format!("Failed to resolve app data dir: {}", e))
+}
+
+/// Append a confirmed detection to the history log
+/// Failures are only logged so they never break detection
+fn record_detection(app: &tauri::AppHandle, result: &detector::DetectionResult) {
+    let detector::DetectionResult::Detected {
+        window_title,
+        parsed,
+        anilist_match,
+        ..
+    } = result
+    else {
+        return;
+    };
+
+    let record = history::HistoryRecord::now(
+        window_title.clone(),
+        anilist_match.as_ref().map(|a| a.id),
+        anilist_match.as_ref().and_then(|a| a.id_mal),
+        anilist_match
+            .as_ref()
+            .and_then(|a| a.title.english.clone().or_else(|| a.title.romaji.clone())),
+        parsed.episode,
+    );
+
+    if let Err(e) = app_data_dir(app).and_then(|dir| history::record_detection(&dir, record)) {
+        warn!("Failed to record detection: {}", e);
+    }
+}
+
+/// Tauri command to get recent detections (newest first)
+///
+/// # Returns
+/// * JSON array of history records
+#[tauri::command]
+fn get_history(app: tauri::AppHandle) -> Result<String, String> {
+    let records = history::get_history(&app_data_dir(&app)?)?;
+    serde_json::to_string(&records).map_err(|e| format!("Serialization error: {}", e))
+}
+
+/// Tauri command to clear the detection history
+#[tauri::command]
+fn clear_history(app: tauri::AppHandle) -> Result<(), String> {
+    history::clear_history(&app_data_dir(&app)?)
+}
+
+/// Tauri command to update anime progress on AniList
+///
+/// # Arguments
+/// * `access_token` - OAuth access token
+/// * `media_id` - AniList media ID
+/// * `progress` - Episode number
+/// * `status` - Optional status (watching, completed, ... or AniList's CURRENT, COMPLETED, ...)
+/// * `episode_offset` - Optional split-cour offset subtracted from `progress`
+/// * `total_episodes` - Optional episode count, used to mark the entry completed
+/// * `confidence` - Match confidence for auto-scrobbles; below `min_confidence`
+///   nothing is updated and an "ambiguous-detection" event is emitted
+/// * `repeat` - Optional rewatch count, for the rewatching (REPEATING) status
+/// * `allow_regression` - Allow progress lower than what's on the list; the
+///   frontend sets it for user edits so detection can't move progress back
+///
+/// # Returns
+/// * JSON with updated entry or error
+#[tauri::command]
+#[allow(clippy::too_many_arguments)]
+async fn update_anime_progress_command(
+    app: tauri::AppHandle,
+    access_token: String,
+    media_id: i32,
+    progress: i32,
+    status: Option<UnifiedStatus>,
+    episode_offset: Option<i32>,
+    total_episodes: Option<i32>,
+    confidence: Option<f64>,
+    repeat: Option<i32>,
+    allow_regression: Option<bool>,
+) -> Result<String, String> {
+    scrobble::scrobble_if_confident(
+        confidence,
+        config::current().min_confidence,
+        |ambiguous| emit_ambiguous_detection(&app, "anilist", media_id as i64, ambiguous),
+        || async {
+            let status = status.map(|s| s.to_anilist().to_string());
+            update_anilist_progress(
+                &app,
+                &access_token,
+                media_id,
+                progress,
+                status,
+                episode_offset,
+                total_episodes,
+                repeat,
+                allow_regression.unwrap_or(false),
+            )
+            .await
+        },
+    )
+    .await
+}
+
+/// Send an AniList progress update, applying the scrobble rules
+#[allow(clippy::too_many_arguments)]
+async fn update_anilist_progress(
+    app: &tauri::AppHandle,
+    access_token: &str,
+    media_id: i32,
+    progress: i32,
+    status: Option<String>,
+    episode_offset: Option<i32>,
+    total_episodes: Option<i32>,
+    repeat: Option<i32>,
+    allow_regression: bool,
+) -> Result<String, String> {
+    let config = config::current();
+    let episode = scrobble::apply_episode_offset(progress, episode_offset)?;
+
+    // If the list can't be read, send the requested status as-is
+    let status = match anilist::get_list_status(access_token, media_id).await {
+        Ok(existing) => {
+            let existing = existing.as_ref();
+            scrobble::check_progress(
+                existing.and_then(|entry| entry.progress),
+                episode,
+                existing.and_then(|entry| entry.status.as_deref())
+                    == Some(UnifiedStatus::Rewatching.to_anilist()),
+                allow_regression,
+            )?;
+            scrobble::start_status(
+                scrobble::TrackerKind::AniList,
+                existing.and_then(|entry| entry.status.as_deref()),
+                status.as_deref(),
+                config.auto_start,
+            )
+        }
+        Err(e) => {
+            warn!("Failed to get AniList list status: {}", e);
+            status
+        }
+    };
+    let status = scrobble::resolve_status(
+        scrobble::TrackerKind::AniList,
+        episode,
+        total_episodes,
+        status.as_deref(),
+        config.auto_complete,
+    );
+    let entry =
+        anilist::update_media_progress(access_token, media_id, episode, status.as_deref(), repeat)
+            .await?;
+
+    if let Err(e) =
+        app_data_dir(app).and_then(|dir| history::mark_scrobbled(&dir, media_id, progress))
+    {
+        warn!("Failed to mark scrobbled: {}", e);
+    }
+
+    serde_json::to_string(&entry).map_err(|e| format!("Serialization error: {}", e))
+}
+
+/// Tell the frontend a scrobble was skipped because the match was uncertain
+fn emit_ambiguous_detection(
+    app: &tauri::AppHandle,
+    provider: &str,
+    media_id: i64,
+    ambiguous: &scrobble::AmbiguousMatch,
+) {
+    warn!(
+        "Skipping {} update for {}: {}",
+        provider, media_id, ambiguous
+    );
+    let payload = serde_json::json!({
+        "provider": provider,
+        "mediaId": media_id,
+        "confidence": ambiguous.confidence,
+        "minConfidence": ambiguous.min_confidence,
+    });
+    let _ = app.emit("ambiguous-detection", payload);
+}
+
+/// Tauri command to find the entry an absolutely numbered episode belongs to
+/// ("Anime - 27" when season 1 has 24 episodes is episode 3 of the sequel)
+///
+/// # Arguments
+/// * `media_id` - Matched AniList media ID
+/// * `episode` - Detected episode number
+///
+/// # Returns
+/// * JSON `{ media_id, episode }` with the corrected entry and episode
+#[tauri::command]
+async fn locate_episode(media_id: i32, episode: i32) -> Result<String, String> {
+    let anime = anilist::get_anime_by_id(media_id).await?;
+    let location =
+        scrobble::locate_episode(media_id, anime.episodes, episode, anilist::get_relations).await?;
+    serde_json::to_string(&location).map_err(|e| format!("Serialization error: {}", e))
+}
+
+/// Tauri command to search anime progressively (word by word)
+/// Uses the parsed title and searches AniList starting with 1 word
+///
+/// # Arguments
+/// * `title` - The parsed anime title to search
+///
+/// # Returns
+/// * JSON with the matched anime title and search info
+#[tauri::command]
+async fn progressive_search_command(title: String) -> Result<String, String> {
+    let result = anilist::progressive_search_anime(&title).await?;
+    serde_json::to_string(&result).map_err(|e| format!("Serialization error: {}", e))
+}
+
+/// Download a chapter with logging shared by the download commands
+async fn download_chapter(
+    chapter_title: String,
+    manga_title: String,
+    urls: Vec<String>,
+    download_dir: String,
+    convert_webp: Option<bool>,
+    headers: Option<HashMap<String, String>>,
+    skip_failed: Option<bool>,
+) -> Result<downloader::DownloadReport, String> {
+    info!(
+        "Received command: {} - {} ({} pages)",
+        manga_title,
+        chapter_title,
+        urls.len()
+    );
+    info!("Download dir: {}", download_dir);
+
+    let options = downloader::DownloadOptions {
+        convert_webp: convert_webp.unwrap_or(false),
+        headers: headers.unwrap_or_default(),
+        skip_failed: skip_failed.unwrap_or(false),
+        ..Default::default()
+    };
+    let result = downloader::download_chapter_to_cbz(
+        chapter_title,
+        manga_title,
+        urls,
+        download_dir,
+        &options,
+    )
+    .await;
+
+    match &result {
+        Ok(report) => info!("Success! CBZ saved to: {}", report.path),
+        Err(e) => warn!("Error: {}", e),
+    }
+
+    result
+}
+
+/// Tauri command to download a chapter as CBZ
+///
+/// # Arguments
+/// * `chapter_title` - Title of the chapter (e.g., "Chapter 1")
+/// * `manga_title` - Title of the manga
+/// * `urls` - List of image URLs to download
+/// * `download_dir` - Directory to save the file in
+/// * `convert_webp` - Re-encode WebP pages as JPEG (default false)
+/// * `headers` - Request headers for the page host, e.g. its Referer; the default
+///   Referer and User-Agent are used for any not given
+/// * `skip_failed` - Leave out pages that keep failing instead of failing the
+///   chapter (default false); their indices are in the report's `skipped_pages`
+///
+/// # Returns
+/// * Path to the downloaded CBZ file
+#[tauri::command]
+async fn download_chapter_command(
+    chapter_title: String,
+    manga_title: String,
+    urls: Vec<String>,
+    download_dir: String,
+    convert_webp: Option<bool>,
+    headers: Option<HashMap<String, String>>,
+    skip_failed: Option<bool>,
+) -> Result<String, String> {
+    let report = download_chapter(
+        chapter_title,
+        manga_title,
+        urls,
+        download_dir,
+        convert_webp,
+        headers,
+        skip_failed,
+    )
+    .await?;
+    Ok(report.path)
+}
+
+/// Tauri command to download a chapter as CBZ and report download stats
+///
+/// Takes the same arguments as `download_chapter_command`.
+///
+/// # Returns
+/// * JSON `{ path, page_count, total_bytes, page_sizes, duration_ms, skipped_pages }`
+#[tauri::command]
+async fn download_chapter_report_command(
+    chapter_title: String,
+    manga_title: String,
+    urls: Vec<String>,
+    download_dir: String,
+    convert_webp: Option<bool>,
+    headers: Option<HashMap<String, String>>,
+    skip_failed: Option<bool>,
+) -> Result<String, String> {
+    let report = download_chapter(
+        chapter_title,
+        manga_title,
+        urls,
+        download_dir,
+        convert_webp,
+        headers,
+        skip_failed,
+    )
+    .await?;
+    serde_json::to_string(&report).map_err(|e| format!("Serialization error: {}", e))
+}
+
+/// Payload of the `download-finished` event
+#[derive(Clone, serde::Serialize)]
+struct DownloadFinished {
+    id: String,
+    path: Option<String>,
+    report: Option<downloader::DownloadReport>,
+    error: Option<String>,
+    cancelled: bool,
+}
+
+/// Tauri command to start a chapter download in the background
+///
+/// Takes the same arguments as `download_chapter_command` but returns immediately.
+/// A `download-finished` event with `{ id, path, report, error, cancelled }` is emitted when done.
+///
+/// # Returns
+/// * Download id, used with `cancel_download`
+#[tauri::command]
+#[allow(clippy::too_many_arguments)]
+fn start_chapter_download(
+    app: tauri::AppHandle,
+    chapter_title: String,
+    manga_title: String,
+    urls: Vec<String>,
+    download_dir: String,
+    convert_webp: Option<bool>,
+    headers: Option<HashMap<String, String>>,
+    skip_failed: Option<bool>,
+) -> String {
+    let (id, mut options) = downloader::register_download();
+    options.convert_webp = convert_webp.unwrap_or(false);
+    options.headers = headers.unwrap_or_default();
+    options.skip_failed = skip_failed.unwrap_or(false);
+    info!(
+        "Starting {}: {} - {} ({} pages)",
+        id,
+        manga_title,
+        chapter_title,
+        urls.len()
+    );
 
-                return Ok(json!({
-                    "status": "detected",
-                    "player": format!("{:?}", player),
-                    "window_title": window_title,
-                    "parsed": {
-                        "title": parsed.title,
-                        "episode": parsed.episode,
-                        "season": parsed.season
-                    },
-                    "anilist_match": anime_match
-                })
-                .to_string());
-            }
-        }
-    }
+    let download_id = id.clone();
+    tauri::async_runtime::spawn(async move {
+        let result = downloader::download_chapter_to_cbz(
+            chapter_title,
+            manga_title,
+            urls,
+            download_dir,
+            &options,
+        )
+        .await;
+        downloader::finish_download(&download_id);
+
+        let payload = match result {
+            Ok(report) => DownloadFinished {
+                id: download_id,
+                path: Some(report.path.clone()),
+                report: Some(report),
+                error: None,
+                cancelled: false,
+            },
+            Err(e) => DownloadFinished {
+                id: download_id,
+                path: None,
+                report: None,
+                cancelled: e == downloader::CANCELLED_ERROR,
+                error: Some(e),
+            },
+        };
+        let _ = app.emit("download-finished", payload);
+    });
 
-    // 3. Fallback
-    let status = if active_title.is_some() {
-        "not_media_player"
-    } else {
-        "no_window"
-    };
+    id
+}
 
-    Ok(json!({
-        "status": status,
-        "window": active_title.unwrap_or_default()
-    })
-    .to_string())
+/// Tauri command to cancel a download started with `start_chapter_download`
+///
+/// # Returns
+/// * true if the download was running and will stop
+#[tauri::command]
+fn cancel_download(id: String) -> bool {
+    downloader::cancel_download(&id)
 }
 
-/// Tauri command to update anime progress on AniList
+/// Tauri command to get the free disk space (in bytes) of the volume containing `path`
+#[tauri::command]
+fn get_available_space(path: String) -> Result<u64, String> {
+    downloader::available_space(std::path::Path::new(&path))
+}
+
+/// Tauri command to find the chapters already downloaded into a manga folder
 ///
 /// # Arguments
-/// * `access_token` - OAuth access token
-/// * `media_id` - AniList media ID
-/// * `progress` - Episode number
-/// * `status` - Optional status (CURRENT, COMPLETED, etc.)
+/// * `manga_dir` - The manga's folder inside the download directory
+/// * `chapters` - Optional chapter titles to check; titles are compared sanitized,
+///   the same way the downloader names its files
 ///
 /// # Returns
-/// * JSON with updated entry or error
+/// * JSON array of downloaded chapter names (CBZ names without ".cbz"), or of
+///   the given `chapters` that are already downloaded
 #[tauri::command]
-async fn update_anime_progress_command(
-    access_token: String,
-    media_id: i32,
-    progress: i32,
-    status: Option<String>,
+fn list_downloaded_chapters(
+    manga_dir: String,
+    chapters: Option<Vec<String>>,
 ) -> Result<String, String> {
-    let status_ref = status.as_deref();
-    let entry =
-        anilist::update_media_progress(&access_token, media_id, progress, status_ref).await?;
-    serde_json::to_string(&entry).map_err(|e| format!("Serialization error: {}", e))
+    let downloaded = downloader::list_downloaded_chapters(std::path::Path::new(&manga_dir))?;
+    let names: Vec<String> = match chapters {
+        Some(chapters) => chapters
+            .into_iter()
+            .filter(|chapter| downloader::is_downloaded(&downloaded, chapter))
+            .collect(),
+        None => downloaded.into_iter().collect(),
+    };
+    serde_json::to_string(&names).map_err(|e| format!("Serialization error: {}", e))
 }
 
-/// Tauri command to search anime progressively (word by word)
-/// Uses the parsed title and searches AniList starting with 1 word
+/// Tauri command to extract a CBZ into a folder of images
 ///
 /// # Arguments
-/// * `title` - The parsed anime title to search
+/// * `path` - Path to the CBZ file
+/// * `dest_dir` - Folder to extract the pages into
 ///
 /// # Returns
-/// * JSON with the matched anime title and search info
+/// * JSON array of extracted image paths in page order
 #[tauri::command]
-async fn progressive_search_command(title: String) -> Result<String, String> {
-    let result = anilist::progressive_search_anime(&title).await?;
-    serde_json::to_string(&result).map_err(|e| format!("Serialization error: {}", e))
+fn extract_cbz(path: String, dest_dir: String) -> Result<String, String> {
+    let paths = downloader::extract_cbz(&path, &dest_dir)?;
+    serde_json::to_string(&paths).map_err(|e| format!("Serialization error: {}", e))
 }
 
-/// Tauri command to download a chapter as CBZ
+/// Tauri command to merge chapter CBZs into a single CBZ, e.g. a volume
 ///
 /// # Arguments
-/// * `chapter_title` - Title of the chapter (e.g., "Chapter 1")
-/// * `manga_title` - Title of the manga
-/// * `urls` - List of image URLs to download
-/// * `download_dir` - Directory to save the file in
+/// * `inputs` - CBZ files in reading order
+/// * `output` - Path of the merged CBZ
+/// * `overwrite` - Replace `output` if it already exists (default false)
 ///
 /// # Returns
-/// * Path to the downloaded CBZ file
+/// * Number of pages in the merged CBZ
 #[tauri::command]
-async fn download_chapter_command(
-    chapter_title: String,
-    manga_title: String,
-    urls: Vec<String>,
-    download_dir: String,
-) -> Result<String, String> {
-    println!(
-        "[Downloader] Received command: {} - {} ({} pages)",
-        manga_title,
-        chapter_title,
-        urls.len()
-    );
-    println!("[Downloader] Download dir: {}", download_dir);
-
-    let result =
-        downloader::download_chapter_to_cbz(chapter_title, manga_title, urls, download_dir).await;
+fn merge_cbz(
+    inputs: Vec<String>,
+    output: String,
+    overwrite: Option<bool>,
+) -> Result<usize, String> {
+    downloader::merge_cbz(&inputs, &output, overwrite.unwrap_or(false))
+}
 
-    match &result {
-        Ok(path) => println!("[Downloader] Success! CBZ saved to: {}", path),
-        Err(e) => println!("[Downloader] Error: {}", e),
-    }
+/// Tauri command to add a chapter download to the queue
+///
+/// # Arguments
+/// * `job` - `{ chapterTitle, mangaTitle, urls, downloadDir }`
+///
+/// # Returns
+/// * Job id; progress is emitted as `download-progress` events with the job status
+#[tauri::command]
+async fn enqueue_download(job: download_queue::DownloadJob) -> Result<String, String> {
+    // Async so the job is spawned from within the Tokio runtime
+    download_queue::enqueue_download(job)
+}
 
-    result
+/// Tauri command to get the state of all queued downloads
+/// Returns JSON array of `{ id, job, state, downloaded_pages, total_pages, path, error }`
+#[tauri::command]
+fn get_queue_status() -> Result<String, String> {
+    let jobs = download_queue::get_queue_status()?;
+    serde_json::to_string(&jobs).map_err(|e| format!("Serialization error: {}", e))
 }
 
-lazy_static::lazy_static! {
-    static ref IMAGE_CACHE: Mutex<HashMap<String, String>> = Mutex::new(HashMap::new());
+/// Tauri command to cancel a queued or running download job
+///
+/// # Returns
+/// * false if the job doesn't exist or has already finished
+#[tauri::command]
+fn cancel_job(id: String) -> Result<bool, String> {
+    download_queue::cancel_job(&id)
 }
 
 /// Tauri command to download an image and return local file path
@@ -405,73 +1384,54 @@ lazy_static::lazy_static! {
 ///
 /// # Arguments
 /// * `url` - HTTP URL of the image to download
+/// * `refresh` - Revalidate a cached image with the server; it's only downloaded
+///   again if it changed (default false)
 ///
 /// # Returns
 /// * Local file path to the cached image
 #[tauri::command]
-async fn download_image_for_notification(url: String) -> Result<String, String> {
-    use std::io::Write;
-    use std::path::PathBuf;
-
-    // Check cache first
-    {
-        let cache = IMAGE_CACHE.lock().map_err(|_| "Cache lock error")?;
-        if let Some(path) = cache.get(&url) {
-            if std::path::Path::new(path).exists() {
-                println!("[ImageCache] Cache hit: {}", url);
-                return Ok(path.clone());
-            }
-        }
-    }
-
-    println!("[ImageCache] Downloading: {}", url);
-
-    // Create cache directory in temp
+async fn download_image_for_notification(
+    url: String,
+    refresh: Option<bool>,
+) -> Result<String, String> {
     let cache_dir = std::env::temp_dir().join("playon_image_cache");
-    std::fs::create_dir_all(&cache_dir)
-        .map_err(|e| format!("Failed to create cache dir: {}", e))?;
-
-    // Generate filename from URL hash
-    let hash = format!("{:x}", md5_hash(&url));
-    let extension = url.split('.').last().unwrap_or("jpg");
-    let filename = format!("{}.{}", hash, extension);
-    let file_path: PathBuf = cache_dir.join(&filename);
-
-    // Download image
-    let response = reqwest::get(&url)
-        .await
-        .map_err(|e| format!("Download failed: {}", e))?;
-
-    let bytes = response
-        .bytes()
-        .await
-        .map_err(|e| format!("Failed to read response: {}", e))?;
-
-    // Save to file
-    let mut file =
-        std::fs::File::create(&file_path).map_err(|e| format!("Failed to create file: {}", e))?;
-    file.write_all(&bytes)
-        .map_err(|e| format!("Failed to write file: {}", e))?;
-
-    let path_str = file_path.to_string_lossy().to_string();
-
-    // Update cache
-    {
-        let mut cache = IMAGE_CACHE.lock().map_err(|_| "Cache lock error")?;
-        cache.insert(url, path_str.clone());
-    }
+    let path = image_cache::fetch_image(
+        &reqwest::Client::new(),
+        &cache_dir,
+        &url,
+        refresh.unwrap_or(false),
+    )
+    .await?;
+    Ok(path.to_string_lossy().to_string())
+}
 
-    println!("[ImageCache] Saved to: {}", path_str);
-    Ok(path_str)
+/// Tauri command to extract a preview frame from a local video
+/// Needs ffmpeg on PATH; without it the error contains "ffmpeg not found"
+/// so the file browser can show a plain icon instead
+///
+/// # Arguments
+/// * `path` - Video file (mp4, mkv, avi, mov, webm, flv, wmv)
+/// * `time_secs` - Position of the frame, in seconds
+///
+/// # Returns
+/// * Local path to the cached PNG
+#[tauri::command]
+async fn extract_thumbnail(path: String, time_secs: f64) -> Result<String, String> {
+    let cache_dir = std::env::temp_dir().join("playon_thumbnails");
+    let thumbnail =
+        thumbnail::extract_thumbnail(&cache_dir, std::path::Path::new(&path), time_secs).await?;
+    Ok(thumbnail.to_string_lossy().to_string())
 }
 
-/// Simple hash function for cache keys
-fn md5_hash(s: &str) -> u64 {
-    use std::collections::hash_map::DefaultHasher;
-    use std::hash::{Hash, Hasher};
-    let mut hasher = DefaultHasher::new();
-    s.hash(&mut hasher);
-    hasher.finish()
+/// Tauri command to get the duration of a local video
+/// Read from the MKV/MP4 headers without decoding, ffprobe otherwise
+///
+/// # Returns
+/// * Duration in seconds, or `null` when the file can't be read
+#[tauri::command]
+async fn get_video_duration(path: String) -> Result<String, String> {
+    let duration = media_meta::get_video_duration(std::path::Path::new(&path)).await;
+    serde_json::to_string(&duration).map_err(|e| format!("Serialization error: {}", e))
 }
 
 /// Tauri command to hide the main window (minimize to tray)
@@ -499,31 +1459,26 @@ fn mal_generate_pkce() -> (String, String) {
 /// Returns JSON with tokens on success
 #[tauri::command]
 async fn mal_start_oauth_flow(client_id: String) -> Result<String, String> {
-    // Generate PKCE
-    let verifier = myanimelist::generate_code_verifier();
-    let challenge = myanimelist::generate_code_challenge(&verifier);
-
     // Use a fixed port for the callback server
     let port: u16 = 17563;
     let redirect_uri = format!("http://localhost:{}", port);
 
-    // Build auth URL
-    let auth_url = format!(
-        "https://myanimelist.net/v1/oauth2/authorize?response_type=code&client_id={}&code_challenge={}&code_challenge_method=plain&redirect_uri={}",
-        urlencoding::encode(&client_id),
-        urlencoding::encode(&challenge),
-        urlencoding::encode(&redirect_uri)
-    );
+    // Build auth URL with PKCE challenge and state
+    let request =
+        oauth::build_authorize_url(oauth::OAuthProvider::Mal, &client_id, &redirect_uri, &[]);
+    let auth_url = request.url;
+    let verifier = request.code_verifier.unwrap_or_default();
 
-    println!("[MAL] Starting OAuth flow...");
-    println!("[MAL] Redirect URI: {}", redirect_uri);
+    info!("Starting OAuth flow...");
+    info!("Redirect URI: {}", redirect_uri);
 
     // Start the callback server in a separate task
     let server_handle =
         tokio::spawn(async move { myanimelist::start_oauth_callback_server(port).await });
 
     // Open browser - properly escape URL for each platform
-    println!("[MAL] Opening browser: {}", auth_url);
+    // The URL carries the PKCE challenge (the verifier itself with the plain method)
+    info!("Opening browser for authorization");
     #[cfg(target_os = "macos")]
     {
         let _ = std::process::Command::new("open").arg(&auth_url).spawn();
@@ -557,7 +1512,7 @@ async fn mal_start_oauth_flow(client_id: String) -> Result<String, String> {
         .map_err(|e| format!("Server task error: {}", e))?
         .map_err(|e| format!("OAuth callback error: {}", e))?;
 
-    println!("[MAL] Received authorization code, exchanging for tokens...");
+    info!("Received authorization code, exchanging for tokens...");
 
     // Exchange code for tokens
     let token_data = myanimelist::exchange_code_for_token(
@@ -621,17 +1576,108 @@ async fn mal_search_manga(
 }
 
 /// Update anime progress on MAL
+/// `episode_offset` is subtracted from `episodes_watched` for split-cour entries,
+/// `total_episodes` is used to mark the entry completed
+/// `status` may be a unified status or MAL's own (watching, on_hold, ...)
+/// Below `min_confidence`, `confidence` skips the update and emits "ambiguous-detection"
+/// `extras` also sets `{ is_rewatching, num_times_rewatched, priority, comments }`
+/// Progress lower than what's on the list is refused unless `allow_regression` is set
 #[tauri::command]
+#[allow(clippy::too_many_arguments)]
 async fn mal_update_anime_progress(
+    app: tauri::AppHandle,
     access_token: String,
     anime_id: i64,
     episodes_watched: i32,
-    status: Option<String>,
+    status: Option<UnifiedStatus>,
+    episode_offset: Option<i32>,
+    total_episodes: Option<i32>,
+    confidence: Option<f64>,
+    extras: Option<myanimelist::MalAnimeUpdateExtras>,
+    allow_regression: Option<bool>,
 ) -> Result<String, String> {
-    let status_ref = status.as_deref();
-    let result =
-        myanimelist::update_anime_progress(&access_token, anime_id, episodes_watched, status_ref)
+    scrobble::scrobble_if_confident(
+        confidence,
+        config::current().min_confidence,
+        |ambiguous| emit_ambiguous_detection(&app, "mal", anime_id, ambiguous),
+        || async {
+            let status = status.map(|s| s.to_mal().to_string());
+            let result = update_mal_progress(
+                &access_token,
+                anime_id,
+                episodes_watched,
+                status,
+                episode_offset,
+                total_episodes,
+                &extras.unwrap_or_default(),
+                allow_regression.unwrap_or(false),
+            )
             .await?;
+
+            if let Err(e) = app_data_dir(&app)
+                .and_then(|dir| history::mark_mal_scrobbled(&dir, anime_id, episodes_watched))
+            {
+                warn!("Failed to mark scrobbled: {}", e);
+            }
+
+            Ok(result)
+        },
+    )
+    .await
+}
+
+/// Send a MAL progress update, applying the scrobble rules
+#[allow(clippy::too_many_arguments)]
+async fn update_mal_progress(
+    access_token: &str,
+    anime_id: i64,
+    episodes_watched: i32,
+    status: Option<String>,
+    episode_offset: Option<i32>,
+    total_episodes: Option<i32>,
+    extras: &myanimelist::MalAnimeUpdateExtras,
+    allow_regression: bool,
+) -> Result<String, String> {
+    let config = config::current();
+    let episodes_watched = scrobble::apply_episode_offset(episodes_watched, episode_offset)?;
+
+    // If the list can't be read, send the requested status as-is
+    let status = match myanimelist::get_anime_list_status(access_token, anime_id).await {
+        Ok(existing) => {
+            let existing = existing.as_ref();
+            scrobble::check_progress(
+                existing.and_then(|entry| entry.num_episodes_watched),
+                episodes_watched,
+                existing.and_then(|entry| entry.is_rewatching) == Some(true),
+                allow_regression,
+            )?;
+            scrobble::start_status(
+                scrobble::TrackerKind::Mal,
+                existing.and_then(|entry| entry.status.as_deref()),
+                status.as_deref(),
+                config.auto_start,
+            )
+        }
+        Err(e) => {
+            warn!("Failed to get MAL list status: {}", e);
+            status
+        }
+    };
+    let status = scrobble::resolve_status(
+        scrobble::TrackerKind::Mal,
+        episodes_watched,
+        total_episodes,
+        status.as_deref(),
+        config.auto_complete,
+    );
+    let result = myanimelist::update_anime_progress(
+        access_token,
+        anime_id,
+        episodes_watched,
+        status.as_deref(),
+        extras,
+    )
+    .await?;
     serde_json::to_string(&result).map_err(|e| format!("Serialization error: {}", e))
 }
 
@@ -643,6 +1689,9 @@ async fn mal_update_manga_progress(
     chapters_read: i32,
     status: Option<String>,
 ) -> Result<String, String> {
+    if let Some(status) = &status {
+        status::validate_mal_manga_status(status)?;
+    }
     let status_ref = status.as_deref();
     let result =
         myanimelist::update_manga_progress(&access_token, manga_id, chapters_read, status_ref)
@@ -651,31 +1700,281 @@ async fn mal_update_manga_progress(
 }
 
 /// Get user's anime list from MAL
+/// Set `fetch_all` to follow pagination and return the whole list
 #[tauri::command]
 async fn mal_get_anime_list(
     access_token: String,
     status: Option<String>,
     limit: Option<i32>,
+    fetch_all: Option<bool>,
 ) -> Result<String, String> {
     let status_ref = status.as_deref();
-    let results =
-        myanimelist::get_anime_list(&access_token, status_ref, limit.unwrap_or(100)).await?;
+    let results = myanimelist::get_anime_list(
+        &access_token,
+        status_ref,
+        limit.unwrap_or(100),
+        fetch_all.unwrap_or(false),
+    )
+    .await?;
     serde_json::to_string(&results).map_err(|e| format!("Serialization error: {}", e))
 }
 
 /// Get user's manga list from MAL
+/// Set `fetch_all` to follow pagination and return the whole list
 #[tauri::command]
 async fn mal_get_manga_list(
     access_token: String,
     status: Option<String>,
     limit: Option<i32>,
+    fetch_all: Option<bool>,
 ) -> Result<String, String> {
     let status_ref = status.as_deref();
-    let results =
-        myanimelist::get_manga_list(&access_token, status_ref, limit.unwrap_or(100)).await?;
+    let results = myanimelist::get_manga_list(
+        &access_token,
+        status_ref,
+        limit.unwrap_or(100),
+        fetch_all.unwrap_or(false),
+    )
+    .await?;
+    serde_json::to_string(&results).map_err(|e| format!("Serialization error: {}", e))
+}
+
+/// Import a MyAnimeList XML export (animelist_*.xml)
+/// Returns JSON `{ entries, skipped }` for the frontend to push to AniList/MAL
+#[tauri::command]
+fn import_mal_xml(path: String) -> Result<String, String> {
+    let result = import::import_mal_xml(&path)?;
+    serde_json::to_string(&result).map_err(|e| format!("Serialization error: {}", e))
+}
+
+/// Export the user's full anime list to a JSON or CSV file
+///
+/// # Arguments
+/// * `provider` - "anilist" or "mal"
+/// * `access_token` - OAuth access token for the provider
+/// * `format` - "json" or "csv"
+/// * `path` - Destination file chosen by the user
+///
+/// # Returns
+/// * Path of the written file
+#[tauri::command]
+async fn export_list(
+    provider: String,
+    access_token: String,
+    format: String,
+    path: String,
+) -> Result<String, String> {
+    export::export_list(&provider, &access_token, &format, &path).await
+}
+
+// ============================================================================
+// TRACKER COMMANDS
+// ============================================================================
+
+/// Search any tracker's catalogue
+///
+/// # Arguments
+/// * `provider` - "anilist" or "mal"
+/// * `access_token` - Token for the provider (AniList searches work without one)
+/// * `limit` - Maximum number of results (default: 10)
+///
+/// # Returns
+/// * JSON array of `{ id, title, episodes, cover, status, progress }`
+#[tauri::command]
+async fn tracker_search(
+    provider: String,
+    access_token: Option<String>,
+    query: String,
+    limit: Option<i32>,
+) -> Result<String, String> {
+    let tracker = tracker::for_provider(&provider, access_token.unwrap_or_default())?;
+    let results = tracker.search(&query, limit.unwrap_or(10)).await?;
+    serde_json::to_string(&results).map_err(|e| format!("Serialization error: {}", e))
+}
+
+/// Search AniList and MAL at the same time
+///
+/// # Arguments
+/// * `mal_token` - MAL access token; MAL is skipped without one
+/// * `limit` - Maximum results per provider (default: 10)
+///
+/// # Returns
+/// * JSON array of `{ provider, results, error }`, one per provider searched
+#[tauri::command]
+async fn search_all_providers(
+    title: String,
+    anilist_token: Option<String>,
+    mal_token: Option<String>,
+    limit: Option<i32>,
+) -> Result<String, String> {
+    let tokens = tracker::ProviderTokens {
+        anilist: anilist_token,
+        mal: mal_token,
+    };
+    let results = tracker::search_all_providers(&title, &tokens, limit.unwrap_or(10)).await;
     serde_json::to_string(&results).map_err(|e| format!("Serialization error: {}", e))
 }
 
+/// Get the user's anime list from any tracker
+///
+/// # Returns
+/// * JSON array of `{ id, title, episodes, cover, status, progress }`
+#[tauri::command]
+async fn tracker_get_list(provider: String, access_token: String) -> Result<String, String> {
+    let tracker = tracker::for_provider(&provider, access_token)?;
+    let list = tracker.get_list().await?;
+    serde_json::to_string(&list).map_err(|e| format!("Serialization error: {}", e))
+}
+
+/// Set progress (and optionally status) on any tracker
+///
+/// Sent as-is: unlike `update_anime_progress_command` no scrobble rules apply.
+#[tauri::command]
+async fn tracker_update_progress(
+    provider: String,
+    access_token: String,
+    id: i64,
+    progress: i32,
+    status: Option<UnifiedStatus>,
+) -> Result<(), String> {
+    let tracker = tracker::for_provider(&provider, access_token)?;
+    tracker.update_progress(id, progress, status).await
+}
+
+// ============================================================================
+// CONFIG COMMANDS
+// ============================================================================
+
+/// Resolve the app config directory
+fn app_config_dir(app: &tauri::AppHandle) -> Result<std::path::PathBuf, String> {
+    app.path()
+        .app_config_dir()
+        .map_err(|e| format!("Failed to resolve app config dir: {}", e))
+}
+
+/// Load settings from config.json (created with defaults on first run)
+/// Returns the config as JSON
+#[tauri::command]
+fn load_config(app: tauri::AppHandle) -> Result<String, String> {
+    let config = config::load(&app_config_dir(&app)?)?;
+    serde_json::to_string(&config).map_err(|e| format!("Serialization error: {}", e))
+}
+
+/// Save settings to config.json and apply them
+#[tauri::command]
+fn save_config(app: tauri::AppHandle, config: config::AppConfig) -> Result<(), String> {
+    config::save(&app_config_dir(&app)?, config)
+}
+
+/// Tauri command to add a window title pattern to the ignore list
+///
+/// # Arguments
+/// * `pattern` - Substring, or regex when `is_regex` is true (both case-insensitive)
+///
+/// # Returns
+/// * JSON array of ignore patterns `{ kind, pattern }` after the change
+#[tauri::command]
+fn add_ignored_pattern(
+    app: tauri::AppHandle,
+    pattern: String,
+    is_regex: Option<bool>,
+) -> Result<String, String> {
+    let pattern = detector::IgnorePattern::new(pattern, is_regex.unwrap_or(false));
+    pattern.validate()?;
+
+    let mut config = config::current();
+    if !config.ignored_patterns.contains(&pattern) {
+        config.ignored_patterns.push(pattern);
+    }
+    let patterns = config.ignored_patterns.clone();
+    config::save(&app_config_dir(&app)?, config)?;
+
+    serde_json::to_string(&patterns).map_err(|e| format!("Serialization error: {}", e))
+}
+
+/// Tauri command to remove a window title pattern from the ignore list
+///
+/// # Returns
+/// * JSON array of ignore patterns `{ kind, pattern }` after the change
+#[tauri::command]
+fn remove_ignored_pattern(
+    app: tauri::AppHandle,
+    pattern: String,
+    is_regex: Option<bool>,
+) -> Result<String, String> {
+    let pattern = detector::IgnorePattern::new(pattern, is_regex.unwrap_or(false));
+
+    let mut config = config::current();
+    config.ignored_patterns.retain(|p| p != &pattern);
+    let patterns = config.ignored_patterns.clone();
+    config::save(&app_config_dir(&app)?, config)?;
+
+    serde_json::to_string(&patterns).map_err(|e| format!("Serialization error: {}", e))
+}
+
+// ============================================================================
+// LOGGING COMMANDS
+// ============================================================================
+
+/// Get the path of the current log file, e.g. to attach to a bug report
+#[tauri::command]
+fn get_log_path() -> Result<String, String> {
+    logging::log_path()
+        .map(|path| path.to_string_lossy().to_string())
+        .ok_or_else(|| "Logging not initialized".to_string())
+}
+
+/// Change the log level at runtime ("trace", "debug", "info", "warn", "error", "off")
+#[tauri::command]
+fn set_log_level(level: String) -> Result<(), String> {
+    logging::set_log_level(&level)
+}
+
+// ============================================================================
+// HEALTH CHECK COMMANDS
+// ============================================================================
+
+/// Test the AniList connection with a lightweight Viewer query
+/// Returns JSON `{ ok, user_name, error_kind, message }`
+#[tauri::command]
+async fn check_anilist(access_token: String) -> Result<String, String> {
+    let result = health::check_anilist(&access_token).await;
+    serde_json::to_string(&result).map_err(|e| format!("Serialization error: {}", e))
+}
+
+/// Test the MAL connection with a lightweight @me request and a client ID check
+/// Returns JSON `{ ok, user_name, error_kind, message }`
+#[tauri::command]
+async fn check_mal(access_token: String, client_id: String) -> Result<String, String> {
+    let result = health::check_mal(&access_token, &client_id).await;
+    serde_json::to_string(&result).map_err(|e| format!("Serialization error: {}", e))
+}
+
+/// Environment info for bug reports
+/// Returns JSON `{ app_version, os, arch, detection_backend, screen_recording_permission }`
+#[tauri::command]
+fn get_diagnostics(app: tauri::AppHandle) -> Result<String, String> {
+    let result = diagnostics::collect(app.package_info().version.to_string());
+    serde_json::to_string(&result).map_err(|e| format!("Serialization error: {}", e))
+}
+
+/// Check the macOS Screen Recording permission needed to read window titles
+/// Returns JSON "granted", "denied" or "not_required" (other platforms)
+#[tauri::command]
+fn has_screen_recording_permission() -> Result<String, String> {
+    let status = diagnostics::screen_recording_permission();
+    serde_json::to_string(&status).map_err(|e| format!("Serialization error: {}", e))
+}
+
+/// Show the macOS Screen Recording permission prompt
+/// Returns the same values as has_screen_recording_permission; a grant from
+/// the prompt only shows up after the app is restarted
+#[tauri::command]
+fn request_screen_recording_permission() -> Result<String, String> {
+    let status = diagnostics::request_screen_recording_permission();
+    serde_json::to_string(&status).map_err(|e| format!("Serialization error: {}", e))
+}
+
 /// Open a new browser window with the given URL
 /// This creates a native WebView window that behaves like a real browser,
 /// bypassing iframe restrictions that block embedded content
@@ -688,7 +1987,7 @@ async fn open_browser_window(
     use tauri::WebviewUrl;
     use tauri::WebviewWindowBuilder;
 
-    println!("[Browser] Opening native window for: {}", url);
+    info!("Opening native window for: {}", url);
 
     // Create a unique window label based on timestamp
     let window_label = format!(
@@ -712,7 +2011,7 @@ async fn open_browser_window(
     .build()
     .map_err(|e| format!("Failed to create window: {}", e))?;
 
-    println!("[Browser] Created window: {}", window_label);
+    info!("Created window: {}", window_label);
 
     Ok(format!("Opened {} in new window", title))
 }
@@ -758,7 +2057,7 @@ async fn resolve_host(host: &str) -> Option<IpAddr> {
         }
     }
 
-    println!("[DNS] Resolving {} via Google DoH...", host);
+    info!("Resolving {} via Google DoH...", host);
     let client = reqwest::Client::new();
     let url = format!("https://dns.google/resolve?name={}&type=A", host);
 
@@ -768,7 +2067,7 @@ async fn resolve_host(host: &str) -> Option<IpAddr> {
                 for answer in answers {
                     if let Some(data) = answer["data"].as_str() {
                         if let Ok(ip) = IpAddr::from_str(data) {
-                            println!("[DNS] Resolved {} -> {}", host, ip);
+                            info!("Resolved {} -> {}", host, ip);
                             let mut cache = DNS_CACHE.lock().unwrap();
                             cache.insert(host.to_string(), ip);
                             return Some(ip);
@@ -778,7 +2077,7 @@ async fn resolve_host(host: &str) -> Option<IpAddr> {
             }
         }
     }
-    println!("[DNS] Failed to resolve {} via DoH", host);
+    warn!("Failed to resolve {} via DoH", host);
     None
 }
 
@@ -857,7 +2156,7 @@ pub fn run() {
             Some(vec!["--minimized"]),
         ))
         .plugin(tauri_plugin_single_instance::init(|app, argv, _cwd| {
-            println!("{}, {argv:?}, {_cwd}", app.package_info().name);
+            info!("{}, {argv:?}, {_cwd}", app.package_info().name);
 
             app.emit("single-instance", argv.clone()).unwrap();
 
@@ -876,20 +2175,63 @@ pub fn run() {
             get_active_window,
             get_active_media_window,
             search_anime_command,
+            search_anime_debounced,
             get_anime_by_id_command,
+            resolve_cross_ids_command,
+            get_relations,
+            get_trending,
+            get_popular_season,
+            locate_episode,
+            get_anilist_user,
+            get_notifications,
+            get_genre_collection,
+            get_media_tags,
+            anilist_raw_query,
+            resolve_anime,
             match_anime_from_window_command,
+            match_anime_from_path_command,
             file_system::get_folder_contents,
+            file_system::hash_file,
+            file_system::find_duplicates,
             exchange_login_code,
+            generate_oauth_state,
+            begin_oauth,
+            parse_auth_callback,
             parse_window_title_command,
+            explain_parse,
+            parse_titles_batch,
+            group_by_series,
             detect_anime_command,
+            get_poll_interval,
+            set_manual_tracking,
+            clear_manual_tracking,
+            detect_from_title,
+            detect_candidates,
+            get_mpv_status,
+            get_vlc_status,
+            now_playing,
+            get_history,
+            clear_history,
             update_anime_progress_command,
             progressive_search_command,
             download_image_for_notification,
             download_image_for_notification,
+            extract_thumbnail,
+            get_video_duration,
             cbz_reader::get_cbz_info,
             cbz_reader::get_cbz_page,
             cbz_reader::is_valid_cbz,
             download_chapter_command,
+            download_chapter_report_command,
+            start_chapter_download,
+            cancel_download,
+            get_available_space,
+            list_downloaded_chapters,
+            extract_cbz,
+            merge_cbz,
+            enqueue_download,
+            get_queue_status,
+            cancel_job,
             hide_window,
             // MAL commands
             mal_generate_pkce,
@@ -903,12 +2245,43 @@ pub fn run() {
             mal_update_manga_progress,
             mal_get_anime_list,
             mal_get_manga_list,
+            import_mal_xml,
+            export_list,
+            // Tracker commands
+            tracker_search,
+            search_all_providers,
+            tracker_get_list,
+            tracker_update_progress,
+            // Config commands
+            load_config,
+            save_config,
+            add_ignored_pattern,
+            remove_ignored_pattern,
+            // Logging commands
+            get_log_path,
+            set_log_level,
+            // Health check commands
+            check_anilist,
+            check_mal,
+            get_diagnostics,
+            has_screen_recording_permission,
+            request_screen_recording_permission,
             // Browser window command
             open_browser_window,
             proxy_request,
             stream_proxy
         ])
         .setup(|app| {
+            // Start logging first so everything after this ends up in the log file
+            match app.path().app_log_dir() {
+                Ok(log_dir) => {
+                    if let Err(e) = logging::init(&log_dir) {
+                        eprintln!("{}", e);
+                    }
+                }
+                Err(e) => eprintln!("Failed to resolve app log dir: {}", e),
+            }
+
             // Register deep links at runtime for development mode (Windows/Linux)
             // This is needed because deep links are only registered on install by default
             #[cfg(any(target_os = "linux", windows))]
@@ -964,21 +2337,42 @@ pub fn run() {
             let start_minimized = args.iter().any(|arg| arg == "--minimized");
 
             if start_minimized {
-                println!("[Startup] Started with --minimized flag, keeping window hidden in tray");
+                info!("Started with --minimized flag, keeping window hidden in tray");
                 // Window is already hidden from config, nothing to do
             } else {
-                println!("[Startup] Normal startup, showing window");
+                info!("Normal startup, showing window");
                 if let Some(window) = app.get_webview_window("main") {
                     let _ = window.show();
                     let _ = window.set_focus();
                 }
             }
 
+            // Load user settings before anything reads them
+            match app_config_dir(app.handle()).and_then(|dir| config::load(&dir)) {
+                Ok(_) => info!("Config loaded"),
+                Err(e) => warn!("Using default config: {}", e),
+            }
+
+            // Compile the title parser patterns before the first detection poll
+            title_parser::warm_up();
+
+            // Start the download queue, resuming jobs left over from the last session
+            let app_handle = app.handle().clone();
+            let data_dir = app_data_dir(&app_handle)?;
+            tauri::async_runtime::spawn(async move {
+                download_queue::init(
+                    data_dir,
+                    std::sync::Arc::new(move |job| {
+                        let _ = app_handle.emit("download-progress", job);
+                    }),
+                );
+            });
+
             Ok(())
         })
         .register_uri_scheme_protocol("manga", |_app, request| {
             let url = request.uri().to_string();
-            println!("[Protocol] Manga Handler called for: {}", url);
+            info!("Manga Handler called for: {}", url);
             // Format: manga://localhost/path/to/file.cbz/page.jpg
             // The path might be URL encoded, so we need to decode it.
 
@@ -1033,7 +2427,7 @@ pub fn run() {
                     .body(bytes)
                     .unwrap(),
                 Err(e) => {
-                    eprintln!("Manga protocol error: {}", e);
+                    error!("Manga protocol error: {}", e);
                     tauri::http::Response::builder()
                         .status(404)
                         .body(Vec::new())
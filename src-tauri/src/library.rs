@@ -0,0 +1,126 @@
+//! Library Grouping
+//!
+//! PURPOSE: Group a folder's worth of file names by the show they belong to
+//! Each name is parsed, then titles are clustered: an exact match after
+//! normalizing case and spacing, or a near-identical fuzzy match, joins an
+//! existing series. That keeps "Frieren - 01" and "frieren  - 02" together
+//! while separating different shows.
+
+use serde::Serialize;
+use std::collections::BTreeMap;
+
+use crate::matcher;
+use crate::title_parser;
+
+/// Titles at least this similar are treated as the same series
+const SAME_SERIES_SIMILARITY: f64 = 0.85;
+
+/// Series name for files whose title couldn't be parsed
+pub const UNKNOWN_SERIES: &str = "Unknown";
+
+/// A file within a series
+#[derive(Debug, Clone, Serialize, PartialEq)]
+pub struct SeriesFile {
+    pub file: String,
+    pub episode: Option<i32>,
+    pub season: Option<i32>,
+}
+
+/// Lowercase and collapse whitespace
+fn normalize(title: &str) -> String {
+    title
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+        .to_lowercase()
+}
+
+/// Group file names by parsed series title
+///
+/// The series name is the title as first seen. Files in a series are sorted by
+/// season, then episode; files without an episode go last.
+pub fn group_by_series(titles: &[String]) -> BTreeMap<String, Vec<SeriesFile>> {
+    // (display name, normalized name, files), in first-seen order
+    let mut groups: Vec<(String, String, Vec<SeriesFile>)> = Vec::new();
+
+    for (title, parsed) in titles.iter().zip(title_parser::parse_titles_batch(titles)) {
+        let name = parsed
+            .title
+            .filter(|t| !t.trim().is_empty())
+            .unwrap_or_else(|| UNKNOWN_SERIES.to_string());
+        let normalized = normalize(&name);
+        let file = SeriesFile {
+            file: title.clone(),
+            episode: parsed.episode,
+            season: parsed.season,
+        };
+
+        let existing = groups
+            .iter()
+            .position(|(_, n, _)| *n == normalized)
+            .or_else(|| {
+                groups.iter().position(|(_, n, _)| {
+                    matcher::title_similarity(n, &normalized) >= SAME_SERIES_SIMILARITY
+                })
+            });
+        match existing {
+            Some(i) => groups[i].2.push(file),
+            None => groups.push((name, normalized, vec![file])),
+        }
+    }
+
+    groups
+        .into_iter()
+        .map(|(name, _, mut files)| {
+            files.sort_by_key(|f| (f.season.unwrap_or(1), f.episode.is_none(), f.episode));
+            (name, files)
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn titles(names: &[&str]) -> Vec<String> {
+        names.iter().map(|n| n.to_string()).collect()
+    }
+
+    fn episodes(files: &[SeriesFile]) -> Vec<Option<i32>> {
+        files.iter().map(|f| f.episode).collect()
+    }
+
+    #[test]
+    fn test_groups_interleaved_series() {
+        let groups = group_by_series(&titles(&[
+            "[SubsPlease] Sousou no Frieren - 03 (1080p).mkv",
+            "[SubsPlease] Dandadan - 02 (1080p).mkv",
+            "[SubsPlease] Sousou no Frieren - 01 (1080p).mkv",
+            "[Erai-raws] sousou  no frieren - 02 [1080p].mkv",
+            "[SubsPlease] Dandadan - 01 (1080p).mkv",
+        ]));
+
+        assert_eq!(groups.len(), 2);
+        assert_eq!(
+            episodes(&groups["Sousou no Frieren"]),
+            vec![Some(1), Some(2), Some(3)]
+        );
+        assert_eq!(episodes(&groups["Dandadan"]), vec![Some(1), Some(2)]);
+        assert_eq!(
+            groups["Dandadan"][0].file,
+            "[SubsPlease] Dandadan - 01 (1080p).mkv"
+        );
+    }
+
+    #[test]
+    fn test_near_identical_titles_share_a_series() {
+        let groups = group_by_series(&titles(&[
+            "Attack on Titan - 01.mkv",
+            "Attack on Titan! - 02.mkv",
+            "Attack on Titan Junior High - 01.mkv",
+        ]));
+
+        assert_eq!(episodes(&groups["Attack on Titan"]), vec![Some(1), Some(2)]);
+        assert_eq!(groups["Attack on Titan Junior High"].len(), 1);
+    }
+}
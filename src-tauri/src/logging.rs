@@ -0,0 +1,238 @@
+//! Logging
+//!
+//! PURPOSE: Keep a log file users can attach to bug reports
+//! Logs go through `tracing` to both stdout (for development) and `playon.log`
+//! in the app log dir. The file is rotated by size, keeping a few old files
+//! (`playon.log.1`, `playon.log.2`, ...).
+//!
+//! Secrets (tokens, authorization codes, PKCE verifiers) must never be logged;
+//! pass them through `redact` so only their length shows up.
+
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use tracing::info;
+use tracing::level_filters::LevelFilter;
+use tracing_subscriber::prelude::*;
+use tracing_subscriber::{fmt, reload, Registry};
+
+/// Name of the current log file
+pub const LOG_FILE_NAME: &str = "playon.log";
+
+/// Size at which the log file is rotated (5 MB)
+const MAX_LOG_BYTES: u64 = 5 * 1024 * 1024;
+
+/// Number of rotated files kept next to the current one
+const MAX_ROTATED_FILES: usize = 3;
+
+/// Log file that rotates once it grows past `max_bytes`
+pub struct RotatingFile {
+    path: PathBuf,
+    max_bytes: u64,
+    max_files: usize,
+    file: File,
+    written: u64,
+}
+
+impl RotatingFile {
+    /// Open (or create) the log file, appending to existing contents
+    pub fn open(path: PathBuf, max_bytes: u64, max_files: usize) -> io::Result<Self> {
+        let file = OpenOptions::new().create(true).append(true).open(&path)?;
+        let written = file.metadata()?.len();
+
+        Ok(Self {
+            path,
+            max_bytes,
+            max_files,
+            file,
+            written,
+        })
+    }
+
+    fn rotated_path(&self, index: usize) -> PathBuf {
+        let mut name = self.path.as_os_str().to_owned();
+        name.push(format!(".{}", index));
+        PathBuf::from(name)
+    }
+
+    /// Shift `log.1` -> `log.2` ..., move the current file to `log.1` and start a new one
+    fn rotate(&mut self) -> io::Result<()> {
+        self.file.flush()?;
+
+        if self.max_files == 0 {
+            let _ = fs::remove_file(&self.path);
+        } else {
+            let _ = fs::remove_file(self.rotated_path(self.max_files));
+            for index in (1..self.max_files).rev() {
+                let _ = fs::rename(self.rotated_path(index), self.rotated_path(index + 1));
+            }
+            fs::rename(&self.path, self.rotated_path(1))?;
+        }
+
+        self.file = OpenOptions::new()
+            .create(true)
+            .write(true)
+            .truncate(true)
+            .open(&self.path)?;
+        self.written = 0;
+        Ok(())
+    }
+}
+
+impl Write for RotatingFile {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        if self.written > 0 && self.written + buf.len() as u64 > self.max_bytes {
+            self.rotate()?;
+        }
+
+        let written = self.file.write(buf)?;
+        self.written += written as u64;
+        Ok(written)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.file.flush()
+    }
+}
+
+lazy_static::lazy_static! {
+    /// Handle to change the level after `init`
+    static ref LEVEL_HANDLE: Mutex<Option<reload::Handle<LevelFilter, Registry>>> = Mutex::new(None);
+    /// Path of the current log file, set by `init`
+    static ref LOG_PATH: Mutex<Option<PathBuf>> = Mutex::new(None);
+}
+
+/// Start logging to stdout and to a rotating file in `log_dir`
+///
+/// # Returns
+/// * Path of the log file
+pub fn init(log_dir: &Path) -> Result<PathBuf, String> {
+    fs::create_dir_all(log_dir).map_err(|e| format!("Failed to create log dir: {}", e))?;
+
+    let path = log_dir.join(LOG_FILE_NAME);
+    let file = RotatingFile::open(path.clone(), MAX_LOG_BYTES, MAX_ROTATED_FILES)
+        .map_err(|e| format!("Failed to open log file: {}", e))?;
+
+    let (filter, handle) = reload::Layer::new(LevelFilter::INFO);
+    tracing_subscriber::registry()
+        .with(filter)
+        .with(fmt::layer().with_ansi(false).with_writer(Mutex::new(file)))
+        .with(fmt::layer())
+        .try_init()
+        .map_err(|e| format!("Failed to initialize logging: {}", e))?;
+
+    if let Ok(mut level_handle) = LEVEL_HANDLE.lock() {
+        *level_handle = Some(handle);
+    }
+    if let Ok(mut log_path) = LOG_PATH.lock() {
+        *log_path = Some(path.clone());
+    }
+
+    Ok(path)
+}
+
+/// Path of the current log file (None before `init`)
+pub fn log_path() -> Option<PathBuf> {
+    LOG_PATH.lock().ok().and_then(|path| path.clone())
+}
+
+/// Change the log level ("trace", "debug", "info", "warn", "error" or "off")
+pub fn set_log_level(level: &str) -> Result<(), String> {
+    let level = parse_level(level)?;
+
+    let guard = LEVEL_HANDLE
+        .lock()
+        .map_err(|_| "Logging lock error".to_string())?;
+    let handle = guard
+        .as_ref()
+        .ok_or_else(|| "Logging not initialized".to_string())?;
+
+    handle
+        .modify(|filter| *filter = level)
+        .map_err(|e| format!("Failed to set log level: {}", e))?;
+
+    info!("Log level set to {}", level);
+    Ok(())
+}
+
+/// Mask a secret for logging, keeping only its length
+#[cfg_attr(not(feature = "debug-auth"), allow(dead_code))]
+pub fn redact(secret: &str) -> String {
+    if secret.is_empty() {
+        "<empty>".to_string()
+    } else {
+        format!("<redacted, {} chars>", secret.chars().count())
+    }
+}
+
+fn parse_level(level: &str) -> Result<LevelFilter, String> {
+    match level.trim().to_lowercase().as_str() {
+        "trace" => Ok(LevelFilter::TRACE),
+        "debug" => Ok(LevelFilter::DEBUG),
+        "info" => Ok(LevelFilter::INFO),
+        "warn" | "warning" => Ok(LevelFilter::WARN),
+        "error" => Ok(LevelFilter::ERROR),
+        "off" => Ok(LevelFilter::OFF),
+        other => Err(format!("Unknown log level: {}", other)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::TempDir;
+
+    #[test]
+    fn test_rotates_by_size() {
+        let dir = TempDir::new("log_rotate");
+        let path = dir.join(LOG_FILE_NAME);
+        let mut file = RotatingFile::open(path.clone(), 10, 2).unwrap();
+
+        for line in ["first\n", "second\n", "third\n", "fourth\n"] {
+            file.write_all(line.as_bytes()).unwrap();
+        }
+        file.flush().unwrap();
+
+        // Each line pushes the file past 10 bytes, so every write starts a new file
+        assert_eq!(fs::read_to_string(&path).unwrap(), "fourth\n");
+        assert_eq!(
+            fs::read_to_string(dir.join("playon.log.1")).unwrap(),
+            "third\n"
+        );
+        assert_eq!(
+            fs::read_to_string(dir.join("playon.log.2")).unwrap(),
+            "second\n"
+        );
+        assert!(!dir.join("playon.log.3").exists());
+    }
+
+    #[test]
+    fn test_appends_to_existing_log() {
+        let dir = TempDir::new("log_append");
+        let path = dir.join(LOG_FILE_NAME);
+        fs::write(&path, "old\n").unwrap();
+
+        let mut file = RotatingFile::open(path.clone(), 1024, 2).unwrap();
+        file.write_all(b"new\n").unwrap();
+
+        assert_eq!(fs::read_to_string(&path).unwrap(), "old\nnew\n");
+    }
+
+    #[test]
+    fn test_redact_masks_secret() {
+        let secret = "dBjftJeZ4CVP-mB92K27uhbUJU1p1r_wW1gFWFOEjXk";
+        let redacted = redact(secret);
+
+        assert_eq!(redacted, "<redacted, 43 chars>");
+        assert!(!redacted.contains("dBjf"));
+        assert_eq!(redact(""), "<empty>");
+    }
+
+    #[test]
+    fn test_parse_level() {
+        assert_eq!(parse_level("DEBUG"), Ok(LevelFilter::DEBUG));
+        assert_eq!(parse_level(" warn "), Ok(LevelFilter::WARN));
+        assert!(parse_level("verbose").is_err());
+    }
+}
@@ -6,6 +6,7 @@
 //! NOTE: Requires Screen Recording permission to see other app windows
 #![cfg(target_os = "macos")]
 
+use core_foundation::array::CFArrayRef;
 use core_foundation::base::TCFType;
 use core_foundation::dictionary::CFDictionaryRef;
 use core_foundation::number::CFNumber;
@@ -14,76 +15,148 @@ use core_graphics::window::{
     kCGNullWindowID, kCGWindowListExcludeDesktopElements, kCGWindowListOptionOnScreenOnly,
     CGWindowListCopyWindowInfo,
 };
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
 
-/// Get the title of the currently active/frontmost window on macOS
+/// How long a window list is reused
 ///
-/// Uses Core Graphics API to get window information.
-/// Falls back to getting any visible window title if frontmost can't be determined.
+/// Detection polls several times per second and each poll asks for both the
+/// active window and all visible windows; one CG call per window is enough.
+const WINDOW_LIST_TTL: Duration = Duration::from_millis(250);
+
+/// The parts of a `CGWindowListCopyWindowInfo` entry detection uses
 ///
-/// # Returns
-/// * `Some(String)` - The window title if successfully retrieved
-/// * `None` - If no window is active or an error occurred
-pub fn get_active_window_title() -> Option<String> {
+/// Copied out of the CF dictionaries so the list can be released right away
+/// and the cache never holds Core Foundation references.
+#[derive(Debug, Clone, PartialEq)]
+struct WindowInfo {
+    name: Option<String>,
+    owner_name: Option<String>,
+    owner_pid: Option<i32>,
+    layer: Option<i32>,
+}
+
+/// A value that's refetched once it's older than `ttl`
+struct TtlCache<T> {
+    ttl: Duration,
+    entry: Option<(Instant, T)>,
+}
+
+impl<T: Clone> TtlCache<T> {
+    fn new(ttl: Duration) -> Self {
+        Self { ttl, entry: None }
+    }
+
+    /// The cached value if it's still fresh at `now`, else `fetch()`'s
+    fn get_or_refresh(&mut self, now: Instant, fetch: impl FnOnce() -> T) -> T {
+        if let Some((fetched_at, value)) = &self.entry {
+            if now.saturating_duration_since(*fetched_at) < self.ttl {
+                return value.clone();
+            }
+        }
+
+        let value = fetch();
+        self.entry = Some((now, value.clone()));
+        value
+    }
+}
+
+lazy_static::lazy_static! {
+    /// Window list shared by the title functions below
+    static ref WINDOW_LIST: Mutex<TtlCache<Arc<Vec<WindowInfo>>>> =
+        Mutex::new(TtlCache::new(WINDOW_LIST_TTL));
+}
+
+/// On-screen windows, front to back, at most `WINDOW_LIST_TTL` old
+fn window_list() -> Arc<Vec<WindowInfo>> {
+    let mut cache = WINDOW_LIST.lock().unwrap_or_else(|e| e.into_inner());
+    cache.get_or_refresh(Instant::now(), copy_window_list)
+}
+
+/// Owns a window list from `CGWindowListCopyWindowInfo` and releases it on drop
+struct WindowListGuard(CFArrayRef);
+
+impl Drop for WindowListGuard {
+    fn drop(&mut self) {
+        unsafe {
+            core_foundation::base::CFRelease(self.0 as _);
+        }
+    }
+}
+
+/// Run `f` on the dictionaries describing the on-screen windows, front to back
+///
+/// The list is released when this returns (or `f` panics), so the dictionary
+/// refs must not outlive `f`; copy what's needed out of them.
+fn with_window_dicts<F, R>(f: F) -> R
+where
+    F: FnOnce(&[CFDictionaryRef]) -> R,
+{
     let options = kCGWindowListOptionOnScreenOnly | kCGWindowListExcludeDesktopElements;
 
     let window_list = unsafe { CGWindowListCopyWindowInfo(options, kCGNullWindowID) };
 
     if window_list.is_null() {
-        return None;
+        return f(&[]);
     }
+    let guard = WindowListGuard(window_list);
 
-    let windows: Vec<CFDictionaryRef> = unsafe {
-        let count = core_foundation::array::CFArrayGetCount(window_list as _);
+    let dicts: Vec<CFDictionaryRef> = unsafe {
+        let count = core_foundation::array::CFArrayGetCount(guard.0);
         (0..count)
-            .filter_map(|i| {
-                let ptr = core_foundation::array::CFArrayGetValueAtIndex(window_list as _, i);
-                if ptr.is_null() {
-                    None
-                } else {
-                    Some(ptr as CFDictionaryRef)
-                }
-            })
+            .map(|i| core_foundation::array::CFArrayGetValueAtIndex(guard.0, i))
+            .filter(|ptr| !ptr.is_null())
+            .map(|ptr| ptr as CFDictionaryRef)
             .collect()
     };
 
-    // Find the frontmost window (layer 0, on screen)
-    // Windows are ordered front to back, so first valid window with a name is usually frontmost
-    for dict in windows.iter() {
-        if let Some(title) = get_window_name(*dict) {
-            if !title.is_empty() && title != "Notification Center" && title != "Control Center" {
-                // Check if this window belongs to a normal app (layer 0)
-                if let Some(layer) = get_window_layer(*dict) {
-                    if layer == 0 {
-                        // Clean up
-                        unsafe {
-                            core_foundation::base::CFRelease(window_list as _);
-                        }
-                        return Some(title);
-                    }
-                }
-            }
-        }
-    }
+    f(&dicts)
+}
 
-    // Fallback: return the first window with any name
-    for dict in windows.iter() {
-        if let Some(title) = get_window_name(*dict) {
-            if !title.is_empty() && title != "Notification Center" && title != "Control Center" {
-                // Clean up
-                unsafe {
-                    core_foundation::base::CFRelease(window_list as _);
-                }
-                return Some(title);
-            }
-        }
-    }
+/// Ask Core Graphics for the on-screen windows
+fn copy_window_list() -> Arc<Vec<WindowInfo>> {
+    let windows = with_window_dicts(|dicts| {
+        dicts
+            .iter()
+            .map(|&dict| WindowInfo {
+                name: get_window_name(dict),
+                owner_name: get_window_owner_name(dict),
+                owner_pid: get_window_owner_pid(dict),
+                layer: get_window_layer(dict),
+            })
+            .collect()
+    });
+    Arc::new(windows)
+}
 
-    // Clean up
-    unsafe {
-        core_foundation::base::CFRelease(window_list as _);
-    }
+/// Windows that aren't part of the system UI
+fn is_app_window(title: &str) -> bool {
+    !title.is_empty() && title != "Notification Center" && title != "Control Center"
+}
 
-    None
+/// Get the title of the currently active/frontmost window on macOS
+///
+/// Uses Core Graphics API to get window information.
+/// Falls back to getting any visible window title if frontmost can't be determined.
+///
+/// # Returns
+/// * `Some(String)` - The window title if successfully retrieved
+/// * `None` - If no window is active or an error occurred
+pub fn get_active_window_title() -> Option<String> {
+    let windows = window_list();
+
+    // Find the frontmost window (layer 0, on screen)
+    // Windows are ordered front to back, so first valid window with a name is usually frontmost
+    let named = || {
+        windows
+            .iter()
+            .filter(|w| w.name.as_deref().is_some_and(is_app_window))
+    };
+    named()
+        .find(|w| w.layer == Some(0))
+        // Fallback: return the first window with any name
+        .or_else(|| named().next())
+        .and_then(|w| w.name.clone())
 }
 
 /// Get titles of all visible windows from common media players and browsers
@@ -119,53 +192,65 @@ pub fn get_all_visible_window_titles() -> Vec<String> {
         "Zen",
     ];
 
-    let options = kCGWindowListOptionOnScreenOnly | kCGWindowListExcludeDesktopElements;
-
-    let window_list = unsafe { CGWindowListCopyWindowInfo(options, kCGNullWindowID) };
-
-    if window_list.is_null() {
-        return titles;
-    }
-
-    let windows: Vec<CFDictionaryRef> = unsafe {
-        let count = core_foundation::array::CFArrayGetCount(window_list as _);
-        (0..count)
-            .filter_map(|i| {
-                let ptr = core_foundation::array::CFArrayGetValueAtIndex(window_list as _, i);
-                if ptr.is_null() {
-                    None
-                } else {
-                    Some(ptr as CFDictionaryRef)
-                }
-            })
-            .collect()
-    };
-
-    for dict in windows.iter() {
-        if let Some(owner) = get_window_owner_name(*dict) {
+    for window in window_list().iter() {
+        if let Some(owner) = &window.owner_name {
             // Check if this is one of our target apps
             let is_target = target_apps
                 .iter()
                 .any(|app| owner.to_lowercase().contains(&app.to_lowercase()));
 
             if is_target {
-                if let Some(title) = get_window_name(*dict) {
+                if let Some(title) = &window.name {
                     if !title.is_empty() {
-                        titles.push(title);
+                        titles.push(title.clone());
                     }
                 }
             }
         }
     }
 
-    // Clean up
-    unsafe {
-        core_foundation::base::CFRelease(window_list as _);
-    }
-
     titles
 }
 
+#[link(name = "CoreGraphics", kind = "framework")]
+extern "C" {
+    // macOS 10.15+
+    fn CGPreflightScreenCaptureAccess() -> bool;
+    fn CGRequestScreenCaptureAccess() -> bool;
+}
+
+/// Whether the app has the Screen Recording permission
+///
+/// Asks the system first; readable window names count as granted too, since
+/// that's what detection actually needs.
+pub fn has_screen_recording_permission() -> bool {
+    unsafe { CGPreflightScreenCaptureAccess() }
+    || window_names_readable()
+}
+
+/// Show the system prompt for the Screen Recording permission
+///
+/// macOS only prompts once; later calls just return the current state. A
+/// permission granted from the prompt takes effect after the app restarts.
+///
+/// # Returns
+/// * `true` - Already granted
+pub fn request_screen_recording_permission() -> bool {
+    unsafe { CGRequestScreenCaptureAccess() }
+}
+
+/// Whether window titles of other apps can be read
+///
+/// Without the Screen Recording permission macOS still lists every window
+/// but leaves out `kCGWindowName` for windows owned by other processes, so
+/// a single named window from another app means the permission is granted.
+pub fn window_names_readable() -> bool {
+    let own_pid = std::process::id() as i32;
+    window_list().iter().any(|w| {
+        w.owner_pid != Some(own_pid) && w.name.as_deref().is_some_and(|name| !name.is_empty())
+    })
+}
+
 /// Extract window name from a Core Foundation dictionary
 fn get_window_name(dict: CFDictionaryRef) -> Option<String> {
     unsafe {
@@ -229,6 +314,27 @@ fn get_window_layer(dict: CFDictionaryRef) -> Option<i32> {
     }
 }
 
+/// Extract the owning process id from a Core Foundation dictionary
+fn get_window_owner_pid(dict: CFDictionaryRef) -> Option<i32> {
+    unsafe {
+        let key = CFString::new("kCGWindowOwnerPID");
+        let mut value: *const std::ffi::c_void = std::ptr::null();
+
+        if core_foundation::dictionary::CFDictionaryGetValueIfPresent(
+            dict,
+            key.as_concrete_TypeRef() as *const _,
+            &mut value,
+        ) != 0
+            && !value.is_null()
+        {
+            let cf_num = CFNumber::wrap_under_get_rule(value as _);
+            cf_num.to_i32()
+        } else {
+            None
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -241,6 +347,56 @@ mod tests {
         // We can't assert much since it depends on what's on screen
     }
 
+    #[test]
+    fn test_with_window_dicts_releases_each_list() {
+        // Each call copies and releases a fresh list; repeated calls must not
+        // crash (double release) or keep returning stale data
+        for _ in 0..100 {
+            let (count, names) = with_window_dicts(|dicts| {
+                let names: Vec<Option<String>> =
+                    dicts.iter().map(|&dict| get_window_name(dict)).collect();
+                (dicts.len(), names)
+            });
+            assert_eq!(count, names.len());
+        }
+    }
+
+    #[test]
+    fn test_cache_reused_within_ttl() {
+        let mut cache = TtlCache::new(Duration::from_millis(250));
+        let start = Instant::now();
+        let mut fetches = 0;
+
+        let first = cache.get_or_refresh(start, || {
+            fetches += 1;
+            fetches
+        });
+        let second = cache.get_or_refresh(start + Duration::from_millis(249), || {
+            fetches += 1;
+            fetches
+        });
+
+        assert_eq!((first, second), (1, 1));
+        assert_eq!(fetches, 1);
+    }
+
+    #[test]
+    fn test_cache_refreshed_after_ttl() {
+        let mut cache = TtlCache::new(Duration::from_millis(250));
+        let start = Instant::now();
+
+        assert_eq!(cache.get_or_refresh(start, || 1), 1);
+        assert_eq!(
+            cache.get_or_refresh(start + Duration::from_millis(250), || 2),
+            2
+        );
+        // The refresh restarts the TTL
+        assert_eq!(
+            cache.get_or_refresh(start + Duration::from_millis(400), || 3),
+            2
+        );
+    }
+
     #[test]
     fn test_get_all_visible_window_titles() {
         let titles = get_all_visible_window_titles();
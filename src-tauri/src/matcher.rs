@@ -0,0 +1,275 @@
+//! Title Matcher
+//!
+//! PURPOSE: Pick the AniList search result that best matches a parsed title
+//! AniList's search ranking favours popularity, so the first result is often a
+//! sequel or spin-off. Each candidate is scored against the query instead.
+//!
+//! SCORING: Dice coefficient over character bigrams of the normalized titles
+//! (lowercase, alphanumeric words only), taking the best of romaji/english/native.
+
+use serde::Serialize;
+use std::collections::HashMap;
+use tracing::info;
+
+use crate::anilist::{self, Anime, AnimeTitle, MediaFormat, MediaListItem};
+use crate::config;
+
+/// Minimum score for a candidate to count as a match
+pub const MIN_CONFIDENCE: f64 = 0.5;
+
+/// Minimum score for a list entry to be preferred over searching
+pub const LIST_ENTRY_CONFIDENCE: f64 = 0.8;
+
+/// Number of AniList results considered when resolving
+const SEARCH_CANDIDATES: i32 = 10;
+
+/// A candidate together with its similarity to the query
+#[derive(Debug, Clone)]
+pub struct ScoredMatch<'a> {
+    pub anime: &'a Anime,
+    /// Similarity in 0.0..=1.0
+    pub score: f64,
+}
+
+/// Everything needed to track an anime on either provider
+#[derive(Debug, Clone, Serialize)]
+pub struct ResolvedAnime {
+    pub anilist_id: i32,
+    /// None when AniList has no MAL mapping for the entry
+    pub mal_id: Option<i64>,
+    pub title: String,
+    pub episodes: Option<i32>,
+    pub cover: Option<String>,
+    /// Similarity of the matched title to the query (0.0 - 1.0)
+    pub confidence: f64,
+}
+
+/// Lowercase and keep only alphanumeric words separated by single spaces
+fn normalize(title: &str) -> String {
+    title
+        .to_lowercase()
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|w| !w.is_empty())
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Similarity of two titles in 0.0..=1.0
+pub fn title_similarity(a: &str, b: &str) -> f64 {
+    let a = normalize(a);
+    let b = normalize(b);
+
+    if a.is_empty() || b.is_empty() {
+        return 0.0;
+    }
+    if a == b {
+        return 1.0;
+    }
+
+    let bigrams = |s: &str| -> HashMap<(char, char), usize> {
+        let chars: Vec<char> = s.chars().collect();
+        let mut counts = HashMap::new();
+        for pair in chars.windows(2) {
+            *counts.entry((pair[0], pair[1])).or_insert(0) += 1;
+        }
+        counts
+    };
+
+    let a_bigrams = bigrams(&a);
+    let b_bigrams = bigrams(&b);
+    let total: usize = a_bigrams.values().sum::<usize>() + b_bigrams.values().sum::<usize>();
+    if total == 0 {
+        // Single characters that differ
+        return 0.0;
+    }
+
+    let shared: usize = a_bigrams
+        .iter()
+        .map(|(bigram, count)| (*count).min(*b_bigrams.get(bigram).unwrap_or(&0)))
+        .sum();
+
+    2.0 * shared as f64 / total as f64
+}
+
+/// Score titles against a query using the best-matching variant
+pub fn score_title(query: &str, title: &AnimeTitle) -> f64 {
+    [&title.romaji, &title.english, &title.native]
+        .into_iter()
+        .flatten()
+        .map(|title| title_similarity(query, title))
+        .fold(0.0, f64::max)
+}
+
+/// Score an anime against a query using its best-matching title
+pub fn score_anime(query: &str, anime: &Anime) -> f64 {
+    score_title(query, &anime.title)
+}
+
+/// Find the list entry matching a query, if one matches closely
+///
+/// Uses the stricter `LIST_ENTRY_CONFIDENCE`: a list entry is trusted over a
+/// fresh search, so a loose match here would hide the right show.
+pub fn best_list_entry<'a>(query: &str, list: &'a [MediaListItem]) -> Option<&'a MediaListItem> {
+    list.iter()
+        .map(|item| (item, score_title(query, &item.media.title)))
+        .filter(|(_, score)| *score >= LIST_ENTRY_CONFIDENCE)
+        .fold(None, |best: Option<(&MediaListItem, f64)>, m| match best {
+            Some(b) if b.1 >= m.1 => Some(b),
+            _ => Some(m),
+        })
+        .map(|(item, _)| item)
+}
+
+/// Pick the highest-scoring candidate at or above `MIN_CONFIDENCE`
+///
+/// Ties keep the earlier candidate, preserving AniList's ranking.
+pub fn best_match<'a>(query: &str, candidates: &'a [Anime]) -> Option<ScoredMatch<'a>> {
+    candidates
+        .iter()
+        .map(|anime| ScoredMatch {
+            anime,
+            score: score_anime(query, anime),
+        })
+        .filter(|m| m.score >= MIN_CONFIDENCE)
+        .fold(None, |best: Option<ScoredMatch>, m| match best {
+            Some(b) if b.score >= m.score => Some(b),
+            _ => Some(m),
+        })
+}
+
+/// Words that mark a title as a movie ("Kimetsu no Yaiba the Movie")
+const MOVIE_WORDS: &[&str] = &["movie", "gekijouban", "gekijōban"];
+
+/// The format a title names itself as, if any
+///
+/// Only movies are recognized: a movie search otherwise tends to match the TV
+/// series of the same name.
+pub fn format_hint(title: &str) -> Option<MediaFormat> {
+    let lower = title.to_lowercase();
+    let is_movie = lower
+        .split(|c: char| !c.is_alphanumeric())
+        .any(|word| MOVIE_WORDS.contains(&word))
+        // Often written straight into the name with no space
+        || lower.contains("劇場版");
+    is_movie.then_some(MediaFormat::Movie)
+}
+
+/// Search AniList for a title and return the best match with both provider ids
+///
+/// A movie title (see `format_hint`) is searched among movies first, then
+/// among every format if no movie comes back.
+///
+/// # Returns
+/// * `Ok(None)` - No candidate scored above `MIN_CONFIDENCE`
+pub async fn resolve_anime(title: &str) -> Result<Option<ResolvedAnime>, String> {
+    let format = format_hint(title);
+    let mut candidates = anilist::search_anime(title, SEARCH_CANDIDATES, false, format).await?;
+    if candidates.is_empty() && format.is_some() {
+        candidates = anilist::search_anime(title, SEARCH_CANDIDATES, false, None).await?;
+    }
+
+    let Some(best) = best_match(title, &candidates) else {
+        info!("No confident match for \"{}\"", title);
+        return Ok(None);
+    };
+
+    let anime = best.anime;
+    info!("Matched \"{}\" to {} ({:.2})", title, anime.id, best.score);
+
+    Ok(Some(ResolvedAnime {
+        anilist_id: anime.id,
+        mal_id: anilist::map_anilist_to_mal(anime),
+        title: anime.title.preferred(config::current().title_language),
+        episodes: anime.episodes,
+        cover: anime
+            .cover_image
+            .large
+            .clone()
+            .or_else(|| anime.cover_image.medium.clone()),
+        confidence: best.score,
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn anime(id: i32, romaji: &str, english: Option<&str>) -> Anime {
+        serde_json::from_value(serde_json::json!({
+            "id": id,
+            "title": { "romaji": romaji, "english": english },
+            "coverImage": {}
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn test_format_hint_for_movies() {
+        assert_eq!(
+            format_hint("Kimetsu no Yaiba the Movie: Mugen Train"),
+            Some(MediaFormat::Movie)
+        );
+        assert_eq!(
+            format_hint("Gekijouban Violet Evergarden"),
+            Some(MediaFormat::Movie)
+        );
+        assert_eq!(format_hint("劇場版呪術廻戦 0"), Some(MediaFormat::Movie));
+        assert_eq!(format_hint("Sousou no Frieren"), None);
+        // Part of a word doesn't count
+        assert_eq!(format_hint("Moviestar Academy"), None);
+    }
+
+    #[test]
+    fn test_similarity_ignores_case_and_punctuation() {
+        assert_eq!(title_similarity("Re:Zero", "re zero"), 1.0);
+        assert!(title_similarity("Frieren", "One Piece") < MIN_CONFIDENCE);
+    }
+
+    #[test]
+    fn test_best_match_prefers_closest_title() {
+        let candidates = vec![
+            anime(
+                1,
+                "Shingeki no Kyojin: The Final Season",
+                Some("Attack on Titan Final Season"),
+            ),
+            anime(2, "Shingeki no Kyojin", Some("Attack on Titan")),
+        ];
+
+        let best = best_match("Attack on Titan", &candidates).unwrap();
+        assert_eq!(best.anime.id, 2);
+        assert_eq!(best.score, 1.0);
+    }
+
+    fn list_item(media_id: i32, romaji: &str) -> MediaListItem {
+        serde_json::from_value(serde_json::json!({
+            "mediaId": media_id,
+            "status": "CURRENT",
+            "progress": 3,
+            "score": 0.0,
+            "media": { "title": { "romaji": romaji } }
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn test_best_list_entry() {
+        let list = vec![
+            list_item(1, "Hunter x Hunter (2011)"),
+            list_item(2, "Hunter x Hunter"),
+            list_item(3, "One Piece"),
+        ];
+        assert_eq!(
+            best_list_entry("Hunter x Hunter", &list).unwrap().media_id,
+            2
+        );
+        assert!(best_list_entry("Sousou no Frieren", &list).is_none());
+    }
+
+    #[test]
+    fn test_no_match_below_confidence() {
+        let candidates = vec![anime(1, "One Piece", None)];
+        assert!(best_match("Sousou no Frieren", &candidates).is_none());
+        assert!(best_match("anything", &[]).is_none());
+    }
+}
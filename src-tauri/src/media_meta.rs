@@ -0,0 +1,781 @@
+//! Media Metadata
+//!
+//! PURPOSE: Read show/episode tags from local video files
+//! Some files have names that say nothing ("video1.mkv") while the container
+//! still carries the show and episode. When the file name parse is
+//! low-confidence, these tags are used instead.
+//!
+//! MP4 tags (`tvsh`, `tves`, `tvsn`, `©nam`) are read with lofty. lofty doesn't
+//! read Matroska, so MKV/WebM files are walked with a small EBML reader that
+//! only looks at the segment title and the `Tags` element.
+//!
+//! The `matroska` crate isn't used because `Matroska::open` loads every
+//! attachment into memory, and anime releases routinely embed tens of MB of
+//! subtitle fonts that way. That's a lot to read just to get a title.
+//!
+//! The files are untrusted, so the reader is bounded. It never recurses: it
+//! only follows Tags > Tag > SimpleTag, so the nesting depth is fixed. The
+//! number of elements it visits and the bytes it keeps are both capped.
+//!
+//! Durations come from the same places (`mvhd`, segment `Info`), with ffprobe
+//! as a fallback for other containers.
+
+use lofty::config::ParseOptions;
+use lofty::file::AudioFile;
+use lofty::mp4::{AtomData, AtomIdent, Ilst, Mp4File};
+use serde::Serialize;
+use std::fs::File;
+use std::io::{self, BufReader, Cursor, Read, Seek, SeekFrom};
+use std::path::Path;
+use tokio::process::Command;
+use tracing::debug;
+
+use crate::thumbnail;
+use crate::title_parser::{self, ParsedTitle};
+
+/// Tags come from the file itself, so they're trusted over most name patterns
+const CONFIDENCE_TAGS: f32 = 0.95;
+
+/// File name parses at or below this confidence are checked against the tags
+const LOW_CONFIDENCE: f32 = title_parser::CONFIDENCE_NUMBER_SIGN;
+
+/// Largest `Info`/`Tags` element read into memory; real ones are a few KB
+const MAX_ELEMENT_SIZE: u64 = 4 * 1024 * 1024;
+
+/// Most element bytes kept in memory per file, across all `Info`/`Tags`
+const MAX_READ_BYTES: u64 = 2 * MAX_ELEMENT_SIZE;
+
+/// Most top-level segment elements scanned; a long episode has a few thousand clusters
+const MAX_SEGMENT_ELEMENTS: usize = 100_000;
+
+/// Most children parsed out of one element body
+const MAX_CHILDREN: usize = 4096;
+
+/// `mvhd` is about 100 bytes; only the start is needed
+const MVHD_MAX_SIZE: u64 = 256;
+
+/// ffprobe binary looked up on PATH, used when the container has no duration
+const FFPROBE: &str = "ffprobe";
+
+// Matroska element IDs (marker bits included)
+const EBML_HEADER: u32 = 0x1A45_DFA3;
+const SEGMENT: u32 = 0x1853_8067;
+const INFO: u32 = 0x1549_A966;
+const SEGMENT_TITLE: u32 = 0x7BA9;
+const TIMESTAMP_SCALE: u32 = 0x2A_D7B1;
+const DURATION: u32 = 0x4489;
+const TAGS: u32 = 0x1254_C367;
+const TAG: u32 = 0x7373;
+const TARGETS: u32 = 0x63C0;
+const TARGET_TYPE_VALUE: u32 = 0x68CA;
+const SIMPLE_TAG: u32 = 0x67C8;
+const TAG_NAME: u32 = 0x45A3;
+const TAG_STRING: u32 = 0x4487;
+
+/// Nanoseconds per `Duration` tick when the file doesn't say
+const DEFAULT_TIMESTAMP_SCALE: u64 = 1_000_000;
+
+/// Matroska target levels: a whole show, a season, a single episode
+const TARGET_COLLECTION: u64 = 70;
+const TARGET_SEASON: u64 = 60;
+const TARGET_EPISODE: u64 = 50;
+
+/// Show and episode info found in a file's tags
+#[derive(Debug, Clone, Default, Serialize, PartialEq)]
+pub struct MediaMeta {
+    /// Container title, often the release name ("[Group] Show - 05")
+    pub title: Option<String>,
+    /// Show name
+    pub show: Option<String>,
+    pub episode: Option<i32>,
+    pub season: Option<i32>,
+}
+
+impl MediaMeta {
+    fn is_empty(&self) -> bool {
+        self.title.is_none() && self.show.is_none() && self.episode.is_none()
+    }
+
+    /// Turn the tags into a parse result
+    ///
+    /// A show tag is used as-is; otherwise the container title is parsed like a
+    /// file name, with any episode/season tags filling the gaps.
+    pub fn to_parsed(&self) -> Option<ParsedTitle> {
+        if let Some(show) = &self.show {
+            return Some(ParsedTitle {
+                title: Some(show.clone()),
+                episode: self.episode,
+                episode_end: None,
+                season: self.season,
+                language: None,
+                confidence: CONFIDENCE_TAGS,
+            });
+        }
+
+        let title = self.title.as_deref()?;
+        let mut parsed = title_parser::parse_window_title(title);
+        parsed.episode = parsed.episode.or(self.episode);
+        parsed.season = parsed.season.or(self.season);
+        Some(parsed)
+    }
+}
+
+/// Read the show/episode tags of a video file
+///
+/// # Returns
+/// * `None` - The file has no usable tags, can't be read, or isn't MKV/WebM/MP4
+pub fn read_media_meta(path: &Path) -> Option<MediaMeta> {
+    let extension = path.extension()?.to_str()?.to_ascii_lowercase();
+
+    let result = match extension.as_str() {
+        "mkv" | "webm" => read_matroska(path),
+        "mp4" | "m4v" | "mov" => read_mp4(path),
+        _ => return None,
+    };
+
+    match result {
+        Ok(meta) => meta.filter(|meta| !meta.is_empty()),
+        Err(e) => {
+            debug!("Failed to read {}: {}", path.display(), e);
+            None
+        }
+    }
+}
+
+/// Parse a local file, falling back to its tags when the name says too little
+///
+/// # Arguments
+/// * `path` - Full path to a video file
+pub fn parse_local_file(path: &str) -> ParsedTitle {
+    let parsed = title_parser::parse_file_path(path);
+    if parsed.confidence > LOW_CONFIDENCE && parsed.episode.is_some() {
+        return parsed;
+    }
+
+    match read_media_meta(Path::new(path)).and_then(|meta| meta.to_parsed()) {
+        Some(tagged) if tagged.confidence > parsed.confidence => tagged,
+        _ => parsed,
+    }
+}
+
+/// Duration of a video in seconds, read from the container without decoding
+///
+/// # Returns
+/// * `None` - Not MKV/WebM/MP4, unreadable, or no duration recorded
+pub fn read_duration(path: &Path) -> Option<f64> {
+    let extension = path.extension()?.to_str()?.to_ascii_lowercase();
+    let mut reader = BufReader::new(File::open(path).ok()?);
+
+    let result = match extension.as_str() {
+        "mkv" | "webm" => matroska_duration(&mut reader),
+        "mp4" | "m4v" | "mov" => mp4_duration(&mut reader),
+        _ => return None,
+    };
+
+    match result {
+        Ok(duration) => duration.filter(|secs| secs.is_finite() && *secs > 0.0),
+        Err(e) => {
+            debug!("Failed to read duration of {}: {}", path.display(), e);
+            None
+        }
+    }
+}
+
+/// Duration of a video in seconds, asking ffprobe when the container can't say
+///
+/// # Returns
+/// * `None` - The duration couldn't be read (or ffprobe isn't installed)
+pub async fn get_video_duration(path: &Path) -> Option<f64> {
+    if let Some(duration) = read_duration(path) {
+        return Some(duration);
+    }
+    if !thumbnail::is_video_path(path) || !path.is_file() {
+        return None;
+    }
+
+    let output = Command::new(FFPROBE)
+        .args(["-v", "error", "-show_entries", "format=duration"])
+        .args(["-of", "default=noprint_wrappers=1:nokey=1"])
+        .arg(path)
+        .kill_on_drop(true)
+        .output()
+        .await
+        .map_err(|e| debug!("ffprobe unavailable: {}", e))
+        .ok()?;
+    if !output.status.success() {
+        return None;
+    }
+
+    String::from_utf8_lossy(&output.stdout)
+        .trim()
+        .parse::<f64>()
+        .ok()
+        .filter(|secs| secs.is_finite() && *secs > 0.0)
+}
+
+// ============================================================================
+// MP4
+// ============================================================================
+
+fn read_mp4(path: &Path) -> Result<Option<MediaMeta>, String> {
+    let mut file = File::open(path).map_err(|e| e.to_string())?;
+    // Video-only files have no audio properties to read
+    let mp4 = Mp4File::read_from(&mut file, ParseOptions::new().read_properties(false))
+        .map_err(|e| e.to_string())?;
+
+    Ok(mp4.ilst().map(|ilst| MediaMeta {
+        title: atom_string(ilst, *b"\xa9nam"),
+        show: atom_string(ilst, *b"tvsh"),
+        episode: atom_number(ilst, *b"tves"),
+        season: atom_number(ilst, *b"tvsn"),
+    }))
+}
+
+/// Duration from the movie header (`moov.mvhd`)
+fn mp4_duration<R: Read + Seek>(reader: &mut R) -> io::Result<Option<f64>> {
+    let file_end = reader.seek(SeekFrom::End(0))?;
+    reader.seek(SeekFrom::Start(0))?;
+
+    let Some(moov_end) = find_box(reader, file_end, b"moov")? else {
+        return Ok(None);
+    };
+    let Some(mvhd_end) = find_box(reader, moov_end, b"mvhd")? else {
+        return Ok(None);
+    };
+    let size = mvhd_end.saturating_sub(reader.stream_position()?);
+    let mvhd = read_body(reader, size.min(MVHD_MAX_SIZE))?;
+
+    // version, flags, creation and modification times, then timescale and duration
+    let (timescale, duration) = match mvhd.first() {
+        Some(0) if mvhd.len() >= 20 => (
+            u32::from_be_bytes(mvhd[12..16].try_into().unwrap()),
+            u64::from(u32::from_be_bytes(mvhd[16..20].try_into().unwrap())),
+        ),
+        Some(1) if mvhd.len() >= 32 => (
+            u32::from_be_bytes(mvhd[20..24].try_into().unwrap()),
+            u64::from_be_bytes(mvhd[24..32].try_into().unwrap()),
+        ),
+        _ => return Ok(None),
+    };
+
+    // All bits set means the duration is unknown
+    if timescale == 0 || duration == u64::from(u32::MAX) || duration == u64::MAX {
+        return Ok(None);
+    }
+    Ok(Some(duration as f64 / f64::from(timescale)))
+}
+
+/// Find a box among the siblings before `end` and move to its body
+///
+/// # Returns
+/// * `Some(end)` - Where the box ends
+fn find_box<R: Read + Seek>(reader: &mut R, end: u64, kind: &[u8; 4]) -> io::Result<Option<u64>> {
+    loop {
+        let start = reader.stream_position()?;
+        if start.saturating_add(8) > end {
+            return Ok(None);
+        }
+
+        let mut header = [0u8; 8];
+        reader.read_exact(&mut header)?;
+        let mut size = u64::from(u32::from_be_bytes(header[..4].try_into().unwrap()));
+        let mut header_len = 8;
+        if size == 1 {
+            let mut large = [0u8; 8];
+            reader.read_exact(&mut large)?;
+            size = u64::from_be_bytes(large);
+            header_len = 16;
+        } else if size == 0 {
+            // Extends to the end of the file
+            size = end - start;
+        }
+        if size < header_len {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "Invalid box size",
+            ));
+        }
+
+        let box_end = start.saturating_add(size).min(end);
+        if &header[4..] == kind {
+            return Ok(Some(box_end));
+        }
+        reader.seek(SeekFrom::Start(box_end))?;
+    }
+}
+
+fn atom_string(ilst: &Ilst, fourcc: [u8; 4]) -> Option<String> {
+    let atom = ilst.get(&AtomIdent::Fourcc(fourcc))?;
+    atom.data().find_map(|data| match data {
+        AtomData::UTF8(text) | AtomData::UTF16(text) => non_empty(text),
+        _ => None,
+    })
+}
+
+fn atom_number(ilst: &Ilst, fourcc: [u8; 4]) -> Option<i32> {
+    let atom = ilst.get(&AtomIdent::Fourcc(fourcc))?;
+    atom.data().find_map(|data| match data {
+        AtomData::SignedInteger(n) => Some(*n),
+        AtomData::UnsignedInteger(n) => i32::try_from(*n).ok(),
+        AtomData::UTF8(text) => text.trim().parse().ok(),
+        _ => None,
+    })
+}
+
+// ============================================================================
+// MATROSKA
+// ============================================================================
+
+fn read_matroska(path: &Path) -> Result<Option<MediaMeta>, String> {
+    let file = File::open(path).map_err(|e| e.to_string())?;
+    read_matroska_from(&mut BufReader::new(file)).map_err(|e| e.to_string())
+}
+
+fn read_matroska_from<R: Read + Seek>(reader: &mut R) -> io::Result<Option<MediaMeta>> {
+    let Some(elements) = segment_elements(reader, &[INFO, TAGS])? else {
+        return Ok(None);
+    };
+
+    let mut meta = MediaMeta::default();
+    let mut tags = Vec::new();
+    for (id, body) in elements {
+        if id == INFO {
+            meta.title = find_child(&body, SEGMENT_TITLE)?
+                .and_then(|title| non_empty(&String::from_utf8_lossy(&title)));
+        } else {
+            tags.extend(parse_tags(&body)?);
+        }
+    }
+
+    for (level, name, value) in tags {
+        match (level, name.to_ascii_uppercase().as_str()) {
+            (TARGET_COLLECTION, "TITLE") => meta.show = meta.show.or(Some(value)),
+            (TARGET_SEASON, "PART_NUMBER") => meta.season = meta.season.or(value.parse().ok()),
+            (TARGET_EPISODE, "PART_NUMBER") => meta.episode = meta.episode.or(value.parse().ok()),
+            _ => {}
+        }
+    }
+
+    Ok(Some(meta))
+}
+
+/// Duration from the segment info: `Duration` ticks times `TimestampScale` ns
+fn matroska_duration<R: Read + Seek>(reader: &mut R) -> io::Result<Option<f64>> {
+    let Some(elements) = segment_elements(reader, &[INFO])? else {
+        return Ok(None);
+    };
+    let Some((_, info)) = elements.into_iter().next() else {
+        return Ok(None);
+    };
+
+    let scale = find_child(&info, TIMESTAMP_SCALE)?
+        .map(|value| read_uint(&value))
+        .unwrap_or(DEFAULT_TIMESTAMP_SCALE);
+    let duration = find_child(&info, DURATION)?.and_then(|value| match value.len() {
+        4 => Some(f64::from(f32::from_be_bytes(value.try_into().ok()?))),
+        8 => Some(f64::from_be_bytes(value.try_into().ok()?)),
+        _ => None,
+    });
+
+    Ok(duration.map(|ticks| ticks * scale as f64 / 1e9))
+}
+
+/// Bodies of the wanted top-level segment elements, in file order
+///
+/// Clusters (the actual video) and anything else are skipped over. Scanning
+/// stops after `MAX_SEGMENT_ELEMENTS` elements, and wanted elements past
+/// `MAX_READ_BYTES` in total are skipped too.
+///
+/// # Returns
+/// * `None` - Not a Matroska file
+fn segment_elements<R: Read + Seek>(
+    reader: &mut R,
+    wanted: &[u32],
+) -> io::Result<Option<Vec<Element>>> {
+    match read_element_header(reader)? {
+        Some((EBML_HEADER, Some(size))) => skip(reader, size)?,
+        _ => return Ok(None),
+    }
+    let segment_end = match read_element_header(reader)? {
+        Some((SEGMENT, size)) => {
+            let start = reader.stream_position()?;
+            size.map(|size| start.saturating_add(size))
+        }
+        _ => return Ok(None),
+    };
+
+    let mut elements = Vec::new();
+    let mut budget = MAX_READ_BYTES;
+
+    for _ in 0..MAX_SEGMENT_ELEMENTS {
+        if let Some(end) = segment_end {
+            if reader.stream_position()? >= end {
+                break;
+            }
+        }
+        let Some((id, size)) = read_element_header(reader)? else {
+            break;
+        };
+        // Unknown-size clusters (live recordings) can't be skipped
+        let Some(size) = size else {
+            break;
+        };
+
+        if wanted.contains(&id) && size <= MAX_ELEMENT_SIZE.min(budget) {
+            budget -= size;
+            elements.push((id, read_body(reader, size)?));
+        } else {
+            skip(reader, size)?;
+        }
+    }
+
+    Ok(Some(elements))
+}
+
+/// `(target level, tag name, value)` for every simple tag
+fn parse_tags(body: &[u8]) -> io::Result<Vec<(u64, String, String)>> {
+    let mut tags = Vec::new();
+
+    for (id, tag) in children(body)? {
+        if id != TAG {
+            continue;
+        }
+
+        let mut level = TARGET_EPISODE;
+        let mut simple_tags = Vec::new();
+        for (id, child) in children(&tag)? {
+            match id {
+                TARGETS => {
+                    if let Some(value) = find_child(&child, TARGET_TYPE_VALUE)? {
+                        level = read_uint(&value);
+                    }
+                }
+                SIMPLE_TAG => simple_tags.push(child),
+                _ => {}
+            }
+        }
+
+        for simple_tag in simple_tags {
+            let name = find_child(&simple_tag, TAG_NAME)?;
+            let value = find_child(&simple_tag, TAG_STRING)?;
+            if let (Some(name), Some(value)) = (name, value) {
+                if let Some(value) = non_empty(&String::from_utf8_lossy(&value)) {
+                    tags.push((level, String::from_utf8_lossy(&name).to_string(), value));
+                }
+            }
+        }
+    }
+
+    Ok(tags)
+}
+
+/// Element ID and body
+type Element = (u32, Vec<u8>);
+
+/// Direct children of an element body, at most `MAX_CHILDREN`
+fn children(body: &[u8]) -> io::Result<Vec<Element>> {
+    let mut cursor = Cursor::new(body);
+    let mut children = Vec::new();
+
+    while let Some((id, size)) = read_element_header(&mut cursor)? {
+        if children.len() == MAX_CHILDREN {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "Too many EBML child elements",
+            ));
+        }
+        let remaining = body.len() as u64 - cursor.position();
+        let size = size.unwrap_or(remaining).min(remaining);
+        children.push((id, read_body(&mut cursor, size)?));
+    }
+
+    Ok(children)
+}
+
+fn find_child(body: &[u8], id: u32) -> io::Result<Option<Vec<u8>>> {
+    Ok(children(body)?
+        .into_iter()
+        .find(|(child_id, _)| *child_id == id)
+        .map(|(_, body)| body))
+}
+
+/// Element ID and size, None at the end of the data
+///
+/// A size of None means "unknown" (all value bits set).
+fn read_element_header<R: Read>(reader: &mut R) -> io::Result<Option<(u32, Option<u64>)>> {
+    let Some((id, _)) = read_vint(reader, true)? else {
+        return Ok(None);
+    };
+    let Some((size, length)) = read_vint(reader, false)? else {
+        return Err(io::ErrorKind::UnexpectedEof.into());
+    };
+
+    let unknown = size == (1u64 << (7 * length)) - 1;
+    Ok(Some((id as u32, (!unknown).then_some(size))))
+}
+
+/// EBML variable-length integer and its length in bytes
+///
+/// IDs keep the length marker bit, sizes don't.
+fn read_vint<R: Read>(reader: &mut R, keep_marker: bool) -> io::Result<Option<(u64, u32)>> {
+    let mut first = [0u8; 1];
+    if reader.read(&mut first)? == 0 {
+        return Ok(None);
+    }
+
+    let length = first[0].leading_zeros() + 1;
+    if length > 8 {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            "Invalid EBML integer",
+        ));
+    }
+
+    let mut value = if keep_marker {
+        u64::from(first[0])
+    } else {
+        u64::from(first[0]) & ((1 << (8 - length)) - 1)
+    };
+    for _ in 1..length {
+        let mut byte = [0u8; 1];
+        reader.read_exact(&mut byte)?;
+        value = (value << 8) | u64::from(byte[0]);
+    }
+
+    Ok(Some((value, length)))
+}
+
+/// Big-endian unsigned integer element value
+fn read_uint(value: &[u8]) -> u64 {
+    value.iter().fold(0, |n, byte| (n << 8) | u64::from(*byte))
+}
+
+fn read_body<R: Read>(reader: &mut R, size: u64) -> io::Result<Vec<u8>> {
+    let mut body = Vec::new();
+    reader.take(size).read_to_end(&mut body)?;
+    if (body.len() as u64) < size {
+        return Err(io::ErrorKind::UnexpectedEof.into());
+    }
+    Ok(body)
+}
+
+fn skip<R: Seek>(reader: &mut R, size: u64) -> io::Result<()> {
+    let offset = i64::try_from(size)
+        .map_err(|_| io::Error::new(io::ErrorKind::InvalidData, "Element too large"))?;
+    reader.seek(SeekFrom::Current(offset)).map(|_| ())
+}
+
+fn non_empty(text: &str) -> Option<String> {
+    let text = text.trim_matches(|c: char| c == '\0' || c.is_whitespace());
+    (!text.is_empty()).then(|| text.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::TempDir;
+
+    const CLUSTER: u32 = 0x1F43_B675;
+
+    /// Encode an element with a 1-byte (small) or 8-byte size
+    fn element(id: u32, body: &[u8]) -> Vec<u8> {
+        let id_bytes = id.to_be_bytes();
+        let skip = id_bytes.iter().take_while(|b| **b == 0).count();
+        let mut out = id_bytes[skip..].to_vec();
+        if body.len() < 0x7F {
+            out.push(0x80 | body.len() as u8);
+        } else {
+            out.push(0x01);
+            out.extend_from_slice(&(body.len() as u64).to_be_bytes()[1..]);
+        }
+        out.extend_from_slice(body);
+        out
+    }
+
+    fn simple_tag(name: &str, value: &str) -> Vec<u8> {
+        let mut body = element(TAG_NAME, name.as_bytes());
+        body.extend(element(TAG_STRING, value.as_bytes()));
+        element(SIMPLE_TAG, &body)
+    }
+
+    fn tag(level: u8, simple_tags: &[(&str, &str)]) -> Vec<u8> {
+        let mut body = element(TARGETS, &element(TARGET_TYPE_VALUE, &[level]));
+        for (name, value) in simple_tags {
+            body.extend(simple_tag(name, value));
+        }
+        element(TAG, &body)
+    }
+
+    fn matroska(segment_children: &[Vec<u8>]) -> Vec<u8> {
+        let mut file = element(EBML_HEADER, &element(0x4282, b"matroska"));
+        file.extend(element(SEGMENT, &segment_children.concat()));
+        file
+    }
+
+    #[test]
+    fn test_matroska_tags() {
+        let mut tags = tag(70, &[("TITLE", "Sousou no Frieren")]);
+        tags.extend(tag(50, &[("PART_NUMBER", "5"), ("TITLE", "Phantoms")]));
+        let file = matroska(&[
+            element(INFO, &element(SEGMENT_TITLE, b"video1")),
+            element(CLUSTER, &[0u8; 300]),
+            element(TAGS, &tags),
+        ]);
+
+        let meta = read_matroska_from(&mut Cursor::new(file)).unwrap().unwrap();
+        assert_eq!(meta.title.as_deref(), Some("video1"));
+        assert_eq!(meta.show.as_deref(), Some("Sousou no Frieren"));
+        assert_eq!(meta.episode, Some(5));
+
+        let parsed = meta.to_parsed().unwrap();
+        assert_eq!(parsed.title.as_deref(), Some("Sousou no Frieren"));
+        assert_eq!(parsed.episode, Some(5));
+    }
+
+    #[test]
+    fn test_too_many_children_is_an_error() {
+        let tags = element(TAGS, &element(TAG, &[]).repeat(MAX_CHILDREN + 1));
+        let file = matroska(&[tags]);
+        assert!(read_matroska_from(&mut Cursor::new(file)).is_err());
+    }
+
+    #[test]
+    fn test_read_bytes_are_capped() {
+        let title = element(SEGMENT_TITLE, b"video1");
+        let mut body = title.clone();
+        body.extend(element(0xEC, &vec![0u8; MAX_ELEMENT_SIZE as usize - 200]));
+        // Each Info is close to the per-element cap; only two fit the budget
+        let info = element(INFO, &body);
+        let file = matroska(&[info.clone(), info.clone(), info]);
+
+        let elements = segment_elements(&mut Cursor::new(file), &[INFO])
+            .unwrap()
+            .unwrap();
+        assert_eq!(elements.len(), 2);
+    }
+
+    #[test]
+    fn test_mutated_files_do_not_panic() {
+        let mut tags = tag(70, &[("TITLE", "Sousou no Frieren")]);
+        tags.extend(tag(50, &[("PART_NUMBER", "5")]));
+        let mut info = element(TIMESTAMP_SCALE, &[0x0F, 0x42, 0x40]);
+        info.extend(element(DURATION, &1_420_500.0f64.to_be_bytes()));
+        info.extend(element(SEGMENT_TITLE, b"video1"));
+        let sample = matroska(&[
+            element(INFO, &info),
+            element(CLUSTER, &[0u8; 64]),
+            element(TAGS, &tags),
+        ]);
+
+        // xorshift, seeded so failures reproduce
+        let mut state = 0x2545_F491_4F6C_DD1Du64;
+        let mut next = move || {
+            state ^= state << 13;
+            state ^= state >> 7;
+            state ^= state << 17;
+            state
+        };
+
+        for _ in 0..5000 {
+            let mut file = sample.clone();
+            for _ in 0..=next() % 8 {
+                let at = next() as usize % file.len();
+                file[at] = next() as u8;
+            }
+            file.truncate(next() as usize % (file.len() + 1));
+
+            let _ = read_matroska_from(&mut Cursor::new(&file));
+            let _ = matroska_duration(&mut Cursor::new(&file));
+        }
+    }
+
+    #[test]
+    fn test_segment_title_is_parsed() {
+        let file = matroska(&[element(
+            INFO,
+            &element(
+                SEGMENT_TITLE,
+                b"[SubsPlease] Sousou no Frieren - 05 (1080p)",
+            ),
+        )]);
+
+        let meta = read_matroska_from(&mut Cursor::new(file)).unwrap().unwrap();
+        let parsed = meta.to_parsed().unwrap();
+        assert_eq!(parsed.title.as_deref(), Some("Sousou no Frieren"));
+        assert_eq!(parsed.episode, Some(5));
+    }
+
+    #[test]
+    fn test_untagged_file_returns_none() {
+        let dir = TempDir::new("untagged");
+
+        let mkv = dir.join("untagged.mkv");
+        std::fs::write(&mkv, matroska(&[element(CLUSTER, &[0u8; 16])])).unwrap();
+        assert_eq!(read_media_meta(&mkv), None);
+
+        let mp4 = dir.join("untagged.mp4");
+        std::fs::write(&mp4, b"not really an mp4").unwrap();
+        assert_eq!(read_media_meta(&mp4), None);
+
+        assert_eq!(read_media_meta(Path::new("/nonexistent/video1.txt")), None);
+    }
+
+    fn mp4_box(kind: &[u8; 4], body: &[u8]) -> Vec<u8> {
+        let mut out = ((body.len() + 8) as u32).to_be_bytes().to_vec();
+        out.extend_from_slice(kind);
+        out.extend_from_slice(body);
+        out
+    }
+
+    /// 23:40 at a timescale of 1000
+    fn sample_mp4() -> Vec<u8> {
+        let mut mvhd = vec![0u8; 12];
+        mvhd.extend(1000u32.to_be_bytes());
+        mvhd.extend(1_420_000u32.to_be_bytes());
+        mvhd.extend([0u8; 80]);
+
+        let mut file = mp4_box(b"ftyp", b"isom\0\0\x02\0isomiso2mp41");
+        file.extend(mp4_box(b"free", &[]));
+        file.extend(mp4_box(b"moov", &mp4_box(b"mvhd", &mvhd)));
+        file.extend(mp4_box(b"mdat", &[0u8; 64]));
+        file
+    }
+
+    #[tokio::test]
+    async fn test_video_duration() {
+        let dir = TempDir::new("duration");
+
+        let mp4 = dir.join("sample.mp4");
+        std::fs::write(&mp4, sample_mp4()).unwrap();
+        let duration = get_video_duration(&mp4).await.unwrap();
+        assert!((duration - 1420.0).abs() < 0.001, "{}", duration);
+
+        let mut info = element(TIMESTAMP_SCALE, &[0x0F, 0x42, 0x40]);
+        info.extend(element(DURATION, &1_420_500.0f64.to_be_bytes()));
+        let mkv = dir.join("sample.mkv");
+        std::fs::write(
+            &mkv,
+            matroska(&[element(INFO, &info), element(CLUSTER, &[0u8; 64])]),
+        )
+        .unwrap();
+        let duration = get_video_duration(&mkv).await.unwrap();
+        assert!((duration - 1420.5).abs() < 0.001, "{}", duration);
+    }
+
+    #[tokio::test]
+    async fn test_unreadable_video_has_no_duration() {
+        let path = std::env::temp_dir().join("playon_missing_video.mkv");
+        assert_eq!(get_video_duration(&path).await, None);
+        assert_eq!(read_duration(Path::new("/anime/notes.txt")), None);
+
+        let empty = matroska(&[element(INFO, &element(DURATION, &[]))]);
+        assert_eq!(matroska_duration(&mut Cursor::new(empty)).unwrap(), None);
+    }
+
+    #[test]
+    fn test_confident_file_name_skips_tags() {
+        // Doesn't exist, so only the name parse can answer
+        let parsed = parse_local_file("/Anime/Frieren/Sousou no Frieren - S01E05.mkv");
+        assert_eq!(parsed.episode, Some(5));
+    }
+}
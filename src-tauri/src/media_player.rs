@@ -24,6 +24,48 @@ pub enum MediaPlayer {
     Generic, // For players detected by file extension
 }
 
+/// Playback state of a detected media player window
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PlayerState {
+    /// The title carries media info (file name, episode, etc.)
+    Playing,
+    /// The player is open but nothing is loaded - the title is just the player name
+    Idle,
+}
+
+/// Titles players show when no file is loaded (compared lowercase)
+const IDLE_TITLES: &[&str] = &[
+    "vlc media player",
+    "vlc",
+    "mpv",
+    "no file - mpv",
+    "mpc-hc",
+    "mpc-be",
+    "media player classic",
+    "potplayer",
+    "kmplayer",
+    "gom player",
+    "windows media player",
+];
+
+/// Detect media player type and playback state from window title
+///
+/// Returns Some((player, PlayerState::Idle)) when the title is only the player name,
+/// e.g. "mpv" or "VLC media player" with no file loaded.
+/// Returns None for windows that aren't media players.
+pub fn detect_player_state(title: &str) -> Option<(MediaPlayer, PlayerState)> {
+    let player = detect_media_player(title)?;
+
+    let title_lower = title.trim().to_lowercase();
+    let state = if IDLE_TITLES.contains(&title_lower.as_str()) {
+        PlayerState::Idle
+    } else {
+        PlayerState::Playing
+    };
+
+    Some((player, state))
+}
+
 /// Detect media player type from window title
 ///
 /// Returns Some(MediaPlayer) if the window belongs to a known media player
@@ -116,6 +158,31 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_idle_player_titles() {
+        assert_eq!(
+            detect_player_state("mpv"),
+            Some((MediaPlayer::MPV, PlayerState::Idle))
+        );
+        assert_eq!(
+            detect_player_state("VLC media player"),
+            Some((MediaPlayer::VLC, PlayerState::Idle))
+        );
+    }
+
+    #[test]
+    fn test_playing_player_titles() {
+        assert_eq!(
+            detect_player_state("Frieren - 05.mkv - mpv"),
+            Some((MediaPlayer::MPV, PlayerState::Playing))
+        );
+        assert_eq!(
+            detect_player_state("Frieren - 05 - VLC media player"),
+            Some((MediaPlayer::VLC, PlayerState::Playing))
+        );
+        assert_eq!(detect_player_state("Visual Studio Code"), None);
+    }
+
     #[test]
     fn test_non_media_window() {
         assert_eq!(detect_media_player("Visual Studio Code"), None);
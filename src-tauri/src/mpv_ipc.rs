@@ -0,0 +1,216 @@
+//! mpv IPC
+//!
+//! PURPOSE: Ask a running mpv for exactly what it's playing
+//! mpv started with `--input-ipc-server=<path>` listens on a unix socket (or a
+//! named pipe on Windows) for JSON commands, one per line. That gives the real
+//! file path and playback position, so detection doesn't have to guess from
+//! the window title.
+//!
+//! When no mpv is listening the socket doesn't exist; that's reported as None,
+//! not as an error.
+
+use serde::Serialize;
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::path::Path;
+use std::time::Duration;
+use tokio::io::{AsyncBufReadExt, AsyncRead, AsyncWrite, AsyncWriteExt, BufReader};
+use tracing::debug;
+
+/// Socket path used when the config doesn't set one
+#[cfg(windows)]
+pub const DEFAULT_IPC_PATH: &str = r"\\.\pipe\mpvsocket";
+#[cfg(not(windows))]
+pub const DEFAULT_IPC_PATH: &str = "/tmp/mpvsocket";
+
+/// How long to wait for mpv to answer all queries
+const IPC_TIMEOUT: Duration = Duration::from_millis(500);
+
+/// Properties queried, in request id order (ids start at 1)
+const PROPERTIES: [&str; 4] = ["media-title", "path", "percent-pos", "time-pos"];
+
+/// What mpv reports as playing
+#[derive(Debug, Clone, Default, Serialize, PartialEq)]
+pub struct MpvStatus {
+    pub media_title: Option<String>,
+    pub path: Option<String>,
+    /// Playback position in percent (0 - 100)
+    pub percent_pos: Option<f64>,
+    /// Playback position in seconds
+    pub time_pos: Option<f64>,
+}
+
+impl MpvStatus {
+    /// Title to parse for detection: the file name, else mpv's media title
+    ///
+    /// File names keep the episode number that `media-title` metadata often drops.
+    pub fn detection_title(&self) -> Option<String> {
+        self.path
+            .as_deref()
+            .and_then(|p| Path::new(p).file_name())
+            .map(|name| name.to_string_lossy().to_string())
+            .or_else(|| self.media_title.clone())
+            .filter(|title| !title.trim().is_empty())
+    }
+}
+
+#[cfg(unix)]
+async fn connect(ipc_path: &str) -> std::io::Result<tokio::net::UnixStream> {
+    tokio::net::UnixStream::connect(ipc_path).await
+}
+
+#[cfg(windows)]
+async fn connect(
+    ipc_path: &str,
+) -> std::io::Result<tokio::net::windows::named_pipe::NamedPipeClient> {
+    tokio::net::windows::named_pipe::ClientOptions::new().open(ipc_path)
+}
+
+/// Query mpv's now-playing info over its IPC socket
+///
+/// # Returns
+/// * `None` - No mpv listening at `ipc_path`, or it didn't answer in time
+pub async fn query_status(ipc_path: &str) -> Option<MpvStatus> {
+    let stream = match connect(ipc_path).await {
+        Ok(stream) => stream,
+        Err(e) => {
+            debug!("mpv IPC not available at {}: {}", ipc_path, e);
+            return None;
+        }
+    };
+
+    match tokio::time::timeout(IPC_TIMEOUT, query_stream(stream)).await {
+        Ok(Ok(status)) => Some(status),
+        Ok(Err(e)) => {
+            debug!("mpv IPC query failed: {}", e);
+            None
+        }
+        Err(_) => {
+            debug!("mpv IPC query timed out");
+            None
+        }
+    }
+}
+
+/// Send the property queries and collect the replies
+///
+/// mpv also writes unrelated event lines to the socket, so replies are matched
+/// by `request_id`. Properties that are unavailable (nothing loaded) stay None.
+async fn query_stream<S>(stream: S) -> Result<MpvStatus, String>
+where
+    S: AsyncRead + AsyncWrite + Unpin,
+{
+    let (reader, mut writer) = tokio::io::split(stream);
+
+    let mut requests = String::new();
+    for (i, property) in PROPERTIES.iter().enumerate() {
+        let request = json!({ "command": ["get_property", property], "request_id": i + 1 });
+        requests.push_str(&request.to_string());
+        requests.push('\n');
+    }
+    writer
+        .write_all(requests.as_bytes())
+        .await
+        .map_err(|e| format!("Failed to write to mpv: {}", e))?;
+
+    let mut replies: HashMap<u64, Value> = HashMap::new();
+    let mut lines = BufReader::new(reader).lines();
+    while replies.len() < PROPERTIES.len() {
+        let line = lines
+            .next_line()
+            .await
+            .map_err(|e| format!("Failed to read from mpv: {}", e))?
+            .ok_or("mpv closed the connection")?;
+
+        let Ok(message) = serde_json::from_str::<Value>(&line) else {
+            continue;
+        };
+        let Some(id) = message.get("request_id").and_then(Value::as_u64) else {
+            continue; // event
+        };
+        let data = if message["error"] == "success" {
+            message["data"].clone()
+        } else {
+            Value::Null
+        };
+        replies.insert(id, data);
+    }
+
+    let reply = |id: u64| replies.get(&id).cloned().unwrap_or(Value::Null);
+    Ok(MpvStatus {
+        media_title: reply(1).as_str().map(str::to_string),
+        path: reply(2).as_str().map(str::to_string),
+        percent_pos: reply(3).as_f64(),
+        time_pos: reply(4).as_f64(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::io::AsyncReadExt;
+
+    /// Reply to the four queries out of order, with an event in between
+    async fn fake_mpv(mut server: tokio::io::DuplexStream, replies: [&str; 4]) {
+        let mut received = Vec::new();
+        while received.iter().filter(|b| **b == b'\n').count() < 4 {
+            let mut buf = [0u8; 256];
+            let n = server.read(&mut buf).await.unwrap();
+            received.extend_from_slice(&buf[..n]);
+        }
+
+        let mut output = String::from("{\"event\":\"playback-restart\"}\n");
+        for reply in replies.iter().rev() {
+            output.push_str(reply);
+            output.push('\n');
+        }
+        server.write_all(output.as_bytes()).await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_query_playing_file() {
+        let (client, server) = tokio::io::duplex(1024);
+        tokio::spawn(fake_mpv(
+            server,
+            [
+                r#"{"data":"Frieren","error":"success","request_id":1}"#,
+                r#"{"data":"/anime/[SubsPlease] Sousou no Frieren - 05 (1080p).mkv","error":"success","request_id":2}"#,
+                r#"{"data":41.5,"error":"success","request_id":3}"#,
+                r#"{"data":598.2,"error":"success","request_id":4}"#,
+            ],
+        ));
+
+        let status = query_stream(client).await.unwrap();
+        assert_eq!(status.media_title, Some("Frieren".to_string()));
+        assert_eq!(status.percent_pos, Some(41.5));
+        assert_eq!(status.time_pos, Some(598.2));
+        assert_eq!(
+            status.detection_title(),
+            Some("[SubsPlease] Sousou no Frieren - 05 (1080p).mkv".to_string())
+        );
+    }
+
+    #[tokio::test]
+    async fn test_query_idle_player() {
+        let (client, server) = tokio::io::duplex(1024);
+        tokio::spawn(fake_mpv(
+            server,
+            [
+                r#"{"error":"property unavailable","request_id":1}"#,
+                r#"{"error":"property unavailable","request_id":2}"#,
+                r#"{"error":"property unavailable","request_id":3}"#,
+                r#"{"error":"property unavailable","request_id":4}"#,
+            ],
+        ));
+
+        let status = query_stream(client).await.unwrap();
+        assert_eq!(status, MpvStatus::default());
+        assert_eq!(status.detection_title(), None);
+    }
+
+    #[tokio::test]
+    async fn test_missing_socket_is_none() {
+        let path = std::env::temp_dir().join("playon_mpv_missing_socket");
+        assert_eq!(query_status(&path.to_string_lossy()).await, None);
+    }
+}
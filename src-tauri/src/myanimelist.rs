@@ -2,10 +2,49 @@
 // OAuth2 with PKCE + REST API for anime/manga tracking
 
 use serde::{Deserialize, Serialize};
+use std::sync::RwLock;
+#[cfg(feature = "debug-auth")]
+use tracing::debug;
+use tracing::{info, warn};
+
+use crate::oauth::{self, ChallengeMethod};
 
 /// MyAnimeList API base URL
-const MAL_API_URL: &str = "https://api.myanimelist.net/v2";
-const MAL_AUTH_URL: &str = "https://myanimelist.net/v1/oauth2";
+pub const MAL_API_URL: &str = "https://api.myanimelist.net/v2";
+/// MyAnimeList OAuth base URL (token endpoint)
+pub const MAL_AUTH_URL: &str = "https://myanimelist.net/v1/oauth2";
+
+lazy_static::lazy_static! {
+    /// Base URLs requests go to, see `set_base_urls`
+    static ref API_URL: RwLock<String> = RwLock::new(MAL_API_URL.to_string());
+    static ref AUTH_URL: RwLock<String> = RwLock::new(MAL_AUTH_URL.to_string());
+}
+
+/// Point MAL API and token requests at other base URLs (a proxy or a local mock)
+pub fn set_base_urls(api_url: &str, auth_url: &str) {
+    if let Ok(mut url) = API_URL.write() {
+        *url = api_url.trim_end_matches('/').to_string();
+    }
+    if let Ok(mut url) = AUTH_URL.write() {
+        *url = auth_url.trim_end_matches('/').to_string();
+    }
+}
+
+/// API base URL in use
+pub fn api_url() -> String {
+    API_URL
+        .read()
+        .map(|url| url.clone())
+        .unwrap_or_else(|_| MAL_API_URL.to_string())
+}
+
+/// OAuth base URL in use
+pub fn auth_url() -> String {
+    AUTH_URL
+        .read()
+        .map(|url| url.clone())
+        .unwrap_or_else(|_| MAL_AUTH_URL.to_string())
+}
 
 // ============================================================================
 // DATA STRUCTURES
@@ -90,6 +129,25 @@ pub struct MalListUpdateResponse {
     pub num_episodes_watched: Option<i32>,
     #[serde(default)]
     pub num_chapters_read: Option<i32>,
+    #[serde(default)]
+    pub is_rewatching: Option<bool>,
+    #[serde(default)]
+    pub num_times_rewatched: Option<i32>,
+    #[serde(default)]
+    pub priority: Option<i32>,
+    #[serde(default)]
+    pub comments: Option<String>,
+}
+
+/// Optional list fields for an anime update, only sent when set
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+#[serde(default)]
+pub struct MalAnimeUpdateExtras {
+    pub is_rewatching: Option<bool>,
+    pub num_times_rewatched: Option<i32>,
+    /// 0 (low), 1 (medium) or 2 (high)
+    pub priority: Option<i32>,
+    pub comments: Option<String>,
 }
 
 // ============================================================================
@@ -111,14 +169,16 @@ pub fn generate_code_verifier() -> String {
         .collect()
 }
 
-/// Generate PKCE code challenge from verifier (plain method - MAL uses plain, not S256)
+/// PKCE method used for MAL (MAL only supports plain, not S256)
+pub const MAL_CHALLENGE_METHOD: ChallengeMethod = ChallengeMethod::Plain;
+
+/// Generate PKCE code challenge from verifier using `MAL_CHALLENGE_METHOD`
 pub fn generate_code_challenge(verifier: &str) -> String {
-    // MAL uses "plain" method, so challenge == verifier
-    verifier.to_string()
+    MAL_CHALLENGE_METHOD.challenge(verifier)
 }
 
 /// Start a localhost server and wait for OAuth callback
-/// Returns the authorization code from the callback
+/// Returns the authorization code from the callback once its `state` is verified
 pub async fn start_oauth_callback_server(port: u16) -> Result<String, String> {
     use tokio::io::{AsyncReadExt, AsyncWriteExt};
     use tokio::net::TcpListener;
@@ -127,7 +187,7 @@ pub async fn start_oauth_callback_server(port: u16) -> Result<String, String> {
         .await
         .map_err(|e| format!("Failed to bind to port {}: {}", port, e))?;
 
-    println!("[MAL] OAuth callback server listening on port {}", port);
+    info!("OAuth callback server listening on port {}", port);
 
     // Accept one connection
     let (mut socket, _) = listener
@@ -142,59 +202,43 @@ pub async fn start_oauth_callback_server(port: u16) -> Result<String, String> {
         .map_err(|e| format!("Failed to read request: {}", e))?;
 
     let request = String::from_utf8_lossy(&buffer[..size]);
-    println!("[MAL] Received callback request");
-
-    // Parse the GET request to extract code
-    if let Some(code) = extract_code_from_request(&request) {
-        // Send success response
-        let response = "HTTP/1.1 200 OK\r\n\
+    info!("Received callback request");
+
+    // Parse the GET request and check the state before accepting the code
+    let callback = request_path(&request)
+        .ok_or_else(|| "Malformed callback request".to_string())
+        .and_then(oauth::parse_auth_callback)
+        .and_then(|callback| {
+            oauth::verify_state(callback.state.as_deref())?;
+            callback
+                .code
+                .ok_or_else(|| "No authorization code in request".to_string())
+        });
+
+    let response = match &callback {
+        Ok(_) => "HTTP/1.1 200 OK\r\n\
             Content-Type: text/html; charset=utf-8\r\n\
             Connection: close\r\n\r\n\
             <!DOCTYPE html><html><head><meta charset=\"utf-8\"></head>\
             <body style='font-family: -apple-system, sans-serif; text-align: center; padding: 50px; background: #1a1a2e; color: white;'>\
             <h1 style='color: #4ade80;'>✓ Login Successful!</h1>\
             <p>You can close this window and return to PLAY-ON.</p>\
-            </body></html>";
-
-        let _ = socket.write_all(response.as_bytes()).await;
-        let _ = socket.flush().await;
-
-        return Ok(code);
-    }
-
-    // Send error response
-    let response = "HTTP/1.1 400 Bad Request\r\n\
-        Content-Type: text/html\r\n\
-        Connection: close\r\n\r\n\
-        <html><body><h1>Error</h1><p>No authorization code received.</p></body></html>";
+            </body></html>",
+        Err(_) => "HTTP/1.1 400 Bad Request\r\n\
+            Content-Type: text/html\r\n\
+            Connection: close\r\n\r\n\
+            <html><body><h1>Error</h1><p>Login could not be completed. Please try again from PLAY-ON.</p></body></html>",
+    };
 
     let _ = socket.write_all(response.as_bytes()).await;
     let _ = socket.flush().await;
 
-    Err("No authorization code in request".to_string())
+    callback
 }
 
-/// Extract authorization code from HTTP GET request
-fn extract_code_from_request(request: &str) -> Option<String> {
-    // Find the query string in the request
-    let first_line = request.lines().next()?;
-
-    // Parse: GET /?code=xxx&state=yyy HTTP/1.1
-    let path = first_line.split_whitespace().nth(1)?;
-
-    // Parse query parameters
-    if let Some(query_start) = path.find('?') {
-        let query = &path[query_start + 1..];
-        for param in query.split('&') {
-            if let Some((key, value)) = param.split_once('=') {
-                if key == "code" {
-                    return Some(urlencoding::decode(value).ok()?.into_owned());
-                }
-            }
-        }
-    }
-
-    None
+/// Extract the path from an HTTP request line (`GET /?code=xxx&state=yyy HTTP/1.1`)
+fn request_path(request: &str) -> Option<&str> {
+    request.lines().next()?.split_whitespace().nth(1)
 }
 
 /// Exchange authorization code for tokens using PKCE
@@ -204,17 +248,20 @@ pub async fn exchange_code_for_token(
     code_verifier: String,
     redirect_uri: String,
 ) -> Result<MalTokenResponse, String> {
+    // Catch malformed verifiers before MAL rejects them with a vague error
+    oauth::validate_code_verifier(&code_verifier)?;
+
     let client = reqwest::Client::new();
 
-    println!("[MAL] === Token Exchange Debug ===");
-    println!("[MAL] Client ID: {}", client_id);
-    println!("[MAL] Code: {}...", &code[..20.min(code.len())]);
-    println!(
-        "[MAL] Code verifier: {}...",
-        &code_verifier[..20.min(code_verifier.len())]
+    // Only built with `--features debug-auth`; secrets are redacted even then
+    #[cfg(feature = "debug-auth")]
+    debug!(
+        client_id = %client_id,
+        code = %crate::logging::redact(&code),
+        code_verifier = %crate::logging::redact(&code_verifier),
+        redirect_uri = %redirect_uri,
+        "Exchanging authorization code for token"
     );
-    println!("[MAL] Code verifier length: {}", code_verifier.len());
-    println!("[MAL] Redirect URI: {}", redirect_uri);
 
     // Use reqwest's form encoding
     let params = [
@@ -225,9 +272,7 @@ pub async fn exchange_code_for_token(
         ("redirect_uri", redirect_uri.as_str()),
     ];
 
-    let url = format!("{}/token", MAL_AUTH_URL);
-    println!("[MAL] Token URL: {}", url);
-
+    let url = format!("{}/token", auth_url());
     let response = client
         .post(&url)
         .header("Content-Type", "application/x-www-form-urlencoded")
@@ -236,11 +281,12 @@ pub async fn exchange_code_for_token(
         .await
         .map_err(|e| format!("Request failed: {}", e))?;
 
-    println!("[MAL] Token response status: {}", response.status());
+    #[cfg(feature = "debug-auth")]
+    debug!("Token response status: {}", response.status());
 
     if !response.status().is_success() {
         let error_text = response.text().await.unwrap_or_default();
-        println!("[MAL] Token exchange error: {}", error_text);
+        warn!("Token exchange error: {}", error_text);
         return Err(format!("Token exchange failed: {}", error_text));
     }
 
@@ -249,7 +295,7 @@ pub async fn exchange_code_for_token(
         .await
         .map_err(|e| format!("Parse error: {}", e))?;
 
-    println!("[MAL] Token exchange successful!");
+    info!("Token exchange successful!");
     Ok(token_data)
 }
 
@@ -267,7 +313,7 @@ pub async fn refresh_token(
     ];
 
     let response = client
-        .post(format!("{}/token", MAL_AUTH_URL))
+        .post(format!("{}/token", auth_url()))
         .form(&params)
         .send()
         .await
@@ -295,15 +341,20 @@ pub async fn get_user_info(access_token: &str) -> Result<MalUser, String> {
     let client = reqwest::Client::new();
 
     let response = client
-        .get(format!("{}/users/@me", MAL_API_URL))
+        .get(format!("{}/users/@me", api_url()))
         .header("Authorization", format!("Bearer {}", access_token))
         .send()
         .await
         .map_err(|e| format!("Request failed: {}", e))?;
 
-    if !response.status().is_success() {
+    let status = response.status();
+    if !status.is_success() {
         let error_text = response.text().await.unwrap_or_default();
-        return Err(format!("Failed to get user info: {}", error_text));
+        return Err(format!(
+            "Failed to get user info (HTTP {}): {}",
+            status.as_u16(),
+            error_text
+        ));
     }
 
     let user: MalUser = response
@@ -314,6 +365,33 @@ pub async fn get_user_info(access_token: &str) -> Result<MalUser, String> {
     Ok(user)
 }
 
+/// Check that a client ID is accepted by MAL without needing a user token
+///
+/// Uses a minimal public search request authenticated with `X-MAL-CLIENT-ID`.
+pub async fn check_client_id(client_id: &str) -> Result<(), String> {
+    let client = reqwest::Client::new();
+
+    let response = client
+        .get(format!("{}/anime", api_url()))
+        .header("X-MAL-CLIENT-ID", client_id)
+        .query(&[("q", "one piece"), ("limit", "1")])
+        .send()
+        .await
+        .map_err(|e| format!("Request failed: {}", e))?;
+
+    let status = response.status();
+    if !status.is_success() {
+        let error_text = response.text().await.unwrap_or_default();
+        return Err(format!(
+            "Client ID check failed (HTTP {}): {}",
+            status.as_u16(),
+            error_text
+        ));
+    }
+
+    Ok(())
+}
+
 // ============================================================================
 // SEARCH API
 // ============================================================================
@@ -327,7 +405,7 @@ pub async fn search_anime(
     let client = reqwest::Client::new();
 
     let response = client
-        .get(format!("{}/anime", MAL_API_URL))
+        .get(format!("{}/anime", api_url()))
         .header("Authorization", format!("Bearer {}", access_token))
         .query(&[
             ("q", query),
@@ -360,7 +438,7 @@ pub async fn search_manga(
     let client = reqwest::Client::new();
 
     let response = client
-        .get(format!("{}/manga", MAL_API_URL))
+        .get(format!("{}/manga", api_url()))
         .header("Authorization", format!("Bearer {}", access_token))
         .query(&[
             ("q", query),
@@ -384,10 +462,84 @@ pub async fn search_manga(
     Ok(search_response.data.into_iter().map(|n| n.node).collect())
 }
 
+#[derive(Debug, Deserialize)]
+struct MalMyListStatusResponse {
+    my_list_status: Option<MalAnimeListStatus>,
+}
+
+/// Get the user's list status and progress for an anime
+///
+/// # Returns
+/// * `Ok(None)` - The anime isn't on the user's list
+pub async fn get_anime_list_status(
+    access_token: &str,
+    anime_id: i64,
+) -> Result<Option<MalAnimeListStatus>, String> {
+    let client = reqwest::Client::new();
+
+    let response = client
+        .get(format!("{}/anime/{}", api_url(), anime_id))
+        .header("Authorization", format!("Bearer {}", access_token))
+        .query(&[("fields", "my_list_status")])
+        .send()
+        .await
+        .map_err(|e| format!("Request failed: {}", e))?;
+
+    let status = response.status();
+    if !status.is_success() {
+        let error_text = response.text().await.unwrap_or_default();
+        return Err(format!(
+            "Failed to get list status (HTTP {}): {}",
+            status.as_u16(),
+            error_text
+        ));
+    }
+
+    let details: MalMyListStatusResponse = response
+        .json()
+        .await
+        .map_err(|e| format!("Parse error: {}", e))?;
+
+    Ok(details.my_list_status)
+}
+
 // ============================================================================
 // LIST UPDATE API
 // ============================================================================
 
+/// Form fields for an anime list update, leaving out everything not provided
+fn anime_update_form(
+    episodes_watched: i32,
+    status: Option<&str>,
+    extras: &MalAnimeUpdateExtras,
+) -> Result<Vec<(&'static str, String)>, String> {
+    let mut params = vec![("num_watched_episodes", episodes_watched.to_string())];
+
+    if let Some(s) = status {
+        params.push(("status", s.to_string()));
+    }
+    if let Some(is_rewatching) = extras.is_rewatching {
+        params.push(("is_rewatching", is_rewatching.to_string()));
+    }
+    if let Some(times) = extras.num_times_rewatched {
+        if times < 0 {
+            return Err(format!("Invalid rewatch count: {}", times));
+        }
+        params.push(("num_times_rewatched", times.to_string()));
+    }
+    if let Some(priority) = extras.priority {
+        if !(0..=2).contains(&priority) {
+            return Err(format!("Invalid priority {}: expected 0, 1 or 2", priority));
+        }
+        params.push(("priority", priority.to_string()));
+    }
+    if let Some(comments) = &extras.comments {
+        params.push(("comments", comments.clone()));
+    }
+
+    Ok(params)
+}
+
 /// Update anime progress on MAL
 ///
 /// # Arguments
@@ -395,22 +547,20 @@ pub async fn search_manga(
 /// * `anime_id` - MAL anime ID
 /// * `episodes_watched` - Number of episodes watched
 /// * `status` - Optional status (watching, completed, on_hold, dropped, plan_to_watch)
+/// * `extras` - Rewatch, priority and comment fields to set as well
 pub async fn update_anime_progress(
     access_token: &str,
     anime_id: i64,
     episodes_watched: i32,
     status: Option<&str>,
+    extras: &MalAnimeUpdateExtras,
 ) -> Result<MalListUpdateResponse, String> {
     let client = reqwest::Client::new();
 
-    let mut params = vec![("num_watched_episodes", episodes_watched.to_string())];
-
-    if let Some(s) = status {
-        params.push(("status", s.to_string()));
-    }
+    let params = anime_update_form(episodes_watched, status, extras)?;
 
     let response = client
-        .patch(format!("{}/anime/{}/my_list_status", MAL_API_URL, anime_id))
+        .patch(format!("{}/anime/{}/my_list_status", api_url(), anime_id))
         .header("Authorization", format!("Bearer {}", access_token))
         .form(&params)
         .send()
@@ -452,7 +602,7 @@ pub async fn update_manga_progress(
     }
 
     let response = client
-        .patch(format!("{}/manga/{}/my_list_status", MAL_API_URL, manga_id))
+        .patch(format!("{}/manga/{}/my_list_status", api_url(), manga_id))
         .header("Authorization", format!("Bearer {}", access_token))
         .form(&params)
         .send()
@@ -480,6 +630,8 @@ pub async fn update_manga_progress(
 #[derive(Debug, Deserialize)]
 struct MalListResponse {
     data: Vec<MalListNode>,
+    #[serde(default)]
+    paging: MalPaging,
 }
 
 #[derive(Debug, Deserialize)]
@@ -488,6 +640,13 @@ struct MalListNode {
     list_status: serde_json::Value, // Can be anime or manga status
 }
 
+/// Paging links of a list response
+#[derive(Debug, Default, Deserialize)]
+struct MalPaging {
+    /// Full URL of the next page (absent on the last page)
+    next: Option<String>,
+}
+
 /// Anime list entry with status
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct MalAnimeListEntry {
@@ -506,77 +665,183 @@ pub struct MalMangaListEntry {
     pub num_chapters_read: i32,
 }
 
-/// Get user's anime list
+/// Largest page size MAL allows for list requests
+const MAL_MAX_LIST_LIMIT: i32 = 1000;
+
+/// Upper bound on pages followed when fetching a whole list
+const MAL_MAX_LIST_PAGES: usize = 50;
+
+/// Attempts per page before giving up on rate limits / server errors
+const MAL_LIST_ATTEMPTS: u32 = 3;
+
+/// Fetch one page of a user list, retrying on rate limits, server errors and network failures
 ///
-/// # Arguments
-/// * `access_token` - OAuth access token
-/// * `status` - Optional filter (watching, completed, on_hold, dropped, plan_to_watch)
-/// * `limit` - Max entries to return
-pub async fn get_anime_list(
+/// `url` is either the list endpoint (with `query_params`) or a `paging.next` link,
+/// which already carries its query string.
+async fn fetch_list_page(
+    client: &reqwest::Client,
     access_token: &str,
-    status: Option<&str>,
-    limit: i32,
-) -> Result<Vec<MalAnimeListEntry>, String> {
+    url: &str,
+    query_params: &[(&str, String)],
+) -> Result<MalListResponse, String> {
+    let mut attempt = 1;
+
+    loop {
+        let result = client
+            .get(url)
+            .header("Authorization", format!("Bearer {}", access_token))
+            .query(query_params)
+            .send()
+            .await;
+
+        let retry_error = match result {
+            Ok(response) if response.status().is_success() => {
+                return response
+                    .json()
+                    .await
+                    .map_err(|e| format!("Parse error: {}", e));
+            }
+            Ok(response) => {
+                let status = response.status();
+                let error_text = response.text().await.unwrap_or_default();
+                let error = format!(
+                    "Failed to get list (HTTP {}): {}",
+                    status.as_u16(),
+                    error_text
+                );
+                if status.as_u16() != 429 && !status.is_server_error() {
+                    return Err(error);
+                }
+                error
+            }
+            Err(e) => format!("Request failed: {}", e),
+        };
+
+        if attempt >= MAL_LIST_ATTEMPTS {
+            return Err(retry_error);
+        }
+
+        warn!(
+            "List request failed (attempt {}/{}), retrying: {}",
+            attempt, MAL_LIST_ATTEMPTS, retry_error
+        );
+        tokio::time::sleep(std::time::Duration::from_millis(500 * attempt as u64)).await;
+        attempt += 1;
+    }
+}
+
+/// Fetch list nodes from `url`, following `paging.next` links when `fetch_all` is set
+async fn fetch_list_nodes(
+    access_token: &str,
+    url: &str,
+    query_params: &[(&str, String)],
+    fetch_all: bool,
+) -> Result<Vec<MalListNode>, String> {
     let client = reqwest::Client::new();
 
-    let mut query_params = vec![
-        ("fields", "list_status,num_episodes".to_string()),
-        ("limit", limit.to_string()),
-    ];
+    let mut page = fetch_list_page(&client, access_token, url, query_params).await?;
+    let mut nodes = Vec::new();
+    let mut pages = 1;
+
+    loop {
+        nodes.extend(page.data);
+
+        let next = match page.paging.next {
+            Some(next) if fetch_all => next,
+            _ => break,
+        };
+
+        if pages >= MAL_MAX_LIST_PAGES {
+            info!("Stopping after {} pages ({} entries)", pages, nodes.len());
+            break;
+        }
+
+        page = fetch_list_page(&client, access_token, &next, &[]).await?;
+        pages += 1;
+    }
+
+    Ok(nodes)
+}
+
+/// Build the query for a list request
+fn list_query(fields: &str, status: Option<&str>, limit: i32) -> Vec<(&'static str, String)> {
+    let mut query_params = vec![("fields", fields.to_string()), ("limit", limit.to_string())];
 
     if let Some(s) = status {
         query_params.push(("status", s.to_string()));
     }
 
-    let response = client
-        .get(format!("{}/users/@me/animelist", MAL_API_URL))
-        .header("Authorization", format!("Bearer {}", access_token))
-        .query(&query_params)
-        .send()
-        .await
-        .map_err(|e| format!("Request failed: {}", e))?;
+    query_params
+}
 
-    if !response.status().is_success() {
-        let error_text = response.text().await.unwrap_or_default();
-        return Err(format!("Failed to get anime list: {}", error_text));
+/// Read an integer field of a list_status object
+fn list_status_i32(list_status: &serde_json::Value, key: &str) -> i32 {
+    list_status.get(key).and_then(|v| v.as_i64()).unwrap_or(0) as i32
+}
+
+/// Read the status field of a list_status object
+fn list_status_str(list_status: &serde_json::Value) -> String {
+    list_status
+        .get("status")
+        .and_then(|v| v.as_str())
+        .unwrap_or("unknown")
+        .to_string()
+}
+
+/// Convert a raw list node into an anime list entry
+fn anime_entry_from_node(item: MalListNode) -> MalAnimeListEntry {
+    MalAnimeListEntry {
+        status: list_status_str(&item.list_status),
+        score: list_status_i32(&item.list_status, "score"),
+        num_episodes_watched: list_status_i32(&item.list_status, "num_episodes_watched"),
+        anime: item.node,
     }
+}
 
-    let list_response: MalListResponse = response
-        .json()
-        .await
-        .map_err(|e| format!("Parse error: {}", e))?;
+/// Convert a raw list node into a manga list entry
+fn manga_entry_from_node(item: MalListNode) -> MalMangaListEntry {
+    MalMangaListEntry {
+        status: list_status_str(&item.list_status),
+        score: list_status_i32(&item.list_status, "score"),
+        num_chapters_read: list_status_i32(&item.list_status, "num_chapters_read"),
+        manga: item.node,
+    }
+}
 
-    let entries: Vec<MalAnimeListEntry> = list_response
-        .data
-        .into_iter()
-        .map(|item| {
-            let status = item
-                .list_status
-                .get("status")
-                .and_then(|v| v.as_str())
-                .unwrap_or("unknown")
-                .to_string();
-            let score = item
-                .list_status
-                .get("score")
-                .and_then(|v| v.as_i64())
-                .unwrap_or(0) as i32;
-            let eps = item
-                .list_status
-                .get("num_episodes_watched")
-                .and_then(|v| v.as_i64())
-                .unwrap_or(0) as i32;
-
-            MalAnimeListEntry {
-                anime: item.node,
-                status,
-                score,
-                num_episodes_watched: eps,
-            }
-        })
-        .collect();
+/// Get user's anime list
+///
+/// # Arguments
+/// * `access_token` - OAuth access token
+/// * `status` - Optional filter (watching, completed, on_hold, dropped, plan_to_watch)
+/// * `limit` - Max entries per page
+/// * `fetch_all` - Follow pagination to return the whole list instead of the first page
+pub async fn get_anime_list(
+    access_token: &str,
+    status: Option<&str>,
+    limit: i32,
+    fetch_all: bool,
+) -> Result<Vec<MalAnimeListEntry>, String> {
+    let nodes = fetch_list_nodes(
+        access_token,
+        &format!("{}/users/@me/animelist", api_url()),
+        &list_query("list_status,num_episodes", status, limit),
+        fetch_all,
+    )
+    .await?;
+
+    Ok(nodes.into_iter().map(anime_entry_from_node).collect())
+}
 
-    Ok(entries)
+/// Get the user's entire anime list
+///
+/// # Arguments
+/// * `access_token` - OAuth access token
+/// * `status` - Optional filter (watching, completed, on_hold, dropped, plan_to_watch)
+pub async fn get_full_anime_list(
+    access_token: &str,
+    status: Option<&str>,
+) -> Result<Vec<MalAnimeListEntry>, String> {
+    get_anime_list(access_token, status, MAL_MAX_LIST_LIMIT, true).await
 }
 
 /// Get user's manga list
@@ -584,70 +849,362 @@ pub async fn get_anime_list(
 /// # Arguments
 /// * `access_token` - OAuth access token
 /// * `status` - Optional filter (reading, completed, on_hold, dropped, plan_to_read)
-/// * `limit` - Max entries to return
+/// * `limit` - Max entries per page
+/// * `fetch_all` - Follow pagination to return the whole list instead of the first page
 pub async fn get_manga_list(
     access_token: &str,
     status: Option<&str>,
     limit: i32,
+    fetch_all: bool,
 ) -> Result<Vec<MalMangaListEntry>, String> {
-    let client = reqwest::Client::new();
+    let nodes = fetch_list_nodes(
+        access_token,
+        &format!("{}/users/@me/mangalist", api_url()),
+        &list_query("list_status,num_chapters", status, limit),
+        fetch_all,
+    )
+    .await?;
+
+    Ok(nodes.into_iter().map(manga_entry_from_node).collect())
+}
 
-    let mut query_params = vec![
-        ("fields", "list_status,num_chapters".to_string()),
-        ("limit", limit.to_string()),
-    ];
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use wiremock::matchers::{any, body_string_contains, header, method, path, query_param};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
 
-    if let Some(s) = status {
-        query_params.push(("status", s.to_string()));
+    /// Tests that point the base URLs elsewhere take this so they don't interleave
+    static BASE_URL_LOCK: tokio::sync::Mutex<()> = tokio::sync::Mutex::const_new(());
+
+    /// A mock MAL API (under `/v2`) that requests go to until it's dropped
+    struct MockApi {
+        server: MockServer,
+        _lock: tokio::sync::MutexGuard<'static, ()>,
     }
 
-    let response = client
-        .get(format!("{}/users/@me/mangalist", MAL_API_URL))
-        .header("Authorization", format!("Bearer {}", access_token))
-        .query(&query_params)
-        .send()
+    impl MockApi {
+        async fn start() -> Self {
+            let lock = BASE_URL_LOCK.lock().await;
+            let server = MockServer::start().await;
+            let uri = server.uri();
+            set_base_urls(&format!("{}/v2", uri), &format!("{}/oauth2", uri));
+            Self {
+                server,
+                _lock: lock,
+            }
+        }
+    }
+
+    impl MockApi {
+        /// Answer the next requests with `responses` in order, one each
+        async fn respond_in_order(&self, responses: Vec<(u16, String)>) {
+            for (status, body) in responses {
+                Mock::given(any())
+                    .respond_with(ResponseTemplate::new(status).set_body_string(body))
+                    .up_to_n_times(1)
+                    .expect(1)
+                    .mount(&self.server)
+                    .await;
+            }
+        }
+
+        /// URL of the mock's anime list endpoint
+        fn list_url(&self) -> String {
+            format!("{}/animelist", self.server.uri())
+        }
+    }
+
+    impl Drop for MockApi {
+        fn drop(&mut self) {
+            set_base_urls(MAL_API_URL, MAL_AUTH_URL);
+        }
+    }
+
+    fn list_page(ids: &[i64], next: Option<&str>) -> String {
+        let data: Vec<serde_json::Value> = ids
+            .iter()
+            .map(|id| {
+                serde_json::json!({
+                    "node": { "id": id, "title": format!("Anime {}", id) },
+                    "list_status": { "status": "watching", "score": 7, "num_episodes_watched": 3 }
+                })
+            })
+            .collect();
+        let paging = match next {
+            Some(next) => serde_json::json!({ "next": next }),
+            None => serde_json::json!({}),
+        };
+        serde_json::json!({ "data": data, "paging": paging }).to_string()
+    }
+
+    #[tokio::test]
+    async fn test_fetch_all_follows_next_page() {
+        let api = MockApi::start().await;
+        let next = format!("{}?offset=2", api.list_url());
+        api.respond_in_order(vec![
+            (200, list_page(&[1, 2], Some(&next))),
+            (200, list_page(&[3], None)),
+        ])
+        .await;
+
+        let nodes = fetch_list_nodes(
+            "token",
+            &api.list_url(),
+            &list_query("list_status", None, 2),
+            true,
+        )
         .await
-        .map_err(|e| format!("Request failed: {}", e))?;
+        .unwrap();
+
+        let entries: Vec<MalAnimeListEntry> =
+            nodes.into_iter().map(anime_entry_from_node).collect();
+        let ids: Vec<i64> = entries.iter().map(|e| e.anime.id).collect();
+        assert_eq!(ids, vec![1, 2, 3]);
+        assert_eq!(entries[2].num_episodes_watched, 3);
+
+        let requests = api.server.received_requests().await.unwrap();
+        assert_eq!(requests.len(), 2);
+        assert_eq!(requests[1].url.path(), "/animelist");
+        assert_eq!(requests[1].url.query(), Some("offset=2"));
+    }
 
-    if !response.status().is_success() {
-        let error_text = response.text().await.unwrap_or_default();
-        return Err(format!("Failed to get manga list: {}", error_text));
+    #[tokio::test]
+    async fn test_single_page_ignores_next() {
+        let api = MockApi::start().await;
+        let next = format!("{}?offset=2", api.list_url());
+        api.respond_in_order(vec![(200, list_page(&[1, 2], Some(&next)))])
+            .await;
+
+        let nodes = fetch_list_nodes("token", &api.list_url(), &[], false)
+            .await
+            .unwrap();
+
+        assert_eq!(nodes.len(), 2);
+        assert_eq!(api.server.received_requests().await.unwrap().len(), 1);
     }
 
-    let list_response: MalListResponse = response
-        .json()
+    #[tokio::test]
+    async fn test_retries_server_error() {
+        let api = MockApi::start().await;
+        api.respond_in_order(vec![(503, "{}".to_string()), (200, list_page(&[1], None))])
+            .await;
+
+        let nodes = fetch_list_nodes("token", &api.list_url(), &[], true)
+            .await
+            .unwrap();
+        assert_eq!(nodes.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_client_error_is_not_retried() {
+        let api = MockApi::start().await;
+        api.respond_in_order(vec![(401, "{\"error\":\"invalid_token\"}".to_string())])
+            .await;
+
+        let err = fetch_list_nodes("token", &api.list_url(), &[], true)
+            .await
+            .unwrap_err();
+        assert!(err.contains("(HTTP 401)"));
+        assert_eq!(api.server.received_requests().await.unwrap().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_overridden_base_urls_receive_requests() {
+        let api = MockApi::start().await;
+        let token =
+            r#"{"access_token":"a","refresh_token":"r","expires_in":3600,"token_type":"Bearer"}"#;
+        api.respond_in_order(vec![(200, "{}".to_string()), (200, token.to_string())])
+            .await;
+
+        // A trailing slash on the API URL is trimmed
+        let base = api.server.uri();
+        set_base_urls(&format!("{}/v2/", base), &format!("{}/oauth2", base));
+        let checked = check_client_id("client").await;
+        let refreshed = refresh_token("r".to_string(), "client".to_string()).await;
+
+        assert!(checked.is_ok());
+        assert_eq!(refreshed.unwrap().access_token, "a");
+        let requests = api.server.received_requests().await.unwrap();
+        assert_eq!(
+            (requests[0].method.as_str(), requests[0].url.path()),
+            ("GET", "/v2/anime")
+        );
+        assert_eq!(
+            (requests[1].method.as_str(), requests[1].url.path()),
+            ("POST", "/oauth2/token")
+        );
+
+        drop(api);
+        assert_eq!(api_url(), MAL_API_URL);
+    }
+
+    #[tokio::test]
+    async fn test_mock_search_anime() {
+        let api = MockApi::start().await;
+        Mock::given(method("GET"))
+            .and(path("/v2/anime"))
+            .and(header("Authorization", "Bearer token"))
+            .and(query_param("q", "sousou no frieren"))
+            .and(query_param("limit", "3"))
+            .and(query_param(
+                "fields",
+                "id,title,main_picture,num_episodes,status",
+            ))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "data": [{ "node": {
+                    "id": 52991,
+                    "title": "Sousou no Frieren",
+                    "main_picture": { "medium": "https://img.example/m.jpg", "large": "https://img.example/l.jpg" },
+                    "num_episodes": 28,
+                    "status": "finished_airing"
+                } }]
+            })))
+            .expect(1)
+            .mount(&api.server)
+            .await;
+
+        let results = search_anime("token", "sousou no frieren", 3).await.unwrap();
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].id, 52991);
+        assert_eq!(results[0].title, "Sousou no Frieren");
+        assert_eq!(results[0].num_episodes, Some(28));
+    }
+
+    #[tokio::test]
+    async fn test_mock_search_anime_unauthorized() {
+        let api = MockApi::start().await;
+        Mock::given(method("GET"))
+            .and(path("/v2/anime"))
+            .respond_with(
+                ResponseTemplate::new(401).set_body_string("{\"error\":\"invalid_token\"}"),
+            )
+            .expect(1)
+            .mount(&api.server)
+            .await;
+
+        let err = search_anime("expired", "frieren", 3).await.unwrap_err();
+        assert!(err.starts_with("Search failed:"));
+        assert!(err.contains("invalid_token"));
+    }
+
+    #[tokio::test]
+    async fn test_mock_update_anime_progress() {
+        let api = MockApi::start().await;
+        Mock::given(method("PATCH"))
+            .and(path("/v2/anime/52991/my_list_status"))
+            .and(header("Authorization", "Bearer token"))
+            .and(header("Content-Type", "application/x-www-form-urlencoded"))
+            .and(body_string_contains("num_watched_episodes=5"))
+            .and(body_string_contains("status=watching"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "status": "watching",
+                "score": 0,
+                "num_episodes_watched": 5
+            })))
+            .expect(1)
+            .mount(&api.server)
+            .await;
+
+        let update = update_anime_progress(
+            "token",
+            52991,
+            5,
+            Some("watching"),
+            &MalAnimeUpdateExtras::default(),
+        )
         .await
-        .map_err(|e| format!("Parse error: {}", e))?;
+        .unwrap();
+        assert_eq!(update.status, "watching");
+        assert_eq!(update.num_episodes_watched, Some(5));
+    }
 
-    let entries: Vec<MalMangaListEntry> = list_response
-        .data
-        .into_iter()
-        .map(|item| {
-            let status = item
-                .list_status
-                .get("status")
-                .and_then(|v| v.as_str())
-                .unwrap_or("unknown")
-                .to_string();
-            let score = item
-                .list_status
-                .get("score")
-                .and_then(|v| v.as_i64())
-                .unwrap_or(0) as i32;
-            let chapters = item
-                .list_status
-                .get("num_chapters_read")
-                .and_then(|v| v.as_i64())
-                .unwrap_or(0) as i32;
-
-            MalMangaListEntry {
-                manga: item.node,
-                status,
-                score,
-                num_chapters_read: chapters,
-            }
-        })
-        .collect();
+    #[test]
+    fn test_update_form_only_has_provided_fields() {
+        let form = anime_update_form(5, None, &MalAnimeUpdateExtras::default()).unwrap();
+        assert_eq!(form, vec![("num_watched_episodes", "5".to_string())]);
+
+        let extras = MalAnimeUpdateExtras {
+            num_times_rewatched: Some(2),
+            comments: Some("Better the second time".to_string()),
+            ..Default::default()
+        };
+        let form = anime_update_form(5, Some("completed"), &extras).unwrap();
+        assert_eq!(
+            form,
+            vec![
+                ("num_watched_episodes", "5".to_string()),
+                ("status", "completed".to_string()),
+                ("num_times_rewatched", "2".to_string()),
+                ("comments", "Better the second time".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_update_form_rejects_invalid_extras() {
+        let priority = MalAnimeUpdateExtras {
+            priority: Some(3),
+            ..Default::default()
+        };
+        assert!(anime_update_form(5, None, &priority).is_err());
+
+        let rewatches = MalAnimeUpdateExtras {
+            num_times_rewatched: Some(-1),
+            ..Default::default()
+        };
+        assert!(anime_update_form(5, None, &rewatches).is_err());
+    }
 
-    Ok(entries)
+    #[tokio::test]
+    async fn test_mock_update_anime_rewatch() {
+        let api = MockApi::start().await;
+        Mock::given(method("PATCH"))
+            .and(path("/v2/anime/52991/my_list_status"))
+            .and(body_string_contains("is_rewatching=true"))
+            .and(body_string_contains("priority=2"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "status": "completed",
+                "score": 9,
+                "num_episodes_watched": 3,
+                "is_rewatching": true,
+                "num_times_rewatched": 1,
+                "priority": 2,
+                "comments": ""
+            })))
+            .expect(1)
+            .mount(&api.server)
+            .await;
+
+        let extras = MalAnimeUpdateExtras {
+            is_rewatching: Some(true),
+            priority: Some(2),
+            ..Default::default()
+        };
+        let update = update_anime_progress("token", 52991, 3, None, &extras)
+            .await
+            .unwrap();
+        assert_eq!(update.is_rewatching, Some(true));
+        assert_eq!(update.num_times_rewatched, Some(1));
+        assert_eq!(update.priority, Some(2));
+    }
+
+    #[tokio::test]
+    async fn test_mock_update_anime_progress_error() {
+        let api = MockApi::start().await;
+        Mock::given(method("PATCH"))
+            .and(path("/v2/anime/52991/my_list_status"))
+            .respond_with(
+                ResponseTemplate::new(400).set_body_string("{\"error\":\"invalid_parameters\"}"),
+            )
+            .expect(1)
+            .mount(&api.server)
+            .await;
+
+        let err = update_anime_progress("token", 52991, -1, None, &Default::default())
+            .await
+            .unwrap_err();
+        assert!(err.starts_with("Update failed:"));
+        assert!(err.contains("invalid_parameters"));
+    }
 }
@@ -0,0 +1,161 @@
+//! Now Playing
+//!
+//! PURPOSE: Pick the most accurate available source for what's playing
+//! Player integrations report the exact file and position, window titles only
+//! what the player chose to show. Sources are asked in `PRIORITY` order and
+//! the first one with an answer wins, so a configured mpv or VLC always beats
+//! title scraping.
+
+use serde::{Deserialize, Serialize};
+use std::future::Future;
+
+/// Where a now-playing result came from
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Source {
+    Mpv,
+    Vlc,
+    Jellyfin,
+    WindowTitle,
+}
+
+/// Sources in the order they're asked, most accurate first
+pub const PRIORITY: [Source; 4] = [
+    Source::Mpv,
+    Source::Vlc,
+    Source::Jellyfin,
+    Source::WindowTitle,
+];
+
+/// Which sources are consulted
+///
+/// VLC and Jellyfin also need their connection settings in the config.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct NowPlayingSources {
+    pub mpv: bool,
+    pub vlc: bool,
+    pub jellyfin: bool,
+    pub window_title: bool,
+}
+
+impl Default for NowPlayingSources {
+    fn default() -> Self {
+        Self {
+            mpv: true,
+            vlc: true,
+            jellyfin: true,
+            window_title: true,
+        }
+    }
+}
+
+impl NowPlayingSources {
+    pub fn enabled(&self, source: Source) -> bool {
+        match source {
+            Source::Mpv => self.mpv,
+            Source::Vlc => self.vlc,
+            Source::Jellyfin => self.jellyfin,
+            Source::WindowTitle => self.window_title,
+        }
+    }
+}
+
+/// What's playing, as reported by one source
+#[derive(Debug, Clone, Serialize, PartialEq)]
+pub struct NowPlaying {
+    pub source: Source,
+    /// Title to parse for detection (file name, window title, ...)
+    pub title: String,
+    /// Player or client name, when the source knows it
+    pub player: Option<String>,
+    /// Playback position in percent (0 - 100), when the source knows it
+    pub position_percent: Option<f64>,
+}
+
+/// Ask the enabled sources in priority order, returning the first answer
+///
+/// `query` is only called for enabled sources, so a disabled player is never
+/// contacted.
+pub async fn first_available<F, Fut>(
+    sources: &NowPlayingSources,
+    mut query: F,
+) -> Option<NowPlaying>
+where
+    F: FnMut(Source) -> Fut,
+    Fut: Future<Output = Option<NowPlaying>>,
+{
+    for source in PRIORITY {
+        if !sources.enabled(source) {
+            continue;
+        }
+        if let Some(now_playing) = query(source).await {
+            return Some(now_playing);
+        }
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Mutex;
+
+    fn playing(source: Source) -> Option<NowPlaying> {
+        Some(NowPlaying {
+            source,
+            title: format!("Frieren - 05 ({:?})", source),
+            player: None,
+            position_percent: None,
+        })
+    }
+
+    #[tokio::test]
+    async fn test_first_source_with_result_wins() {
+        let asked = Mutex::new(Vec::new());
+        let result = first_available(&NowPlayingSources::default(), |source| {
+            asked.lock().unwrap().push(source);
+            async move {
+                match source {
+                    Source::Mpv => None,
+                    _ => playing(source),
+                }
+            }
+        })
+        .await;
+
+        assert_eq!(result.unwrap().source, Source::Vlc);
+        // Jellyfin and window titles weren't needed
+        assert_eq!(*asked.lock().unwrap(), vec![Source::Mpv, Source::Vlc]);
+    }
+
+    #[tokio::test]
+    async fn test_disabled_sources_are_skipped() {
+        let sources = NowPlayingSources {
+            mpv: false,
+            vlc: false,
+            ..Default::default()
+        };
+        let asked = Mutex::new(Vec::new());
+        let result = first_available(&sources, |source| {
+            asked.lock().unwrap().push(source);
+            async move { playing(source) }
+        })
+        .await;
+
+        assert_eq!(result.unwrap().source, Source::Jellyfin);
+        assert_eq!(*asked.lock().unwrap(), vec![Source::Jellyfin]);
+    }
+
+    #[tokio::test]
+    async fn test_nothing_playing() {
+        let result = first_available(&NowPlayingSources::default(), |_| async { None }).await;
+        assert_eq!(result, None);
+    }
+
+    #[test]
+    fn test_serializes_source() {
+        let json = serde_json::to_value(playing(Source::WindowTitle)).unwrap();
+        assert_eq!(json["source"], "window_title");
+    }
+}
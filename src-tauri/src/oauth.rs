@@ -0,0 +1,395 @@
+//! OAuth PKCE Helpers
+//!
+//! PURPOSE: Share PKCE code challenge generation between providers
+//! MAL only accepts the `plain` method, where the challenge is the verifier
+//! itself. Other providers may require `S256` (RFC 7636 section 4.2):
+//! BASE64URL(SHA256(verifier)) without padding.
+//!
+//! Also parses the `playon://` callback URLs: the authorization code flow puts
+//! its parameters in the query, the implicit flow in the fragment.
+//!
+//! `build_authorize_url` builds the per-provider authorize URLs so the frontend
+//! doesn't have to know each provider's parameters.
+//!
+//! CSRF: each flow sends a random `state` from `generate_oauth_state`; a callback
+//! is only accepted if it returns a state we issued. States are single-use.
+
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use base64::Engine;
+use rand::distributions::Alphanumeric;
+use rand::Rng;
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+use std::collections::HashSet;
+use std::sync::Mutex;
+
+use crate::myanimelist;
+
+/// AniList authorize endpoint
+const ANILIST_AUTHORIZE_URL: &str = "https://anilist.co/api/v2/oauth/authorize";
+
+/// MyAnimeList authorize endpoint
+const MAL_AUTHORIZE_URL: &str = "https://myanimelist.net/v1/oauth2/authorize";
+
+/// Length of generated OAuth state tokens
+const STATE_LENGTH: usize = 32;
+
+lazy_static::lazy_static! {
+    /// States issued for flows that haven't completed yet
+    static ref PENDING_STATES: Mutex<HashSet<String>> = Mutex::new(HashSet::new());
+}
+
+/// How the code challenge is derived from the verifier
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ChallengeMethod {
+    Plain,
+    S256,
+}
+
+impl ChallengeMethod {
+    /// Value for the `code_challenge_method` query parameter
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Plain => "plain",
+            Self::S256 => "S256",
+        }
+    }
+
+    /// Derive the code challenge for a verifier
+    pub fn challenge(&self, verifier: &str) -> String {
+        match self {
+            Self::Plain => verifier.to_string(),
+            Self::S256 => URL_SAFE_NO_PAD.encode(Sha256::digest(verifier.as_bytes())),
+        }
+    }
+}
+
+/// Check a code verifier against RFC 7636 section 4.1:
+/// 43-128 characters from the unreserved set `[A-Za-z0-9-._~]`
+pub fn validate_code_verifier(verifier: &str) -> Result<(), String> {
+    let len = verifier.len();
+    if !(43..=128).contains(&len) {
+        return Err(format!(
+            "Invalid code verifier: length {} is outside 43-128",
+            len
+        ));
+    }
+
+    if let Some(c) = verifier
+        .chars()
+        .find(|c| !(c.is_ascii_alphanumeric() || matches!(c, '-' | '.' | '_' | '~')))
+    {
+        return Err(format!(
+            "Invalid code verifier: character {:?} not allowed",
+            c
+        ));
+    }
+
+    Ok(())
+}
+
+/// Generate a random `state` for an authorize URL and remember it
+pub fn generate_oauth_state() -> String {
+    let state: String = rand::thread_rng()
+        .sample_iter(&Alphanumeric)
+        .take(STATE_LENGTH)
+        .map(char::from)
+        .collect();
+
+    if let Ok(mut pending) = PENDING_STATES.lock() {
+        pending.insert(state.clone());
+    }
+    state
+}
+
+/// Check that a callback returned a state we issued, consuming it
+pub fn verify_state(state: Option<&str>) -> Result<(), String> {
+    let mut pending = PENDING_STATES
+        .lock()
+        .map_err(|_| "OAuth state lock error".to_string())?;
+
+    match state {
+        Some(state) if pending.remove(state) => Ok(()),
+        Some(_) => Err("Security error: OAuth state mismatch".to_string()),
+        None => Err("Security error: OAuth state missing from callback".to_string()),
+    }
+}
+
+/// Providers with an authorization code flow
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OAuthProvider {
+    AniList,
+    Mal,
+}
+
+impl OAuthProvider {
+    pub fn parse(provider: &str) -> Result<Self, String> {
+        match provider.to_lowercase().as_str() {
+            "anilist" => Ok(Self::AniList),
+            "mal" | "myanimelist" => Ok(Self::Mal),
+            other => Err(format!("Unknown provider: {}", other)),
+        }
+    }
+}
+
+/// An authorize URL plus the values the caller must keep for the callback
+#[derive(Debug, Clone, Serialize)]
+pub struct AuthorizeRequest {
+    pub url: String,
+    pub state: String,
+    /// PKCE verifier for the token exchange (MAL only)
+    pub code_verifier: Option<String>,
+}
+
+/// Build the authorize URL for a provider
+///
+/// A fresh `state` is generated for every URL; MAL also gets a PKCE
+/// challenge using `MAL_CHALLENGE_METHOD`.
+///
+/// # Arguments
+/// * `provider` - Provider to log in to
+/// * `client_id` - OAuth client ID
+/// * `redirect_uri` - Where the provider sends the user back to
+/// * `scopes` - Requested scopes, left out of the URL when empty
+pub fn build_authorize_url(
+    provider: OAuthProvider,
+    client_id: &str,
+    redirect_uri: &str,
+    scopes: &[String],
+) -> AuthorizeRequest {
+    let state = generate_oauth_state();
+
+    let mut params = vec![
+        ("response_type", "code".to_string()),
+        ("client_id", client_id.to_string()),
+        ("redirect_uri", redirect_uri.to_string()),
+    ];
+
+    let (base_url, code_verifier) = match provider {
+        OAuthProvider::AniList => (ANILIST_AUTHORIZE_URL, None),
+        OAuthProvider::Mal => {
+            let verifier = myanimelist::generate_code_verifier();
+            params.push((
+                "code_challenge",
+                myanimelist::generate_code_challenge(&verifier),
+            ));
+            params.push((
+                "code_challenge_method",
+                myanimelist::MAL_CHALLENGE_METHOD.as_str().to_string(),
+            ));
+            (MAL_AUTHORIZE_URL, Some(verifier))
+        }
+    };
+
+    if !scopes.is_empty() {
+        params.push(("scope", scopes.join(" ")));
+    }
+    params.push(("state", state.clone()));
+
+    let query = params
+        .iter()
+        .map(|(key, value)| format!("{}={}", key, urlencoding::encode(value)))
+        .collect::<Vec<_>>()
+        .join("&");
+
+    AuthorizeRequest {
+        url: format!("{}?{}", base_url, query),
+        state,
+        code_verifier,
+    }
+}
+
+/// Parameters of an OAuth redirect
+#[derive(Debug, Clone, Default, Serialize, PartialEq)]
+pub struct AuthCallback {
+    pub code: Option<String>,
+    pub state: Option<String>,
+    pub access_token: Option<String>,
+    pub token_type: Option<String>,
+    pub expires_in: Option<i64>,
+    pub error: Option<String>,
+    pub error_description: Option<String>,
+}
+
+/// Decode a form-encoded value ("+" is a space)
+fn decode_param(value: &str) -> String {
+    let value = value.replace('+', " ");
+    urlencoding::decode(&value)
+        .map(|v| v.into_owned())
+        .unwrap_or(value)
+}
+
+/// Extract the OAuth parameters from a callback URL
+///
+/// Reads both the query (`?code=...`) and the fragment (`#access_token=...`);
+/// when a parameter appears in both, the query wins.
+///
+/// # Returns
+/// * `Err` - If the URL carries none of code, access_token or error
+pub fn parse_auth_callback(url: &str) -> Result<AuthCallback, String> {
+    let (rest, fragment) = url.split_once('#').unwrap_or((url, ""));
+    let query = rest.split_once('?').map(|(_, q)| q).unwrap_or("");
+
+    let mut callback = AuthCallback::default();
+    let params = query
+        .split('&')
+        .chain(fragment.split('&'))
+        .filter_map(|pair| pair.split_once('='));
+
+    for (key, value) in params {
+        let value = decode_param(value);
+        let field = match key {
+            "code" => &mut callback.code,
+            "state" => &mut callback.state,
+            "access_token" => &mut callback.access_token,
+            "token_type" => &mut callback.token_type,
+            "error" => &mut callback.error,
+            "error_description" => &mut callback.error_description,
+            "expires_in" => {
+                callback.expires_in = callback.expires_in.or(value.parse().ok());
+                continue;
+            }
+            _ => continue,
+        };
+        field.get_or_insert(value);
+    }
+
+    if callback.code.is_none() && callback.access_token.is_none() && callback.error.is_none() {
+        return Err("No OAuth parameters in callback URL".to_string());
+    }
+
+    Ok(callback)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Sample from RFC 7636 Appendix B
+    const RFC_VERIFIER: &str = "dBjftJeZ4CVP-mB92K27uhbUJU1p1r_wW1gFWFOEjXk";
+    const RFC_CHALLENGE: &str = "E9Melhoa2OwvFrEMTJguCHaoeK1t8URWbuGJSstw-cM";
+
+    #[test]
+    fn test_s256_matches_rfc_vector() {
+        assert_eq!(ChallengeMethod::S256.challenge(RFC_VERIFIER), RFC_CHALLENGE);
+        assert_eq!(ChallengeMethod::S256.as_str(), "S256");
+    }
+
+    #[test]
+    fn test_validate_code_verifier() {
+        assert!(validate_code_verifier(RFC_VERIFIER).is_ok());
+        assert!(validate_code_verifier(&"a~b.c_d-".repeat(16)).is_ok());
+
+        let too_short = validate_code_verifier(&"a".repeat(42)).unwrap_err();
+        assert!(too_short.contains("length 42"));
+
+        let too_long = validate_code_verifier(&"a".repeat(129)).unwrap_err();
+        assert!(too_long.contains("length 129"));
+
+        let bad_char = validate_code_verifier(&format!("{}+", "a".repeat(50))).unwrap_err();
+        assert!(bad_char.contains("'+'"));
+    }
+
+    #[test]
+    fn test_plain_returns_verifier() {
+        assert_eq!(ChallengeMethod::Plain.challenge(RFC_VERIFIER), RFC_VERIFIER);
+        assert_eq!(ChallengeMethod::Plain.as_str(), "plain");
+    }
+
+    #[test]
+    fn test_matching_state_is_accepted_once() {
+        let state = generate_oauth_state();
+        assert_eq!(state.len(), STATE_LENGTH);
+
+        let callback =
+            parse_auth_callback(&format!("playon://auth?code=abc&state={}", state)).unwrap();
+        assert!(verify_state(callback.state.as_deref()).is_ok());
+
+        // Replaying the same callback fails
+        assert!(verify_state(callback.state.as_deref()).is_err());
+    }
+
+    #[test]
+    fn test_mismatched_state_is_rejected() {
+        generate_oauth_state();
+
+        let err = verify_state(Some("forged")).unwrap_err();
+        assert!(err.starts_with("Security error"));
+        assert!(verify_state(None).is_err());
+    }
+
+    #[test]
+    fn test_anilist_authorize_url() {
+        let request = build_authorize_url(OAuthProvider::AniList, "123", "playon://auth", &[]);
+
+        assert_eq!(
+            request.url,
+            format!(
+                "https://anilist.co/api/v2/oauth/authorize?response_type=code&client_id=123\
+                 &redirect_uri=playon%3A%2F%2Fauth&state={}",
+                request.state
+            )
+        );
+        assert_eq!(request.code_verifier, None);
+    }
+
+    #[test]
+    fn test_mal_authorize_url_has_pkce() {
+        let scopes = vec!["write:users".to_string()];
+        let request =
+            build_authorize_url(OAuthProvider::Mal, "abc", "http://localhost:17563", &scopes);
+
+        let verifier = request.code_verifier.clone().unwrap();
+        assert!(validate_code_verifier(&verifier).is_ok());
+        assert!(request
+            .url
+            .starts_with("https://myanimelist.net/v1/oauth2/authorize?response_type=code"));
+        assert!(request
+            .url
+            .contains(&format!("&code_challenge={}&", verifier)));
+        assert!(request.url.contains("&code_challenge_method=plain&"));
+        assert!(request.url.contains("&scope=write%3Ausers&"));
+        assert!(verify_state(Some(&request.state)).is_ok());
+    }
+
+    #[test]
+    fn test_unknown_provider() {
+        assert_eq!(OAuthProvider::parse("MAL"), Ok(OAuthProvider::Mal));
+        assert!(OAuthProvider::parse("kitsu").is_err());
+    }
+
+    #[test]
+    fn test_parse_query_callback() {
+        let callback = parse_auth_callback("playon://auth?code=def50200a%2Bb&state=xyz").unwrap();
+        assert_eq!(callback.code.as_deref(), Some("def50200a+b"));
+        assert_eq!(callback.state.as_deref(), Some("xyz"));
+        assert_eq!(callback.access_token, None);
+    }
+
+    #[test]
+    fn test_parse_fragment_callback() {
+        let callback = parse_auth_callback(
+            "playon://auth#access_token=eyJ0eXAi&token_type=Bearer&expires_in=31536000",
+        )
+        .unwrap();
+        assert_eq!(callback.access_token.as_deref(), Some("eyJ0eXAi"));
+        assert_eq!(callback.token_type.as_deref(), Some("Bearer"));
+        assert_eq!(callback.expires_in, Some(31536000));
+        assert_eq!(callback.code, None);
+    }
+
+    #[test]
+    fn test_parse_error_callback() {
+        let callback = parse_auth_callback(
+            "playon://auth?error=access_denied&error_description=The+user+denied+access",
+        )
+        .unwrap();
+        assert_eq!(callback.error.as_deref(), Some("access_denied"));
+        assert_eq!(
+            callback.error_description.as_deref(),
+            Some("The user denied access")
+        );
+
+        assert!(parse_auth_callback("playon://auth").is_err());
+    }
+}
@@ -0,0 +1,507 @@
+//! Scrobble Rules
+//!
+//! PURPOSE: Decide what progress is actually sent to a tracker for a detected episode
+//! Keeps the rules in one place so AniList and MAL updates behave the same.
+//!
+//! CONFIDENCE: Auto-scrobbles carry the fuzzy matcher's confidence. Updating the
+//! wrong show is worse than not updating at all, so matches below the
+//! configured `min_confidence` are skipped and reported instead.
+
+use serde::Serialize;
+use std::fmt;
+use std::future::Future;
+
+use crate::anilist::MediaRelation;
+use crate::status::UnifiedStatus;
+
+/// Sequel hops followed before giving up on placing an episode
+const MAX_SEQUEL_HOPS: usize = 8;
+
+/// Tracker an update is sent to; the status vocabularies differ
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TrackerKind {
+    AniList,
+    Mal,
+}
+
+impl TrackerKind {
+    /// This tracker's value for a unified status
+    pub fn status(&self, status: UnifiedStatus) -> &'static str {
+        match self {
+            Self::AniList => status.to_anilist(),
+            Self::Mal => status.to_mal(),
+        }
+    }
+
+    /// Status for an entry currently being watched
+    pub fn watching_status(&self) -> &'static str {
+        self.status(UnifiedStatus::Watching)
+    }
+
+    /// Status for a finished entry
+    pub fn completed_status(&self) -> &'static str {
+        self.status(UnifiedStatus::Completed)
+    }
+}
+
+/// Convert a detected episode number to the tracker entry's numbering
+///
+/// Split-cour shows often number the second cour from 13 in file names while
+/// AniList/MAL list it as a separate entry starting at 1. With an offset of 12,
+/// episode 14 becomes episode 2 of the second entry.
+///
+/// # Arguments
+/// * `episode` - Episode number as detected
+/// * `offset` - Number of episodes that belong to earlier entries (None = 0)
+///
+/// # Returns
+/// * `Ok(episode)` - Episode number for the tracker entry
+/// * `Err` - If the offset pushes the episode below 1 (it belongs to an earlier entry)
+///
+/// Without an offset the episode is returned as is, so progress 0 can still be set.
+pub fn apply_episode_offset(episode: i32, offset: Option<i32>) -> Result<i32, String> {
+    match offset {
+        Some(offset) if offset < 0 => Err(format!(
+            "Episode offset must not be negative (got {})",
+            offset
+        )),
+        Some(offset) if offset > 0 => {
+            let corrected = episode - offset;
+            if corrected < 1 {
+                return Err(format!(
+                    "Episode {} is not part of this entry (offset {})",
+                    episode, offset
+                ));
+            }
+            Ok(corrected)
+        }
+        _ => Ok(episode),
+    }
+}
+
+/// Refuse progress lower than what's already recorded on the user's list
+///
+/// Detection can briefly read a wrong, lower episode number (a preview file,
+/// a mis-parsed title); without this check it would overwrite real progress.
+///
+/// Entries being rewatched are never refused: the list keeps showing the
+/// full count while the rewatch starts over from episode 1.
+///
+/// # Arguments
+/// * `current` - Progress on the user's list, None if unknown or not on the list
+/// * `episode` - Progress about to be sent (after any offset)
+/// * `rewatching` - Whether the list entry is being rewatched
+///   (AniList REPEATING, MAL `is_rewatching`)
+/// * `allow_regression` - Send lower progress anyway (manual fixes)
+///
+/// # Returns
+/// * `Err` - If `episode` is below `current` outside a rewatch and regressions aren't allowed
+pub fn check_progress(
+    current: Option<i32>,
+    episode: i32,
+    rewatching: bool,
+    allow_regression: bool,
+) -> Result<(), String> {
+    match current {
+        Some(current) if episode < current && !rewatching && !allow_regression => Err(format!(
+            "Skipped update: episode {} is behind the recorded progress {}",
+            episode, current
+        )),
+        _ => Ok(()),
+    }
+}
+
+/// Pick the status to send based on what's already on the user's list
+///
+/// With `auto_start` on, a show that isn't on the list yet is added as
+/// "watching". An entry that is already completed is never moved back to
+/// "watching"; the status is dropped so only progress is updated.
+///
+/// # Arguments
+/// * `tracker` - Tracker the update goes to
+/// * `existing` - Current list status, None if the show isn't on the list
+/// * `requested` - Status requested by the caller
+/// * `auto_start` - Whether the auto-start rule is enabled
+pub fn start_status(
+    tracker: TrackerKind,
+    existing: Option<&str>,
+    requested: Option<&str>,
+    auto_start: bool,
+) -> Option<String> {
+    match existing {
+        None if auto_start => Some(requested.unwrap_or(tracker.watching_status()).to_string()),
+        Some(existing)
+            if existing == tracker.completed_status()
+                && requested == Some(tracker.watching_status()) =>
+        {
+            None
+        }
+        _ => requested.map(str::to_string),
+    }
+}
+
+/// Pick the status to send with a progress update
+///
+/// With `auto_complete` on, reaching the final episode turns a missing or
+/// "watching" status into "completed". Other explicit statuses (paused,
+/// dropped, ...) are left alone, as is anything when the episode count is unknown.
+///
+/// # Arguments
+/// * `tracker` - Tracker the update goes to
+/// * `episode` - Episode number for the tracker entry (after any offset)
+/// * `total_episodes` - Episode count of the entry, if known
+/// * `requested` - Status requested by the caller
+/// * `auto_complete` - Whether the auto-complete rule is enabled
+pub fn resolve_status(
+    tracker: TrackerKind,
+    episode: i32,
+    total_episodes: Option<i32>,
+    requested: Option<&str>,
+    auto_complete: bool,
+) -> Option<String> {
+    let is_final_episode = total_episodes.is_some_and(|total| total > 0 && episode >= total);
+    let watching = requested.is_none_or(|status| status == tracker.watching_status());
+
+    if auto_complete && is_final_episode && watching {
+        return Some(tracker.completed_status().to_string());
+    }
+
+    requested.map(str::to_string)
+}
+
+/// The entry an episode belongs to and its number within that entry
+#[derive(Debug, Clone, Copy, Serialize, PartialEq, Eq)]
+pub struct EpisodeLocation {
+    pub media_id: i32,
+    pub episode: i32,
+}
+
+/// Find the entry an absolutely numbered episode belongs to
+///
+/// Files often keep counting across seasons ("Anime - 27") while AniList lists
+/// each season as its own entry. Starting from the matched entry, sequels are
+/// followed while the episode is past the current entry's count, subtracting
+/// each count on the way. An entry with an unknown count (still airing) takes
+/// whatever is left.
+///
+/// # Arguments
+/// * `media_id` - Matched AniList entry
+/// * `episodes` - Its episode count, if known
+/// * `episode` - Detected episode number
+/// * `get_relations` - Relation lookup, `anilist::get_relations` outside tests
+///
+/// # Returns
+/// * The original entry and episode when it already fits or there's no sequel
+pub async fn locate_episode<F, Fut>(
+    media_id: i32,
+    episodes: Option<i32>,
+    episode: i32,
+    mut get_relations: F,
+) -> Result<EpisodeLocation, String>
+where
+    F: FnMut(i32) -> Fut,
+    Fut: Future<Output = Result<Vec<MediaRelation>, String>>,
+{
+    let mut location = EpisodeLocation { media_id, episode };
+    let mut count = episodes;
+
+    for _ in 0..MAX_SEQUEL_HOPS {
+        let Some(total) = count.filter(|total| *total > 0 && location.episode > *total) else {
+            break;
+        };
+
+        let sequel = get_relations(location.media_id)
+            .await?
+            .into_iter()
+            .find(|relation| relation.relation_type == "SEQUEL");
+        let Some(sequel) = sequel else {
+            break;
+        };
+
+        location = EpisodeLocation {
+            media_id: sequel.id,
+            episode: location.episode - total,
+        };
+        count = sequel.episodes;
+    }
+
+    Ok(location)
+}
+
+/// A match too uncertain to scrobble
+#[derive(Debug, Clone, Serialize, PartialEq)]
+pub struct AmbiguousMatch {
+    pub confidence: f64,
+    pub min_confidence: f64,
+}
+
+impl fmt::Display for AmbiguousMatch {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "Match confidence {:.2} is below the minimum {:.2}",
+            self.confidence, self.min_confidence
+        )
+    }
+}
+
+/// Run `update` only if the match is confident enough
+///
+/// A missing `confidence` means the user picked the entry themselves, so the
+/// update always runs. Otherwise, below `min_confidence`, `on_ambiguous` is
+/// called and an error is returned without running `update`.
+pub async fn scrobble_if_confident<T, F, Fut>(
+    confidence: Option<f64>,
+    min_confidence: f64,
+    on_ambiguous: impl FnOnce(&AmbiguousMatch),
+    update: F,
+) -> Result<T, String>
+where
+    F: FnOnce() -> Fut,
+    Fut: Future<Output = Result<T, String>>,
+{
+    if let Some(confidence) = confidence.filter(|c| *c < min_confidence) {
+        let ambiguous = AmbiguousMatch {
+            confidence,
+            min_confidence,
+        };
+        on_ambiguous(&ambiguous);
+        return Err(format!("Skipped update: {}", ambiguous));
+    }
+
+    update().await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_split_cour_offset() {
+        assert_eq!(apply_episode_offset(14, Some(12)), Ok(2));
+        assert_eq!(apply_episode_offset(13, Some(12)), Ok(1));
+    }
+
+    #[test]
+    fn test_no_offset() {
+        assert_eq!(apply_episode_offset(5, None), Ok(5));
+        assert_eq!(apply_episode_offset(5, Some(0)), Ok(5));
+        // Resetting progress has no offset
+        assert_eq!(apply_episode_offset(0, None), Ok(0));
+        assert_eq!(apply_episode_offset(0, Some(0)), Ok(0));
+    }
+
+    #[test]
+    fn test_episode_before_offset() {
+        assert!(apply_episode_offset(12, Some(12)).is_err());
+        assert!(apply_episode_offset(5, Some(-1)).is_err());
+    }
+
+    #[test]
+    fn test_forward_progress_allowed() {
+        assert_eq!(check_progress(Some(4), 5, false, false), Ok(()));
+        assert_eq!(check_progress(Some(5), 5, false, false), Ok(()));
+        // Not on the list yet
+        assert_eq!(check_progress(None, 1, false, false), Ok(()));
+    }
+
+    #[test]
+    fn test_backward_progress_blocked() {
+        assert!(check_progress(Some(8), 3, false, false).is_err());
+        assert_eq!(check_progress(Some(8), 3, false, true), Ok(()));
+    }
+
+    #[test]
+    fn test_rewatch_progress_allowed() {
+        // A rewatch of a finished 12-episode show starts over below the list count
+        assert_eq!(check_progress(Some(12), 1, true, false), Ok(()));
+        assert_eq!(check_progress(Some(12), 3, true, false), Ok(()));
+    }
+
+    #[test]
+    fn test_final_episode_completes() {
+        assert_eq!(
+            resolve_status(TrackerKind::AniList, 12, Some(12), Some("CURRENT"), true),
+            Some("COMPLETED".to_string())
+        );
+        assert_eq!(
+            resolve_status(TrackerKind::Mal, 12, Some(12), None, true),
+            Some("completed".to_string())
+        );
+    }
+
+    #[test]
+    fn test_mid_series_keeps_status() {
+        assert_eq!(
+            resolve_status(TrackerKind::AniList, 5, Some(12), Some("CURRENT"), true),
+            Some("CURRENT".to_string())
+        );
+        assert_eq!(
+            resolve_status(TrackerKind::Mal, 5, Some(12), None, true),
+            None
+        );
+        // Unknown episode count (airing shows)
+        assert_eq!(resolve_status(TrackerKind::Mal, 5, None, None, true), None);
+    }
+
+    #[test]
+    fn test_auto_complete_respects_toggle_and_explicit_status() {
+        assert_eq!(
+            resolve_status(TrackerKind::AniList, 12, Some(12), Some("CURRENT"), false),
+            Some("CURRENT".to_string())
+        );
+        assert_eq!(
+            resolve_status(TrackerKind::AniList, 12, Some(12), Some("PAUSED"), true),
+            Some("PAUSED".to_string())
+        );
+    }
+
+    #[test]
+    fn test_new_entry_starts_watching() {
+        assert_eq!(
+            start_status(TrackerKind::AniList, None, None, true),
+            Some("CURRENT".to_string())
+        );
+        assert_eq!(
+            start_status(TrackerKind::Mal, None, None, true),
+            Some("watching".to_string())
+        );
+        assert_eq!(start_status(TrackerKind::Mal, None, None, false), None);
+    }
+
+    #[test]
+    fn test_completed_entry_is_not_reopened() {
+        assert_eq!(
+            start_status(
+                TrackerKind::AniList,
+                Some("COMPLETED"),
+                Some("CURRENT"),
+                true
+            ),
+            None
+        );
+        assert_eq!(
+            start_status(TrackerKind::Mal, Some("completed"), Some("watching"), true),
+            None
+        );
+        // An explicit rewatch is still allowed
+        assert_eq!(
+            start_status(
+                TrackerKind::AniList,
+                Some("COMPLETED"),
+                Some("REPEATING"),
+                true
+            ),
+            Some("REPEATING".to_string())
+        );
+    }
+
+    #[tokio::test]
+    async fn test_low_confidence_skips_update() {
+        let mut reported = None;
+        let result: Result<(), String> = scrobble_if_confident(
+            Some(0.3),
+            0.5,
+            |ambiguous| reported = Some(ambiguous.clone()),
+            || async { panic!("update must not run below the threshold") },
+        )
+        .await;
+
+        assert!(result.unwrap_err().contains("below the minimum"));
+        assert_eq!(
+            reported,
+            Some(AmbiguousMatch {
+                confidence: 0.3,
+                min_confidence: 0.5
+            })
+        );
+    }
+
+    #[tokio::test]
+    async fn test_confident_or_manual_match_updates() {
+        for confidence in [Some(0.5), Some(0.9), None] {
+            let result = scrobble_if_confident(
+                confidence,
+                0.5,
+                |_| panic!("not ambiguous"),
+                || async { Ok(12) },
+            )
+            .await;
+            assert_eq!(result, Ok(12));
+        }
+    }
+
+    fn relation(relation_type: &str, id: i32, episodes: Option<i32>) -> MediaRelation {
+        serde_json::from_value(serde_json::json!({
+            "relation_type": relation_type,
+            "id": id,
+            "title": { "romaji": format!("Entry {}", id) },
+            "episodes": episodes
+        }))
+        .unwrap()
+    }
+
+    /// Season 1 (id 1, 24 eps) -> season 2 (id 2, 12 eps) -> season 3 (id 3, airing)
+    async fn two_season_relations(id: i32) -> Result<Vec<MediaRelation>, String> {
+        Ok(match id {
+            1 => vec![
+                relation("SIDE_STORY", 10, Some(1)),
+                relation("SEQUEL", 2, Some(12)),
+            ],
+            2 => vec![
+                relation("PREQUEL", 1, Some(24)),
+                relation("SEQUEL", 3, None),
+            ],
+            _ => vec![relation("PREQUEL", 2, Some(12))],
+        })
+    }
+
+    #[tokio::test]
+    async fn test_episode_past_first_season_moves_to_sequel() {
+        assert_eq!(
+            locate_episode(1, Some(24), 27, two_season_relations).await,
+            Ok(EpisodeLocation {
+                media_id: 2,
+                episode: 3
+            })
+        );
+        assert_eq!(
+            locate_episode(1, Some(24), 40, two_season_relations).await,
+            Ok(EpisodeLocation {
+                media_id: 3,
+                episode: 4
+            })
+        );
+    }
+
+    #[tokio::test]
+    async fn test_episode_within_season_stays() {
+        let no_lookup = |_| async { panic!("no relations needed") };
+        assert_eq!(
+            locate_episode(1, Some(24), 24, no_lookup).await,
+            Ok(EpisodeLocation {
+                media_id: 1,
+                episode: 24
+            })
+        );
+        // Unknown count (airing), nothing to compare against
+        assert_eq!(
+            locate_episode(1, None, 27, no_lookup).await,
+            Ok(EpisodeLocation {
+                media_id: 1,
+                episode: 27
+            })
+        );
+    }
+
+    #[tokio::test]
+    async fn test_episode_without_sequel_stays() {
+        assert_eq!(
+            locate_episode(3, Some(12), 15, two_season_relations).await,
+            Ok(EpisodeLocation {
+                media_id: 3,
+                episode: 15
+            })
+        );
+    }
+}
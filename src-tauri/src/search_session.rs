@@ -0,0 +1,155 @@
+//! Search Sessions
+//!
+//! PURPOSE: Let type-ahead searches cancel the request they replace
+//! Each search UI picks a session name. Starting a search in a session
+//! cancels the one still running there, so a slow older request can't
+//! resolve after, and overwrite, the results of a newer one.
+//!
+//! Debounced searches wait for a quiet period first. A keystroke during the
+//! wait cancels it the same way, so rapid typing ends in a single request.
+
+use std::collections::HashMap;
+use std::future::Future;
+use std::sync::Mutex;
+use std::time::Duration;
+use tokio_util::sync::CancellationToken;
+
+/// Error returned by a search that a newer search in its session replaced
+pub const SEARCH_CANCELLED: &str = "Search cancelled";
+
+/// Longest quiet period a debounced search may wait for
+pub const MAX_DEBOUNCE: Duration = Duration::from_secs(2);
+
+/// Running searches by session name
+#[derive(Default)]
+struct Sessions {
+    running: HashMap<String, (u64, CancellationToken)>,
+    next_id: u64,
+}
+
+lazy_static::lazy_static! {
+    static ref SESSIONS: Mutex<Sessions> = Mutex::new(Sessions::default());
+}
+
+/// Run a search in a session, cancelling the search already running there
+///
+/// # Returns
+/// * `Ok(output)` - The search finished before a newer one started
+/// * `Err(SEARCH_CANCELLED)` - A newer search in the session replaced it
+pub async fn run<F: Future>(session: &str, search: F) -> Result<F::Output, String> {
+    let token = CancellationToken::new();
+    let id = {
+        let mut sessions = SESSIONS.lock().map_err(|_| "Search session lock error")?;
+        sessions.next_id += 1;
+        let id = sessions.next_id;
+        if let Some((_, previous)) = sessions
+            .running
+            .insert(session.to_string(), (id, token.clone()))
+        {
+            previous.cancel();
+        }
+        id
+    };
+
+    let result = tokio::select! {
+        _ = token.cancelled() => Err(SEARCH_CANCELLED.to_string()),
+        output = search => Ok(output),
+    };
+
+    // Only forget the session if a newer search hasn't taken it over
+    if let Ok(mut sessions) = SESSIONS.lock() {
+        if sessions
+            .running
+            .get(session)
+            .is_some_and(|(running, _)| *running == id)
+        {
+            sessions.running.remove(session);
+        }
+    }
+
+    result
+}
+
+/// Run a search once no newer search has started in the session for `delay`
+///
+/// `delay` is capped at `MAX_DEBOUNCE`. A search started during the wait
+/// replaces this one, which then fails with `SEARCH_CANCELLED` without ever
+/// running.
+pub async fn run_debounced<F: Future>(
+    session: &str,
+    delay: Duration,
+    search: F,
+) -> Result<F::Output, String> {
+    run(session, async move {
+        tokio::time::sleep(delay.min(MAX_DEBOUNCE)).await;
+        search.await
+    })
+    .await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+
+    #[tokio::test]
+    async fn test_new_search_cancels_previous() {
+        let stale = tokio::spawn(run("test_cancel", async {
+            tokio::time::sleep(Duration::from_secs(30)).await;
+            "stale"
+        }));
+        // Let the first search register before the second replaces it
+        tokio::time::sleep(Duration::from_millis(50)).await;
+
+        let fresh = run("test_cancel", async { "fresh" }).await;
+        assert_eq!(fresh, Ok("fresh"));
+
+        let stale = tokio::time::timeout(Duration::from_secs(1), stale)
+            .await
+            .expect("cancelled search should stop waiting")
+            .unwrap();
+        assert_eq!(stale, Err(SEARCH_CANCELLED.to_string()));
+    }
+
+    #[tokio::test]
+    async fn test_sessions_are_independent() {
+        let other = tokio::spawn(run("test_independent_a", async {
+            tokio::time::sleep(Duration::from_millis(200)).await;
+            1
+        }));
+        tokio::time::sleep(Duration::from_millis(50)).await;
+
+        assert_eq!(run("test_independent_b", async { 2 }).await, Ok(2));
+        assert_eq!(other.await.unwrap(), Ok(1));
+    }
+
+    #[tokio::test]
+    async fn test_rapid_debounced_calls_search_once() {
+        let searches = Arc::new(AtomicUsize::new(0));
+        let mut calls = Vec::new();
+        for keystroke in 0..5 {
+            let searches = searches.clone();
+            calls.push(tokio::spawn(run_debounced(
+                "test_debounce",
+                Duration::from_millis(100),
+                async move {
+                    searches.fetch_add(1, Ordering::SeqCst);
+                    keystroke
+                },
+            )));
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+
+        let mut results = Vec::new();
+        for call in calls {
+            results.push(call.await.unwrap());
+        }
+
+        assert_eq!(searches.load(Ordering::SeqCst), 1);
+        assert_eq!(results[4], Ok(4));
+        assert!(results[..4]
+            .iter()
+            .all(|result| result == &Err(SEARCH_CANCELLED.to_string())));
+    }
+}
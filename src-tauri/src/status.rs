@@ -0,0 +1,219 @@
+//! List Status Vocabulary
+//!
+//! PURPOSE: One status type for both trackers
+//! AniList uses CURRENT/COMPLETED/PAUSED/DROPPED/PLANNING/REPEATING while MAL
+//! uses watching/completed/on_hold/dropped/plan_to_watch. Commands take a
+//! `UnifiedStatus` and convert at the edge, so callers never string-match
+//! provider vocabularies themselves.
+//!
+//! Deserializing accepts the unified names as well as either provider's own
+//! values, so existing callers that send "CURRENT" or "on_hold" keep working.
+
+use serde::{Deserialize, Serialize};
+
+/// A list status independent of the tracker
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case", try_from = "String")]
+pub enum UnifiedStatus {
+    Watching,
+    Completed,
+    Paused,
+    Dropped,
+    Planning,
+    /// MAL has no separate status for this and reports it as watching
+    Rewatching,
+}
+
+impl UnifiedStatus {
+    pub const ALL: [UnifiedStatus; 6] = [
+        Self::Watching,
+        Self::Completed,
+        Self::Paused,
+        Self::Dropped,
+        Self::Planning,
+        Self::Rewatching,
+    ];
+
+    /// AniList MediaListStatus value
+    pub fn to_anilist(self) -> &'static str {
+        match self {
+            Self::Watching => "CURRENT",
+            Self::Completed => "COMPLETED",
+            Self::Paused => "PAUSED",
+            Self::Dropped => "DROPPED",
+            Self::Planning => "PLANNING",
+            Self::Rewatching => "REPEATING",
+        }
+    }
+
+    /// MAL anime list status value
+    pub fn to_mal(self) -> &'static str {
+        match self {
+            Self::Watching | Self::Rewatching => "watching",
+            Self::Completed => "completed",
+            Self::Paused => "on_hold",
+            Self::Dropped => "dropped",
+            Self::Planning => "plan_to_watch",
+        }
+    }
+
+    /// Unified name, as used in serialized output
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Watching => "watching",
+            Self::Completed => "completed",
+            Self::Paused => "paused",
+            Self::Dropped => "dropped",
+            Self::Planning => "planning",
+            Self::Rewatching => "rewatching",
+        }
+    }
+
+    pub fn from_anilist(status: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|s| s.to_anilist() == status)
+    }
+
+    /// MAL's "watching" always maps to `Watching`
+    pub fn from_mal(status: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|s| s.to_mal() == status)
+    }
+
+    /// Parse a unified, AniList or MAL status name (case-insensitive)
+    pub fn parse(status: &str) -> Result<Self, String> {
+        let lower = status.trim().to_lowercase();
+        Self::ALL
+            .into_iter()
+            .find(|s| s.as_str() == lower)
+            .or_else(|| Self::from_anilist(&lower.to_uppercase()))
+            .or_else(|| Self::from_mal(&lower))
+            .ok_or_else(|| {
+                format!(
+                    "Invalid status \"{}\". Valid values: {} (or AniList's {}, or MAL's {})",
+                    status,
+                    names(Self::as_str),
+                    names(Self::to_anilist),
+                    names(Self::to_mal)
+                )
+            })
+    }
+}
+
+/// Comma-separated distinct names for the error message
+fn names(name: fn(UnifiedStatus) -> &'static str) -> String {
+    let mut names: Vec<&str> = Vec::new();
+    for status in UnifiedStatus::ALL {
+        if !names.contains(&name(status)) {
+            names.push(name(status));
+        }
+    }
+    names.join(", ")
+}
+
+/// MAL manga list status values
+pub const MAL_MANGA_STATUSES: &[&str] =
+    &["reading", "completed", "on_hold", "dropped", "plan_to_read"];
+
+/// Check a MAL manga status before sending it
+pub fn validate_mal_manga_status(status: &str) -> Result<(), String> {
+    if MAL_MANGA_STATUSES.contains(&status) {
+        Ok(())
+    } else {
+        Err(format!(
+            "Invalid status \"{}\". Valid values: {}",
+            status,
+            MAL_MANGA_STATUSES.join(", ")
+        ))
+    }
+}
+
+impl TryFrom<String> for UnifiedStatus {
+    type Error = String;
+
+    fn try_from(status: String) -> Result<Self, Self::Error> {
+        Self::parse(&status)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_anilist_round_trip() {
+        let expected = [
+            (UnifiedStatus::Watching, "CURRENT"),
+            (UnifiedStatus::Completed, "COMPLETED"),
+            (UnifiedStatus::Paused, "PAUSED"),
+            (UnifiedStatus::Dropped, "DROPPED"),
+            (UnifiedStatus::Planning, "PLANNING"),
+            (UnifiedStatus::Rewatching, "REPEATING"),
+        ];
+        for (status, anilist) in expected {
+            assert_eq!(status.to_anilist(), anilist);
+            assert_eq!(UnifiedStatus::from_anilist(anilist), Some(status));
+        }
+    }
+
+    #[test]
+    fn test_mal_round_trip() {
+        let expected = [
+            (UnifiedStatus::Watching, "watching"),
+            (UnifiedStatus::Completed, "completed"),
+            (UnifiedStatus::Paused, "on_hold"),
+            (UnifiedStatus::Dropped, "dropped"),
+            (UnifiedStatus::Planning, "plan_to_watch"),
+        ];
+        for (status, mal) in expected {
+            assert_eq!(status.to_mal(), mal);
+            assert_eq!(UnifiedStatus::from_mal(mal), Some(status));
+        }
+
+        // Rewatching is lossy on MAL
+        assert_eq!(UnifiedStatus::Rewatching.to_mal(), "watching");
+        assert_eq!(
+            UnifiedStatus::from_mal("watching"),
+            Some(UnifiedStatus::Watching)
+        );
+    }
+
+    #[test]
+    fn test_deserialize_any_vocabulary() {
+        for (json, status) in [
+            ("\"paused\"", UnifiedStatus::Paused),
+            ("\"PAUSED\"", UnifiedStatus::Paused),
+            ("\"on_hold\"", UnifiedStatus::Paused),
+            ("\"REPEATING\"", UnifiedStatus::Rewatching),
+            ("\"plan_to_watch\"", UnifiedStatus::Planning),
+        ] {
+            assert_eq!(serde_json::from_str::<UnifiedStatus>(json).unwrap(), status);
+        }
+        assert!(serde_json::from_str::<UnifiedStatus>("\"binging\"").is_err());
+        // Case doesn't matter
+        assert_eq!(
+            UnifiedStatus::parse("WATCHING"),
+            Ok(UnifiedStatus::Watching)
+        );
+        assert_eq!(
+            serde_json::to_string(&UnifiedStatus::Planning).unwrap(),
+            "\"planning\""
+        );
+    }
+
+    #[test]
+    fn test_invalid_status_lists_valid_values() {
+        let err = UnifiedStatus::parse("WATCHED").unwrap_err();
+        assert!(err.starts_with("Invalid status \"WATCHED\""));
+        assert!(err.contains("watching, completed, paused, dropped, planning, rewatching"));
+        assert!(err.contains("CURRENT"));
+        assert!(err.contains("plan_to_watch"));
+    }
+
+    #[test]
+    fn test_mal_manga_status() {
+        assert!(validate_mal_manga_status("reading").is_ok());
+        assert!(validate_mal_manga_status("plan_to_read").is_ok());
+
+        let err = validate_mal_manga_status("watching").unwrap_err();
+        assert!(err.contains("reading, completed, on_hold, dropped, plan_to_read"));
+    }
+}
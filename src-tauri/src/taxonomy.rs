@@ -0,0 +1,195 @@
+//! AniList Taxonomy Cache
+//!
+//! PURPOSE: Serve AniList's genre and tag lists for filtering UIs without
+//! refetching them every time
+//! Both lists rarely change, so they're cached in the app data dir
+//! (`genres.json`, `media_tags.json`) for a week.
+//!
+//! If a refetch fails, a stale cache is returned rather than an error.
+
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::future::Future;
+use std::path::Path;
+use std::time::SystemTime;
+use tracing::warn;
+
+use crate::anilist::{self, MediaTag};
+
+/// How long cached lists are used before refetching (7 days)
+pub const TAXONOMY_TTL_SECS: u64 = 7 * 24 * 60 * 60;
+
+const GENRES_FILE: &str = "genres.json";
+const TAGS_FILE: &str = "media_tags.json";
+
+/// A cached list with the time it was fetched
+#[derive(Debug, Serialize, Deserialize)]
+struct CachedList<T> {
+    /// Unix timestamp (seconds) of the fetch
+    fetched_at: u64,
+    items: Vec<T>,
+}
+
+fn now_secs() -> u64 {
+    SystemTime::now()
+        .duration_since(SystemTime::UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or_default()
+}
+
+fn read_cache<T: DeserializeOwned>(path: &Path) -> Option<CachedList<T>> {
+    let json = fs::read_to_string(path).ok()?;
+    serde_json::from_str(&json).ok()
+}
+
+fn write_cache<T: Serialize>(path: &Path, cache: &CachedList<T>) -> Result<(), String> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent).map_err(|e| format!("Failed to create data dir: {}", e))?;
+    }
+    let json = serde_json::to_string(cache).map_err(|e| format!("Serialization error: {}", e))?;
+    fs::write(path, json).map_err(|e| format!("Failed to write cache: {}", e))
+}
+
+/// Return the cached list if it's fresh, otherwise fetch and cache it
+///
+/// # Returns
+/// * The cached list when it's younger than `ttl_secs`
+/// * The fetched list otherwise
+/// * The stale cached list if fetching fails
+pub async fn load_or_fetch<T, F, Fut>(
+    path: &Path,
+    ttl_secs: u64,
+    fetch: F,
+) -> Result<Vec<T>, String>
+where
+    T: Serialize + DeserializeOwned,
+    F: FnOnce() -> Fut,
+    Fut: Future<Output = Result<Vec<T>, String>>,
+{
+    let cached = read_cache::<T>(path);
+    let now = now_secs();
+
+    if let Some(cache) = &cached {
+        if now.saturating_sub(cache.fetched_at) < ttl_secs {
+            return Ok(cached.map(|c| c.items).unwrap_or_default());
+        }
+    }
+
+    match fetch().await {
+        Ok(items) => {
+            let cache = CachedList {
+                fetched_at: now,
+                items,
+            };
+            if let Err(e) = write_cache(path, &cache) {
+                warn!("Failed to cache {}: {}", path.display(), e);
+            }
+            Ok(cache.items)
+        }
+        Err(e) => match cached {
+            Some(cache) => {
+                warn!("Refetch failed, using stale {}: {}", path.display(), e);
+                Ok(cache.items)
+            }
+            None => Err(e),
+        },
+    }
+}
+
+/// Get AniList's genre list, cached in `data_dir`
+pub async fn get_genre_collection(data_dir: &Path) -> Result<Vec<String>, String> {
+    load_or_fetch(
+        &data_dir.join(GENRES_FILE),
+        TAXONOMY_TTL_SECS,
+        anilist::fetch_genre_collection,
+    )
+    .await
+}
+
+/// Get AniList's media tag list, cached in `data_dir`
+pub async fn get_media_tags(data_dir: &Path) -> Result<Vec<MediaTag>, String> {
+    load_or_fetch(
+        &data_dir.join(TAGS_FILE),
+        TAXONOMY_TTL_SECS,
+        anilist::fetch_media_tags,
+    )
+    .await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::TempDir;
+
+    fn genres(names: &[&str]) -> Vec<String> {
+        names.iter().map(|n| n.to_string()).collect()
+    }
+
+    #[tokio::test]
+    async fn test_fetches_on_miss_then_uses_cache() {
+        let dir = TempDir::new("taxonomy");
+        let path = dir.join(GENRES_FILE);
+
+        let fetched = load_or_fetch(&path, 60, || async { Ok(genres(&["Action", "Drama"])) })
+            .await
+            .unwrap();
+        assert_eq!(fetched, genres(&["Action", "Drama"]));
+
+        let cached: Vec<String> = load_or_fetch(&path, 60, || async {
+            panic!("fresh cache should not be refetched")
+        })
+        .await
+        .unwrap();
+        assert_eq!(cached, genres(&["Action", "Drama"]));
+    }
+
+    #[tokio::test]
+    async fn test_expired_cache_is_refetched() {
+        let dir = TempDir::new("taxonomy_expired");
+        let path = dir.join(GENRES_FILE);
+        write_cache(
+            &path,
+            &CachedList {
+                fetched_at: 0,
+                items: genres(&["Action"]),
+            },
+        )
+        .unwrap();
+
+        let items = load_or_fetch(&path, 60, || async { Ok(genres(&["Action", "Romance"])) })
+            .await
+            .unwrap();
+        assert_eq!(items, genres(&["Action", "Romance"]));
+        assert_eq!(read_cache::<String>(&path).unwrap().items.len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_failed_refetch_returns_stale_cache() {
+        let dir = TempDir::new("taxonomy_stale");
+        let path = dir.join(GENRES_FILE);
+        write_cache(
+            &path,
+            &CachedList {
+                fetched_at: 0,
+                items: genres(&["Action"]),
+            },
+        )
+        .unwrap();
+
+        let items = load_or_fetch(&path, 60, || async {
+            Err::<Vec<String>, _>("offline".to_string())
+        })
+        .await
+        .unwrap();
+        assert_eq!(items, genres(&["Action"]));
+
+        // Without a cache the error comes through
+        let missing = dir.join("missing.json");
+        let result = load_or_fetch(&missing, 60, || async {
+            Err::<Vec<String>, _>("offline".to_string())
+        })
+        .await;
+        assert_eq!(result, Err("offline".to_string()));
+    }
+}
@@ -0,0 +1,45 @@
+//! Test Utilities
+//!
+//! PURPOSE: Fixtures shared by the unit tests
+//! `TempDir` gives each test its own scratch directory and removes it when
+//! dropped, so a failing assertion doesn't leave files behind.
+
+use std::ops::Deref;
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+/// A fresh directory under the system temp dir, deleted on drop
+pub struct TempDir(PathBuf);
+
+impl TempDir {
+    /// Create `playon_<name>_<nanos>` in the system temp dir
+    pub fn new(name: &str) -> Self {
+        let nanos = SystemTime::now()
+            .duration_since(SystemTime::UNIX_EPOCH)
+            .unwrap()
+            .as_nanos();
+        let dir = std::env::temp_dir().join(format!("playon_{}_{}", name, nanos));
+        std::fs::create_dir_all(&dir).unwrap();
+        Self(dir)
+    }
+}
+
+impl Deref for TempDir {
+    type Target = Path;
+
+    fn deref(&self) -> &Path {
+        &self.0
+    }
+}
+
+impl AsRef<Path> for TempDir {
+    fn as_ref(&self) -> &Path {
+        &self.0
+    }
+}
+
+impl Drop for TempDir {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_dir_all(&self.0);
+    }
+}
@@ -0,0 +1,181 @@
+//! Thumbnails
+//!
+//! PURPOSE: Grab preview frames from local video files for the file browser
+//! Frames are extracted with the ffmpeg found on PATH and saved as PNGs in a
+//! cache directory, keyed by file path and timestamp, so each preview is only
+//! extracted once.
+//!
+//! ffmpeg isn't bundled. When it's missing, extraction fails with
+//! `FFMPEG_NOT_FOUND` in the message so the UI can fall back to an icon.
+
+use std::path::{Path, PathBuf};
+use tokio::process::Command;
+use tracing::info;
+
+/// Extensions of files thumbnails are extracted from
+pub const VIDEO_EXTENSIONS: [&str; 7] = ["mp4", "mkv", "avi", "mov", "webm", "flv", "wmv"];
+
+/// Marker in the error returned when ffmpeg isn't installed
+pub const FFMPEG_NOT_FOUND: &str = "ffmpeg not found";
+
+/// ffmpeg binary looked up on PATH
+const FFMPEG: &str = "ffmpeg";
+
+/// Thumbnail width in pixels; the height keeps the aspect ratio
+const THUMBNAIL_WIDTH: u32 = 320;
+
+/// Whether the path has a known video extension
+pub fn is_video_path(path: &Path) -> bool {
+    path.extension()
+        .and_then(|ext| ext.to_str())
+        .is_some_and(|ext| VIDEO_EXTENSIONS.contains(&ext.to_ascii_lowercase().as_str()))
+}
+
+/// Cached thumbnail location for a file and timestamp
+///
+/// The timestamp is rounded to milliseconds, so 12.0 and 12.0001 share a frame.
+pub fn cache_path(cache_dir: &Path, path: &Path, time_secs: f64) -> PathBuf {
+    let millis = (time_secs * 1000.0).round() as u64;
+    let mut hasher = blake3::Hasher::new();
+    hasher.update(path.to_string_lossy().as_bytes());
+    hasher.update(&millis.to_le_bytes());
+    cache_dir.join(format!("{}.png", &hasher.finalize().to_hex()[..32]))
+}
+
+/// Extract a frame from a video and return the PNG path
+///
+/// # Arguments
+/// * `cache_dir` - Directory thumbnails are stored in (created if missing)
+/// * `path` - Video file
+/// * `time_secs` - Position of the frame in the video
+///
+/// # Returns
+/// * `Ok(path)` - Cached PNG, extracted now or earlier
+/// * `Err` - Not a video, missing file, ffmpeg missing (`FFMPEG_NOT_FOUND`) or failed
+pub async fn extract_thumbnail(
+    cache_dir: &Path,
+    path: &Path,
+    time_secs: f64,
+) -> Result<PathBuf, String> {
+    extract_with(FFMPEG, cache_dir, path, time_secs).await
+}
+
+async fn extract_with(
+    ffmpeg: &str,
+    cache_dir: &Path,
+    path: &Path,
+    time_secs: f64,
+) -> Result<PathBuf, String> {
+    if !is_video_path(path) {
+        return Err(format!("Not a video file: {}", path.display()));
+    }
+    if !time_secs.is_finite() || time_secs < 0.0 {
+        return Err(format!("Invalid thumbnail time: {}", time_secs));
+    }
+    if !path.is_file() {
+        return Err(format!("File does not exist: {}", path.display()));
+    }
+
+    let thumbnail = cache_path(cache_dir, path, time_secs);
+    if thumbnail.exists() {
+        return Ok(thumbnail);
+    }
+
+    std::fs::create_dir_all(cache_dir).map_err(|e| format!("Failed to create cache dir: {}", e))?;
+
+    // Written under a temporary name so a failed run never looks cached
+    let partial = thumbnail.with_extension("part.png");
+    let output = Command::new(ffmpeg)
+        .args(["-v", "error", "-y", "-ss"])
+        .arg(format!("{:.3}", time_secs))
+        .arg("-i")
+        .arg(path)
+        .args(["-frames:v", "1", "-vf"])
+        .arg(format!("scale={}:-2", THUMBNAIL_WIDTH))
+        .arg(&partial)
+        .kill_on_drop(true)
+        .output()
+        .await
+        .map_err(|e| match e.kind() {
+            std::io::ErrorKind::NotFound => format!(
+                "{}: install ffmpeg and make sure it's on PATH",
+                FFMPEG_NOT_FOUND
+            ),
+            _ => format!("Failed to run ffmpeg: {}", e),
+        })?;
+
+    // Seeking past the end exits successfully without writing a frame
+    if !output.status.success() || !partial.exists() {
+        let _ = std::fs::remove_file(&partial);
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        return Err(format!(
+            "ffmpeg could not extract a frame at {}s: {}",
+            time_secs,
+            stderr.trim()
+        ));
+    }
+
+    std::fs::rename(&partial, &thumbnail)
+        .map_err(|e| format!("Failed to save thumbnail: {}", e))?;
+    info!("Extracted {} at {}s", path.display(), time_secs);
+    Ok(thumbnail)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::TempDir;
+
+    #[test]
+    fn test_video_extensions() {
+        assert!(is_video_path(Path::new("/anime/Frieren - 05.mkv")));
+        assert!(is_video_path(Path::new("C:\\anime\\EPISODE.MP4")));
+        assert!(!is_video_path(Path::new("/manga/chapter.cbz")));
+        assert!(!is_video_path(Path::new("/anime/notes")));
+    }
+
+    #[test]
+    fn test_cache_key_uses_path_and_time() {
+        let dir = Path::new("/cache");
+        let video = Path::new("/anime/ep1.mkv");
+
+        assert_eq!(cache_path(dir, video, 12.0), cache_path(dir, video, 12.0));
+        assert_ne!(cache_path(dir, video, 12.0), cache_path(dir, video, 30.0));
+        assert_ne!(
+            cache_path(dir, video, 12.0),
+            cache_path(dir, Path::new("/anime/ep2.mkv"), 12.0)
+        );
+    }
+
+    #[tokio::test]
+    async fn test_rejects_non_video_path() {
+        let error = extract_thumbnail(Path::new("/cache"), Path::new("/manga/ch1.cbz"), 1.0)
+            .await
+            .unwrap_err();
+        assert!(error.contains("Not a video"));
+    }
+
+    #[tokio::test]
+    async fn test_missing_ffmpeg_is_reported() {
+        let dir = TempDir::new("thumbnail_missing");
+        let video = dir.join("ep1.mkv");
+        std::fs::write(&video, b"").unwrap();
+
+        let error = extract_with("playon-no-such-ffmpeg", &dir, &video, 1.0)
+            .await
+            .unwrap_err();
+        assert!(error.starts_with(FFMPEG_NOT_FOUND));
+    }
+
+    #[tokio::test]
+    async fn test_cached_thumbnail_skips_ffmpeg() {
+        let dir = TempDir::new("thumbnail_cached");
+        let video = dir.join("ep1.mkv");
+        std::fs::write(&video, b"").unwrap();
+        let cached = cache_path(&dir, &video, 5.0);
+        std::fs::write(&cached, b"png").unwrap();
+
+        let result = extract_with("playon-no-such-ffmpeg", &dir, &video, 5.0).await;
+        assert_eq!(result, Ok(cached));
+    }
+}
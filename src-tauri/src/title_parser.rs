@@ -6,6 +6,93 @@
 /// APPROACH: Use regex patterns to extract structured data
 use regex::Regex;
 use serde::{Deserialize, Serialize};
+use std::sync::{Arc, Mutex};
+
+lazy_static::lazy_static! {
+    // Fixed patterns, compiled once. Option-dependent patterns (player states,
+    // site names, boilerplate) are cached per `ParserOptions`, see `OptionPatterns`.
+    static ref WHITESPACE_RE: Regex = Regex::new(r"\s+").unwrap();
+    static ref LANGUAGE_RE: Regex = Regex::new(
+        r"(?i)[\[\(]\s*(?:english\s+)?(dub|sub)(?:bed)?\s*[\]\)]|\b(?:english\s+)?(dub|sub)bed\b|\benglish\s+(dub|sub)\b",
+    )
+    .unwrap();
+    static ref SEASON_FOLDER_RE: Regex = Regex::new(
+        r"(?i)(?:\bseason\s*(\d{1,2})\b|\bs(\d{1,2})\b|\b(\d{1,2})(?:st|nd|rd|th)\s+season\b)",
+    )
+    .unwrap();
+    static ref CRUNCHYROLL_RE: Regex =
+        Regex::new(r"(?i)^(.+?)\s+(?:Season\s+(\d{1,2})\s+)?(?:Episode|Ep\.?|E)\s*(\d{1,4})\b")
+            .unwrap();
+    static ref NETFLIX_RE: Regex = Regex::new(
+        r"(?i)^(.+?):\s*(?:(Limited Series)|(?:Season|Part)\s+(\d{1,2}))\s*(?::\s*(.*))?$",
+    )
+    .unwrap();
+    static ref NETFLIX_EPISODE_RE: Regex =
+        Regex::new(r"(?i)^\W*(?:Episode|Ep\.?|E)\s*(\d{1,3})\b").unwrap();
+    // A range end must not run into a resolution ("S01E05 - 1080p")
+    static ref SEASON_EPISODE_RE: Regex = Regex::new(
+        r"(?i)(.+?)\s*[Ss](\d{1,2})\s*[Ee](\d{1,3})(?:\s*-\s*[Ee]?(\d{1,3})(?:[^\dp]|$))?",
+    )
+    .unwrap();
+    // The number has to stand on its own so resolutions like "1920x1080" don't match
+    static ref CROSS_EPISODE_RE: Regex =
+        Regex::new(r"(?i)(.+?)\s+(\d{1,2})[x×](\d{1,3})(?:[^\dp]|$)").unwrap();
+    // The title part may be empty for bare filenames like "Episode 05.mkv"
+    static ref EPISODE_KEYWORD_RE: Regex =
+        Regex::new(r"(?i)(.*?)\s*(?:Episode|Ep\.?)\s*(\d{1,3})").unwrap();
+    // Match: Title - 05 [quality] or Title - 05.mkv
+    static ref DASH_NUMBER_RE: Regex =
+        Regex::new(r"(.+?)\s*-\s*(\d{1,3})(?:\s*[\[\(]|\s*\.|\s*$)").unwrap();
+    // Match: 05 - Title.mkv. At most 3 digits, so years and "1080" are out
+    static ref LEADING_NUMBER_RE: Regex =
+        Regex::new(r"^\s*(\d{1,3})\s*-\s*(.*\p{L}.*)$").unwrap();
+    // Digits must be followed by a non-digit so CRC-like runs aren't cut short
+    static ref NUMBER_SIGN_RE: Regex =
+        Regex::new(r"(?i)(.+?)\s*(?:#|\bNo\.?)\s*(\d{1,4})(?:\D|$)").unwrap();
+    static ref SUBGROUP_RE: Regex = Regex::new(r"^\s*\[[^\]]+\]\s*").unwrap();
+    static ref QUALITY_RE: Regex = Regex::new(r"[\[\(]\s*(?:\d{3,4}p|BD|HEVC|x264|x265|AAC|FLAC|10bit|Hi10P|WEB-DL|WEB|BDRip|BluRay)\s*[\]\)]").unwrap();
+    static ref HASH_RE: Regex = Regex::new(r"\s*\[[A-Fa-f0-9]{8}\]\s*$").unwrap();
+    static ref BROWSER_SUFFIX_RE: Regex = Regex::new(&format!(
+        r"(?i)\s+[-–—]\s+(?:{})\s*$",
+        BROWSER_SUFFIXES
+            .iter()
+            .map(|b| regex::escape(b))
+            .collect::<Vec<_>>()
+            .join("|")
+    ))
+    .unwrap();
+    static ref OPTION_PATTERNS: Mutex<Vec<(ParserOptions, Arc<OptionPatterns>)>> =
+        Mutex::new(Vec::new());
+}
+
+/// How many distinct `ParserOptions` keep their compiled patterns
+///
+/// The app only ever uses the configured options, so this just keeps a
+/// settings change (or a test with custom options) from thrashing the cache.
+const OPTION_PATTERNS_CAPACITY: usize = 8;
+
+/// Compile the fixed parser patterns now instead of on the first parse
+///
+/// Called at startup so a broken pattern fails right away rather than in the
+/// middle of detection.
+pub fn warm_up() {
+    lazy_static::initialize(&WHITESPACE_RE);
+    lazy_static::initialize(&LANGUAGE_RE);
+    lazy_static::initialize(&SEASON_FOLDER_RE);
+    lazy_static::initialize(&CRUNCHYROLL_RE);
+    lazy_static::initialize(&NETFLIX_RE);
+    lazy_static::initialize(&NETFLIX_EPISODE_RE);
+    lazy_static::initialize(&SEASON_EPISODE_RE);
+    lazy_static::initialize(&CROSS_EPISODE_RE);
+    lazy_static::initialize(&EPISODE_KEYWORD_RE);
+    lazy_static::initialize(&DASH_NUMBER_RE);
+    lazy_static::initialize(&LEADING_NUMBER_RE);
+    lazy_static::initialize(&NUMBER_SIGN_RE);
+    lazy_static::initialize(&SUBGROUP_RE);
+    lazy_static::initialize(&QUALITY_RE);
+    lazy_static::initialize(&HASH_RE);
+    lazy_static::initialize(&BROWSER_SUFFIX_RE);
+}
 
 /// Result of parsing a window title
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
@@ -14,11 +101,108 @@ pub struct ParsedTitle {
     pub title: Option<String>,
     /// Episode number (if detected)
     pub episode: Option<i32>,
+    /// Last episode of a multi-episode file ("S01E01-E03"), None for a single episode
+    pub episode_end: Option<i32>,
     /// Season number (if detected)
     pub season: Option<i32>,
+    /// "DUB" or "SUB" when the title marks the audio/subtitle version
+    pub language: Option<String>,
+    /// How reliable the parse is (0.0 - 1.0), set by the strategy that matched
+    #[serde(default)]
+    pub confidence: f32,
+}
+
+/// Explicit "S02E05" or "2x05" markers
+pub const CONFIDENCE_SEASON_EPISODE: f32 = 0.95;
+/// "[Group] Title - 05", the usual fansub release name
+pub const CONFIDENCE_FANSUB: f32 = 0.9;
+/// "Episode 5" or "Ep 5"
+pub const CONFIDENCE_EPISODE_KEYWORD: f32 = 0.8;
+/// "Title - 05" without a release group
+pub const CONFIDENCE_DASH_NUMBER: f32 = 0.7;
+/// "Title #05" or "Title No.5", also used for issue and part numbers
+pub const CONFIDENCE_NUMBER_SIGN: f32 = 0.5;
+/// A streaming site's own title layout (see `StreamingSite`)
+pub const CONFIDENCE_STREAMING_SITE: f32 = 0.9;
+/// Nothing matched; the whole title was kept and there's no episode
+pub const CONFIDENCE_FALLBACK: f32 = 0.2;
+
+/// Playback-state words some players add to the window title, e.g. "(Paused)"
+pub const DEFAULT_PLAYER_STATE_WORDS: &[&str] = &["Paused", "Playing", "Buffering", "Muted"];
+
+/// Words streaming sites put in front of the show name
+pub const DEFAULT_BROWSER_PREFIXES: &[&str] = &["Watch"];
+
+/// Phrases streaming sites add around the episode
+pub const DEFAULT_BROWSER_BOILERPLATE: &[&str] = &[
+    "English Subbed",
+    "English Dubbed",
+    "English Sub",
+    "English Dub",
+    "Online Free",
+    "Online",
+];
+
+/// Streaming site names that trail browser titles
+pub const DEFAULT_SITE_NAMES: &[&str] = &[
+    "HiAnime",
+    "Crunchyroll",
+    "Netflix",
+    "9anime",
+    "AniWave",
+    "Gogoanime",
+    "Zoro",
+    "YouTube",
+];
+
+/// Words whose dots are part of the name, kept when dots become spaces
+pub const DEFAULT_PRESERVED_DOT_WORDS: &[&str] = &["Re.Zero", "Dr.", "Mr.", "Mrs.", "Ms."];
+
+/// Browser names appended to window titles
+const BROWSER_SUFFIXES: &[&str] = &[
+    "Google Chrome",
+    "Mozilla Firefox",
+    "Firefox",
+    "Microsoft\u{200b} Edge",
+    "Microsoft Edge",
+    "Opera",
+    "Brave",
+];
+
+/// Options that control how window titles are cleaned before parsing
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(default)]
+pub struct ParserOptions {
+    /// Words stripped when wrapped in brackets or parentheses ("[Buffering]", "(Muted)")
+    pub player_state_words: Vec<String>,
+    /// Words stripped from the start of browser titles ("Watch ...")
+    pub browser_prefixes: Vec<String>,
+    /// Phrases stripped anywhere in browser titles ("English Subbed")
+    pub browser_boilerplate: Vec<String>,
+    /// Site names stripped from the end of browser titles ("| HiAnime", "at HiAnime")
+    pub site_names: Vec<String>,
+    /// Words whose dots are kept when file names are split on dots ("Re.Zero")
+    pub preserved_dot_words: Vec<String>,
+}
+
+fn to_strings(words: &[&str]) -> Vec<String> {
+    words.iter().map(|w| w.to_string()).collect()
+}
+
+impl Default for ParserOptions {
+    fn default() -> Self {
+        Self {
+            player_state_words: to_strings(DEFAULT_PLAYER_STATE_WORDS),
+            browser_prefixes: to_strings(DEFAULT_BROWSER_PREFIXES),
+            browser_boilerplate: to_strings(DEFAULT_BROWSER_BOILERPLATE),
+            site_names: to_strings(DEFAULT_SITE_NAMES),
+            preserved_dot_words: to_strings(DEFAULT_PRESERVED_DOT_WORDS),
+        }
+    }
 }
 
 /// Parse anime title and episode from a window title
+/// Uses the parser options from the app config.
 ///
 /// # Arguments
 /// * `window_title` - The window title from a media player
@@ -29,45 +213,250 @@ pub struct ParsedTitle {
 /// # Supported Formats
 /// - `[SubGroup] Anime Title - 05 [1080p].mkv - VLC media player`
 /// - `Anime Title S02E05.mkv - mpv`
+/// - `Anime Title 2x05.mkv`
+/// - `Anime Title #05.mkv` or `Anime Title No.5.mkv`
 /// - `Anime Title Episode 12 - MPC-HC`
 /// - `Anime Title - 05.mp4`
+/// - `05 - Anime Title.mkv`
 /// - `Anime_Title_01.mkv` (underscores as spaces)
 /// - `Anime.Title.01.mkv` (dots as spaces)
+/// - Crunchyroll: `Anime Season 2 Episode 5 - Episode Title - Watch on Crunchyroll`
+/// - Netflix: `Anime: Season 2: Episode Title | Netflix`
 pub fn parse_window_title(window_title: &str) -> ParsedTitle {
-    // First, remove the media player suffix
-    let cleaned = remove_player_suffix(window_title);
+    parse_window_title_with(window_title, &crate::config::current().parser)
+}
+
+/// Parse many titles at once, in input order
+///
+/// Reads the parser options once instead of per title, for listing a folder.
+pub fn parse_titles_batch(titles: &[String]) -> Vec<ParsedTitle> {
+    let options = crate::config::current().parser;
+    titles
+        .iter()
+        .map(|title| parse_window_title_with(title, &options))
+        .collect()
+}
+
+/// Parse a window title using custom parser options
+///
+/// See `parse_window_title` for the supported formats.
+pub fn parse_window_title_with(window_title: &str, options: &ParserOptions) -> ParsedTitle {
+    explain_parse_with(window_title, options).result
+}
+
+/// Every intermediate step of parsing one title, for debugging misparses
+#[derive(Debug, Clone, Serialize)]
+pub struct ParseExplanation {
+    pub input: String,
+    /// After removing player states like "(Paused)"
+    pub canonical: String,
+    /// After removing site boilerplate and the player suffix
+    pub cleaned: String,
+    /// After turning `_` and `.` into spaces, what the strategies see
+    pub normalized: String,
+    /// Streaming site the title was recognized as, if any
+    pub site: Option<String>,
+    /// Name of the strategy that produced `result` ("fallback" if none did)
+    pub strategy: &'static str,
+    pub result: ParsedTitle,
+}
+
+/// Parse a title and report every step along the way
+///
+/// Uses the parser options from the app config, like `parse_window_title`.
+pub fn explain_parse(window_title: &str) -> ParseExplanation {
+    explain_parse_with(window_title, &crate::config::current().parser)
+}
+
+/// `explain_parse` with custom parser options
+pub fn explain_parse_with(window_title: &str, options: &ParserOptions) -> ParseExplanation {
+    // Canonicalize first so "(Paused)" and "(Playing)" variants parse the same
+    let canonical = canonicalize_window_title(window_title, options);
+
+    // Take out "(Dub)" / "[Sub]" markers before they end up in the title
+    let (language, canonical) = extract_language(&canonical);
+
+    // Remove streaming site boilerplate, then the media player suffix
+    let cleaned = remove_player_suffix(&clean_browser_title(&canonical, options));
 
     // Normalize separators: treat _ and . as spaces (common in filenames)
-    let normalized = normalize_separators(&cleaned);
+    let normalized = normalize_separators(&cleaned, options);
+
+    // Sites with a fixed layout get their own parser, the rest the generic one
+    let site = detect_site(&canonical);
+    let (strategy, parsed) = site
+        .and_then(|site| Some((site.name(), try_parse_site(site, &normalized)?)))
+        .unwrap_or_else(|| parse_normalized(&normalized));
 
-    // Try different parsing strategies
-    if let Some(result) = try_parse_season_episode(&normalized) {
-        return result;
+    ParseExplanation {
+        input: window_title.to_string(),
+        canonical,
+        cleaned,
+        normalized,
+        site: site.map(|site| site.name().to_string()),
+        strategy,
+        result: ParsedTitle { language, ..parsed },
     }
+}
+
+/// A parsing strategy, returning None when the title doesn't fit its format
+type Strategy = fn(&str) -> Option<ParsedTitle>;
+
+/// Generic strategies with their names, tried in order
+const STRATEGIES: &[(&str, Strategy)] = &[
+    ("season_episode", try_parse_season_episode),
+    ("episode_keyword", try_parse_episode_keyword),
+    ("dash_number", try_parse_dash_number),
+    ("leading_number", try_parse_leading_number),
+    ("number_sign", try_parse_number_sign),
+    ("bracketed", try_parse_bracketed),
+];
 
-    if let Some(result) = try_parse_episode_keyword(&normalized) {
-        return result;
+/// Run the parsing strategies on a cleaned, normalized title
+///
+/// Returns the name of the strategy that matched with its result.
+fn parse_normalized(normalized: &str) -> (&'static str, ParsedTitle) {
+    for (name, strategy) in STRATEGIES {
+        if let Some(result) = strategy(normalized) {
+            return (name, result);
+        }
     }
 
-    if let Some(result) = try_parse_dash_number(&normalized) {
-        return result;
+    // Fallback: just clean the title
+    (
+        "fallback",
+        ParsedTitle {
+            title: Some(clean_title(normalized)),
+            episode: None,
+            episode_end: None,
+            season: None,
+            language: None,
+            confidence: CONFIDENCE_FALLBACK,
+        },
+    )
+}
+
+/// Find a dub/sub marker and remove it from the title
+///
+/// Recognizes "(Dub)", "[Sub]", "English Dub", "Subbed" and the like.
+/// Returns "DUB" or "SUB" with the title minus the marker.
+fn extract_language(title: &str) -> (Option<String>, String) {
+    let Some(caps) = LANGUAGE_RE.captures(title) else {
+        return (None, title.to_string());
+    };
+
+    let language = (1..=3)
+        .find_map(|i| caps.get(i))
+        .map(|m| m.as_str().to_uppercase());
+    let without = LANGUAGE_RE.replace_all(title, " ");
+    let without = without.split_whitespace().collect::<Vec<_>>().join(" ");
+    (language, without)
+}
+
+/// Parse anime info from a local file path
+///
+/// Parses the file name like a window title, then fills in context from the
+/// parent folders: the season from folders like "Season 2" or "S2", and the
+/// series title when the file name alone doesn't carry one ("Episode 05.mkv").
+///
+/// # Arguments
+/// * `path` - Full path to a video file (`/` or `\` separated)
+pub fn parse_file_path(path: &str) -> ParsedTitle {
+    let segments: Vec<&str> = path
+        .split(['/', '\\'])
+        .filter(|s| !s.trim().is_empty())
+        .collect();
+    let file_name = segments.last().copied().unwrap_or(path);
+
+    let mut parsed = parse_window_title(file_name);
+
+    if parsed.season.is_none() {
+        parsed.season = parse_season_from_path(path);
     }
 
-    if let Some(result) = try_parse_bracketed(&normalized) {
-        return result;
+    // No usable title in the file name - use the closest non-season folder
+    if parsed.title.as_deref().is_none_or(str::is_empty) {
+        let options = crate::config::current().parser;
+        let folders = &segments[..segments.len().saturating_sub(1)];
+        parsed.title = folders
+            .iter()
+            .rev()
+            .find(|folder| season_from_folder(folder).is_none())
+            .map(|folder| clean_title(&normalize_separators(folder, &options)))
+            .filter(|t| !t.is_empty());
     }
 
-    // Fallback: just clean the title
-    ParsedTitle {
-        title: Some(clean_title(&normalized)),
-        episode: None,
-        season: None,
+    parsed
+}
+
+/// Extract a season number from the parent folders of a file path
+///
+/// Checks folders from the closest to the furthest, so
+/// `/Anime/Season 2/Episode 05.mkv` returns `Some(2)`.
+///
+/// # Supported Folder Names
+/// - `Season 2`, `Season 02`
+/// - `S2`, `S02`
+/// - `2nd Season`
+pub fn parse_season_from_path(path: &str) -> Option<i32> {
+    let segments: Vec<&str> = path
+        .split(['/', '\\'])
+        .filter(|s| !s.trim().is_empty())
+        .collect();
+
+    // Skip the file name itself, only folders give season context
+    let folders = &segments[..segments.len().saturating_sub(1)];
+    folders
+        .iter()
+        .rev()
+        .find_map(|folder| season_from_folder(folder))
+}
+
+/// Try to read a season number from a single folder name
+fn season_from_folder(folder: &str) -> Option<i32> {
+    let caps = SEASON_FOLDER_RE.captures(folder)?;
+    let season = caps
+        .get(1)
+        .or_else(|| caps.get(2))
+        .or_else(|| caps.get(3))?;
+    season.as_str().parse().ok()
+}
+
+/// Build the AniList search query for a parsed title
+///
+/// Seasons after the first are appended ("Title Season 2") so the search
+/// prefers the sequel entry over the first season.
+pub fn build_search_query(parsed: &ParsedTitle) -> Option<String> {
+    let title = parsed.title.as_ref()?;
+    match parsed.season {
+        Some(season) if season > 1 => Some(format!("{} Season {}", title, season)),
+        _ => Some(title.clone()),
+    }
+}
+
+/// Canonicalize a window title by removing live player-state tags
+///
+/// Players like VLC and PotPlayer append states such as "(Paused)" or
+/// "[Buffering]" that change while the same episode keeps playing.
+/// Removing them keeps the title stable across polls.
+pub fn canonicalize_window_title(title: &str, options: &ParserOptions) -> String {
+    let patterns = option_patterns(options);
+
+    let mut result = title.to_string();
+    if let Some(state_re) = &patterns.player_state {
+        result = state_re.replace_all(&result, "").to_string();
     }
+
+    // Collapse whitespace left behind by removed tags
+    WHITESPACE_RE.replace_all(&result, " ").trim().to_string()
 }
 
 /// Normalize common filename separators to spaces
 /// Converts underscores and dots to spaces (except dots in file extensions)
-fn normalize_separators(title: &str) -> String {
+///
+/// Dots in decimal numbers ("12.5") and inside `preserved_dot_words`
+/// ("Re.Zero") are kept.
+fn normalize_separators(title: &str, options: &ParserOptions) -> String {
     let mut result = title.to_string();
 
     // First, protect file extensions by temporarily replacing them
@@ -87,11 +476,26 @@ fn normalize_separators(title: &str) -> String {
     result = result.replace('_', " ");
 
     // Replace dots with spaces (these are likely word separators in filenames)
-    result = result.replace('.', " ");
+    let preserved = preserved_ranges(&result, &options.preserved_dot_words);
+    let chars: Vec<(usize, char)> = result.char_indices().collect();
+    result = chars
+        .iter()
+        .enumerate()
+        .map(|(i, &(pos, c))| {
+            if c != '.' {
+                return c;
+            }
+            let is_preserved = preserved.iter().any(|range| range.contains(&pos));
+            if is_decimal_point(&chars, i) || is_preserved {
+                '.'
+            } else {
+                ' '
+            }
+        })
+        .collect();
 
     // Clean up multiple spaces
-    let space_re = Regex::new(r"\s+").unwrap();
-    result = space_re.replace_all(&result, " ").to_string();
+    result = WHITESPACE_RE.replace_all(&result, " ").to_string();
 
     // Add extension back (will be removed by clean_title later)
     result.push_str(&ext_found);
@@ -99,66 +503,329 @@ fn normalize_separators(title: &str) -> String {
     result.trim().to_string()
 }
 
+/// Whether the dot at `i` joins two standalone numbers, like "12.5"
+///
+/// Digits glued to letters don't count, so "S01E05.1080p" still splits.
+fn is_decimal_point(chars: &[(usize, char)], i: usize) -> bool {
+    let digits_before = chars[..i]
+        .iter()
+        .rev()
+        .take_while(|(_, c)| c.is_ascii_digit())
+        .count();
+    let digits_after = chars[i + 1..]
+        .iter()
+        .take_while(|(_, c)| c.is_ascii_digit())
+        .count();
+    let standalone = |c: Option<&(usize, char)>| c.is_none_or(|(_, c)| !c.is_alphanumeric());
+
+    digits_before > 0
+        && digits_after > 0
+        && standalone(
+            (i - digits_before)
+                .checked_sub(1)
+                .and_then(|j| chars.get(j)),
+        )
+        && standalone(chars.get(i + digits_after + 1))
+}
+
+/// Byte ranges where `words` appear in `title` as whole words, ignoring case
+fn preserved_ranges(title: &str, words: &[String]) -> Vec<std::ops::Range<usize>> {
+    // ASCII lowercasing keeps byte offsets the same
+    let lower = title.to_ascii_lowercase();
+    let is_word_char = |c: Option<char>| c.is_some_and(char::is_alphanumeric);
+
+    let mut ranges = Vec::new();
+    for word in words.iter().map(|w| w.trim()).filter(|w| !w.is_empty()) {
+        let needle = word.to_ascii_lowercase();
+        for (start, _) in lower.match_indices(&needle) {
+            let end = start + needle.len();
+            let starts_word = !is_word_char(title[..start].chars().next_back());
+            // "Dr." ends the word itself; "Re.Zero" mustn't run into more letters
+            let ends_word = !needle.ends_with(char::is_alphanumeric)
+                || !is_word_char(title[end..].chars().next());
+            if starts_word && ends_word {
+                ranges.push(start..end);
+            }
+        }
+    }
+    ranges
+}
+
+/// Patterns built from the user-editable word lists in `ParserOptions`
+///
+/// `None` when the list is empty (or the pattern didn't compile), in which
+/// case that cleanup step is skipped.
+struct OptionPatterns {
+    player_state: Option<Regex>,
+    site: Option<Regex>,
+    prefix: Option<Regex>,
+    boilerplate: Option<Regex>,
+}
+
+impl OptionPatterns {
+    fn new(options: &ParserOptions) -> Self {
+        let words: Vec<String> = options
+            .player_state_words
+            .iter()
+            .map(|w| w.trim())
+            .filter(|w| !w.is_empty())
+            .map(regex::escape)
+            .collect();
+        let player_state = if words.is_empty() {
+            None
+        } else {
+            let pattern = format!(r"(?i)\s*[\[\(]\s*(?:{})\s*[\]\)]", words.join("|"));
+            Regex::new(&pattern).ok()
+        };
+
+        let site = phrase_alternation(&options.site_names).and_then(|sites| {
+            Regex::new(&format!(r"(?i)\s+(?:[-–—|]|at|on)\s+(?:{})\s*$", sites)).ok()
+        });
+        let prefix = phrase_alternation(&options.browser_prefixes)
+            .and_then(|prefixes| Regex::new(&format!(r"(?i)^\s*(?:{})\s+", prefixes)).ok());
+        let boilerplate = phrase_alternation(&options.browser_boilerplate)
+            .and_then(|phrases| Regex::new(&format!(r"(?i)\b(?:{})\b", phrases)).ok());
+
+        Self {
+            player_state,
+            site,
+            prefix,
+            boilerplate,
+        }
+    }
+}
+
+/// Get the compiled patterns for `options`, compiling them on first use
+fn option_patterns(options: &ParserOptions) -> Arc<OptionPatterns> {
+    let mut cache = OPTION_PATTERNS.lock().unwrap_or_else(|e| e.into_inner());
+    if let Some((_, patterns)) = cache.iter().find(|(cached, _)| cached == options) {
+        return patterns.clone();
+    }
+
+    let patterns = Arc::new(OptionPatterns::new(options));
+    if cache.len() >= OPTION_PATTERNS_CAPACITY {
+        cache.remove(0);
+    }
+    cache.push((options.clone(), patterns.clone()));
+    patterns
+}
+
+/// Build a case-insensitive alternation of whole phrases, longest first
+fn phrase_alternation(phrases: &[String]) -> Option<String> {
+    let mut phrases: Vec<&str> = phrases
+        .iter()
+        .map(|p| p.trim())
+        .filter(|p| !p.is_empty())
+        .collect();
+    if phrases.is_empty() {
+        return None;
+    }
+    // Longest first so "English Subbed" wins over "English Sub"
+    phrases.sort_by_key(|p| std::cmp::Reverse(p.len()));
+    let escaped: Vec<String> = phrases.into_iter().map(regex::escape).collect();
+    Some(escaped.join("|"))
+}
+
+/// Remove streaming site boilerplate from a browser window title
+///
+/// Strips, in order: the browser name ("- Google Chrome"), the page segment
+/// after the last " | ", trailing site names ("at HiAnime", "- Crunchyroll"),
+/// leading words like "Watch", and phrases like "English Subbed".
+pub fn clean_browser_title(title: &str, options: &ParserOptions) -> String {
+    let patterns = option_patterns(options);
+    let mut result = BROWSER_SUFFIX_RE.replace(title.trim(), "").to_string();
+
+    // Sites put their name after a pipe ("Show Episode 5 | HiAnime")
+    if let Some(pos) = result.rfind(" | ") {
+        result.truncate(pos);
+    }
+
+    if let Some(site_re) = &patterns.site {
+        result = site_re.replace(&result, "").to_string();
+    }
+
+    if let Some(prefix_re) = &patterns.prefix {
+        result = prefix_re.replace(&result, "").to_string();
+    }
+
+    if let Some(phrase_re) = &patterns.boilerplate {
+        result = phrase_re.replace_all(&result, "").to_string();
+    }
+
+    WHITESPACE_RE.replace_all(&result, " ").trim().to_string()
+}
+
+/// Media player names appended to window titles
+const PLAYER_SUFFIXES: &[&str] = &[
+    " - VLC media player",
+    " - VLC",
+    " - mpv",
+    " - MPC-HC",
+    " - MPC-BE",
+    " - Media Player Classic",
+    " - Windows Media Player",
+    " - PotPlayer",
+    " – VLC media player", // en-dash variant
+];
+
 /// Remove common media player suffixes from window title
+///
+/// Only a suffix at the very end counts, so a player name inside the title
+/// ("Anime - mpv tricks.mkv - VLC media player") is kept. The longest match
+/// wins, so "- VLC media player" is removed whole rather than as "- VLC".
 fn remove_player_suffix(title: &str) -> String {
-    let suffixes = [
-        " - VLC media player",
-        " - VLC",
-        " - mpv",
-        " - MPC-HC",
-        " - MPC-BE",
-        " - Media Player Classic",
-        " - Windows Media Player",
-        " - PotPlayer",
-        " – VLC media player", // en-dash variant
-    ];
+    let title = title.trim_end();
+    let suffix_len = PLAYER_SUFFIXES
+        .iter()
+        .filter(|suffix| {
+            title.len() >= suffix.len()
+                && title.is_char_boundary(title.len() - suffix.len())
+                && title[title.len() - suffix.len()..].eq_ignore_ascii_case(suffix)
+        })
+        .map(|suffix| suffix.len())
+        .max()
+        .unwrap_or(0);
+    title[..title.len() - suffix_len].trim().to_string()
+}
 
-    let mut result = title.to_string();
-    for suffix in &suffixes {
-        if let Some(pos) = result.to_lowercase().rfind(&suffix.to_lowercase()) {
-            result = result[..pos].to_string();
-            break;
+/// Streaming sites whose browser titles have a known layout
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StreamingSite {
+    /// "Anime Season 2 Episode 5 - Episode Title - Watch on Crunchyroll"
+    Crunchyroll,
+    /// "Anime: Season 2: Episode Title | Netflix", "Anime: Limited Series: ..."
+    Netflix,
+}
+
+impl StreamingSite {
+    /// Lowercase name, as shown in `ParseExplanation`
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Crunchyroll => "crunchyroll",
+            Self::Netflix => "netflix",
         }
     }
-    result.trim().to_string()
 }
 
-/// Try to parse S##E## format (e.g., "Anime S02E05")
+/// Which streaming site a browser title comes from, if it names one
+pub fn detect_site(title: &str) -> Option<StreamingSite> {
+    let lower = title.to_lowercase();
+    if lower.contains("crunchyroll") {
+        Some(StreamingSite::Crunchyroll)
+    } else if lower.contains("netflix") {
+        Some(StreamingSite::Netflix)
+    } else {
+        None
+    }
+}
+
+/// Parse a cleaned browser title with the site's own layout
+///
+/// Returns None when the title doesn't follow it, so generic parsing runs instead.
+fn try_parse_site(site: StreamingSite, title: &str) -> Option<ParsedTitle> {
+    match site {
+        StreamingSite::Crunchyroll => try_parse_crunchyroll(title),
+        StreamingSite::Netflix => try_parse_netflix(title),
+    }
+}
+
+/// "Anime Season 2 Episode 5 - Episode Title", the season being optional
+fn try_parse_crunchyroll(title: &str) -> Option<ParsedTitle> {
+    let caps = CRUNCHYROLL_RE.captures(title)?;
+
+    let anime_title = clean_title(caps.get(1)?.as_str());
+    let season = caps.get(2).and_then(|m| m.as_str().parse().ok());
+    let episode: i32 = caps.get(3)?.as_str().parse().ok()?;
+
+    Some(ParsedTitle {
+        title: Some(anime_title),
+        episode: Some(episode),
+        episode_end: None,
+        season,
+        language: None,
+        confidence: CONFIDENCE_STREAMING_SITE,
+    })
+}
+
+/// "Anime: Season 2: Episode Title" or "Anime: Limited Series: Episode Title"
+///
+/// Netflix rarely puts the episode number in the title; it's taken when the
+/// episode segment starts with one ("Episode 3", "E3").
+fn try_parse_netflix(title: &str) -> Option<ParsedTitle> {
+    let caps = NETFLIX_RE.captures(title)?;
+
+    let anime_title = clean_title(caps.get(1)?.as_str());
+    let season = if caps.get(2).is_some() {
+        Some(1)
+    } else {
+        caps.get(3).and_then(|m| m.as_str().parse().ok())
+    };
+
+    let episode = caps
+        .get(4)
+        .and_then(|m| NETFLIX_EPISODE_RE.captures(m.as_str()))
+        .and_then(|c| c[1].parse().ok());
+
+    Some(ParsedTitle {
+        title: Some(anime_title),
+        episode,
+        episode_end: None,
+        season,
+        language: None,
+        confidence: CONFIDENCE_STREAMING_SITE,
+    })
+}
+
+/// Try to parse S##E## format (e.g., "Anime S02E05") or #x## (e.g., "Anime 1x05")
+///
+/// Also takes ranges like "S01E01-E03" or "S01E01-03", filling `episode_end`.
 fn try_parse_season_episode(title: &str) -> Option<ParsedTitle> {
-    let re = Regex::new(r"(?i)(.+?)\s*[Ss](\d{1,2})\s*[Ee](\d{1,3})").ok()?;
-    let caps = re.captures(title)?;
+    let caps = SEASON_EPISODE_RE
+        .captures(title)
+        .or_else(|| CROSS_EPISODE_RE.captures(title))?;
 
     let anime_title = clean_title(caps.get(1)?.as_str());
     let season: i32 = caps.get(2)?.as_str().parse().ok()?;
     let episode: i32 = caps.get(3)?.as_str().parse().ok()?;
+    let episode_end = caps
+        .get(4)
+        .and_then(|m| m.as_str().parse::<i32>().ok())
+        .filter(|end| *end > episode);
 
     Some(ParsedTitle {
         title: Some(anime_title),
         episode: Some(episode),
+        episode_end,
         season: Some(season),
+        language: None,
+        confidence: CONFIDENCE_SEASON_EPISODE,
     })
 }
 
 /// Try to parse "Episode ##" or "Ep ##" format
 fn try_parse_episode_keyword(title: &str) -> Option<ParsedTitle> {
-    let re = Regex::new(r"(?i)(.+?)\s*(?:Episode|Ep\.?)\s*(\d{1,3})").ok()?;
-    let caps = re.captures(title)?;
+    let caps = EPISODE_KEYWORD_RE.captures(title)?;
 
     let anime_title = clean_title(caps.get(1)?.as_str());
     let episode: i32 = caps.get(2)?.as_str().parse().ok()?;
 
     Some(ParsedTitle {
-        title: Some(anime_title),
+        title: if anime_title.is_empty() {
+            None
+        } else {
+            Some(anime_title)
+        },
         episode: Some(episode),
+        episode_end: None,
         season: None,
+        language: None,
+        confidence: CONFIDENCE_EPISODE_KEYWORD,
     })
 }
 
 /// Try to parse "Anime - ## " format (common in fansubs)
 fn try_parse_dash_number(title: &str) -> Option<ParsedTitle> {
-    // Match: Title - 05 [quality] or Title - 05.mkv
-    let re = Regex::new(r"(.+?)\s*-\s*(\d{1,3})(?:\s*[\[\(]|\s*\.|\s*$)").ok()?;
-    let caps = re.captures(title)?;
+    let caps = DASH_NUMBER_RE.captures(title)?;
 
     let anime_title = clean_title(caps.get(1)?.as_str());
     let episode: i32 = caps.get(2)?.as_str().parse().ok()?;
@@ -166,18 +833,76 @@ fn try_parse_dash_number(title: &str) -> Option<ParsedTitle> {
     Some(ParsedTitle {
         title: Some(anime_title),
         episode: Some(episode),
+        episode_end: None,
         season: None,
+        language: None,
+        confidence: if has_release_group(title) {
+            CONFIDENCE_FANSUB
+        } else {
+            CONFIDENCE_DASH_NUMBER
+        },
+    })
+}
+
+/// Resolutions short enough to pass for an episode number ("720 - Title")
+const RESOLUTION_HEIGHTS: &[i32] = &[360, 480, 540, 576, 720];
+
+/// Try to parse "05 - Anime" format, episode first
+fn try_parse_leading_number(title: &str) -> Option<ParsedTitle> {
+    let caps = LEADING_NUMBER_RE.captures(title)?;
+
+    let episode: i32 = caps.get(1)?.as_str().parse().ok()?;
+    if RESOLUTION_HEIGHTS.contains(&episode) {
+        return None;
+    }
+    let anime_title = clean_title(caps.get(2)?.as_str());
+    if anime_title.is_empty() {
+        return None;
+    }
+
+    Some(ParsedTitle {
+        title: Some(anime_title),
+        episode: Some(episode),
+        episode_end: None,
+        season: None,
+        language: None,
+        confidence: CONFIDENCE_DASH_NUMBER,
+    })
+}
+
+/// Try to parse "Anime #05" or "Anime No.5" format
+fn try_parse_number_sign(title: &str) -> Option<ParsedTitle> {
+    let caps = NUMBER_SIGN_RE.captures(title)?;
+
+    let anime_title = clean_title(caps.get(1)?.as_str());
+    let episode: i32 = caps.get(2)?.as_str().parse().ok()?;
+
+    Some(ParsedTitle {
+        title: Some(anime_title),
+        episode: Some(episode),
+        episode_end: None,
+        season: None,
+        language: None,
+        confidence: CONFIDENCE_NUMBER_SIGN,
     })
 }
 
 /// Try to parse [SubGroup] Title - ## [quality] format
 fn try_parse_bracketed(title: &str) -> Option<ParsedTitle> {
     // First, remove leading [SubGroup] tag
-    let re_subgroup = Regex::new(r"^\s*\[[^\]]+\]\s*").ok()?;
-    let without_subgroup = re_subgroup.replace(title, "").to_string();
+    let without_subgroup = SUBGROUP_RE.replace(title, "").to_string();
 
     // Now try dash-number parsing on the cleaned string
-    try_parse_dash_number(&without_subgroup)
+    let parsed = try_parse_dash_number(&without_subgroup)?;
+    Some(ParsedTitle {
+        confidence: CONFIDENCE_FANSUB,
+        ..parsed
+    })
+}
+
+/// Whether a title starts with a "[Group]" release tag
+fn has_release_group(title: &str) -> bool {
+    SUBGROUP_RE.is_match(title)
 }
 
 /// Clean up a title string by removing common noise
@@ -193,16 +918,13 @@ fn clean_title(title: &str) -> String {
     }
 
     // Remove quality tags like [1080p], (720p), [BD], etc.
-    let quality_re = Regex::new(r"[\[\(]\s*(?:\d{3,4}p|BD|HEVC|x264|x265|AAC|FLAC|10bit|Hi10P|WEB-DL|WEB|BDRip|BluRay)\s*[\]\)]").unwrap();
-    result = quality_re.replace_all(&result, "").to_string();
+    result = QUALITY_RE.replace_all(&result, "").to_string();
 
     // Remove subgroup tags at the start
-    let subgroup_re = Regex::new(r"^\s*\[[^\]]+\]\s*").unwrap();
-    result = subgroup_re.replace(&result, "").to_string();
+    result = SUBGROUP_RE.replace(&result, "").to_string();
 
     // Remove hash tags at the end like [ABCD1234]
-    let hash_re = Regex::new(r"\s*\[[A-Fa-f0-9]{8}\]\s*$").unwrap();
-    result = hash_re.replace(&result, "").to_string();
+    result = HASH_RE.replace(&result, "").to_string();
 
     // Clean up extra whitespace and dashes
     result = result.trim().to_string();
@@ -237,6 +959,101 @@ mod tests {
         assert_eq!(result.season, Some(5));
     }
 
+    #[test]
+    fn test_cross_season_episode_format() {
+        let result = parse_window_title("Anime 1x05.mkv");
+        assert_eq!(result.title, Some("Anime".to_string()));
+        assert_eq!(result.episode, Some(5));
+        assert_eq!(result.season, Some(1));
+
+        let result = parse_window_title("Anime 2×05.mkv");
+        assert_eq!(result.episode, Some(5));
+        assert_eq!(result.season, Some(2));
+    }
+
+    #[test]
+    fn test_resolution_is_not_season_episode() {
+        let result = parse_window_title("[SubsPlease] Anime - 03 (1920x1080).mkv");
+        assert_eq!(result.title, Some("Anime".to_string()));
+        assert_eq!(result.episode, Some(3));
+        assert_eq!(result.season, None);
+
+        let result = parse_window_title("Anime 1280x720.mkv");
+        assert_eq!(result.season, None);
+    }
+
+    #[test]
+    fn test_number_sign_format() {
+        let result = parse_window_title("[Grp] Anime #05.mkv");
+        assert_eq!(result.title, Some("Anime".to_string()));
+        assert_eq!(result.episode, Some(5));
+
+        let result = parse_window_title("Anime No.12.mkv");
+        assert_eq!(result.title, Some("Anime".to_string()));
+        assert_eq!(result.episode, Some(12));
+
+        // "no" as a word in the title isn't an episode marker
+        let result = parse_window_title("Sousou no Frieren #03 [ABCD1234].mkv");
+        assert_eq!(result.title, Some("Sousou no Frieren".to_string()));
+        assert_eq!(result.episode, Some(3));
+    }
+
+    #[test]
+    fn test_parse_titles_batch_keeps_order() {
+        let titles: Vec<String> = [
+            "[SubsPlease] Sousou no Frieren - 05 (1080p).mkv",
+            "My Hero Academia S05E12.mp4",
+            "Attack on Titan Episode 25",
+            "Anime 1x05.mkv",
+            "Just A Movie.mkv",
+        ]
+        .iter()
+        .map(|t| t.to_string())
+        .collect();
+
+        let parsed = parse_titles_batch(&titles);
+        let summary: Vec<(Option<&str>, Option<i32>, Option<i32>)> = parsed
+            .iter()
+            .map(|p| (p.title.as_deref(), p.episode, p.season))
+            .collect();
+        assert_eq!(
+            summary,
+            vec![
+                (Some("Sousou no Frieren"), Some(5), None),
+                (Some("My Hero Academia"), Some(12), Some(5)),
+                (Some("Attack on Titan"), Some(25), None),
+                (Some("Anime"), Some(5), Some(1)),
+                (Some("Just A Movie"), None, None),
+            ]
+        );
+        assert!(parse_titles_batch(&[]).is_empty());
+    }
+
+    #[test]
+    fn test_batch_parse_compiles_patterns_once() {
+        let options = ParserOptions {
+            site_names: vec!["BatchSite".to_string()],
+            ..Default::default()
+        };
+        let titles: Vec<String> = (1..=1000)
+            .map(|n| {
+                format!(
+                    "Watch Frieren Episode {} English Sub - BatchSite - Google Chrome",
+                    n % 100 + 1
+                )
+            })
+            .collect();
+
+        let before = option_patterns(&options);
+        for (n, title) in (1..=1000).zip(&titles) {
+            let parsed = parse_window_title_with(title, &options);
+            assert_eq!(parsed.title.as_deref(), Some("Frieren"));
+            assert_eq!(parsed.episode, Some(n % 100 + 1));
+        }
+        // Still the same compiled patterns, nothing was rebuilt along the way
+        assert!(Arc::ptr_eq(&before, &option_patterns(&options)));
+    }
+
     #[test]
     fn test_episode_keyword() {
         let result = parse_window_title("Attack on Titan Episode 25 - MPC-HC");
@@ -258,11 +1075,412 @@ mod tests {
         // Episode may or may not be detected depending on title format
     }
 
+    #[test]
+    fn test_season_from_folder_path() {
+        let result = parse_file_path("/Anime/Season 2/Episode 05.mkv");
+        assert_eq!(result.title, Some("Anime".to_string()));
+        assert_eq!(result.episode, Some(5));
+        assert_eq!(result.season, Some(2));
+    }
+
+    #[test]
+    fn test_parse_season_from_path() {
+        assert_eq!(
+            parse_season_from_path("/Anime/Season 2/Episode 05.mkv"),
+            Some(2)
+        );
+        assert_eq!(parse_season_from_path("D:\\Anime\\S02\\05.mkv"), Some(2));
+        assert_eq!(parse_season_from_path("/Anime/2nd Season/05.mkv"), Some(2));
+        assert_eq!(parse_season_from_path("/Anime/Frieren - 05.mkv"), None);
+        // Season info in the file name itself is handled by the parser
+        assert_eq!(parse_season_from_path("/Anime/Show S03E01.mkv"), None);
+    }
+
+    #[test]
+    fn test_build_search_query_with_season() {
+        let parsed = parse_file_path("/Jujutsu Kaisen/Season 2/Jujutsu Kaisen - 05.mkv");
+        assert_eq!(
+            build_search_query(&parsed),
+            Some("Jujutsu Kaisen Season 2".to_string())
+        );
+
+        let first_season = parse_file_path("/Frieren/Season 1/Frieren - 05.mkv");
+        assert_eq!(
+            build_search_query(&first_season),
+            Some("Frieren".to_string())
+        );
+    }
+
+    #[test]
+    fn test_strips_player_state() {
+        let result = parse_window_title("Anime - 05 (Paused) - VLC media player");
+        assert_eq!(result.title, Some("Anime".to_string()));
+        assert_eq!(result.episode, Some(5));
+
+        let playing = parse_window_title("Anime - 05 [Buffering] - VLC media player");
+        assert_eq!(playing, result);
+    }
+
+    #[test]
+    fn test_custom_player_state_words() {
+        let options = ParserOptions {
+            player_state_words: vec!["Pausiert".to_string()],
+            ..Default::default()
+        };
+        assert_eq!(
+            canonicalize_window_title("Anime - 05 (Pausiert) - VLC", &options),
+            "Anime - 05 - VLC"
+        );
+        // Words outside the configured list are kept
+        assert_eq!(
+            canonicalize_window_title("Anime - 05 (Paused) - VLC", &options),
+            "Anime - 05 (Paused) - VLC"
+        );
+    }
+
+    #[test]
+    fn test_clean_browser_title() {
+        let options = ParserOptions::default();
+        assert_eq!(
+            clean_browser_title(
+                "Watch Frieren Episode 5 English Subbed | HiAnime - Google Chrome",
+                &options
+            ),
+            "Frieren Episode 5"
+        );
+        assert_eq!(
+            clean_browser_title(
+                "Chitose Is In The Ramune Bottle Episode 1 English Sub at Hianime - Google Chrome",
+                &options
+            ),
+            "Chitose Is In The Ramune Bottle Episode 1"
+        );
+        // Dashes that aren't followed by a known site are kept
+        assert_eq!(
+            clean_browser_title("Frieren - 05 - Mozilla Firefox", &options),
+            "Frieren - 05"
+        );
+    }
+
+    #[test]
+    fn test_browser_title_parses_cleanly() {
+        let result = parse_window_title(
+            "Chitose Is In The Ramune Bottle Episode 1 English Sub at Hianime - Google Chrome",
+        );
+        assert_eq!(
+            result.title,
+            Some("Chitose Is In The Ramune Bottle".to_string())
+        );
+        assert_eq!(result.episode, Some(1));
+    }
+
+    #[test]
+    fn test_custom_site_names() {
+        let options = ParserOptions {
+            site_names: vec!["AnimeSite".to_string()],
+            ..Default::default()
+        };
+        assert_eq!(
+            clean_browser_title("Frieren Episode 5 - AnimeSite", &options),
+            "Frieren Episode 5"
+        );
+        assert_eq!(
+            clean_browser_title("Frieren Episode 5 - HiAnime", &options),
+            "Frieren Episode 5 - HiAnime"
+        );
+    }
+
     #[test]
     fn test_removes_player_suffix() {
         let result = remove_player_suffix("Anime - 01 - VLC media player");
         assert_eq!(result, "Anime - 01");
     }
+
+    #[test]
+    fn test_dots_between_digits_are_kept() {
+        let options = ParserOptions::default();
+        assert_eq!(
+            normalize_separators("Anime.12.5.mkv", &options),
+            "Anime 12.5.mkv"
+        );
+        assert_eq!(
+            normalize_separators("12.5.Anime.mkv", &options),
+            "12.5 Anime.mkv"
+        );
+        assert_eq!(
+            normalize_separators("Anime.2023.1080p.mkv", &options),
+            "Anime 2023 1080p.mkv"
+        );
+        assert_eq!(
+            normalize_separators("Anime.Title.S01E05.1080p.mkv", &options),
+            "Anime Title S01E05 1080p.mkv"
+        );
+    }
+
+    #[test]
+    fn test_preserved_dot_words() {
+        let result = parse_window_title("Re.Zero - 05.mkv");
+        assert_eq!(result.title, Some("Re.Zero".to_string()));
+        assert_eq!(result.episode, Some(5));
+
+        let options = ParserOptions::default();
+        assert_eq!(
+            normalize_separators("re.zero.kara.Hajimeru.S01E05.mkv", &options),
+            "re.zero kara Hajimeru S01E05.mkv"
+        );
+        // Only whole words are preserved
+        assert_eq!(
+            normalize_separators("Pre.Zeroth.mkv", &options),
+            "Pre Zeroth.mkv"
+        );
+
+        let options = ParserOptions {
+            preserved_dot_words: vec!["S.H.E".to_string()],
+            ..Default::default()
+        };
+        assert_eq!(
+            normalize_separators("S.H.E.Episode.3.mkv", &options),
+            "S.H.E Episode 3.mkv"
+        );
+    }
+
+    #[test]
+    fn test_explain_parse_steps() {
+        let options = ParserOptions::default();
+        let explanation = explain_parse_with(
+            "[SubsPlease] Sousou_no_Frieren - 05 (1080p).mkv (Paused) - VLC media player",
+            &options,
+        );
+        assert_eq!(
+            explanation.canonical,
+            "[SubsPlease] Sousou_no_Frieren - 05 (1080p).mkv - VLC media player"
+        );
+        assert_eq!(
+            explanation.cleaned,
+            "[SubsPlease] Sousou_no_Frieren - 05 (1080p).mkv"
+        );
+        assert_eq!(
+            explanation.normalized,
+            "[SubsPlease] Sousou no Frieren - 05 (1080p).mkv"
+        );
+        assert_eq!(explanation.site, None);
+        assert_eq!(explanation.strategy, "dash_number");
+        assert_eq!(
+            explanation.result.title,
+            Some("Sousou no Frieren".to_string())
+        );
+        assert_eq!(explanation.result.episode, Some(5));
+    }
+
+    #[test]
+    fn test_explain_parse_names_site_and_fallback() {
+        let options = ParserOptions::default();
+        let explanation = explain_parse_with(
+            "Frieren Season 2 Episode 5 - Departure - Watch on Crunchyroll",
+            &options,
+        );
+        assert_eq!(explanation.site.as_deref(), Some("crunchyroll"));
+        assert_eq!(explanation.strategy, "crunchyroll");
+
+        assert_eq!(
+            explain_parse_with("Just A Movie.mkv", &options).strategy,
+            "fallback"
+        );
+    }
+
+    #[test]
+    fn test_leading_episode_number() {
+        let result = parse_window_title("05 - Frieren.mkv");
+        assert_eq!(result.title, Some("Frieren".to_string()));
+        assert_eq!(result.episode, Some(5));
+        assert_eq!(result.confidence, CONFIDENCE_DASH_NUMBER);
+
+        let result = parse_window_title("12 - Sousou no Frieren [1080p].mkv - mpv");
+        assert_eq!(result.title, Some("Sousou no Frieren".to_string()));
+        assert_eq!(result.episode, Some(12));
+    }
+
+    #[test]
+    fn test_leading_resolution_or_year_is_not_episode() {
+        for title in [
+            "1080 - something",
+            "720 - something.mkv",
+            "2023 - Frieren.mkv",
+        ] {
+            let result = parse_window_title(title);
+            assert_eq!(result.episode, None, "{}", title);
+            assert_eq!(result.confidence, CONFIDENCE_FALLBACK, "{}", title);
+        }
+    }
+
+    #[test]
+    fn test_player_name_mid_title_is_kept() {
+        assert_eq!(
+            remove_player_suffix("Anime - mpv tricks.mkv - VLC media player"),
+            "Anime - mpv tricks.mkv"
+        );
+        assert_eq!(
+            remove_player_suffix("Anime - VLC media player - 05.mkv"),
+            "Anime - VLC media player - 05.mkv"
+        );
+        assert_eq!(remove_player_suffix("mpv tutorial - mpv  "), "mpv tutorial");
+        assert_eq!(remove_player_suffix("Anime - 01 - vlc"), "Anime - 01");
+
+        let result = parse_window_title("Anime - mpv tricks - 05.mkv - PotPlayer");
+        assert_eq!(result.title, Some("Anime - mpv tricks".to_string()));
+        assert_eq!(result.episode, Some(5));
+    }
+
+    #[test]
+    fn test_fixed_patterns_compile() {
+        // Panics if any pattern is invalid
+        warm_up();
+    }
+
+    #[test]
+    fn test_confidence_by_format() {
+        let confidence = |title: &str| parse_window_title(title).confidence;
+
+        assert_eq!(confidence("Frieren S01E05.mkv"), CONFIDENCE_SEASON_EPISODE);
+        assert_eq!(
+            confidence("[SubsPlease] Frieren - 05 (1080p).mkv"),
+            CONFIDENCE_FANSUB
+        );
+        assert_eq!(confidence("Frieren Episode 5"), CONFIDENCE_EPISODE_KEYWORD);
+        assert_eq!(confidence("Frieren - 05.mkv"), CONFIDENCE_DASH_NUMBER);
+        assert_eq!(confidence("Frieren #05.mkv"), CONFIDENCE_NUMBER_SIGN);
+        assert_eq!(confidence("Frieren.mkv"), CONFIDENCE_FALLBACK);
+    }
+
+    #[test]
+    fn test_confidence_ordering() {
+        let titles = [
+            "Frieren S01E05.mkv",
+            "[SubsPlease] Frieren - 05 (1080p).mkv",
+            "Frieren Episode 5",
+            "Frieren - 05.mkv",
+            "Frieren #05.mkv",
+            "Frieren.mkv",
+        ];
+        let confidences: Vec<f32> = titles
+            .iter()
+            .map(|title| parse_window_title(title).confidence)
+            .collect();
+
+        assert!(confidences.windows(2).all(|pair| pair[0] > pair[1]));
+        assert!(confidences.iter().all(|c| (0.0..=1.0).contains(c)));
+    }
+
+    #[test]
+    fn test_episode_range() {
+        for title in [
+            "Frieren S01E01-E03.mkv",
+            "Frieren S01E01-03.mkv",
+            "Frieren S01E01 - E03 [1080p].mkv",
+        ] {
+            let result = parse_window_title(title);
+            assert_eq!(result.title, Some("Frieren".to_string()), "{}", title);
+            assert_eq!(result.season, Some(1));
+            assert_eq!(result.episode, Some(1));
+            assert_eq!(result.episode_end, Some(3), "{}", title);
+        }
+    }
+
+    #[test]
+    fn test_single_episode_has_no_range() {
+        let result = parse_window_title("Frieren S01E05 - 1080p.mkv");
+        assert_eq!(result.episode, Some(5));
+        assert_eq!(result.episode_end, None);
+
+        let result = parse_window_title("Frieren S02E05.mkv - mpv");
+        assert_eq!(result.season, Some(2));
+        assert_eq!(result.episode, Some(5));
+        assert_eq!(result.episode_end, None);
+    }
+
+    #[test]
+    fn test_crunchyroll_titles() {
+        let result = parse_window_title(
+            "Frieren: Beyond Journey's End Season 2 Episode 5 - The Hero's Statue - Watch on Crunchyroll - Google Chrome",
+        );
+        assert_eq!(
+            result.title,
+            Some("Frieren: Beyond Journey's End".to_string())
+        );
+        assert_eq!(result.season, Some(2));
+        assert_eq!(result.episode, Some(5));
+        assert_eq!(result.confidence, CONFIDENCE_STREAMING_SITE);
+
+        let result = parse_window_title(
+            "Watch Dan Da Dan Episode 3 - It's a Space Alien, Isn't It?! - Crunchyroll",
+        );
+        assert_eq!(result.title, Some("Dan Da Dan".to_string()));
+        assert_eq!(result.season, None);
+        assert_eq!(result.episode, Some(3));
+    }
+
+    #[test]
+    fn test_netflix_titles() {
+        let result =
+            parse_window_title("Devilman Crybaby: Limited Series: \"I Need You\" | Netflix");
+        assert_eq!(result.title, Some("Devilman Crybaby".to_string()));
+        assert_eq!(result.season, Some(1));
+        assert_eq!(result.episode, None);
+
+        let result = parse_window_title(
+            "Castlevania: Season 2: Episode 3 \"Old Homes\" - Netflix - Mozilla Firefox",
+        );
+        assert_eq!(result.title, Some("Castlevania".to_string()));
+        assert_eq!(result.season, Some(2));
+        assert_eq!(result.episode, Some(3));
+
+        // Colons in the show name stay part of it
+        let result = parse_window_title("Pluto: Part 1: Episode 2 | Netflix");
+        assert_eq!(result.title, Some("Pluto".to_string()));
+        assert_eq!(result.season, Some(1));
+        assert_eq!(result.episode, Some(2));
+    }
+
+    #[test]
+    fn test_site_layout_falls_back_to_generic() {
+        // Not in Netflix's layout, parsed like any other title
+        let result = parse_window_title("Frieren - 05 | Netflix");
+        assert_eq!(result.title, Some("Frieren".to_string()));
+        assert_eq!(result.episode, Some(5));
+        assert_eq!(result.confidence, CONFIDENCE_DASH_NUMBER);
+    }
+
+    #[test]
+    fn test_dub_marker() {
+        let result = parse_window_title("Anime (Dub) - 05");
+        assert_eq!(result.title, Some("Anime".to_string()));
+        assert_eq!(result.episode, Some(5));
+        assert_eq!(result.language, Some("DUB".to_string()));
+    }
+
+    #[test]
+    fn test_sub_marker() {
+        let result = parse_window_title("[Group] Anime - 05 [Sub]");
+        assert_eq!(result.title, Some("Anime".to_string()));
+        assert_eq!(result.episode, Some(5));
+        assert_eq!(result.language, Some("SUB".to_string()));
+    }
+
+    #[test]
+    fn test_language_phrases() {
+        let result = parse_window_title("Frieren Episode 5 English Dub - Google Chrome");
+        assert_eq!(result.title, Some("Frieren".to_string()));
+        assert_eq!(result.language, Some("DUB".to_string()));
+
+        assert_eq!(
+            parse_window_title("Frieren Subbed - 05").language,
+            Some("SUB".to_string())
+        );
+        // "Sub" inside a word isn't a marker
+        let result = parse_window_title("Subway Surfers - 05");
+        assert_eq!(result.language, None);
+        assert_eq!(result.title, Some("Subway Surfers".to_string()));
+    }
 }
 
     #[test]
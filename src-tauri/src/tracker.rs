@@ -0,0 +1,498 @@
+//! Trackers
+//!
+//! PURPOSE: One interface over the list sites (AniList, MyAnimeList)
+//! Each provider has its own ids, result types and status vocabulary, so code
+//! that should work with any of them ended up written once per provider. The
+//! `Tracker` trait maps both onto `MediaEntry` and `UnifiedStatus`; a new
+//! provider only needs an implementation here.
+
+use async_trait::async_trait;
+use serde::Serialize;
+use std::time::Duration;
+use tokio::time::Instant;
+use tracing::warn;
+
+use crate::anilist::{self, Anime, MediaListItem};
+use crate::config;
+use crate::myanimelist::{self, MalAnimeListEntry, MalAnimeUpdateExtras, MalMediaNode};
+use crate::status::UnifiedStatus;
+
+/// An anime as any tracker reports it
+#[derive(Debug, Clone, Serialize, PartialEq)]
+pub struct MediaEntry {
+    /// The provider's own id
+    pub id: i64,
+    pub title: String,
+    pub episodes: Option<i32>,
+    pub cover: Option<String>,
+    /// List status (None for search results)
+    pub status: Option<UnifiedStatus>,
+    /// Episodes watched (None for search results)
+    pub progress: Option<i32>,
+}
+
+/// Spaces out requests to one provider
+///
+/// Callers wait their turn, so bursts (searching every provider for a whole
+/// folder) stay under the provider's limit instead of getting HTTP 429s.
+pub struct RateLimiter {
+    interval: Duration,
+    next_slot: tokio::sync::Mutex<Option<Instant>>,
+}
+
+impl RateLimiter {
+    pub const fn new(interval: Duration) -> Self {
+        Self {
+            interval,
+            next_slot: tokio::sync::Mutex::const_new(None),
+        }
+    }
+
+    /// Wait until a request may be sent
+    pub async fn acquire(&self) {
+        let mut next_slot = self.next_slot.lock().await;
+        if let Some(at) = *next_slot {
+            tokio::time::sleep_until(at).await;
+        }
+        *next_slot = Some(Instant::now() + self.interval);
+    }
+}
+
+/// AniList allows 90 requests a minute
+static ANILIST_RATE_LIMITER: RateLimiter = RateLimiter::new(Duration::from_millis(700));
+
+/// MAL doesn't publish a limit; about one request a second stays clear of it
+static MAL_RATE_LIMITER: RateLimiter = RateLimiter::new(Duration::from_millis(1000));
+
+/// A list-tracking site
+#[async_trait]
+pub trait Tracker: Send + Sync {
+    /// Short provider name ("anilist", "mal"), as used in events and settings
+    fn name(&self) -> &'static str;
+
+    /// Limiter shared by every tracker of this provider
+    ///
+    /// Each trait method waits on it once before its first request. Calls
+    /// made directly through `anilist::`/`myanimelist::` don't go through it.
+    fn rate_limiter(&self) -> &RateLimiter;
+
+    /// Search the provider's catalogue
+    async fn search(&self, query: &str, limit: i32) -> Result<Vec<MediaEntry>, String>;
+
+    /// Set the watched episode count, and the status when given
+    async fn update_progress(
+        &self,
+        id: i64,
+        progress: i32,
+        status: Option<UnifiedStatus>,
+    ) -> Result<(), String>;
+
+    /// The user's whole anime list
+    async fn get_list(&self) -> Result<Vec<MediaEntry>, String>;
+}
+
+/// AniList, searched anonymously and updated with the user's token
+pub struct AniListTracker {
+    access_token: String,
+}
+
+impl AniListTracker {
+    pub fn new(access_token: String) -> Self {
+        Self { access_token }
+    }
+}
+
+impl From<Anime> for MediaEntry {
+    fn from(anime: Anime) -> Self {
+        Self {
+            id: anime.id as i64,
+            title: anime.title.preferred(config::current().title_language),
+            episodes: anime.episodes,
+            cover: anime.cover_image.large.or(anime.cover_image.medium),
+            status: None,
+            progress: None,
+        }
+    }
+}
+
+impl From<MediaListItem> for MediaEntry {
+    fn from(item: MediaListItem) -> Self {
+        Self {
+            id: item.media_id as i64,
+            title: item.media.title.preferred(config::current().title_language),
+            episodes: item.media.episodes,
+            cover: None,
+            status: UnifiedStatus::from_anilist(&item.status),
+            progress: Some(item.progress),
+        }
+    }
+}
+
+#[async_trait]
+impl Tracker for AniListTracker {
+    fn name(&self) -> &'static str {
+        "anilist"
+    }
+
+    fn rate_limiter(&self) -> &RateLimiter {
+        &ANILIST_RATE_LIMITER
+    }
+
+    async fn search(&self, query: &str, limit: i32) -> Result<Vec<MediaEntry>, String> {
+        self.rate_limiter().acquire().await;
+        let results = anilist::search_anime(query, limit, false, None).await?;
+        Ok(results.into_iter().map(MediaEntry::from).collect())
+    }
+
+    async fn update_progress(
+        &self,
+        id: i64,
+        progress: i32,
+        status: Option<UnifiedStatus>,
+    ) -> Result<(), String> {
+        let id = i32::try_from(id).map_err(|_| format!("Invalid AniList id: {}", id))?;
+        let status = status.map(UnifiedStatus::to_anilist);
+        self.rate_limiter().acquire().await;
+        anilist::update_media_progress(&self.access_token, id, progress, status, None).await?;
+        Ok(())
+    }
+
+    async fn get_list(&self) -> Result<Vec<MediaEntry>, String> {
+        self.rate_limiter().acquire().await;
+        let list = anilist::get_media_list(&self.access_token).await?;
+        Ok(list.into_iter().map(MediaEntry::from).collect())
+    }
+}
+
+/// MyAnimeList; every request needs the user's token
+pub struct MalTracker {
+    access_token: String,
+}
+
+impl MalTracker {
+    pub fn new(access_token: String) -> Self {
+        Self { access_token }
+    }
+}
+
+impl From<MalMediaNode> for MediaEntry {
+    fn from(node: MalMediaNode) -> Self {
+        Self {
+            id: node.id,
+            title: node.title,
+            episodes: node.num_episodes,
+            cover: node.main_picture.and_then(|p| p.large.or(p.medium)),
+            status: None,
+            progress: None,
+        }
+    }
+}
+
+impl From<MalAnimeListEntry> for MediaEntry {
+    fn from(entry: MalAnimeListEntry) -> Self {
+        Self {
+            status: UnifiedStatus::from_mal(&entry.status),
+            progress: Some(entry.num_episodes_watched),
+            ..Self::from(entry.anime)
+        }
+    }
+}
+
+#[async_trait]
+impl Tracker for MalTracker {
+    fn name(&self) -> &'static str {
+        "mal"
+    }
+
+    fn rate_limiter(&self) -> &RateLimiter {
+        &MAL_RATE_LIMITER
+    }
+
+    async fn search(&self, query: &str, limit: i32) -> Result<Vec<MediaEntry>, String> {
+        self.rate_limiter().acquire().await;
+        let results = myanimelist::search_anime(&self.access_token, query, limit).await?;
+        Ok(results.into_iter().map(MediaEntry::from).collect())
+    }
+
+    async fn update_progress(
+        &self,
+        id: i64,
+        progress: i32,
+        status: Option<UnifiedStatus>,
+    ) -> Result<(), String> {
+        // MAL reports rewatching as "watching" plus a flag
+        let extras = MalAnimeUpdateExtras {
+            is_rewatching: status.map(|s| s == UnifiedStatus::Rewatching),
+            ..Default::default()
+        };
+        let status = status.map(UnifiedStatus::to_mal);
+        self.rate_limiter().acquire().await;
+        myanimelist::update_anime_progress(&self.access_token, id, progress, status, &extras)
+            .await?;
+        Ok(())
+    }
+
+    async fn get_list(&self) -> Result<Vec<MediaEntry>, String> {
+        self.rate_limiter().acquire().await;
+        let list = myanimelist::get_full_anime_list(&self.access_token, None).await?;
+        Ok(list.into_iter().map(MediaEntry::from).collect())
+    }
+}
+
+/// The tracker for a provider name ("anilist" or "mal")
+pub fn for_provider(name: &str, access_token: String) -> Result<Box<dyn Tracker>, String> {
+    match name.trim().to_lowercase().as_str() {
+        "anilist" => Ok(Box::new(AniListTracker::new(access_token))),
+        "mal" | "myanimelist" => Ok(Box::new(MalTracker::new(access_token))),
+        _ => Err(format!(
+            "Unknown tracker \"{}\": expected anilist or mal",
+            name
+        )),
+    }
+}
+
+/// Tokens for the providers to search; MAL is skipped without one
+#[derive(Debug, Clone, Default)]
+pub struct ProviderTokens {
+    pub anilist: Option<String>,
+    pub mal: Option<String>,
+}
+
+/// One provider's answer in `search_all_providers`
+#[derive(Debug, Clone, Serialize, PartialEq)]
+pub struct ProviderSearchResult {
+    pub provider: &'static str,
+    pub results: Vec<MediaEntry>,
+    /// Why the search failed; the other providers' results are still returned
+    pub error: Option<String>,
+}
+
+/// Search every provider that can be searched, concurrently
+///
+/// AniList is always searched (it works without a token), MAL only with one.
+pub async fn search_all_providers(
+    title: &str,
+    tokens: &ProviderTokens,
+    limit: i32,
+) -> Vec<ProviderSearchResult> {
+    let mut trackers: Vec<Box<dyn Tracker>> = vec![Box::new(AniListTracker::new(
+        tokens.anilist.clone().unwrap_or_default(),
+    ))];
+    if let Some(token) = &tokens.mal {
+        trackers.push(Box::new(MalTracker::new(token.clone())));
+    }
+    search_trackers(title, &trackers, limit).await
+}
+
+/// Search each tracker at the same time (each waits on its own rate limiter)
+///
+/// Results come back in `trackers` order. A failing tracker doesn't fail the
+/// others; its error is reported in its result.
+pub async fn search_trackers(
+    title: &str,
+    trackers: &[Box<dyn Tracker>],
+    limit: i32,
+) -> Vec<ProviderSearchResult> {
+    futures::future::join_all(trackers.iter().map(|tracker| async move {
+        match tracker.search(title, limit).await {
+            Ok(results) => ProviderSearchResult {
+                provider: tracker.name(),
+                results,
+                error: None,
+            },
+            Err(e) => {
+                warn!("{} search failed: {}", tracker.name(), e);
+                ProviderSearchResult {
+                    provider: tracker.name(),
+                    results: Vec::new(),
+                    error: Some(e),
+                }
+            }
+        }
+    }))
+    .await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Mutex;
+
+    /// Keeps a list in memory and records updates
+    struct MockTracker {
+        list: Mutex<Vec<MediaEntry>>,
+        limiter: RateLimiter,
+    }
+
+    impl MockTracker {
+        fn new(list: Vec<MediaEntry>) -> Self {
+            Self {
+                list: Mutex::new(list),
+                limiter: RateLimiter::new(Duration::ZERO),
+            }
+        }
+    }
+
+    /// Fails every request, like a provider that's down
+    struct FailingTracker;
+
+    #[async_trait]
+    impl Tracker for FailingTracker {
+        fn name(&self) -> &'static str {
+            "failing"
+        }
+
+        fn rate_limiter(&self) -> &RateLimiter {
+            static LIMITER: RateLimiter = RateLimiter::new(Duration::ZERO);
+            &LIMITER
+        }
+
+        async fn search(&self, _query: &str, _limit: i32) -> Result<Vec<MediaEntry>, String> {
+            Err("Search failed: HTTP 503".to_string())
+        }
+
+        async fn update_progress(
+            &self,
+            _id: i64,
+            _progress: i32,
+            _status: Option<UnifiedStatus>,
+        ) -> Result<(), String> {
+            Err("Update failed: HTTP 503".to_string())
+        }
+
+        async fn get_list(&self) -> Result<Vec<MediaEntry>, String> {
+            Err("List failed: HTTP 503".to_string())
+        }
+    }
+
+    fn entry(id: i64, title: &str) -> MediaEntry {
+        MediaEntry {
+            id,
+            title: title.to_string(),
+            episodes: Some(28),
+            cover: None,
+            status: None,
+            progress: None,
+        }
+    }
+
+    #[async_trait]
+    impl Tracker for MockTracker {
+        fn name(&self) -> &'static str {
+            "mock"
+        }
+
+        fn rate_limiter(&self) -> &RateLimiter {
+            &self.limiter
+        }
+
+        async fn search(&self, query: &str, limit: i32) -> Result<Vec<MediaEntry>, String> {
+            Ok(vec![entry(1, query)]
+                .into_iter()
+                .take(limit as usize)
+                .collect())
+        }
+
+        async fn update_progress(
+            &self,
+            id: i64,
+            progress: i32,
+            status: Option<UnifiedStatus>,
+        ) -> Result<(), String> {
+            let mut list = self.list.lock().unwrap();
+            let found = list
+                .iter_mut()
+                .find(|e| e.id == id)
+                .ok_or_else(|| format!("{} isn't on the list", id))?;
+            found.progress = Some(progress);
+            found.status = status.or(found.status);
+            Ok(())
+        }
+
+        async fn get_list(&self) -> Result<Vec<MediaEntry>, String> {
+            Ok(self.list.lock().unwrap().clone())
+        }
+    }
+
+    #[tokio::test]
+    async fn test_mock_tracker_behind_trait_object() {
+        let tracker: Box<dyn Tracker> =
+            Box::new(MockTracker::new(vec![entry(1, "Sousou no Frieren")]));
+
+        let found = tracker.search("Frieren", 5).await.unwrap();
+        assert_eq!(found[0].title, "Frieren");
+
+        // Trackers can be moved into tasks
+        let handle = tokio::spawn(async move {
+            tracker
+                .update_progress(1, 5, Some(UnifiedStatus::Watching))
+                .await
+                .unwrap();
+            assert!(tracker.update_progress(2, 1, None).await.is_err());
+            tracker.get_list().await.unwrap()
+        });
+        let list = handle.await.unwrap();
+        assert_eq!(list[0].progress, Some(5));
+        assert_eq!(list[0].status, Some(UnifiedStatus::Watching));
+    }
+
+    #[tokio::test]
+    async fn test_search_trackers_reports_partial_failure() {
+        let trackers: Vec<Box<dyn Tracker>> = vec![
+            Box::new(FailingTracker),
+            Box::new(MockTracker::new(Vec::new())),
+        ];
+
+        let results = search_trackers("Frieren", &trackers, 5).await;
+        assert_eq!(results.len(), 2);
+        assert_eq!(results[0].provider, "failing");
+        assert!(results[0].results.is_empty());
+        assert_eq!(results[0].error.as_deref(), Some("Search failed: HTTP 503"));
+        assert_eq!(results[1].provider, "mock");
+        assert_eq!(results[1].results, vec![entry(1, "Frieren")]);
+        assert_eq!(results[1].error, None);
+    }
+
+    #[tokio::test]
+    async fn test_rate_limiter_spaces_requests() {
+        let limiter = RateLimiter::new(Duration::from_millis(50));
+        let start = Instant::now();
+        limiter.acquire().await;
+        limiter.acquire().await;
+        limiter.acquire().await;
+        // The first request goes right away, the next two wait a slot each
+        assert!(start.elapsed() >= Duration::from_millis(100));
+    }
+
+    #[test]
+    fn test_for_provider() {
+        assert_eq!(
+            for_provider("AniList", String::new()).unwrap().name(),
+            "anilist"
+        );
+        assert_eq!(for_provider("mal", String::new()).unwrap().name(), "mal");
+        assert!(for_provider("kitsu", String::new()).is_err());
+    }
+
+    #[test]
+    fn test_mal_list_entry_to_media_entry() {
+        let entry: MalAnimeListEntry = serde_json::from_value(serde_json::json!({
+            "anime": {
+                "id": 52991,
+                "title": "Sousou no Frieren",
+                "main_picture": { "medium": "https://img.example/m.jpg", "large": null },
+                "num_episodes": 28
+            },
+            "status": "on_hold",
+            "score": 9,
+            "num_episodes_watched": 12
+        }))
+        .unwrap();
+
+        let entry = MediaEntry::from(entry);
+        assert_eq!(entry.id, 52991);
+        assert_eq!(entry.cover.as_deref(), Some("https://img.example/m.jpg"));
+        assert_eq!(entry.status, Some(UnifiedStatus::Paused));
+        assert_eq!(entry.progress, Some(12));
+    }
+}
@@ -0,0 +1,227 @@
+//! VLC HTTP Interface
+//!
+//! PURPOSE: Read VLC's exact playback state from its web interface
+//! With the HTTP interface enabled (Preferences > Interface > Main interfaces >
+//! Web, plus a Lua HTTP password), VLC serves `/requests/status.json` with the
+//! playing file, its length and the position. That allows percentage-based
+//! scrobbling without parsing the window title.
+//!
+//! AUTH: HTTP basic with an empty username and the Lua HTTP password.
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::time::Duration;
+use tracing::debug;
+
+/// VLC answers locally, so anything slower means it isn't there
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// VLC's default HTTP interface port
+pub const DEFAULT_PORT: u16 = 8080;
+
+/// Where VLC's HTTP interface listens
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(default)]
+pub struct VlcHttpSettings {
+    pub host: String,
+    pub port: u16,
+    /// Lua HTTP password
+    pub password: String,
+}
+
+impl Default for VlcHttpSettings {
+    fn default() -> Self {
+        Self {
+            host: "127.0.0.1".to_string(),
+            port: DEFAULT_PORT,
+            password: String::new(),
+        }
+    }
+}
+
+/// Playback state as VLC reports it
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum VlcState {
+    Playing,
+    Paused,
+    Stopped,
+}
+
+/// What VLC is playing
+#[derive(Debug, Clone, Serialize, PartialEq)]
+pub struct VlcStatus {
+    /// Title metadata, falling back to the file name
+    pub title: Option<String>,
+    pub filename: Option<String>,
+    /// Length in seconds (0 when nothing is loaded)
+    pub length: i64,
+    /// Position in 0.0..=1.0
+    pub position: f64,
+    /// Elapsed time in seconds
+    pub time: i64,
+    pub state: VlcState,
+}
+
+/// Parse the body of `/requests/status.json`
+pub fn parse_status(json: &Value) -> Result<VlcStatus, String> {
+    let state: VlcState = serde_json::from_value(json["state"].clone())
+        .map_err(|e| format!("Unexpected VLC state: {}", e))?;
+
+    let meta = &json["information"]["category"]["meta"];
+    let filename = meta["filename"].as_str().map(str::to_string);
+    let title = meta["title"]
+        .as_str()
+        .map(str::to_string)
+        .or_else(|| filename.clone());
+
+    Ok(VlcStatus {
+        title,
+        filename,
+        length: json["length"].as_i64().unwrap_or(0),
+        position: json["position"].as_f64().unwrap_or(0.0),
+        time: json["time"].as_i64().unwrap_or(0),
+        state,
+    })
+}
+
+/// Get the playback state from VLC's HTTP interface
+///
+/// # Arguments
+/// * `host` - Usually "127.0.0.1"
+/// * `port` - HTTP interface port (VLC's default is `DEFAULT_PORT`)
+/// * `password` - Lua HTTP password
+///
+/// # Returns
+/// * `Ok(None)` - VLC isn't running or the interface is disabled
+/// * `Err` - VLC answered but rejected the password or sent something unexpected
+pub async fn vlc_status(
+    host: &str,
+    port: u16,
+    password: &str,
+) -> Result<Option<VlcStatus>, String> {
+    let url = format!("http://{}:{}/requests/status.json", host, port);
+
+    let client = reqwest::Client::builder()
+        .timeout(REQUEST_TIMEOUT)
+        .build()
+        .map_err(|e| format!("Failed to build HTTP client: {}", e))?;
+
+    let response = match client.get(&url).basic_auth("", Some(password)).send().await {
+        Ok(response) => response,
+        Err(e) if e.is_connect() || e.is_timeout() => {
+            debug!("VLC HTTP interface not reachable at {}: {}", url, e);
+            return Ok(None);
+        }
+        Err(e) => return Err(format!("Request failed: {}", e)),
+    };
+
+    let status = response.status();
+    if status == reqwest::StatusCode::UNAUTHORIZED {
+        return Err("VLC rejected the HTTP interface password".to_string());
+    }
+    if !status.is_success() {
+        return Err(format!(
+            "Failed to get VLC status (HTTP {})",
+            status.as_u16()
+        ));
+    }
+
+    let json: Value = response
+        .json()
+        .await
+        .map_err(|e| format!("Failed to parse response: {}", e))?;
+
+    parse_status(&json).map(Some)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+    use wiremock::matchers::{header, method, path};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    #[test]
+    fn test_parse_playing_status() {
+        let body = json!({
+            "state": "playing",
+            "length": 1420,
+            "position": 0.41,
+            "time": 582,
+            "information": {
+                "category": {
+                    "meta": { "filename": "[SubsPlease] Sousou no Frieren - 05 (1080p).mkv" }
+                }
+            }
+        });
+
+        let status = parse_status(&body).unwrap();
+        assert_eq!(status.state, VlcState::Playing);
+        assert_eq!(status.length, 1420);
+        assert_eq!(status.position, 0.41);
+        assert_eq!(status.time, 582);
+        assert_eq!(
+            status.title.as_deref(),
+            Some("[SubsPlease] Sousou no Frieren - 05 (1080p).mkv")
+        );
+    }
+
+    #[test]
+    fn test_parse_stopped_status() {
+        let status = parse_status(&json!({ "state": "stopped", "length": 0 })).unwrap();
+        assert_eq!(status.state, VlcState::Stopped);
+        assert_eq!(status.title, None);
+        assert!(parse_status(&json!({ "state": "rewinding" })).is_err());
+    }
+
+    /// Serve `status.json` to requests with the password "secret", 401 otherwise
+    async fn vlc_server() -> MockServer {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/requests/status.json"))
+            .and(header("authorization", "Basic OnNlY3JldA=="))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                "state": "paused",
+                "length": 1420,
+                "position": 0.5,
+                "time": 710,
+                "information": { "category": { "meta": { "title": "Frieren 05" } } }
+            })))
+            .mount(&server)
+            .await;
+        Mock::given(method("GET"))
+            .respond_with(ResponseTemplate::new(401))
+            .mount(&server)
+            .await;
+        server
+    }
+
+    #[tokio::test]
+    async fn test_status_is_read_with_basic_auth() {
+        let server = vlc_server().await;
+        let port = server.address().port();
+
+        let status = vlc_status("127.0.0.1", port, "secret")
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(status.state, VlcState::Paused);
+        assert_eq!(status.title.as_deref(), Some("Frieren 05"));
+        assert_eq!(status.time, 710);
+
+        let error = vlc_status("127.0.0.1", port, "wrong").await.unwrap_err();
+        assert!(error.contains("password"), "{}", error);
+    }
+
+    #[tokio::test]
+    async fn test_connection_refused_is_none() {
+        // Bind then drop to get a port nothing listens on
+        let port = std::net::TcpListener::bind("127.0.0.1:0")
+            .unwrap()
+            .local_addr()
+            .unwrap()
+            .port();
+        assert_eq!(vlc_status("127.0.0.1", port, "secret").await, Ok(None));
+    }
+}
@@ -1,12 +1,63 @@
 #![cfg(windows)]
 
-use winapi::shared::minwindef::{BOOL, LPARAM};
+use winapi::shared::minwindef::{BOOL, DWORD, LPARAM};
 use winapi::shared::windef::HWND;
-use winapi::um::winnt::LPWSTR;
+use winapi::um::handleapi::CloseHandle;
+use winapi::um::processthreadsapi::OpenProcess;
+use winapi::um::winbase::QueryFullProcessImageNameW;
+use winapi::um::winnt::{LPWSTR, PROCESS_QUERY_LIMITED_INFORMATION};
 use winapi::um::winuser::{
-    EnumWindows, GetForegroundWindow, GetWindowTextLengthW, GetWindowTextW, IsWindowVisible,
+    EnumWindows, GetForegroundWindow, GetWindowTextLengthW, GetWindowTextW,
+    GetWindowThreadProcessId, IsIconic, IsWindowVisible,
 };
 
+use crate::config;
+
+/// A top-level window and the process that owns it
+#[derive(Debug, Clone, PartialEq)]
+pub struct WindowInfo {
+    pub title: String,
+    pub process_id: u32,
+    /// Executable name, e.g. "vlc.exe" (None if the process can't be opened)
+    pub process_name: Option<String>,
+}
+
+/// Executable name of a process
+///
+/// QueryFullProcessImageNameW only needs PROCESS_QUERY_LIMITED_INFORMATION,
+/// which is also granted for elevated processes the module APIs can't read.
+unsafe fn get_process_name(process_id: DWORD) -> Option<String> {
+    let handle = OpenProcess(PROCESS_QUERY_LIMITED_INFORMATION, 0, process_id);
+    if handle.is_null() {
+        return None;
+    }
+
+    let mut buffer: Vec<u16> = vec![0; 1024];
+    // In: buffer size; out: characters written, without the null terminator
+    let mut size = buffer.len() as DWORD;
+    let ok = QueryFullProcessImageNameW(handle, 0, buffer.as_mut_ptr(), &mut size);
+    CloseHandle(handle);
+
+    if ok == 0 || size == 0 {
+        return None;
+    }
+
+    let path = String::from_utf16_lossy(&buffer[..size as usize]);
+    Some(exe_file_name(&path).to_string())
+}
+
+/// File name part of a Windows executable path
+fn exe_file_name(path: &str) -> &str {
+    path.rsplit(['\\', '/']).next().unwrap_or(path)
+}
+
+/// Whether a process is one of the configured players/browsers (case-insensitive)
+fn is_target_process(process_name: &str, targets: &[String]) -> bool {
+    targets
+        .iter()
+        .any(|target| target.eq_ignore_ascii_case(process_name))
+}
+
 unsafe fn get_foreground_window() -> Option<HWND> {
     let hwnd = GetForegroundWindow();
     if hwnd.is_null() {
@@ -16,25 +67,48 @@ unsafe fn get_foreground_window() -> Option<HWND> {
     }
 }
 
-pub unsafe fn get_window_title(hwnd: HWND) -> Option<String> {
-    if IsWindowVisible(hwnd) == 0 {
-        return None;
+/// Decode the UTF-16 text written by GetWindowTextW
+///
+/// `written` excludes the null terminator but is clamped to the buffer anyway.
+/// Invalid sequences (e.g. a lone surrogate from a truncated emoji) become
+/// U+FFFD instead of discarding the whole title.
+fn decode_title(buffer: &[u16], written: usize) -> Option<String> {
+    let text = &buffer[..written.min(buffer.len())];
+    let text = match text.iter().position(|&c| c == 0) {
+        Some(end) => &text[..end],
+        None => text,
+    };
+
+    if text.is_empty() {
+        None
+    } else {
+        Some(String::from_utf16_lossy(text))
     }
+}
 
+/// Read a window's title regardless of its visibility
+unsafe fn read_window_text(hwnd: HWND) -> Option<String> {
     let length = GetWindowTextLengthW(hwnd);
     if length <= 0 {
         return None;
     }
 
+    // Room for the title plus the null terminator
     let mut buffer: Vec<u16> = vec![0; (length + 1) as usize];
-
     let written = GetWindowTextW(hwnd, buffer.as_mut_ptr() as LPWSTR, length + 1);
+    if written <= 0 {
+        return None;
+    }
 
-    if written == 0 {
+    decode_title(&buffer, written as usize)
+}
+
+pub unsafe fn get_window_title(hwnd: HWND) -> Option<String> {
+    if IsWindowVisible(hwnd) == 0 {
         return None;
     }
 
-    String::from_utf16(&buffer[..written as usize]).ok()
+    read_window_text(hwnd)
 }
 
 pub fn get_active_window_title() -> Option<String> {
@@ -44,20 +118,117 @@ pub fn get_active_window_title() -> Option<String> {
     }
 }
 
-/// Callback for EnumWindows to collect all visible windows
+/// Callback for EnumWindows to collect visible and minimized windows
+///
+/// Minimized players (e.g. VLC playing in the background) are included; windows
+/// with blank titles are skipped.
 unsafe extern "system" fn enum_windows_callback(hwnd: HWND, lparam: LPARAM) -> BOOL {
-    let titles = &mut *(lparam as *mut Vec<String>);
-    if let Some(title) = get_window_title(hwnd) {
-        titles.push(title);
+    let windows = &mut *(lparam as *mut Vec<WindowInfo>);
+
+    if IsWindowVisible(hwnd) != 0 || IsIconic(hwnd) != 0 {
+        if let Some(title) = read_window_text(hwnd) {
+            if !title.trim().is_empty() {
+                let mut process_id: DWORD = 0;
+                GetWindowThreadProcessId(hwnd, &mut process_id);
+                windows.push(WindowInfo {
+                    title,
+                    process_id,
+                    process_name: get_process_name(process_id),
+                });
+            }
+        }
     }
     1 // Continue enumeration
 }
 
-/// Returns titles of all visible windows
-pub fn get_all_visible_window_titles() -> Vec<String> {
-    let mut titles: Vec<String> = Vec::new();
+/// Returns all visible (including minimized) windows with their owning process
+pub fn get_visible_windows() -> Vec<WindowInfo> {
+    let mut windows: Vec<WindowInfo> = Vec::new();
     unsafe {
-        EnumWindows(Some(enum_windows_callback), &mut titles as *mut _ as LPARAM);
+        EnumWindows(
+            Some(enum_windows_callback),
+            &mut windows as *mut _ as LPARAM,
+        );
+    }
+    windows
+}
+
+/// Returns titles of visible (including minimized) windows owned by media
+/// players or browsers
+///
+/// Mirrors `mac_name::get_all_visible_window_titles`; the process list comes from
+/// `AppConfig::player_processes`.
+pub fn get_all_visible_window_titles() -> Vec<String> {
+    let targets = config::current().player_processes;
+
+    get_visible_windows()
+        .into_iter()
+        .filter(|window| {
+            window
+                .process_name
+                .as_deref()
+                .is_some_and(|name| is_target_process(name, &targets))
+        })
+        .map(|window| window.title)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_decode_title_with_surrogate_pair() {
+        // "Ep 1 😀" followed by the null terminator
+        let mut buffer: Vec<u16> = "Ep 1 😀".encode_utf16().collect();
+        assert_eq!(buffer.len(), 7); // the emoji is a surrogate pair
+        buffer.push(0);
+
+        assert_eq!(decode_title(&buffer, 7), Some("Ep 1 😀".to_string()));
+        // A count past the terminator is clamped to the text
+        assert_eq!(decode_title(&buffer, 20), Some("Ep 1 😀".to_string()));
+    }
+
+    #[test]
+    fn test_decode_title_keeps_invalid_sequences() {
+        // Lone high surrogate, as left by a title cut in the middle of an emoji
+        let buffer = [0x0041, 0xD83D, 0x0042];
+        assert_eq!(decode_title(&buffer, 3), Some("A\u{FFFD}B".to_string()));
+        assert_eq!(decode_title(&[0], 1), None);
+    }
+
+    #[test]
+    fn test_exe_file_name() {
+        assert_eq!(
+            exe_file_name("C:\\Program Files\\VideoLAN\\VLC\\vlc.exe"),
+            "vlc.exe"
+        );
+        assert_eq!(exe_file_name("mpv.exe"), "mpv.exe");
+    }
+
+    #[test]
+    fn test_is_target_process() {
+        let targets = vec!["vlc.exe".to_string(), "msedge.exe".to_string()];
+
+        assert!(is_target_process("vlc.exe", &targets));
+        assert!(is_target_process("VLC.EXE", &targets));
+        assert!(!is_target_process("explorer.exe", &targets));
+        assert!(!is_target_process("vlc", &targets));
+        assert!(!is_target_process("vlc.exe", &[]));
+    }
+
+    #[test]
+    fn test_get_visible_windows() {
+        let windows = get_visible_windows();
+        println!("Visible windows: {:?}", windows);
+        assert!(windows.iter().all(|window| window.process_id != 0));
+    }
+
+    #[test]
+    fn test_get_all_visible_window_titles() {
+        let titles = get_all_visible_window_titles();
+        println!("Visible window titles: {:?}", titles);
+        // We can't assert specific values since it depends on running apps
+        assert!(titles.iter().all(|title| !title.trim().is_empty()));
     }
-    titles
 }
@@ -173,18 +173,23 @@ export async function searchManga(
 
 /**
  * Update anime progress on MAL
+ *
+ * Progress lower than what's on the list is refused unless `allowRegression`
+ * is set; pass true for edits the user made (e.g. decrementing an episode).
  */
 export async function updateAnimeProgress(
     accessToken: string,
     animeId: number,
     episodesWatched: number,
-    status?: MalAnimeStatus
+    status?: MalAnimeStatus,
+    allowRegression: boolean = false
 ): Promise<MalListUpdateResponse> {
     const resultStr = await invoke<string>('mal_update_anime_progress', {
         accessToken,
         animeId,
         episodesWatched,
         status,
+        allowRegression,
     });
     return JSON.parse(resultStr);
 }
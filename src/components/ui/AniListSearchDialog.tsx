@@ -123,14 +123,18 @@ function AniListSearchDialog({
                 setResults(data);
             } else {
                 // Use Tauri command for anime search
+                // A newer search in the session cancels this one if it's still running
                 const response = await invoke<string>('search_anime_command', {
                     query: query.trim(),
-                    limit: 10
+                    limit: 10,
+                    session: 'anilist-search-dialog'
                 });
                 const data: MediaResult[] = JSON.parse(response);
                 setResults(data);
             }
         } catch (err) {
+            // Replaced by a newer search, which sets the results
+            if (err === 'Search cancelled') return;
             console.error(`AniList ${mediaType.toLowerCase()} search failed:`, err);
             setResults([]);
         } finally {
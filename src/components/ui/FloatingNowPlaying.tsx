@@ -163,7 +163,9 @@ export function FloatingNowPlaying({ onAnimeDetected }: FloatingNowPlayingProps)
     useEffect(() => {
         const detectAnime = async () => {
             try {
-                const result = await invoke<string>('detect_anime_command');
+                // Signed-in users get shows already on their list matched first
+                const accessToken = localStorage.getItem('anilist_token') || localStorage.getItem('token');
+                const result = await invoke<string>('detect_anime_command', { accessToken, poller: 'floating-now-playing' });
                 const parsed: DetectionResult = JSON.parse(result);
 
                 // Check if auto-detection found a DIFFERENT anime than manual session
@@ -342,9 +344,21 @@ export function FloatingNowPlaying({ onAnimeDetected }: FloatingNowPlayingProps)
             }
         };
 
-        detectAnime();
-        const interval = setInterval(detectAnime, 3000);
-        return () => clearInterval(interval);
+        // The backend stretches the interval while nothing is playing
+        let timeout: ReturnType<typeof setTimeout> | undefined;
+        let cancelled = false;
+        const poll = async () => {
+            await detectAnime();
+            if (cancelled) return;
+            const delay = await invoke<number>('get_poll_interval', { poller: 'floating-now-playing' }).catch(() => 3000);
+            if (!cancelled) timeout = setTimeout(poll, delay);
+        };
+
+        poll();
+        return () => {
+            cancelled = true;
+            clearTimeout(timeout);
+        };
     }, [onAnimeDetected, getMappingForFilePath, createMatchFromMapping, manualSession, clearManualSession]);
 
     // Track local stats every minute
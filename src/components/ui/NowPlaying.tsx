@@ -191,7 +191,9 @@ export function NowPlaying({ onAnimeDetected }: NowPlayingProps) {
     useEffect(() => {
         const detectAnime = async () => {
             try {
-                const result = await invoke<string>('detect_anime_command');
+                // Signed-in users get shows already on their list matched first
+                const accessToken = localStorage.getItem('anilist_token') || localStorage.getItem('token');
+                const result = await invoke<string>('detect_anime_command', { accessToken, poller: 'now-playing' });
                 const parsed: DetectionResult = JSON.parse(result);
                 setDetection(parsed);
                 setError(null);
@@ -263,9 +265,21 @@ export function NowPlaying({ onAnimeDetected }: NowPlayingProps) {
             }
         };
 
-        detectAnime();
-        const interval = setInterval(detectAnime, 3000);
-        return () => clearInterval(interval);
+        // The backend stretches the interval while nothing is playing
+        let timeout: ReturnType<typeof setTimeout> | undefined;
+        let cancelled = false;
+        const poll = async () => {
+            await detectAnime();
+            if (cancelled) return;
+            const delay = await invoke<number>('get_poll_interval', { poller: 'now-playing' }).catch(() => 3000);
+            if (!cancelled) timeout = setTimeout(poll, delay);
+        };
+
+        poll();
+        return () => {
+            cancelled = true;
+            clearTimeout(timeout);
+        };
     }, [onAnimeDetected]);
 
     const isPlaying = detection?.status === 'detected';
@@ -121,7 +121,7 @@ export function useDiscordRPC(enabled: boolean = true, privacyLevel: 'full' | 'm
 
         try {
             // Run detection to get window info (we need this even for manual sessions to parse episode)
-            const result = await invoke<string>('detect_anime_command');
+            const result = await invoke<string>('detect_anime_command', { poller: 'discord-rpc' });
             const data: DetectedAnime = JSON.parse(result);
 
             // Check if auto-detection found a DIFFERENT anime than manual session
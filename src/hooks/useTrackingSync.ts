@@ -59,7 +59,7 @@ export function useTrackingSync() {
         if (malAuth.isAuthenticated && malAuth.accessToken && malId) {
             try {
                 const malStatus = status ? malClient.anilistToMalAnimeStatus(status) : undefined;
-                await malClient.updateAnimeProgress(malAuth.accessToken, malId, progress, malStatus);
+                await malClient.updateAnimeProgress(malAuth.accessToken, malId, progress, malStatus, true);
                 result.mal.success = true;
                 console.log(`[Sync] MAL anime ${malId} updated to episode ${progress}`);
             } catch (e) {
@@ -25,8 +25,14 @@ import { addToOfflineQueue } from './offlineQueue';
 
 /**
  * Sync a single entry to AniList
+ *
+ * Progress lower than what's on the list is refused unless `allowRegression`
+ * is set, so a misdetected episode can't overwrite real progress.
  */
-export async function syncEntryToAniList(entry: LocalAnimeEntry): Promise<boolean> {
+export async function syncEntryToAniList(
+    entry: LocalAnimeEntry,
+    allowRegression: boolean = false
+): Promise<boolean> {
     // Must have AniList ID and token
     const token = localStorage.getItem('anilist_token') || localStorage.getItem('token');
 
@@ -52,6 +58,8 @@ export async function syncEntryToAniList(entry: LocalAnimeEntry): Promise<boolea
             mediaId: entry.anilistId,
             progress: entry.episode,
             status: anilistStatus,
+            totalEpisodes: entry.totalEpisodes,
+            allowRegression,
         });
 
         const parsed = JSON.parse(result);
@@ -255,8 +263,8 @@ export async function updateAndSync(
     // Update local DB first (instant)
     const entry = updateProgress(id, data);
 
-    // Then try to sync to AniList (background)
-    const synced = await syncEntryToAniList(entry);
+    // Then try to sync to AniList (background); the user set this progress
+    const synced = await syncEntryToAniList(entry, true);
 
     return { local: entry, synced };
 }
@@ -120,7 +120,8 @@ function AnimeDetails() {
                             malAuth.accessToken,
                             malId,
                             newProgress,
-                            malStatus
+                            malStatus,
+                            true
                         );
                         console.log('[AnimeDetails] MAL progress synced:', newProgress);
                     }
@@ -371,11 +371,13 @@ export async function syncEntryToMal(
             : ANILIST_TO_MAL_MANGA_STATUS[entry.anilist.status] as malClient.MalMangaStatus;
 
         if (entry.type === 'anime') {
+            // Mirrors the AniList list, which may be behind MAL on purpose
             await malClient.updateAnimeProgress(
                 malAccessToken,
                 entry.malId,
                 entry.anilist.progress,
-                malStatus as malClient.MalAnimeStatus
+                malStatus as malClient.MalAnimeStatus,
+                true
             );
         } else {
             await malClient.updateMangaProgress(